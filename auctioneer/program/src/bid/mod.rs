@@ -8,10 +8,14 @@ use mpl_auction_house::{
     constants::{AUCTIONEER, FEE_PAYER, PREFIX},
     cpi::accounts::AuctioneerBuy as AHBuy,
     program::AuctionHouse as AuctionHouseProgram,
+    utils::create_or_allocate_account_raw,
     AuctionHouse,
 };
 
-use crate::{constants::*, sell::config::*, utils::*};
+use crate::{
+    bid_cooldown::cooldown::*, bid_history::history::*, constants::*, errors::*, pda::*,
+    sell::config::*, utils::*,
+};
 
 /// Accounts for the [`private_bid_with_auctioneer` handler](fn.private_bid_with_auctioneer.html).
 #[derive(Accounts)]
@@ -92,7 +96,7 @@ pub struct AuctioneerBuy<'info> {
 
     /// CHECK: Not dangerous. Account seeds checked in constraint.
     /// Buyer trade state PDA.
-    #[account(mut, seeds = [PREFIX.as_bytes(), wallet.key().as_ref(), auction_house.key().as_ref(), token_account.key().as_ref(), treasury_mint.key().as_ref(), token_account.mint.as_ref(), buyer_price.to_le_bytes().as_ref(), token_size.to_le_bytes().as_ref()], seeds::program=auction_house_program, bump = trade_state_bump)]
+    #[account(mut, seeds = [PREFIX.as_bytes(), wallet.key().as_ref(), auction_house.key().as_ref(), token_account.key().as_ref(), treasury_mint.key().as_ref(), token_account.mint.as_ref(), buyer_price.to_le_bytes().as_ref(), listing_config.bid_token_size(token_size).to_le_bytes().as_ref()], seeds::program=auction_house_program, bump = trade_state_bump)]
     buyer_trade_state: UncheckedAccount<'info>,
 
     /// CHECK: Is used as a seed for ah_auctioneer_pda.
@@ -126,12 +130,64 @@ pub fn auctioneer_buy<'info>(
     token_size: u64,
 ) -> Result<()> {
     assert_auction_active(&ctx.accounts.listing_config)?;
-    assert_higher_bid(&ctx.accounts.listing_config, buyer_price)?;
-    assert_exceeds_reserve_price(&ctx.accounts.listing_config, buyer_price)?;
-    process_time_extension(&mut ctx.accounts.listing_config)?;
-    ctx.accounts.listing_config.highest_bid.amount = buyer_price;
-    ctx.accounts.listing_config.highest_bid.buyer_trade_state =
-        ctx.accounts.buyer_trade_state.key();
+    if ctx.accounts.listing_config.multi_unit {
+        assert_winning_unit_bid(&ctx.accounts.listing_config, buyer_price)?;
+        assert_exceeds_reserve_price(&ctx.accounts.listing_config, buyer_price)?;
+        process_time_extension(&mut ctx.accounts.listing_config)?;
+        ctx.accounts.listing_config.insert_unit_bid(Bid {
+            version: ListingConfigVersion::V0,
+            amount: buyer_price,
+            buyer_trade_state: ctx.accounts.buyer_trade_state.key(),
+        });
+    } else {
+        assert_higher_bid(&ctx.accounts.listing_config, buyer_price)?;
+        assert_exceeds_reserve_price(&ctx.accounts.listing_config, buyer_price)?;
+        process_time_extension(&mut ctx.accounts.listing_config)?;
+        ctx.accounts.listing_config.highest_bid.amount = buyer_price;
+        ctx.accounts.listing_config.highest_bid.buyer_trade_state =
+            ctx.accounts.buyer_trade_state.key();
+    }
+    let bid_token_size = ctx.accounts.listing_config.bid_token_size(token_size);
+
+    let mut remaining_accounts_counter: usize = 0;
+    if ctx.accounts.listing_config.has_bid_history {
+        let bid_history_info = ctx
+            .remaining_accounts
+            .get(remaining_accounts_counter)
+            .ok_or(AuctioneerError::MissingBidHistory)?;
+        remaining_accounts_counter += 1;
+        let mut bid_history: Account<BidHistory> = Account::try_from(bid_history_info)?;
+        if bid_history.listing_config != ctx.accounts.listing_config.key() {
+            return err!(AuctioneerError::BidHistoryMismatch);
+        }
+
+        bid_history.append(BidRecord {
+            bidder: ctx.accounts.wallet.key(),
+            amount: buyer_price,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        bid_history.exit(&crate::id())?;
+    }
+
+    // The bidder's cooldown account is followed by the payer of its rent.
+    if ctx.accounts.listing_config.bid_cooldown > 0 {
+        let bid_cooldown_info = ctx
+            .remaining_accounts
+            .get(remaining_accounts_counter)
+            .ok_or(AuctioneerError::MissingBidCooldown)?;
+        let bid_cooldown_payer = ctx
+            .remaining_accounts
+            .get(remaining_accounts_counter + 1)
+            .ok_or(AuctioneerError::MissingBidCooldown)?;
+        process_bid_cooldown(
+            &ctx.accounts.listing_config,
+            bid_cooldown_info,
+            &ctx.accounts.wallet.to_account_info(),
+            bid_cooldown_payer,
+            &ctx.accounts.rent.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+        )?;
+    }
 
     let cpi_program = ctx.accounts.auction_house_program.to_account_info();
     let cpi_accounts = AHBuy {
@@ -170,6 +226,61 @@ pub fn auctioneer_buy<'info>(
         trade_state_bump,
         escrow_payment_bump,
         buyer_price,
-        token_size,
+        bid_token_size,
     )
 }
+
+/// Check the bidder is not bidding again before the listing's bid cooldown has elapsed, creating
+/// the bidder's cooldown account on their first bid with `payer` paying its rent.
+fn process_bid_cooldown<'info>(
+    listing_config: &Account<'info, ListingConfig>,
+    bid_cooldown_info: &AccountInfo<'info>,
+    wallet: &AccountInfo<'info>,
+    payer: &AccountInfo<'info>,
+    rent: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+) -> Result<()> {
+    let listing_config_key = listing_config.key();
+    let (bid_cooldown_key, bump) = find_bid_cooldown_address(&listing_config_key, wallet.key);
+    if bid_cooldown_info.key() != bid_cooldown_key {
+        return err!(AuctioneerError::BidCooldownMismatch);
+    }
+
+    let current_timestamp = Clock::get()?.unix_timestamp;
+
+    if bid_cooldown_info.data_is_empty() {
+        create_or_allocate_account_raw(
+            crate::id(),
+            bid_cooldown_info,
+            rent,
+            system_program,
+            payer,
+            BID_COOLDOWN_SIZE,
+            &[],
+            &[
+                BID_COOLDOWN.as_bytes(),
+                listing_config_key.as_ref(),
+                wallet.key.as_ref(),
+                &[bump],
+            ],
+        )?;
+
+        let bid_cooldown = BidCooldown {
+            listing_config: listing_config_key,
+            bidder: wallet.key(),
+            last_bid_time: current_timestamp,
+            bump,
+        };
+        bid_cooldown.try_serialize(&mut *bid_cooldown_info.try_borrow_mut_data()?)?;
+    } else {
+        let mut bid_cooldown: Account<BidCooldown> = Account::try_from(bid_cooldown_info)?;
+        if current_timestamp < bid_cooldown.last_bid_time + i64::from(listing_config.bid_cooldown) {
+            return err!(AuctioneerError::BidCooldownActive);
+        }
+
+        bid_cooldown.last_bid_time = current_timestamp;
+        bid_cooldown.exit(&crate::id())?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,13 @@
+use anchor_lang::prelude::*;
+use solana_program::clock::UnixTimestamp;
+
+pub const BID_COOLDOWN_SIZE: usize = 8 + 32 + 32 + 8 + 1;
+
+/// Tracks the last time a bidder placed a bid on a listing with a bid cooldown.
+#[account]
+pub struct BidCooldown {
+    pub listing_config: Pubkey,
+    pub bidder: Pubkey,
+    pub last_bid_time: UnixTimestamp,
+    pub bump: u8,
+}
@@ -0,0 +1,39 @@
+pub mod cooldown;
+
+use crate::{bid_cooldown::cooldown::*, constants::*, errors::*};
+
+use anchor_lang::{prelude::*, AnchorDeserialize};
+
+/// Accounts for the [`close_bid_cooldown` handler](fn.close_bid_cooldown.html).
+#[derive(Accounts)]
+pub struct CloseBidCooldown<'info> {
+    /// Bidder wallet account, receives the bid cooldown rent.
+    #[account(mut)]
+    pub bidder: Signer<'info>,
+
+    /// CHECK: Only used to check the listing has been closed.
+    /// The Listing Config the bid cooldown belongs to.
+    pub listing_config: UncheckedAccount<'info>,
+
+    /// The bid cooldown account to close.
+    #[account(
+        mut,
+        seeds=[BID_COOLDOWN.as_bytes(), listing_config.key().as_ref(), bidder.key().as_ref()],
+        bump=bid_cooldown.bump,
+        has_one=listing_config,
+        has_one=bidder,
+        close=bidder,
+    )]
+    pub bid_cooldown: Account<'info, BidCooldown>,
+}
+
+/// Close the bid cooldown of a bidder once the Listing Config has been closed by `execute_sale` or `cancel`.
+pub fn close_bid_cooldown<'info>(
+    ctx: Context<'_, '_, '_, 'info, CloseBidCooldown<'info>>,
+) -> Result<()> {
+    if ctx.accounts.listing_config.lamports() > 0 {
+        return err!(AuctioneerError::ListingNotClosed);
+    }
+
+    Ok(())
+}
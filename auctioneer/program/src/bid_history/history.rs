@@ -0,0 +1,39 @@
+use anchor_lang::prelude::*;
+use solana_program::clock::UnixTimestamp;
+
+pub const BID_RECORD_SIZE: usize = 32 + 8 + 8;
+pub const MAX_BID_HISTORY_CAPACITY: u8 = 100;
+
+pub fn bid_history_size(capacity: u8) -> usize {
+    8 + 32 + 32 + 1 + 1 + 1 + 4 + (capacity as usize) * BID_RECORD_SIZE
+}
+
+#[derive(AnchorDeserialize, AnchorSerialize, Clone)]
+pub struct BidRecord {
+    pub bidder: Pubkey,
+    pub amount: u64,
+    pub timestamp: UnixTimestamp,
+}
+
+/// Ring buffer holding the last `capacity` bids placed on a listing.
+#[account]
+pub struct BidHistory {
+    pub listing_config: Pubkey,
+    pub seller: Pubkey,
+    pub bump: u8,
+    pub capacity: u8,
+    /// Index of the slot the next bid will be written to once the buffer is full.
+    pub head: u8,
+    pub bids: Vec<BidRecord>,
+}
+
+impl BidHistory {
+    pub fn append(&mut self, record: BidRecord) {
+        if self.bids.len() < self.capacity as usize {
+            self.bids.push(record);
+        } else {
+            self.bids[self.head as usize] = record;
+            self.head = (self.head + 1) % self.capacity;
+        }
+    }
+}
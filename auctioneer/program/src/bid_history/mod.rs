@@ -0,0 +1,112 @@
+pub mod history;
+
+use crate::{bid_history::history::*, constants::*, errors::*, sell::config::*};
+
+use anchor_lang::{prelude::*, AnchorDeserialize};
+use anchor_spl::token::TokenAccount;
+
+use mpl_auction_house::{self, constants::PREFIX, AuctionHouse};
+
+/// Accounts for the [`create_bid_history` handler](fn.create_bid_history.html).
+#[derive(Accounts)]
+#[instruction(token_size: u64, capacity: u8)]
+pub struct CreateBidHistory<'info> {
+    /// Seller wallet account, pays for the bid history account.
+    #[account(mut)]
+    pub wallet: Signer<'info>,
+
+    /// The Listing Config used for listing settings
+    #[account(
+        mut,
+        seeds=[
+            LISTING_CONFIG.as_bytes(),
+            wallet.key().as_ref(),
+            auction_house.key().as_ref(),
+            token_account.key().as_ref(),
+            auction_house.treasury_mint.as_ref(),
+            token_account.mint.as_ref(),
+            &token_size.to_le_bytes()
+        ],
+        bump=listing_config.bump,
+    )]
+    pub listing_config: Account<'info, ListingConfig>,
+
+    /// The bid history account recording the latest bids of the listing.
+    #[account(
+        init,
+        payer=wallet,
+        space=bid_history_size(capacity),
+        seeds=[BID_HISTORY.as_bytes(), listing_config.key().as_ref()],
+        bump,
+    )]
+    pub bid_history: Account<'info, BidHistory>,
+
+    /// SPL token account containing token for sale.
+    pub token_account: Box<Account<'info, TokenAccount>>,
+
+    /// Auction House instance PDA account.
+    #[account(seeds=[PREFIX.as_bytes(), auction_house.creator.as_ref(), auction_house.treasury_mint.as_ref()], seeds::program=mpl_auction_house::id(), bump=auction_house.bump)]
+    pub auction_house: Box<Account<'info, AuctionHouse>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Create a bid history account for a listing, recording the last `capacity` bids placed on it.
+pub fn create_bid_history<'info>(
+    ctx: Context<'_, '_, '_, 'info, CreateBidHistory<'info>>,
+    _token_size: u64,
+    capacity: u8,
+) -> Result<()> {
+    if capacity == 0 || capacity > MAX_BID_HISTORY_CAPACITY {
+        return err!(AuctioneerError::InvalidBidHistoryCapacity);
+    }
+
+    let bid_history = &mut ctx.accounts.bid_history;
+    bid_history.listing_config = ctx.accounts.listing_config.key();
+    bid_history.seller = ctx.accounts.wallet.key();
+    bid_history.capacity = capacity;
+    bid_history.head = 0;
+    bid_history.bids = Vec::with_capacity(capacity as usize);
+    bid_history.bump = *ctx
+        .bumps
+        .get("bid_history")
+        .ok_or(AuctioneerError::BumpSeedNotInHashMap)?;
+
+    ctx.accounts.listing_config.has_bid_history = true;
+
+    Ok(())
+}
+
+/// Accounts for the [`close_bid_history` handler](fn.close_bid_history.html).
+#[derive(Accounts)]
+pub struct CloseBidHistory<'info> {
+    /// Seller wallet account, receives the bid history rent.
+    #[account(mut)]
+    pub seller: Signer<'info>,
+
+    /// CHECK: Only used to check the listing has been closed.
+    /// The Listing Config the bid history belongs to.
+    pub listing_config: UncheckedAccount<'info>,
+
+    /// The bid history account to close.
+    #[account(
+        mut,
+        seeds=[BID_HISTORY.as_bytes(), listing_config.key().as_ref()],
+        bump=bid_history.bump,
+        has_one=listing_config,
+        has_one=seller,
+        close=seller,
+    )]
+    pub bid_history: Account<'info, BidHistory>,
+}
+
+/// Close the bid history of a listing once its Listing Config has been closed by `execute_sale` or `cancel`.
+pub fn close_bid_history<'info>(
+    ctx: Context<'_, '_, '_, 'info, CloseBidHistory<'info>>,
+) -> Result<()> {
+    if ctx.accounts.listing_config.lamports() > 0 {
+        return err!(AuctioneerError::ListingNotClosed);
+    }
+
+    Ok(())
+}
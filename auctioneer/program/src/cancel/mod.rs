@@ -98,13 +98,28 @@ pub fn auctioneer_cancel<'info>(
     buyer_price: u64,
     token_size: u64,
 ) -> Result<()> {
-    if !ctx.accounts.listing_config.allow_high_bid_cancel
-        && (ctx.accounts.trade_state.key()
-            == ctx.accounts.listing_config.highest_bid.buyer_trade_state)
+    let trade_state_key = ctx.accounts.trade_state.key();
+    let listing_config = &mut ctx.accounts.listing_config;
+    if listing_config.multi_unit {
+        if listing_config
+            .unit_bids
+            .iter()
+            .any(|bid| bid.buyer_trade_state == trade_state_key)
+        {
+            if !listing_config.allow_high_bid_cancel {
+                return err!(AuctioneerError::CannotCancelHighestBid);
+            }
+            listing_config.remove_unit_bid(&trade_state_key);
+        }
+    } else if !listing_config.allow_high_bid_cancel
+        && (trade_state_key == listing_config.highest_bid.buyer_trade_state)
     {
         return err!(AuctioneerError::CannotCancelHighestBid);
     }
 
+    // A multi-unit listing and its bids are each placed for a single unit.
+    let trade_state_token_size = listing_config.bid_token_size(token_size);
+
     let cpi_program = ctx.accounts.auction_house_program.to_account_info();
     let cpi_accounts = AHCancel {
         wallet: ctx.accounts.wallet.to_account_info(),
@@ -121,7 +136,7 @@ pub fn auctioneer_cancel<'info>(
 
     let cancel_data = mpl_auction_house::instruction::AuctioneerCancel {
         buyer_price,
-        token_size,
+        token_size: trade_state_token_size,
     };
 
     let ix = solana_program::instruction::Instruction {
@@ -1,2 +1,4 @@
 pub const LISTING_CONFIG: &str = "listing_config";
 pub const AUCTIONEER_BUYER_PRICE: u64 = u64::MAX;
+pub const BID_HISTORY: &str = "bid_history";
+pub const BID_COOLDOWN: &str = "bid_cooldown";
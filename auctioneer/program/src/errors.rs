@@ -41,4 +41,68 @@ pub enum AuctioneerError {
     // 6009
     #[msg("The highest bidder is not allowed to cancel")]
     CannotCancelHighestBid,
+
+    // 6010
+    #[msg("The bid history capacity must be between 1 and 100")]
+    InvalidBidHistoryCapacity,
+
+    // 6011
+    #[msg("The bid history account does not belong to this listing")]
+    BidHistoryMismatch,
+
+    // 6012
+    #[msg("The listing requires its bid history account to be passed in")]
+    MissingBidHistory,
+
+    // 6013
+    #[msg("The listing must be closed before closing its bid history")]
+    ListingNotClosed,
+
+    // 6014
+    #[msg("The maximum end time must be after the end time")]
+    InvalidMaxEndTime,
+
+    // 6015
+    #[msg("The listing requires the bidder's cooldown account to be passed in")]
+    MissingBidCooldown,
+
+    // 6016
+    #[msg("The bid cooldown account does not match the listing and bidder")]
+    BidCooldownMismatch,
+
+    // 6017
+    #[msg("The bidder must wait for the bid cooldown to elapse before bidding again")]
+    BidCooldownActive,
+
+    // 6018
+    #[msg("The buyer, the seller or the Auction House authority must sign")]
+    NoValidSignerPresent,
+
+    // 6019
+    #[msg("A multi-unit auction must sell between 2 and the maximum number of units")]
+    InvalidAuctionUnits,
+
+    // 6020
+    #[msg("Multi-unit listings must be settled with the multi-unit sale instruction")]
+    MultiUnitListing,
+
+    // 6021
+    #[msg("The instruction is only valid for multi-unit listings")]
+    NotMultiUnitListing,
+
+    // 6022
+    #[msg("The buyer trade state is not one of the winning bids")]
+    NotWinningBid,
+
+    // 6023
+    #[msg("The Listing Config is not a V0 Listing Config")]
+    ListingConfigNotV0,
+
+    // 6024
+    #[msg("The token account holds fewer tokens than the auction sells")]
+    NotEnoughAuctionUnits,
+
+    // 6025
+    #[msg("A winner who bid above the clearing price must sign to buy at the clearing price")]
+    WinnerMustSign,
 }
@@ -0,0 +1,408 @@
+use anchor_lang::{prelude::*, AnchorDeserialize, InstructionData};
+use anchor_spl::{associated_token::AssociatedToken, token::Token};
+
+use mpl_auction_house::{
+    self,
+    constants::{AUCTIONEER, FEE_PAYER, PREFIX, SIGNER, TREASURY},
+    cpi::accounts::{
+        AuctioneerBuy as AHBuy, AuctioneerCancel as AHCancel,
+        AuctioneerExecuteSale as AHExecuteSale,
+    },
+    pda::find_trade_state_address,
+    program::AuctionHouse as AuctionHouseProgram,
+    AuctionHouse,
+};
+
+use crate::{constants::*, errors::*, sell::config::*, utils::*};
+
+use solana_program::program::invoke_signed;
+
+/// The accounts passed in `remaining_accounts` for each winning bid being settled: the buyer, the
+/// buyer trade state, the buyer escrow payment account, the buyer receipt token account and the
+/// buyer trade state for a bid at the clearing price.
+pub const WINNER_ACCOUNTS_LEN: usize = 5;
+
+/// Accounts for the [`auctioneer_execute_multi_unit_sale` handler](fn.auctioneer_execute_multi_unit_sale.html).
+#[derive(Accounts)]
+#[instruction(free_trade_state_bump: u8, program_as_signer_bump: u8, auctioneer_authority_bump: u8, token_size: u64)]
+pub struct AuctioneerExecuteMultiUnitSale<'info> {
+    /// Auction House Program
+    pub auction_house_program: Program<'info, AuctionHouseProgram>,
+
+    // Accounts used for Auctioneer
+    /// The Listing Config used for listing settings
+    #[account(
+        mut,
+        seeds=[
+            LISTING_CONFIG.as_bytes(),
+            seller.key().as_ref(),
+            auction_house.key().as_ref(),
+            token_account.key().as_ref(),
+            auction_house.treasury_mint.as_ref(),
+            token_mint.key().as_ref(),
+            &token_size.to_le_bytes()
+        ],
+        bump=listing_config.bump,
+    )]
+    pub listing_config: Box<Account<'info, ListingConfig>>,
+
+    // Accounts passed into Auction House CPI call
+    /// Seller user wallet account, paying back the part of each winning bid above the clearing price.
+    #[account(mut)]
+    pub seller: Signer<'info>,
+
+    /// CHECK: Verified through CPI
+    // cannot mark these as real Accounts or else we blow stack size limit
+    ///Token account where the SPL token is stored.
+    #[account(mut)]
+    pub token_account: UncheckedAccount<'info>,
+
+    /// CHECK: Verified through CPI
+    /// Token mint account for the SPL token.
+    pub token_mint: UncheckedAccount<'info>,
+
+    /// CHECK: Verified through CPI
+    /// Metaplex metadata account decorating SPL mint account.
+    pub metadata: UncheckedAccount<'info>,
+
+    /// CHECK: Verified through CPI
+    // cannot mark these as real Accounts or else we blow stack size limit
+    /// Auction House treasury mint account.
+    pub treasury_mint: UncheckedAccount<'info>,
+
+    /// CHECK: Verified through CPI
+    /// Seller SOL or SPL account to receive payment at.
+    #[account(mut)]
+    pub seller_payment_receipt_account: UncheckedAccount<'info>,
+
+    /// CHECK: Verified through CPI
+    /// Auction House instance authority.
+    pub authority: UncheckedAccount<'info>,
+
+    /// Auction House instance PDA account.
+    #[account(seeds=[PREFIX.as_bytes(), auction_house.creator.as_ref(), auction_house.treasury_mint.as_ref()], seeds::program=auction_house_program, bump=auction_house.bump, has_one=treasury_mint, has_one=auction_house_treasury, has_one=auction_house_fee_account)]
+    pub auction_house: Box<Account<'info, AuctionHouse>>,
+
+    /// CHECK: Not dangerous. Account seeds checked in constraint.
+    /// Auction House instance fee account.
+    #[account(mut, seeds=[PREFIX.as_bytes(), auction_house.key().as_ref(), FEE_PAYER.as_bytes()], seeds::program=auction_house_program, bump=auction_house.fee_payer_bump)]
+    pub auction_house_fee_account: UncheckedAccount<'info>,
+
+    /// CHECK: Not dangerous. Account seeds checked in constraint.
+    /// Auction House instance treasury account.
+    #[account(mut, seeds=[PREFIX.as_bytes(), auction_house.key().as_ref(), TREASURY.as_bytes()], seeds::program=auction_house_program, bump=auction_house.treasury_bump)]
+    pub auction_house_treasury: UncheckedAccount<'info>,
+
+    /// CHECK: Not dangerous. Account seeds checked in constraint.
+    /// Seller trade state PDA account encoding the sell order, which is placed for a single unit.
+    #[account(mut, seeds=[PREFIX.as_bytes(), seller.key().as_ref(), auction_house.key().as_ref(), token_account.key().as_ref(), auction_house.treasury_mint.as_ref(), token_mint.key().as_ref(), &u64::MAX.to_le_bytes(), &1u64.to_le_bytes()], seeds::program=auction_house_program, bump=seller_trade_state.to_account_info().data.borrow()[0])]
+    pub seller_trade_state: UncheckedAccount<'info>,
+
+    /// CHECK: Not dangerous. Account seeds checked in constraint.
+    /// Free seller trade state PDA account encoding a free sell order.
+    #[account(mut, seeds=[PREFIX.as_bytes(), seller.key().as_ref(), auction_house.key().as_ref(), token_account.key().as_ref(), auction_house.treasury_mint.as_ref(), token_mint.key().as_ref(), &0u64.to_le_bytes(), &1u64.to_le_bytes()], seeds::program=auction_house_program, bump=free_trade_state_bump)]
+    pub free_trade_state: UncheckedAccount<'info>,
+
+    /// CHECK: Verified through CPI
+    /// The auctioneer program PDA running this auction.
+    #[account(seeds = [AUCTIONEER.as_bytes(), auction_house.key().as_ref()], bump=auctioneer_authority_bump)]
+    pub auctioneer_authority: UncheckedAccount<'info>,
+
+    /// CHECK: Not dangerous. Account seeds checked in constraint.
+    /// The auctioneer PDA owned by Auction House storing scopes.
+    #[account(
+        seeds = [
+            AUCTIONEER.as_bytes(),
+            auction_house.key().as_ref(),
+            auctioneer_authority.key().as_ref()
+            ],
+        seeds::program=auction_house_program,
+        bump = ah_auctioneer_pda.bump,
+    )]
+    pub ah_auctioneer_pda: Account<'info, mpl_auction_house::Auctioneer>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    pub ata_program: Program<'info, AssociatedToken>,
+
+    /// CHECK: Not dangerous. Account seeds checked in constraint.
+    #[account(seeds=[PREFIX.as_bytes(), SIGNER.as_bytes()], seeds::program=auction_house_program, bump=program_as_signer_bump)]
+    pub program_as_signer: UncheckedAccount<'info>,
+
+    pub rent: Sysvar<'info, Rent>,
+}
+
+/// Settle winning bids of a multi-unit auction that is over, selling one unit to each winner at the clearing price, which is the lowest winning bid. A winner who bid above the clearing price has their bid canceled and placed again at the clearing price, so Auction House sells their unit at the clearing price and the rest of their bid stays in their escrow; such a winner must sign along with the seller. Winners can be settled over several transactions; the accounts of `winner_count` winners are passed first in `remaining_accounts`, followed by the creator accounts. Once every winner is settled, the sell order for any units left without a bid is canceled and the Listing Config is closed.
+pub fn auctioneer_execute_multi_unit_sale<'info>(
+    ctx: Context<'_, '_, '_, 'info, AuctioneerExecuteMultiUnitSale<'info>>,
+    free_trade_state_bump: u8,
+    program_as_signer_bump: u8,
+    auctioneer_authority_bump: u8,
+    _token_size: u64,
+    winner_count: u8,
+) -> Result<()> {
+    assert_auction_over(&ctx.accounts.listing_config)?;
+    if !ctx.accounts.listing_config.multi_unit {
+        return err!(AuctioneerError::NotMultiUnitListing);
+    }
+
+    // Every winner pays the same price, fixed by the first settlement.
+    if ctx.accounts.listing_config.clearing_price == 0 {
+        if let Some(lowest_bid) = ctx.accounts.listing_config.unit_bids.last() {
+            ctx.accounts.listing_config.clearing_price = lowest_bid.amount;
+        }
+    }
+    let clearing_price = ctx.accounts.listing_config.clearing_price;
+
+    let winner_accounts_len = usize::from(winner_count) * WINNER_ACCOUNTS_LEN;
+    if ctx.remaining_accounts.len() < winner_accounts_len {
+        return err!(AuctioneerError::NotWinningBid);
+    }
+    let (winner_accounts, creator_accounts) = ctx.remaining_accounts.split_at(winner_accounts_len);
+
+    let auction_house = &ctx.accounts.auction_house;
+    let ah_key = auction_house.key();
+
+    let auctioneer_seeds = [
+        AUCTIONEER.as_bytes(),
+        ah_key.as_ref(),
+        &[auctioneer_authority_bump],
+    ];
+
+    for winner in winner_accounts.chunks(WINNER_ACCOUNTS_LEN) {
+        let buyer = &winner[0];
+        let buyer_trade_state = &winner[1];
+        let escrow_payment_account = &winner[2];
+        let buyer_receipt_token_account = &winner[3];
+        let clearing_trade_state = &winner[4];
+
+        let bid = ctx
+            .accounts
+            .listing_config
+            .remove_unit_bid(&buyer_trade_state.key())
+            .ok_or(AuctioneerError::NotWinningBid)?;
+
+        let (escrow_payment_key, escrow_payment_bump) = Pubkey::find_program_address(
+            &[PREFIX.as_bytes(), ah_key.as_ref(), buyer.key.as_ref()],
+            &mpl_auction_house::id(),
+        );
+        if escrow_payment_account.key() != escrow_payment_key {
+            return err!(AuctioneerError::NotWinningBid);
+        }
+
+        // A bid above the clearing price is canceled and placed again at the clearing price, the
+        // rest of it stays in the buyer's escrow. Only the buyer can bid with Auction House.
+        let buyer_trade_state = if bid.amount > clearing_price {
+            if !buyer.is_signer {
+                return err!(AuctioneerError::WinnerMustSign);
+            }
+
+            let (clearing_trade_state_key, clearing_trade_state_bump) = find_trade_state_address(
+                buyer.key,
+                &ah_key,
+                ctx.accounts.token_account.key,
+                ctx.accounts.treasury_mint.key,
+                ctx.accounts.token_mint.key,
+                clearing_price,
+                1,
+            );
+            if clearing_trade_state.key() != clearing_trade_state_key {
+                return err!(AuctioneerError::NotWinningBid);
+            }
+
+            let cpi_program = ctx.accounts.auction_house_program.to_account_info();
+            let cpi_accounts = AHCancel {
+                wallet: buyer.to_account_info(),
+                token_account: ctx.accounts.token_account.to_account_info(),
+                token_mint: ctx.accounts.token_mint.to_account_info(),
+                auction_house: ctx.accounts.auction_house.to_account_info(),
+                auction_house_fee_account: ctx.accounts.auction_house_fee_account.to_account_info(),
+                trade_state: buyer_trade_state.to_account_info(),
+                authority: ctx.accounts.authority.to_account_info(),
+                auctioneer_authority: ctx.accounts.auctioneer_authority.to_account_info(),
+                ah_auctioneer_pda: ctx.accounts.ah_auctioneer_pda.to_account_info(),
+                token_program: ctx.accounts.token_program.to_account_info(),
+            };
+
+            let cancel_data = mpl_auction_house::instruction::AuctioneerCancel {
+                buyer_price: bid.amount,
+                token_size: 1,
+            };
+
+            let ix = solana_program::instruction::Instruction {
+                program_id: cpi_program.key(),
+                accounts: cpi_accounts
+                    .to_account_metas(None)
+                    .into_iter()
+                    .zip(cpi_accounts.to_account_infos())
+                    .map(|mut pair| {
+                        pair.0.is_signer = pair.1.is_signer;
+                        if pair.0.pubkey == ctx.accounts.auctioneer_authority.key() {
+                            pair.0.is_signer = true;
+                        }
+                        pair.0
+                    })
+                    .collect(),
+                data: cancel_data.data(),
+            };
+
+            invoke_signed(&ix, &cpi_accounts.to_account_infos(), &[&auctioneer_seeds])?;
+
+            let cpi_accounts = AHBuy {
+                wallet: buyer.to_account_info(),
+                payment_account: buyer.to_account_info(),
+                transfer_authority: buyer.to_account_info(),
+                treasury_mint: ctx.accounts.treasury_mint.to_account_info(),
+                token_account: ctx.accounts.token_account.to_account_info(),
+                metadata: ctx.accounts.metadata.to_account_info(),
+                escrow_payment_account: escrow_payment_account.to_account_info(),
+                auction_house: ctx.accounts.auction_house.to_account_info(),
+                auction_house_fee_account: ctx.accounts.auction_house_fee_account.to_account_info(),
+                buyer_trade_state: clearing_trade_state.to_account_info(),
+                authority: ctx.accounts.authority.to_account_info(),
+                auctioneer_authority: ctx.accounts.auctioneer_authority.to_account_info(),
+                ah_auctioneer_pda: ctx.accounts.ah_auctioneer_pda.to_account_info(),
+                token_program: ctx.accounts.token_program.to_account_info(),
+                system_program: ctx.accounts.system_program.to_account_info(),
+                rent: ctx.accounts.rent.to_account_info(),
+            };
+
+            let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
+            mpl_auction_house::cpi::auctioneer_buy(
+                cpi_ctx.with_signer(&[&auctioneer_seeds]),
+                clearing_trade_state_bump,
+                escrow_payment_bump,
+                clearing_price,
+                1,
+            )?;
+
+            clearing_trade_state
+        } else {
+            buyer_trade_state
+        };
+
+        let cpi_program = ctx.accounts.auction_house_program.to_account_info();
+        let cpi_accounts = AHExecuteSale {
+            buyer: buyer.to_account_info(),
+            seller: ctx.accounts.seller.to_account_info(),
+            token_account: ctx.accounts.token_account.to_account_info(),
+            token_mint: ctx.accounts.token_mint.to_account_info(),
+            metadata: ctx.accounts.metadata.to_account_info(),
+            treasury_mint: ctx.accounts.treasury_mint.to_account_info(),
+            escrow_payment_account: escrow_payment_account.to_account_info(),
+            seller_payment_receipt_account: ctx
+                .accounts
+                .seller_payment_receipt_account
+                .to_account_info(),
+            buyer_receipt_token_account: buyer_receipt_token_account.to_account_info(),
+            auction_house: ctx.accounts.auction_house.to_account_info(),
+            auction_house_fee_account: ctx.accounts.auction_house_fee_account.to_account_info(),
+            auction_house_treasury: ctx.accounts.auction_house_treasury.to_account_info(),
+            buyer_trade_state: buyer_trade_state.to_account_info(),
+            seller_trade_state: ctx.accounts.seller_trade_state.to_account_info(),
+            free_trade_state: ctx.accounts.free_trade_state.to_account_info(),
+            authority: ctx.accounts.authority.to_account_info(),
+            auctioneer_authority: ctx.accounts.auctioneer_authority.to_account_info(),
+            ah_auctioneer_pda: ctx.accounts.ah_auctioneer_pda.to_account_info(),
+            token_program: ctx.accounts.token_program.to_account_info(),
+            system_program: ctx.accounts.system_program.to_account_info(),
+            ata_program: ctx.accounts.ata_program.to_account_info(),
+            program_as_signer: ctx.accounts.program_as_signer.to_account_info(),
+            rent: ctx.accounts.rent.to_account_info(),
+        };
+
+        // Every winner buys a single unit at the clearing price.
+        let execute_sale_data = mpl_auction_house::instruction::AuctioneerExecuteSale {
+            escrow_payment_bump,
+            _free_trade_state_bump: free_trade_state_bump,
+            program_as_signer_bump,
+            buyer_price: clearing_price,
+            token_size: 1,
+        };
+
+        let mut cpi_account_metas: Vec<AccountMeta> = cpi_accounts
+            .to_account_metas(None)
+            .into_iter()
+            .zip(cpi_accounts.to_account_infos())
+            .map(|mut pair| {
+                pair.0.is_signer = pair.1.is_signer;
+                if pair.0.pubkey == ctx.accounts.auctioneer_authority.key() {
+                    pair.0.is_signer = true;
+                }
+                pair.0
+            })
+            .collect();
+
+        cpi_account_metas.append(&mut creator_accounts.to_vec().to_account_metas(None));
+
+        let mut cpi_account_infos: Vec<AccountInfo> = cpi_accounts.to_account_infos();
+        cpi_account_infos.append(&mut creator_accounts.to_vec());
+
+        let ix = solana_program::instruction::Instruction {
+            program_id: cpi_program.key(),
+            accounts: cpi_account_metas,
+            data: execute_sale_data.data(),
+        };
+
+        invoke_signed(&ix, &cpi_account_infos, &[&auctioneer_seeds])?;
+    }
+
+    if ctx.accounts.listing_config.unit_bids.is_empty() {
+        // Cancel the sell order for the units left without a winning bid.
+        if ctx.accounts.seller_trade_state.lamports() > 0 {
+            let cpi_program = ctx.accounts.auction_house_program.to_account_info();
+            let cpi_accounts = AHCancel {
+                wallet: ctx.accounts.seller.to_account_info(),
+                token_account: ctx.accounts.token_account.to_account_info(),
+                token_mint: ctx.accounts.token_mint.to_account_info(),
+                auction_house: ctx.accounts.auction_house.to_account_info(),
+                auction_house_fee_account: ctx.accounts.auction_house_fee_account.to_account_info(),
+                trade_state: ctx.accounts.seller_trade_state.to_account_info(),
+                authority: ctx.accounts.authority.to_account_info(),
+                auctioneer_authority: ctx.accounts.auctioneer_authority.to_account_info(),
+                ah_auctioneer_pda: ctx.accounts.ah_auctioneer_pda.to_account_info(),
+                token_program: ctx.accounts.token_program.to_account_info(),
+            };
+
+            let cancel_data = mpl_auction_house::instruction::AuctioneerCancel {
+                buyer_price: AUCTIONEER_BUYER_PRICE,
+                token_size: 1,
+            };
+
+            let ix = solana_program::instruction::Instruction {
+                program_id: cpi_program.key(),
+                accounts: cpi_accounts
+                    .to_account_metas(None)
+                    .into_iter()
+                    .zip(cpi_accounts.to_account_infos())
+                    .map(|mut pair| {
+                        pair.0.is_signer = pair.1.is_signer;
+                        if pair.0.pubkey == ctx.accounts.auctioneer_authority.key() {
+                            pair.0.is_signer = true;
+                        }
+                        pair.0
+                    })
+                    .collect(),
+                data: cancel_data.data(),
+            };
+
+            invoke_signed(&ix, &cpi_accounts.to_account_infos(), &[&auctioneer_seeds])?;
+        }
+
+        // Close the Listing Config account once every winner has been settled.
+        let listing_config = &ctx.accounts.listing_config.to_account_info();
+        let seller = &ctx.accounts.seller.to_account_info();
+
+        let listing_config_lamports = listing_config.lamports();
+        **seller.lamports.borrow_mut() = seller
+            .lamports()
+            .checked_add(listing_config_lamports)
+            .unwrap();
+        **listing_config.lamports.borrow_mut() = 0;
+
+        let mut source_data = listing_config.data.borrow_mut();
+        source_data.fill(0);
+    }
+
+    Ok(())
+}
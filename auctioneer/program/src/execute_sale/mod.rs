@@ -153,6 +153,7 @@ pub fn auctioneer_execute_sale<'info>(
     token_size: u64,
 ) -> Result<()> {
     assert_auction_over(&ctx.accounts.listing_config)?;
+    assert_single_unit(&ctx.accounts.listing_config)?;
     assert_highest_bidder(
         &ctx.accounts.listing_config,
         ctx.accounts.buyer_trade_state.key(),
@@ -1,17 +1,26 @@
 #![allow(clippy::result_large_err)]
 pub mod authorize;
 pub mod bid;
+pub mod bid_cooldown;
+pub mod bid_history;
 pub mod cancel;
 pub mod constants;
 pub mod deposit;
 pub mod errors;
+pub mod execute_multi_unit_sale;
 pub mod execute_sale;
+pub mod migrate;
 pub mod pda;
 pub mod sell;
+pub mod settle;
 pub mod utils;
 pub mod withdraw;
 
-use crate::{authorize::*, bid::*, cancel::*, deposit::*, execute_sale::*, sell::*, withdraw::*};
+use crate::sell::config::ListingConfigV1Args;
+use crate::{
+    authorize::*, bid::*, bid_cooldown::*, bid_history::*, cancel::*, deposit::*,
+    execute_multi_unit_sale::*, execute_sale::*, migrate::*, sell::*, settle::*, withdraw::*,
+};
 
 use anchor_lang::prelude::*;
 
@@ -82,6 +91,26 @@ pub mod auctioneer {
         )
     }
 
+    /// Settle winning bids of a multi-unit auction, selling one unit to each winner at the clearing price.
+    #[inline(never)]
+    pub fn execute_multi_unit_sale<'info>(
+        ctx: Context<'_, '_, '_, 'info, AuctioneerExecuteMultiUnitSale<'info>>,
+        free_trade_state_bump: u8,
+        program_as_signer_bump: u8,
+        auctioneer_authority_bump: u8,
+        token_size: u64,
+        winner_count: u8,
+    ) -> Result<()> {
+        auctioneer_execute_multi_unit_sale(
+            ctx,
+            free_trade_state_bump,
+            program_as_signer_bump,
+            auctioneer_authority_bump,
+            token_size,
+            winner_count,
+        )
+    }
+
     /// Create a sell bid by creating a `seller_trade_state` account and approving the program as the token delegate.
     pub fn sell<'info>(
         ctx: Context<'_, '_, '_, 'info, AuctioneerSell<'info>>,
@@ -112,6 +141,42 @@ pub mod auctioneer {
             time_ext_period,
             time_ext_delta,
             allow_high_bid_cancel,
+            ListingConfigV1Args::default(),
+        )
+    }
+
+    /// Create a sell bid like `sell`, also setting the listing settings added in V1.
+    pub fn sell_v1<'info>(
+        ctx: Context<'_, '_, '_, 'info, AuctioneerSell<'info>>,
+        trade_state_bump: u8,
+        free_trade_state_bump: u8,
+        program_as_signer_bump: u8,
+        auctioneer_authority_bump: u8,
+        token_size: u64,
+        start_time: UnixTimestamp,
+        end_time: UnixTimestamp,
+        reserve_price: Option<u64>,
+        min_bid_increment: Option<u64>,
+        time_ext_period: Option<u32>,
+        time_ext_delta: Option<u32>,
+        allow_high_bid_cancel: Option<bool>,
+        listing_config_args: ListingConfigV1Args,
+    ) -> Result<()> {
+        auctioneer_sell(
+            ctx,
+            trade_state_bump,
+            free_trade_state_bump,
+            program_as_signer_bump,
+            auctioneer_authority_bump,
+            token_size,
+            start_time,
+            end_time,
+            reserve_price,
+            min_bid_increment,
+            time_ext_period,
+            time_ext_delta,
+            allow_high_bid_cancel,
+            listing_config_args,
         )
     }
 
@@ -133,4 +198,54 @@ pub mod auctioneer {
             token_size,
         )
     }
+
+    /// Settle an auction that is over, whoever calls it, executing the sale to the highest bidder if the reserve price is met or refunding the highest bid and canceling the listing otherwise.
+    #[inline(never)]
+    pub fn settle<'info>(
+        ctx: Context<'_, '_, '_, 'info, AuctioneerSettle<'info>>,
+        escrow_payment_bump: u8,
+        free_trade_state_bump: u8,
+        program_as_signer_bump: u8,
+        auctioneer_authority_bump: u8,
+        token_size: u64,
+    ) -> Result<()> {
+        auctioneer_settle(
+            ctx,
+            escrow_payment_bump,
+            free_trade_state_bump,
+            program_as_signer_bump,
+            auctioneer_authority_bump,
+            token_size,
+        )
+    }
+
+    /// Create a bid history account recording the last `capacity` bids placed on a listing.
+    pub fn create_bid_history<'info>(
+        ctx: Context<'_, '_, '_, 'info, CreateBidHistory<'info>>,
+        token_size: u64,
+        capacity: u8,
+    ) -> Result<()> {
+        bid_history::create_bid_history(ctx, token_size, capacity)
+    }
+
+    /// Close the bid history account of a listing that has been sold or canceled, returning the rent to the seller.
+    pub fn close_bid_history<'info>(
+        ctx: Context<'_, '_, '_, 'info, CloseBidHistory<'info>>,
+    ) -> Result<()> {
+        bid_history::close_bid_history(ctx)
+    }
+
+    /// Close a bidder's cooldown account on a listing that has been sold or canceled, returning the rent to the bidder.
+    pub fn close_bid_cooldown<'info>(
+        ctx: Context<'_, '_, '_, 'info, CloseBidCooldown<'info>>,
+    ) -> Result<()> {
+        bid_cooldown::close_bid_cooldown(ctx)
+    }
+
+    /// Migrate a Listing Config created before versioning to the V1 layout.
+    pub fn migrate_listing_config<'info>(
+        ctx: Context<'_, '_, '_, 'info, MigrateListingConfig<'info>>,
+    ) -> Result<()> {
+        migrate::migrate_listing_config(ctx)
+    }
 }
@@ -0,0 +1,59 @@
+use crate::{errors::*, sell::config::*};
+
+use anchor_lang::{
+    prelude::*,
+    system_program::{transfer, Transfer},
+    AnchorDeserialize, Discriminator,
+};
+
+/// Accounts for the [`migrate_listing_config` handler](fn.migrate_listing_config.html).
+#[derive(Accounts)]
+pub struct MigrateListingConfig<'info> {
+    /// Pays for the space added to the Listing Config.
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// CHECK: Deserialized as a V0 Listing Config in the handler.
+    /// The V0 Listing Config to migrate.
+    #[account(mut, owner=crate::id())]
+    pub listing_config: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Migrate a Listing Config created before versioning to V1, reallocating it to the V1 size with the V1 settings disabled. V0 Listing Configs can't be deserialized by the other instructions until they are migrated.
+pub fn migrate_listing_config<'info>(
+    ctx: Context<'_, '_, '_, 'info, MigrateListingConfig<'info>>,
+) -> Result<()> {
+    let listing_config = ctx.accounts.listing_config.to_account_info();
+
+    let listing_config_v0 = {
+        let data = listing_config.try_borrow_data()?;
+        if data.len() != LISTING_CONFIG_V0_SIZE || data[..8] != ListingConfig::discriminator() {
+            return err!(AuctioneerError::ListingConfigNotV0);
+        }
+        ListingConfigV0::deserialize(&mut &data[8..])?
+    };
+
+    let rent_shortfall = Rent::get()?
+        .minimum_balance(LISTING_CONFIG_SIZE)
+        .saturating_sub(listing_config.lamports());
+    if rent_shortfall > 0 {
+        transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.payer.to_account_info(),
+                    to: listing_config.clone(),
+                },
+            ),
+            rent_shortfall,
+        )?;
+    }
+
+    listing_config.realloc(LISTING_CONFIG_SIZE, true)?;
+    ListingConfig::from(listing_config_v0)
+        .try_serialize(&mut *listing_config.try_borrow_mut_data()?)?;
+
+    Ok(())
+}
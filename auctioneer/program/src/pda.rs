@@ -28,3 +28,18 @@ pub fn find_listing_config_address(
 pub fn find_auctioneer_authority_seeds(auction_house: &Pubkey) -> (Pubkey, u8) {
     Pubkey::find_program_address(&[AUCTIONEER.as_bytes(), auction_house.as_ref()], &id())
 }
+
+pub fn find_bid_history_address(listing_config: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[BID_HISTORY.as_bytes(), listing_config.as_ref()], &id())
+}
+
+pub fn find_bid_cooldown_address(listing_config: &Pubkey, bidder: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            BID_COOLDOWN.as_bytes(),
+            listing_config.as_ref(),
+            bidder.as_ref(),
+        ],
+        &id(),
+    )
+}
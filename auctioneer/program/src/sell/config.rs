@@ -2,11 +2,38 @@ use anchor_lang::prelude::*;
 use solana_program::clock::UnixTimestamp;
 
 pub const BID_SIZE: usize = 8 + 1 + 32;
-pub const LISTING_CONFIG_SIZE: usize = 8 + 1 + 8 + 8 + BID_SIZE + 1 + 8 + 8 + 4 + 4 + 1;
+/// Size of the Listing Configs created before versioning, which must be migrated to V1.
+pub const LISTING_CONFIG_V0_SIZE: usize = 8 + 1 + 8 + 8 + BID_SIZE + 1 + 8 + 8 + 4 + 4 + 1;
+pub const LISTING_CONFIG_SIZE: usize =
+    8 + 1 + 8 + 8 + BID_SIZE + 1 + 8 + 8 + 4 + 4 + 1 + 1 + 8 + 4 + 1 + 8 + 8 + 4;
+/// The most units a multi-unit auction can sell, bounding the size of its winning bids list.
+pub const MAX_AUCTION_UNITS: u64 = 20;
+
+/// Space needed for a Listing Config selling `token_size` units.
+pub fn listing_config_size(multi_unit: bool, token_size: u64) -> usize {
+    if multi_unit {
+        LISTING_CONFIG_SIZE + (token_size as usize) * BID_SIZE
+    } else {
+        LISTING_CONFIG_SIZE
+    }
+}
 
 #[derive(AnchorDeserialize, AnchorSerialize, Clone)]
 pub enum ListingConfigVersion {
     V0,
+    V1,
+}
+
+/// Listing settings added in V1, passed to `sell_v1` so the `sell` instruction keeps its layout.
+#[derive(AnchorDeserialize, AnchorSerialize, Clone, Default)]
+pub struct ListingConfigV1Args {
+    /// Time extensions never push the end time past this, which must be after the end time.
+    /// `None` leaves the auction uncapped.
+    pub max_end_time: Option<UnixTimestamp>,
+    /// Seconds a bidder must wait between bids.
+    pub bid_cooldown: Option<u32>,
+    /// Sell each of the `token_size` units to a different bidder at a uniform price.
+    pub multi_unit: Option<bool>,
 }
 
 #[derive(AnchorDeserialize, AnchorSerialize, Clone)]
@@ -28,4 +55,94 @@ pub struct ListingConfig {
     pub time_ext_period: u32,
     pub time_ext_delta: u32,
     pub allow_high_bid_cancel: bool,
+    pub has_bid_history: bool,
+    pub max_end_time: UnixTimestamp,
+    pub bid_cooldown: u32,
+    pub multi_unit: bool,
+    pub units: u64,
+    pub clearing_price: u64,
+    /// The current winning bids of a multi-unit auction, highest first.
+    pub unit_bids: Vec<Bid>,
+}
+
+/// The Listing Config layout before the V1 fields were added.
+#[derive(AnchorDeserialize, AnchorSerialize, Clone)]
+pub struct ListingConfigV0 {
+    pub version: ListingConfigVersion,
+    pub start_time: UnixTimestamp,
+    pub end_time: UnixTimestamp,
+    pub highest_bid: Bid,
+    pub bump: u8,
+    pub reserve_price: u64,
+    pub min_bid_increment: u64,
+    pub time_ext_period: u32,
+    pub time_ext_delta: u32,
+    pub allow_high_bid_cancel: bool,
+}
+
+impl From<ListingConfigV0> for ListingConfig {
+    /// Upgrade a V0 Listing Config, leaving the V1 settings disabled.
+    fn from(listing_config: ListingConfigV0) -> Self {
+        ListingConfig {
+            version: ListingConfigVersion::V1,
+            start_time: listing_config.start_time,
+            end_time: listing_config.end_time,
+            highest_bid: listing_config.highest_bid,
+            bump: listing_config.bump,
+            reserve_price: listing_config.reserve_price,
+            min_bid_increment: listing_config.min_bid_increment,
+            time_ext_period: listing_config.time_ext_period,
+            time_ext_delta: listing_config.time_ext_delta,
+            allow_high_bid_cancel: listing_config.allow_high_bid_cancel,
+            has_bid_history: false,
+            max_end_time: 0,
+            bid_cooldown: 0,
+            multi_unit: false,
+            units: 0,
+            clearing_price: 0,
+            unit_bids: Vec::new(),
+        }
+    }
+}
+
+impl ListingConfig {
+    /// The number of tokens a single bid is placed for.
+    pub fn bid_token_size(&self, token_size: u64) -> u64 {
+        if self.multi_unit {
+            1
+        } else {
+            token_size
+        }
+    }
+
+    /// Record a multi-unit bid in price order, dropping the lowest bid once every unit is taken.
+    /// Bids at the same price keep their time priority.
+    pub fn insert_unit_bid(&mut self, bid: Bid) {
+        let position = self
+            .unit_bids
+            .iter()
+            .position(|b| bid.amount > b.amount)
+            .unwrap_or(self.unit_bids.len());
+        self.unit_bids.insert(position, bid);
+        self.unit_bids.truncate(self.units as usize);
+        self.highest_bid = self.unit_bids[0].clone();
+    }
+
+    /// Remove a multi-unit bid, returning it if it was one of the winning bids.
+    pub fn remove_unit_bid(&mut self, buyer_trade_state: &Pubkey) -> Option<Bid> {
+        let position = self
+            .unit_bids
+            .iter()
+            .position(|b| b.buyer_trade_state == *buyer_trade_state)?;
+        let bid = self.unit_bids.remove(position);
+        self.highest_bid = match self.unit_bids.first() {
+            Some(highest_bid) => highest_bid.clone(),
+            None => Bid {
+                version: ListingConfigVersion::V0,
+                amount: 0,
+                buyer_trade_state: Pubkey::default(),
+            },
+        };
+        Some(bid)
+    }
 }
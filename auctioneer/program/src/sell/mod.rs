@@ -2,8 +2,12 @@ pub mod config;
 
 use crate::{constants::*, errors::*, sell::config::*};
 
-use anchor_lang::{prelude::*, AnchorDeserialize, InstructionData};
-use anchor_spl::token::{Token, TokenAccount};
+use anchor_lang::{
+    prelude::*,
+    system_program::{transfer, Transfer},
+    AnchorDeserialize, InstructionData,
+};
+use anchor_spl::token::{approve, Approve, Token, TokenAccount};
 
 use mpl_auction_house::{
     self,
@@ -68,14 +72,14 @@ pub struct AuctioneerSell<'info> {
     #[account(mut, seeds=[PREFIX.as_bytes(), auction_house.key().as_ref(), FEE_PAYER.as_bytes()], seeds::program=auction_house_program, bump=auction_house.fee_payer_bump)]
     pub auction_house_fee_account: UncheckedAccount<'info>,
 
-    /// CHECK: Not dangerous. Account seeds checked in constraint.
-    /// Seller trade state PDA account encoding the sell order.
-    #[account(mut, seeds=[PREFIX.as_bytes(), wallet.key().as_ref(), auction_house.key().as_ref(), token_account.key().as_ref(), auction_house.treasury_mint.as_ref(), token_account.mint.as_ref(), &u64::MAX.to_le_bytes(), &token_size.to_le_bytes()], seeds::program=auction_house_program, bump=trade_state_bump)]
+    /// CHECK: Verified through CPI
+    /// Seller trade state PDA account encoding the sell order, placed for a single unit by multi-unit listings.
+    #[account(mut)]
     pub seller_trade_state: UncheckedAccount<'info>,
 
-    /// CHECK: Not dangerous. Account seeds checked in constraint.
+    /// CHECK: Verified through CPI
     /// Free seller trade state PDA account encoding a free sell order.
-    #[account(mut, seeds=[PREFIX.as_bytes(), wallet.key().as_ref(), auction_house.key().as_ref(), token_account.key().as_ref(), auction_house.treasury_mint.as_ref(), token_account.mint.as_ref(), &0u64.to_le_bytes(), &token_size.to_le_bytes()], seeds::program=auction_house_program, bump=free_trade_state_bump)]
+    #[account(mut)]
     pub free_seller_trade_state: UncheckedAccount<'info>,
 
     /// CHECK: Verified through CPI
@@ -119,8 +123,50 @@ pub fn auctioneer_sell<'info>(
     time_ext_period: Option<u32>,
     time_ext_delta: Option<u32>,
     allow_high_bid_cancel: Option<bool>,
+    listing_config_args: ListingConfigV1Args,
 ) -> Result<()> {
-    ctx.accounts.listing_config.version = ListingConfigVersion::V0;
+    let ListingConfigV1Args {
+        max_end_time,
+        bid_cooldown,
+        multi_unit,
+    } = listing_config_args;
+
+    // Only `None` leaves the auction uncapped, a cap must fall after the listing's end time.
+    if let Some(max_end_time) = max_end_time {
+        if max_end_time <= 0 || max_end_time <= end_time {
+            return err!(AuctioneerError::InvalidMaxEndTime);
+        }
+    }
+
+    let multi_unit = multi_unit.unwrap_or(false);
+    if multi_unit && (token_size < 2 || token_size > MAX_AUCTION_UNITS) {
+        return err!(AuctioneerError::InvalidAuctionUnits);
+    }
+    if multi_unit && ctx.accounts.token_account.amount < token_size {
+        return err!(AuctioneerError::NotEnoughAuctionUnits);
+    }
+
+    // Make room for the winning bids of a multi-unit auction.
+    if multi_unit {
+        let listing_config = ctx.accounts.listing_config.to_account_info();
+        let space = listing_config_size(multi_unit, token_size);
+        let rent_shortfall = Rent::get()?
+            .minimum_balance(space)
+            .saturating_sub(listing_config.lamports());
+        transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.wallet.to_account_info(),
+                    to: listing_config.clone(),
+                },
+            ),
+            rent_shortfall,
+        )?;
+        listing_config.realloc(space, false)?;
+    }
+
+    ctx.accounts.listing_config.version = ListingConfigVersion::V1;
     ctx.accounts.listing_config.highest_bid.version = ListingConfigVersion::V0;
     ctx.accounts.listing_config.start_time = start_time;
     ctx.accounts.listing_config.end_time = end_time;
@@ -129,6 +175,13 @@ pub fn auctioneer_sell<'info>(
     ctx.accounts.listing_config.time_ext_period = time_ext_period.unwrap_or(0);
     ctx.accounts.listing_config.time_ext_delta = time_ext_delta.unwrap_or(0);
     ctx.accounts.listing_config.allow_high_bid_cancel = allow_high_bid_cancel.unwrap_or(false);
+    ctx.accounts.listing_config.has_bid_history = false;
+    ctx.accounts.listing_config.max_end_time = max_end_time.unwrap_or(0);
+    ctx.accounts.listing_config.bid_cooldown = bid_cooldown.unwrap_or(0);
+    ctx.accounts.listing_config.multi_unit = multi_unit;
+    ctx.accounts.listing_config.units = token_size;
+    ctx.accounts.listing_config.clearing_price = 0;
+    ctx.accounts.listing_config.unit_bids = Vec::new();
     ctx.accounts.listing_config.bump = *ctx
         .bumps
         .get("listing_config")
@@ -152,11 +205,13 @@ pub fn auctioneer_sell<'info>(
         rent: ctx.accounts.rent.to_account_info(),
     };
 
+    // A multi-unit listing is placed with Auction House for a single unit, so that each unit can
+    // be sold to a different bidder, and the program is approved as the delegate of every unit below.
     let sell_data = mpl_auction_house::instruction::AuctioneerSell {
         trade_state_bump,
         free_trade_state_bump,
         program_as_signer_bump,
-        token_size,
+        token_size: ctx.accounts.listing_config.bid_token_size(token_size),
     };
 
     let ix = solana_program::instruction::Instruction {
@@ -189,5 +244,19 @@ pub fn auctioneer_sell<'info>(
 
     invoke_signed(&ix, &cpi_accounts.to_account_infos(), &[&auctioneer_seeds])?;
 
+    if multi_unit {
+        approve(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Approve {
+                    to: ctx.accounts.token_account.to_account_info(),
+                    delegate: ctx.accounts.program_as_signer.to_account_info(),
+                    authority: ctx.accounts.wallet.to_account_info(),
+                },
+            ),
+            token_size,
+        )?;
+    }
+
     Ok(())
 }
@@ -0,0 +1,405 @@
+use anchor_lang::{prelude::*, AnchorDeserialize, InstructionData};
+use anchor_spl::{associated_token::AssociatedToken, token::Token};
+
+use mpl_auction_house::{
+    self,
+    constants::{AUCTIONEER, FEE_PAYER, PREFIX, SIGNER, TREASURY},
+    cpi::accounts::{
+        AuctioneerCancel as AHCancel, AuctioneerExecuteSale as AHExecuteSale,
+        AuctioneerWithdraw as AHWithdraw,
+    },
+    program::AuctionHouse as AuctionHouseProgram,
+    AuctionHouse,
+};
+
+use crate::{constants::*, errors::*, sell::config::*, utils::*};
+
+use solana_program::program::invoke_signed;
+
+/// Accounts for the [`auctioneer_settle` handler](fn.auctioneer_settle.html).
+#[derive(Accounts)]
+#[instruction(escrow_payment_bump: u8, free_trade_state_bump: u8, program_as_signer_bump: u8, auctioneer_authority_bump: u8, token_size: u64)]
+pub struct AuctioneerSettle<'info> {
+    /// Auction House Program
+    pub auction_house_program: Program<'info, AuctionHouseProgram>,
+
+    /// Whoever settles the auction.
+    pub cranker: Signer<'info>,
+
+    // Accounts used for Auctioneer
+    /// The Listing Config used for listing settings
+    #[account(
+        mut,
+        seeds=[
+            LISTING_CONFIG.as_bytes(),
+            seller.key().as_ref(),
+            auction_house.key().as_ref(),
+            token_account.key().as_ref(),
+            auction_house.treasury_mint.as_ref(),
+            token_mint.key().as_ref(),
+            &token_size.to_le_bytes()
+        ],
+        bump=listing_config.bump,
+    )]
+    pub listing_config: Box<Account<'info, ListingConfig>>,
+
+    // Accounts passed into Auction House CPI call
+    /// CHECK: Verified through CPI
+    /// Buyer user wallet account.
+    #[account(mut)]
+    pub buyer: UncheckedAccount<'info>,
+
+    /// CHECK: Verified through CPI
+    /// Seller user wallet account.
+    #[account(mut)]
+    pub seller: UncheckedAccount<'info>,
+
+    /// CHECK: Verified through CPI
+    // cannot mark these as real Accounts or else we blow stack size limit
+    ///Token account where the SPL token is stored.
+    #[account(mut)]
+    pub token_account: UncheckedAccount<'info>,
+
+    /// CHECK: Verified through CPI
+    /// Token mint account for the SPL token.
+    pub token_mint: UncheckedAccount<'info>,
+
+    /// CHECK: Verified through CPI
+    /// Metaplex metadata account decorating SPL mint account.
+    pub metadata: UncheckedAccount<'info>,
+
+    /// CHECK: Verified through CPI
+    // cannot mark these as real Accounts or else we blow stack size limit
+    /// Auction House treasury mint account.
+    pub treasury_mint: UncheckedAccount<'info>,
+
+    /// CHECK: Not dangerous. Account seeds checked in constraint.
+    /// Buyer escrow payment account.
+    #[account(mut, seeds=[PREFIX.as_bytes(), auction_house.key().as_ref(), buyer.key().as_ref()], seeds::program=auction_house_program, bump=escrow_payment_bump)]
+    pub escrow_payment_account: UncheckedAccount<'info>,
+
+    /// CHECK: Verified through CPI
+    /// Seller SOL or SPL account to receive payment at.
+    #[account(mut)]
+    pub seller_payment_receipt_account: UncheckedAccount<'info>,
+
+    /// CHECK: Verified through CPI
+    /// Buyer SPL token account to receive purchased item at.
+    #[account(mut)]
+    pub buyer_receipt_token_account: UncheckedAccount<'info>,
+
+    /// CHECK: Verified through CPI
+    /// Buyer SOL or SPL account refunded a highest bid below the reserve price.
+    #[account(mut)]
+    pub buyer_payment_receipt_account: UncheckedAccount<'info>,
+
+    /// CHECK: Verified through CPI
+    /// Auction House instance authority.
+    pub authority: UncheckedAccount<'info>,
+
+    /// Auction House instance PDA account.
+    #[account(seeds=[PREFIX.as_bytes(), auction_house.creator.as_ref(), auction_house.treasury_mint.as_ref()], seeds::program=auction_house_program, bump=auction_house.bump, has_one=treasury_mint, has_one=auction_house_treasury, has_one=auction_house_fee_account)]
+    pub auction_house: Box<Account<'info, AuctionHouse>>,
+
+    /// CHECK: Not dangerous. Account seeds checked in constraint.
+    /// Auction House instance fee account.
+    #[account(mut, seeds=[PREFIX.as_bytes(), auction_house.key().as_ref(), FEE_PAYER.as_bytes()], seeds::program=auction_house_program, bump=auction_house.fee_payer_bump)]
+    pub auction_house_fee_account: UncheckedAccount<'info>,
+
+    /// CHECK: Not dangerous. Account seeds checked in constraint.
+    /// Auction House instance treasury account.
+    #[account(mut, seeds=[PREFIX.as_bytes(), auction_house.key().as_ref(), TREASURY.as_bytes()], seeds::program=auction_house_program, bump=auction_house.treasury_bump)]
+    pub auction_house_treasury: UncheckedAccount<'info>,
+
+    /// CHECK: Verified through CPI
+    /// Buyer trade state PDA account encoding the highest bid.
+    #[account(mut)]
+    pub buyer_trade_state: UncheckedAccount<'info>,
+
+    /// CHECK: Not dangerous. Account seeds checked in constraint.
+    /// Seller trade state PDA account encoding the sell order.
+    #[account(mut, seeds=[PREFIX.as_bytes(), seller.key().as_ref(), auction_house.key().as_ref(), token_account.key().as_ref(), auction_house.treasury_mint.as_ref(), token_mint.key().as_ref(), &u64::MAX.to_le_bytes(), &token_size.to_le_bytes()], seeds::program=auction_house_program, bump=seller_trade_state.to_account_info().data.borrow()[0])]
+    pub seller_trade_state: UncheckedAccount<'info>,
+
+    /// CHECK: Not dangerous. Account seeds checked in constraint.
+    /// Free seller trade state PDA account encoding a free sell order.
+    #[account(mut, seeds=[PREFIX.as_bytes(), seller.key().as_ref(), auction_house.key().as_ref(), token_account.key().as_ref(), auction_house.treasury_mint.as_ref(), token_mint.key().as_ref(), &0u64.to_le_bytes(), &token_size.to_le_bytes()], seeds::program=auction_house_program, bump=free_trade_state_bump)]
+    pub free_trade_state: UncheckedAccount<'info>,
+
+    /// CHECK: Verified through CPI
+    /// The auctioneer program PDA running this auction.
+    #[account(seeds = [AUCTIONEER.as_bytes(), auction_house.key().as_ref()], bump=auctioneer_authority_bump)]
+    pub auctioneer_authority: UncheckedAccount<'info>,
+
+    /// CHECK: Not dangerous. Account seeds checked in constraint.
+    /// The auctioneer PDA owned by Auction House storing scopes.
+    #[account(
+        seeds = [
+            AUCTIONEER.as_bytes(),
+            auction_house.key().as_ref(),
+            auctioneer_authority.key().as_ref()
+            ],
+        seeds::program=auction_house_program,
+        bump = ah_auctioneer_pda.bump,
+    )]
+    pub ah_auctioneer_pda: Account<'info, mpl_auction_house::Auctioneer>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    pub ata_program: Program<'info, AssociatedToken>,
+
+    /// CHECK: Not dangerous. Account seeds checked in constraint.
+    #[account(seeds=[PREFIX.as_bytes(), SIGNER.as_bytes()], seeds::program=auction_house_program, bump=program_as_signer_bump)]
+    pub program_as_signer: UncheckedAccount<'info>,
+
+    pub rent: Sysvar<'info, Rent>,
+}
+
+/// Settle an auction once it is over, whoever calls it. If the highest bid meets the reserve price the sale is executed to the highest bidder, otherwise the highest bid is canceled and refunded from the buyer's escrow and the listing is canceled. Auction House only pays its fees from a wallet that signs, so the transaction still needs the signature of the buyer, the seller or the Auction House authority: the buyer can execute a sale or take back a losing bid, the seller can execute a sale or cancel the listing and the authority can do both. The Listing Config is closed to the seller once the listing is gone. Auction House pays the sale proceeds straight to the seller, so settling is not rewarded.
+pub fn auctioneer_settle<'info>(
+    ctx: Context<'_, '_, '_, 'info, AuctioneerSettle<'info>>,
+    escrow_payment_bump: u8,
+    free_trade_state_bump: u8,
+    program_as_signer_bump: u8,
+    auctioneer_authority_bump: u8,
+    token_size: u64,
+) -> Result<()> {
+    assert_auction_over(&ctx.accounts.listing_config)?;
+    assert_single_unit(&ctx.accounts.listing_config)?;
+
+    let listing_config = &ctx.accounts.listing_config;
+    if listing_config.highest_bid.amount > 0 {
+        assert_highest_bidder(listing_config, ctx.accounts.buyer_trade_state.key())?;
+    }
+    // A highest bid that was canceled or refunded leaves an empty trade state behind.
+    let has_bid =
+        listing_config.highest_bid.amount > 0 && !ctx.accounts.buyer_trade_state.data_is_empty();
+    let has_winning_bid =
+        has_bid && listing_config.highest_bid.amount >= listing_config.reserve_price;
+
+    // Without a sale each trade state is closed by Auction House for its own wallet, so a
+    // losing bid is refunded when the buyer signs and the listing is canceled when the seller
+    // signs, the authority can do either.
+    let refund_bid = has_bid
+        && !has_winning_bid
+        && (ctx.accounts.buyer.is_signer || ctx.accounts.authority.is_signer);
+    let close_listing =
+        has_winning_bid || ctx.accounts.seller.is_signer || ctx.accounts.authority.is_signer;
+    if !refund_bid && !close_listing {
+        return err!(AuctioneerError::NoValidSignerPresent);
+    }
+
+    let auction_house = &ctx.accounts.auction_house;
+    let ah_key = auction_house.key();
+
+    let auctioneer_seeds = [
+        AUCTIONEER.as_bytes(),
+        ah_key.as_ref(),
+        &[auctioneer_authority_bump],
+    ];
+
+    if has_winning_bid {
+        let cpi_program = ctx.accounts.auction_house_program.to_account_info();
+        let cpi_accounts = AHExecuteSale {
+            buyer: ctx.accounts.buyer.to_account_info(),
+            seller: ctx.accounts.seller.to_account_info(),
+            token_account: ctx.accounts.token_account.to_account_info(),
+            token_mint: ctx.accounts.token_mint.to_account_info(),
+            metadata: ctx.accounts.metadata.to_account_info(),
+            treasury_mint: ctx.accounts.treasury_mint.to_account_info(),
+            escrow_payment_account: ctx.accounts.escrow_payment_account.to_account_info(),
+            seller_payment_receipt_account: ctx
+                .accounts
+                .seller_payment_receipt_account
+                .to_account_info(),
+            buyer_receipt_token_account: ctx.accounts.buyer_receipt_token_account.to_account_info(),
+            auction_house: ctx.accounts.auction_house.to_account_info(),
+            auction_house_fee_account: ctx.accounts.auction_house_fee_account.to_account_info(),
+            auction_house_treasury: ctx.accounts.auction_house_treasury.to_account_info(),
+            buyer_trade_state: ctx.accounts.buyer_trade_state.to_account_info(),
+            seller_trade_state: ctx.accounts.seller_trade_state.to_account_info(),
+            free_trade_state: ctx.accounts.free_trade_state.to_account_info(),
+            authority: ctx.accounts.authority.to_account_info(),
+            auctioneer_authority: ctx.accounts.auctioneer_authority.to_account_info(),
+            ah_auctioneer_pda: ctx.accounts.ah_auctioneer_pda.to_account_info(),
+            token_program: ctx.accounts.token_program.to_account_info(),
+            system_program: ctx.accounts.system_program.to_account_info(),
+            ata_program: ctx.accounts.ata_program.to_account_info(),
+            program_as_signer: ctx.accounts.program_as_signer.to_account_info(),
+            rent: ctx.accounts.rent.to_account_info(),
+        };
+
+        let execute_sale_data = mpl_auction_house::instruction::AuctioneerExecuteSale {
+            escrow_payment_bump,
+            _free_trade_state_bump: free_trade_state_bump,
+            program_as_signer_bump,
+            buyer_price: listing_config.highest_bid.amount,
+            token_size,
+        };
+
+        let mut cpi_account_metas: Vec<AccountMeta> = cpi_accounts
+            .to_account_metas(None)
+            .into_iter()
+            .zip(cpi_accounts.to_account_infos())
+            .map(|mut pair| {
+                pair.0.is_signer = pair.1.is_signer;
+                if pair.0.pubkey == ctx.accounts.auctioneer_authority.key() {
+                    pair.0.is_signer = true;
+                }
+                pair.0
+            })
+            .collect();
+
+        cpi_account_metas.append(&mut ctx.remaining_accounts.to_vec().to_account_metas(None));
+
+        let mut cpi_account_infos: Vec<AccountInfo> = cpi_accounts.to_account_infos();
+        cpi_account_infos.append(&mut ctx.remaining_accounts.to_vec());
+
+        let ix = solana_program::instruction::Instruction {
+            program_id: cpi_program.key(),
+            accounts: cpi_account_metas,
+            data: execute_sale_data.data(),
+        };
+
+        invoke_signed(&ix, &cpi_account_infos, &[&auctioneer_seeds])?;
+    } else {
+        if refund_bid {
+            let cpi_program = ctx.accounts.auction_house_program.to_account_info();
+            let cpi_accounts = AHCancel {
+                wallet: ctx.accounts.buyer.to_account_info(),
+                token_account: ctx.accounts.token_account.to_account_info(),
+                token_mint: ctx.accounts.token_mint.to_account_info(),
+                auction_house: ctx.accounts.auction_house.to_account_info(),
+                auction_house_fee_account: ctx.accounts.auction_house_fee_account.to_account_info(),
+                trade_state: ctx.accounts.buyer_trade_state.to_account_info(),
+                authority: ctx.accounts.authority.to_account_info(),
+                auctioneer_authority: ctx.accounts.auctioneer_authority.to_account_info(),
+                ah_auctioneer_pda: ctx.accounts.ah_auctioneer_pda.to_account_info(),
+                token_program: ctx.accounts.token_program.to_account_info(),
+            };
+
+            let cancel_data = mpl_auction_house::instruction::AuctioneerCancel {
+                buyer_price: listing_config.highest_bid.amount,
+                token_size,
+            };
+
+            let ix = solana_program::instruction::Instruction {
+                program_id: cpi_program.key(),
+                accounts: cpi_accounts
+                    .to_account_metas(None)
+                    .into_iter()
+                    .zip(cpi_accounts.to_account_infos())
+                    .map(|mut pair| {
+                        pair.0.is_signer = pair.1.is_signer;
+                        if pair.0.pubkey == ctx.accounts.auctioneer_authority.key() {
+                            pair.0.is_signer = true;
+                        }
+                        pair.0
+                    })
+                    .collect(),
+                data: cancel_data.data(),
+            };
+
+            invoke_signed(&ix, &cpi_accounts.to_account_infos(), &[&auctioneer_seeds])?;
+
+            // Give the canceled bid back to the buyer.
+            let cpi_accounts = AHWithdraw {
+                wallet: ctx.accounts.buyer.to_account_info(),
+                receipt_account: ctx.accounts.buyer_payment_receipt_account.to_account_info(),
+                escrow_payment_account: ctx.accounts.escrow_payment_account.to_account_info(),
+                treasury_mint: ctx.accounts.treasury_mint.to_account_info(),
+                authority: ctx.accounts.authority.to_account_info(),
+                auction_house: ctx.accounts.auction_house.to_account_info(),
+                auction_house_fee_account: ctx.accounts.auction_house_fee_account.to_account_info(),
+                auctioneer_authority: ctx.accounts.auctioneer_authority.to_account_info(),
+                ah_auctioneer_pda: ctx.accounts.ah_auctioneer_pda.to_account_info(),
+                token_program: ctx.accounts.token_program.to_account_info(),
+                system_program: ctx.accounts.system_program.to_account_info(),
+                ata_program: ctx.accounts.ata_program.to_account_info(),
+                rent: ctx.accounts.rent.to_account_info(),
+            };
+
+            let withdraw_data = mpl_auction_house::instruction::AuctioneerWithdraw {
+                escrow_payment_bump,
+                amount: listing_config.highest_bid.amount,
+            };
+
+            let ix = solana_program::instruction::Instruction {
+                program_id: cpi_program.key(),
+                accounts: cpi_accounts
+                    .to_account_metas(None)
+                    .into_iter()
+                    .zip(cpi_accounts.to_account_infos())
+                    .map(|mut pair| {
+                        pair.0.is_signer = pair.1.is_signer;
+                        if pair.0.pubkey == ctx.accounts.auctioneer_authority.key() {
+                            pair.0.is_signer = true;
+                        }
+                        pair.0
+                    })
+                    .collect(),
+                data: withdraw_data.data(),
+            };
+
+            invoke_signed(&ix, &cpi_accounts.to_account_infos(), &[&auctioneer_seeds])?;
+        }
+
+        if close_listing {
+            let cpi_program = ctx.accounts.auction_house_program.to_account_info();
+            let cpi_accounts = AHCancel {
+                wallet: ctx.accounts.seller.to_account_info(),
+                token_account: ctx.accounts.token_account.to_account_info(),
+                token_mint: ctx.accounts.token_mint.to_account_info(),
+                auction_house: ctx.accounts.auction_house.to_account_info(),
+                auction_house_fee_account: ctx.accounts.auction_house_fee_account.to_account_info(),
+                trade_state: ctx.accounts.seller_trade_state.to_account_info(),
+                authority: ctx.accounts.authority.to_account_info(),
+                auctioneer_authority: ctx.accounts.auctioneer_authority.to_account_info(),
+                ah_auctioneer_pda: ctx.accounts.ah_auctioneer_pda.to_account_info(),
+                token_program: ctx.accounts.token_program.to_account_info(),
+            };
+
+            let cancel_data = mpl_auction_house::instruction::AuctioneerCancel {
+                buyer_price: AUCTIONEER_BUYER_PRICE,
+                token_size,
+            };
+
+            let ix = solana_program::instruction::Instruction {
+                program_id: cpi_program.key(),
+                accounts: cpi_accounts
+                    .to_account_metas(None)
+                    .into_iter()
+                    .zip(cpi_accounts.to_account_infos())
+                    .map(|mut pair| {
+                        pair.0.is_signer = pair.1.is_signer;
+                        if pair.0.pubkey == ctx.accounts.auctioneer_authority.key() {
+                            pair.0.is_signer = true;
+                        }
+                        pair.0
+                    })
+                    .collect(),
+                data: cancel_data.data(),
+            };
+
+            invoke_signed(&ix, &cpi_accounts.to_account_infos(), &[&auctioneer_seeds])?;
+        }
+    }
+
+    // The Listing Config stays open until the listing is gone, so the seller can still settle.
+    if close_listing {
+        let listing_config = &ctx.accounts.listing_config.to_account_info();
+        let seller = &ctx.accounts.seller.to_account_info();
+
+        let listing_config_lamports = listing_config.lamports();
+        **seller.lamports.borrow_mut() = seller
+            .lamports()
+            .checked_add(listing_config_lamports)
+            .unwrap();
+        **listing_config.lamports.borrow_mut() = 0;
+
+        let mut source_data = listing_config.data.borrow_mut();
+        source_data.fill(0);
+    }
+
+    Ok(())
+}
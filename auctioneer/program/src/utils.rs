@@ -26,6 +26,14 @@ pub fn assert_auction_over(listing_config: &Account<ListingConfig>) -> Result<()
     Ok(())
 }
 
+pub fn assert_single_unit(listing_config: &Account<ListingConfig>) -> Result<()> {
+    if listing_config.multi_unit {
+        return err!(AuctioneerError::MultiUnitListing);
+    }
+
+    Ok(())
+}
+
 pub fn assert_higher_bid(
     listing_config: &Account<ListingConfig>,
     new_bid_price: u64,
@@ -41,6 +49,29 @@ pub fn assert_higher_bid(
     Ok(())
 }
 
+pub fn assert_winning_unit_bid(
+    listing_config: &Account<ListingConfig>,
+    new_bid_price: u64,
+) -> Result<()> {
+    // Until every unit has a bid any bid meeting the reserve price wins one.
+    if (listing_config.unit_bids.len() as u64) < listing_config.units {
+        return Ok(());
+    }
+
+    let lowest_bid = match listing_config.unit_bids.last() {
+        Some(bid) => bid.amount,
+        None => return Ok(()),
+    };
+
+    if new_bid_price <= lowest_bid {
+        return err!(AuctioneerError::BidTooLow);
+    } else if new_bid_price < (lowest_bid + listing_config.min_bid_increment) {
+        return err!(AuctioneerError::BelowBidIncrement);
+    }
+
+    Ok(())
+}
+
 pub fn assert_exceeds_reserve_price(
     listing_config: &Account<ListingConfig>,
     new_bid_price: u64,
@@ -68,7 +99,12 @@ pub fn process_time_extension(listing_config: &mut Account<ListingConfig>) -> Re
     let current_timestamp = clock.unix_timestamp;
 
     if current_timestamp >= (listing_config.end_time - i64::from(listing_config.time_ext_period)) {
-        listing_config.end_time += i64::from(listing_config.time_ext_delta);
+        let mut new_end_time = listing_config.end_time + i64::from(listing_config.time_ext_delta);
+        // A max end time of zero leaves the auction uncapped.
+        if listing_config.max_end_time > 0 {
+            new_end_time = new_end_time.min(listing_config.max_end_time);
+        }
+        listing_config.end_time = listing_config.end_time.max(new_end_time);
     }
 
     Ok(())
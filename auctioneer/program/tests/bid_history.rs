@@ -0,0 +1,233 @@
+#![cfg(feature = "test-bpf")]
+pub mod common;
+pub mod utils;
+
+use common::*;
+use mpl_auctioneer::{bid_history::history::BidHistory, pda::find_bid_history_address};
+use solana_sdk::instruction::AccountMeta;
+use std::{assert_eq, time::SystemTime};
+use utils::setup_functions::*;
+
+#[tokio::test]
+async fn bid_history_ring_buffer() {
+    let mut context = auctioneer_program_test().start_with_context().await;
+    // Payer Wallet
+    let (ah, ahkey, _) = existing_auction_house_test_context(&mut context)
+        .await
+        .unwrap();
+    let test_metadata = Metadata::new();
+
+    airdrop(&mut context, &test_metadata.token.pubkey(), 1000000000)
+        .await
+        .unwrap();
+    test_metadata
+        .create(
+            &mut context,
+            "Test".to_string(),
+            "TST".to_string(),
+            "uri".to_string(),
+            None,
+            10,
+            false,
+            1,
+        )
+        .await
+        .unwrap();
+
+    let ((sell_acc, listing_config_address), sell_tx) = sell(
+        &mut context,
+        &ahkey,
+        &ah,
+        &test_metadata,
+        (SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .expect("Time went backwards")
+            .as_secs()
+            - 60) as i64,
+        (SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .expect("Time went backwards")
+            .as_secs()
+            + 60) as i64,
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
+    );
+    context
+        .banks_client
+        .process_transaction(sell_tx)
+        .await
+        .unwrap();
+
+    let (_, history_tx) = create_bid_history(
+        &mut context,
+        &ahkey,
+        &test_metadata,
+        &listing_config_address,
+        2,
+    );
+    context
+        .banks_client
+        .process_transaction(history_tx)
+        .await
+        .unwrap();
+    let (bid_history, _) = find_bid_history_address(&listing_config_address);
+
+    let mut buyers = Vec::new();
+    for price in [1000000000, 2000000000, 3000000000] {
+        let buyer = Keypair::new();
+        airdrop(&mut context, &buyer.pubkey(), 10000000000)
+            .await
+            .unwrap();
+        let (_, deposit_tx) = deposit(&mut context, &ahkey, &ah, &test_metadata, &buyer, price);
+        context
+            .banks_client
+            .process_transaction(deposit_tx)
+            .await
+            .unwrap();
+
+        let (_acc, buy_tx) = buy_with_remaining_accounts(
+            &mut context,
+            &ahkey,
+            &ah,
+            &test_metadata,
+            &test_metadata.token.pubkey(),
+            &buyer,
+            &sell_acc.wallet,
+            &listing_config_address,
+            price,
+            vec![AccountMeta::new(bid_history, false)],
+        );
+        context
+            .banks_client
+            .process_transaction(buy_tx)
+            .await
+            .unwrap();
+        buyers.push(buyer);
+    }
+
+    let history_account = context
+        .banks_client
+        .get_account(bid_history)
+        .await
+        .unwrap()
+        .unwrap();
+    let history = BidHistory::try_deserialize(&mut history_account.data.as_ref()).unwrap();
+
+    // The third bid overwrote the oldest one.
+    assert_eq!(history.bids.len(), 2);
+    assert_eq!(history.head, 1);
+    assert_eq!(history.bids[0].bidder, buyers[2].pubkey());
+    assert_eq!(history.bids[0].amount, 3000000000);
+    assert_eq!(history.bids[1].bidder, buyers[1].pubkey());
+    assert_eq!(history.bids[1].amount, 2000000000);
+}
+
+#[tokio::test]
+async fn bid_history_missing_failure() {
+    let mut context = auctioneer_program_test().start_with_context().await;
+    // Payer Wallet
+    let (ah, ahkey, _) = existing_auction_house_test_context(&mut context)
+        .await
+        .unwrap();
+    let test_metadata = Metadata::new();
+
+    airdrop(&mut context, &test_metadata.token.pubkey(), 1000000000)
+        .await
+        .unwrap();
+    test_metadata
+        .create(
+            &mut context,
+            "Test".to_string(),
+            "TST".to_string(),
+            "uri".to_string(),
+            None,
+            10,
+            false,
+            1,
+        )
+        .await
+        .unwrap();
+
+    let ((sell_acc, listing_config_address), sell_tx) = sell(
+        &mut context,
+        &ahkey,
+        &ah,
+        &test_metadata,
+        (SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .expect("Time went backwards")
+            .as_secs()
+            - 60) as i64,
+        (SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .expect("Time went backwards")
+            .as_secs()
+            + 60) as i64,
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
+    );
+    context
+        .banks_client
+        .process_transaction(sell_tx)
+        .await
+        .unwrap();
+
+    let (_, history_tx) = create_bid_history(
+        &mut context,
+        &ahkey,
+        &test_metadata,
+        &listing_config_address,
+        10,
+    );
+    context
+        .banks_client
+        .process_transaction(history_tx)
+        .await
+        .unwrap();
+
+    let buyer = Keypair::new();
+    airdrop(&mut context, &buyer.pubkey(), 10000000000)
+        .await
+        .unwrap();
+    let (_, deposit_tx) = deposit(
+        &mut context,
+        &ahkey,
+        &ah,
+        &test_metadata,
+        &buyer,
+        1000000000,
+    );
+    context
+        .banks_client
+        .process_transaction(deposit_tx)
+        .await
+        .unwrap();
+
+    let (_acc, buy_tx) = buy(
+        &mut context,
+        &ahkey,
+        &ah,
+        &test_metadata,
+        &test_metadata.token.pubkey(),
+        &buyer,
+        &sell_acc.wallet,
+        &listing_config_address,
+        1000000000,
+    );
+    let result = context
+        .banks_client
+        .process_transaction(buy_tx)
+        .await
+        .unwrap_err();
+    assert_error!(result, MISSING_BID_HISTORY);
+}
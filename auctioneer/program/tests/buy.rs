@@ -3,7 +3,8 @@ pub mod common;
 pub mod utils;
 
 use common::*;
-use mpl_auctioneer::sell::config::ListingConfig;
+use mpl_auctioneer::{pda::find_bid_cooldown_address, sell::config::ListingConfig};
+use solana_sdk::instruction::AccountMeta;
 use std::{assert_eq, time::SystemTime};
 use utils::setup_functions::*;
 
@@ -53,6 +54,8 @@ async fn buy_success() {
         None,
         None,
         None,
+        None,
+        None,
     );
     context
         .banks_client
@@ -142,6 +145,8 @@ async fn multiple_bids() {
         None,
         None,
         None,
+        None,
+        None,
     );
     context
         .banks_client
@@ -276,6 +281,8 @@ async fn buy_below_reserve_failure() {
         None,
         None,
         None,
+        None,
+        None,
     );
     context
         .banks_client
@@ -366,6 +373,8 @@ async fn buy_above_reserve_success() {
         None,
         None,
         None,
+        None,
+        None,
     );
     context
         .banks_client
@@ -455,6 +464,8 @@ async fn multiple_bids_increment_failure() {
         None,
         None,
         None,
+        None,
+        None,
     );
     context
         .banks_client
@@ -590,6 +601,8 @@ async fn multiple_bids_increment_success() {
         None,
         None,
         None,
+        None,
+        None,
     );
     context
         .banks_client
@@ -724,6 +737,8 @@ async fn multiple_bids_time_ext_success() {
         Some(60),
         Some(60),
         None,
+        None,
+        None,
     );
     context
         .banks_client
@@ -838,3 +853,227 @@ async fn multiple_bids_time_ext_success() {
     let config2 = ListingConfig::try_deserialize(&mut listing2.as_ref()).unwrap();
     assert_eq!(config2.end_time, end_time_t0 + 120);
 }
+
+#[tokio::test]
+async fn time_ext_capped_by_max_end_time() {
+    let mut context = auctioneer_program_test().start_with_context().await;
+    // Payer Wallet
+    let (ah, ahkey, _) = existing_auction_house_test_context(&mut context)
+        .await
+        .unwrap();
+    let test_metadata = Metadata::new();
+
+    airdrop(&mut context, &test_metadata.token.pubkey(), 1000000000)
+        .await
+        .unwrap();
+    test_metadata
+        .create(
+            &mut context,
+            "Test".to_string(),
+            "TST".to_string(),
+            "uri".to_string(),
+            None,
+            10,
+            false,
+            1,
+        )
+        .await
+        .unwrap();
+
+    let end_time = (SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .expect("Time went backwards")
+        .as_secs()
+        + 60) as i64;
+    let ((sell_acc, listing_config_address), sell_tx) = sell(
+        &mut context,
+        &ahkey,
+        &ah,
+        &test_metadata,
+        (SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .expect("Time went backwards")
+            .as_secs()
+            - 60) as i64,
+        end_time,
+        None,
+        None,
+        Some(60),
+        Some(60),
+        None,
+        Some(end_time + 30),
+        None,
+    );
+    context
+        .banks_client
+        .process_transaction(sell_tx)
+        .await
+        .unwrap();
+
+    context.warp_to_slot(400).unwrap();
+
+    let buyer = Keypair::new();
+    airdrop(&mut context, &buyer.pubkey(), 10000000000)
+        .await
+        .unwrap();
+    let (_, deposit_tx) = deposit(
+        &mut context,
+        &ahkey,
+        &ah,
+        &test_metadata,
+        &buyer,
+        1000000000,
+    );
+    context
+        .banks_client
+        .process_transaction(deposit_tx)
+        .await
+        .unwrap();
+
+    let (_acc, buy_tx) = buy(
+        &mut context,
+        &ahkey,
+        &ah,
+        &test_metadata,
+        &test_metadata.token.pubkey(),
+        &buyer,
+        &sell_acc.wallet,
+        &listing_config_address,
+        1000000000,
+    );
+    context
+        .banks_client
+        .process_transaction(buy_tx)
+        .await
+        .unwrap();
+
+    let listing = context
+        .banks_client
+        .get_account(listing_config_address)
+        .await
+        .unwrap()
+        .unwrap()
+        .data;
+
+    let config = ListingConfig::try_deserialize(&mut listing.as_ref()).unwrap();
+    assert_eq!(config.end_time, end_time + 30);
+}
+
+#[tokio::test]
+async fn bid_cooldown_failure() {
+    let mut context = auctioneer_program_test().start_with_context().await;
+    // Payer Wallet
+    let (ah, ahkey, _) = existing_auction_house_test_context(&mut context)
+        .await
+        .unwrap();
+    let test_metadata = Metadata::new();
+
+    airdrop(&mut context, &test_metadata.token.pubkey(), 1000000000)
+        .await
+        .unwrap();
+    test_metadata
+        .create(
+            &mut context,
+            "Test".to_string(),
+            "TST".to_string(),
+            "uri".to_string(),
+            None,
+            10,
+            false,
+            1,
+        )
+        .await
+        .unwrap();
+
+    let ((sell_acc, listing_config_address), sell_tx) = sell(
+        &mut context,
+        &ahkey,
+        &ah,
+        &test_metadata,
+        (SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .expect("Time went backwards")
+            .as_secs()
+            - 60) as i64,
+        (SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .expect("Time went backwards")
+            .as_secs()
+            + 60) as i64,
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
+        Some(60),
+    );
+    context
+        .banks_client
+        .process_transaction(sell_tx)
+        .await
+        .unwrap();
+
+    let buyer = Keypair::new();
+    airdrop(&mut context, &buyer.pubkey(), 10000000000)
+        .await
+        .unwrap();
+    let (_, deposit_tx) = deposit(
+        &mut context,
+        &ahkey,
+        &ah,
+        &test_metadata,
+        &buyer,
+        2000000000,
+    );
+    context
+        .banks_client
+        .process_transaction(deposit_tx)
+        .await
+        .unwrap();
+
+    let (bid_cooldown, _) = find_bid_cooldown_address(&listing_config_address, &buyer.pubkey());
+
+    let (_acc0, buy_tx0) = buy_with_remaining_accounts(
+        &mut context,
+        &ahkey,
+        &ah,
+        &test_metadata,
+        &test_metadata.token.pubkey(),
+        &buyer,
+        &sell_acc.wallet,
+        &listing_config_address,
+        1000000000,
+        vec![
+            AccountMeta::new(bid_cooldown, false),
+            AccountMeta::new(buyer.pubkey(), true),
+        ],
+    );
+    context
+        .banks_client
+        .process_transaction(buy_tx0)
+        .await
+        .unwrap();
+
+    let (_acc1, buy_tx1) = buy_with_remaining_accounts(
+        &mut context,
+        &ahkey,
+        &ah,
+        &test_metadata,
+        &test_metadata.token.pubkey(),
+        &buyer,
+        &sell_acc.wallet,
+        &listing_config_address,
+        2000000000,
+        vec![
+            AccountMeta::new(bid_cooldown, false),
+            AccountMeta::new(buyer.pubkey(), true),
+        ],
+    );
+    let result = context
+        .banks_client
+        .process_transaction(buy_tx1)
+        .await
+        .unwrap_err();
+    assert_error!(result, BID_COOLDOWN_ACTIVE);
+}
@@ -57,6 +57,8 @@ async fn cancel_listing() {
         None,
         None,
         None,
+        None,
+        None,
     );
     context
         .banks_client
@@ -159,6 +161,8 @@ async fn cancel_bid() {
         None,
         None,
         Some(true),
+        None,
+        None,
     );
     context
         .banks_client
@@ -283,6 +287,8 @@ async fn cancel_highest_bid() {
         None,
         None,
         Some(false),
+        None,
+        None,
     );
     context
         .banks_client
@@ -27,6 +27,7 @@ pub const MISSING_AUCTIONEER_SCOPE: u32 = 6029;
 pub const NO_AUCTIONEER_PROGRAM_SET: u32 = 6031;
 pub const INVALID_SEEDS_OR_NO_DELEGATE: u32 = 6036;
 pub const TOO_MANY_SCOPES: u32 = 6032;
+pub const NO_PAYER_PRESENT: u32 = 6012;
 
 pub const TEN_SOL: u64 = 10_000_000_000;
 pub const ONE_SOL: u64 = 1_000_000_000;
@@ -34,7 +35,14 @@ pub const ONE_SOL: u64 = 1_000_000_000;
 pub const AUCTION_NOT_STARTED: u32 = 6001;
 pub const AUCTION_ENDED: u32 = 6002;
 pub const AUCTION_ACTIVE: u32 = 6003;
+pub const BID_TOO_LOW: u32 = 6004;
 pub const NOT_HIGH_BIDDER: u32 = 6006;
 pub const BELOW_RESERVE_PRICE: u32 = 6007;
 pub const BELOW_BID_INCREMENT: u32 = 6008;
 pub const CANNOT_CANCEL_HIGHEST_BID: u32 = 6009;
+pub const MISSING_BID_HISTORY: u32 = 6012;
+pub const INVALID_MAX_END_TIME: u32 = 6014;
+pub const BID_COOLDOWN_ACTIVE: u32 = 6017;
+pub const NO_VALID_SIGNER_PRESENT: u32 = 6018;
+pub const LISTING_CONFIG_NOT_V0: u32 = 6023;
+pub const WINNER_MUST_SIGN: u32 = 6025;
@@ -74,6 +74,8 @@ async fn execute_sale_early_failure() {
         None,
         None,
         None,
+        None,
+        None,
     );
     context
         .banks_client
@@ -229,6 +231,8 @@ async fn execute_sale_success() {
         None,
         None,
         None,
+        None,
+        None,
     );
     context
         .banks_client
@@ -433,6 +437,8 @@ async fn execute_sale_two_bids_success() {
         None,
         None,
         None,
+        None,
+        None,
     );
     context
         .banks_client
@@ -659,6 +665,8 @@ async fn execute_sale_two_bids_failure() {
         None,
         None,
         None,
+        None,
+        None,
     );
     context
         .banks_client
@@ -857,6 +865,8 @@ async fn execute_sale_with_creators(metadata_creators: Vec<(Pubkey, u8)>) {
         None,
         None,
         None,
+        None,
+        None,
     );
     context
         .banks_client
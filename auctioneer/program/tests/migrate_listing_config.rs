@@ -0,0 +1,112 @@
+#![cfg(feature = "test-bpf")]
+pub mod common;
+pub mod utils;
+
+use common::*;
+use utils::setup_functions::*;
+
+use anchor_lang::Discriminator;
+use mpl_auctioneer::sell::config::*;
+use solana_program::system_program;
+use solana_sdk::account::{Account as SolanaAccount, AccountSharedData};
+
+#[tokio::test]
+async fn migrate_v0_listing_config_success() {
+    let mut context = auctioneer_program_test().start_with_context().await;
+    let listing_config_address = Pubkey::new_unique();
+
+    // A Listing Config as it was written before versioning.
+    let listing_config_v0 = ListingConfigV0 {
+        version: ListingConfigVersion::V0,
+        start_time: 10,
+        end_time: 20,
+        highest_bid: Bid {
+            version: ListingConfigVersion::V0,
+            amount: ONE_SOL,
+            buyer_trade_state: Pubkey::new_unique(),
+        },
+        bump: 254,
+        reserve_price: ONE_SOL,
+        min_bid_increment: 100,
+        time_ext_period: 30,
+        time_ext_delta: 60,
+        allow_high_bid_cancel: true,
+    };
+    let mut data = ListingConfig::discriminator().to_vec();
+    data.append(&mut listing_config_v0.try_to_vec().unwrap());
+    assert_eq!(data.len(), LISTING_CONFIG_V0_SIZE);
+
+    let rent = context.banks_client.get_rent().await.unwrap();
+    context.set_account(
+        &listing_config_address,
+        &AccountSharedData::from(SolanaAccount {
+            lamports: rent.minimum_balance(LISTING_CONFIG_V0_SIZE),
+            data,
+            owner: mpl_auctioneer::id(),
+            executable: false,
+            rent_epoch: 0,
+        }),
+    );
+
+    let payer = context.payer.pubkey();
+    let migrate_instruction = move || Instruction {
+        program_id: mpl_auctioneer::id(),
+        data: mpl_auctioneer::instruction::MigrateListingConfig {}.data(),
+        accounts: mpl_auctioneer::accounts::MigrateListingConfig {
+            payer,
+            listing_config: listing_config_address,
+            system_program: system_program::id(),
+        }
+        .to_account_metas(None),
+    };
+
+    let tx = Transaction::new_signed_with_payer(
+        &[migrate_instruction()],
+        Some(&context.payer.pubkey()),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+    context.banks_client.process_transaction(tx).await.unwrap();
+
+    let listing_config_account = context
+        .banks_client
+        .get_account(listing_config_address)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(listing_config_account.data.len(), LISTING_CONFIG_SIZE);
+    assert!(listing_config_account.lamports >= rent.minimum_balance(LISTING_CONFIG_SIZE));
+
+    let listing_config =
+        ListingConfig::try_deserialize(&mut listing_config_account.data.as_ref()).unwrap();
+    assert!(matches!(listing_config.version, ListingConfigVersion::V1));
+    assert_eq!(listing_config.end_time, 20);
+    assert_eq!(listing_config.highest_bid.amount, ONE_SOL);
+    assert_eq!(
+        listing_config.highest_bid.buyer_trade_state,
+        listing_config_v0.highest_bid.buyer_trade_state
+    );
+    assert_eq!(listing_config.bump, 254);
+    assert_eq!(listing_config.time_ext_delta, 60);
+    assert!(listing_config.allow_high_bid_cancel);
+    assert!(!listing_config.has_bid_history);
+    assert_eq!(listing_config.max_end_time, 0);
+    assert_eq!(listing_config.bid_cooldown, 0);
+    assert!(!listing_config.multi_unit);
+    assert!(listing_config.unit_bids.is_empty());
+
+    // A migrated Listing Config can't be migrated again.
+    context.warp_to_slot(100).unwrap();
+    let tx = Transaction::new_signed_with_payer(
+        &[migrate_instruction()],
+        Some(&context.payer.pubkey()),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+    let result = context
+        .banks_client
+        .process_transaction(tx)
+        .await
+        .unwrap_err();
+    assert_error!(result, LISTING_CONFIG_NOT_V0);
+}
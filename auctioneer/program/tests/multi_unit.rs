@@ -0,0 +1,462 @@
+#![cfg(feature = "test-bpf")]
+pub mod common;
+pub mod utils;
+
+use common::*;
+use utils::setup_functions::*;
+
+use anchor_lang::{InstructionData, ToAccountMetas};
+use mpl_testing_utils::{solana::airdrop, utils::Metadata};
+use solana_sdk::{compute_budget::ComputeBudgetInstruction, signer::Signer};
+
+use std::{assert_eq, time::SystemTime};
+
+use solana_program::{instruction::AccountMeta, system_program, sysvar};
+
+use solana_program::program_pack::Pack;
+
+use mpl_auction_house::pda::{
+    find_auctioneer_pda, find_escrow_payment_address, find_program_as_signer_address,
+    find_trade_state_address,
+};
+use mpl_auctioneer::{pda::find_auctioneer_authority_seeds, sell::config::ListingConfigV1Args};
+use solana_sdk::{pubkey::Pubkey, signature::Keypair, transaction::Transaction};
+use spl_associated_token_account::get_associated_token_address;
+use spl_token::state::Account;
+
+fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .expect("Time went backwards")
+        .as_secs() as i64
+}
+
+fn execute_multi_unit_sale(
+    context: &mut ProgramTestContext,
+    ahkey: &Pubkey,
+    ah: &AuctionHouse,
+    test_metadata: &Metadata,
+    sell_acc: &mpl_auctioneer::accounts::AuctioneerSell,
+    listing_config: &Pubkey,
+    winners: &[(&Keypair, Pubkey, bool)],
+    clearing_price: u64,
+    token_size: u64,
+) -> Transaction {
+    let (auctioneer_authority, aa_bump) = find_auctioneer_authority_seeds(ahkey);
+    let (auctioneer_pda, _) = find_auctioneer_pda(ahkey, &auctioneer_authority);
+    let mut accounts = mpl_auctioneer::accounts::AuctioneerExecuteMultiUnitSale {
+        auction_house_program: mpl_auction_house::id(),
+        listing_config: *listing_config,
+        seller: test_metadata.token.pubkey(),
+        authority: ah.authority,
+        auction_house: *ahkey,
+        metadata: test_metadata.pubkey,
+        token_account: sell_acc.token_account,
+        seller_trade_state: sell_acc.seller_trade_state,
+        token_program: spl_token::id(),
+        free_trade_state: sell_acc.free_seller_trade_state,
+        seller_payment_receipt_account: test_metadata.token.pubkey(),
+        token_mint: test_metadata.mint.pubkey(),
+        auction_house_fee_account: ah.auction_house_fee_account,
+        auction_house_treasury: ah.auction_house_treasury,
+        treasury_mint: ah.treasury_mint,
+        program_as_signer: sell_acc.program_as_signer,
+        system_program: system_program::id(),
+        ata_program: spl_associated_token_account::id(),
+        rent: sysvar::rent::id(),
+        auctioneer_authority,
+        ah_auctioneer_pda: auctioneer_pda,
+    }
+    .to_account_metas(None);
+
+    // Winners who bid above the clearing price sign so their bid can be placed again at it.
+    let mut signers = vec![&test_metadata.token];
+    for (buyer, buyer_trade_state, signs) in winners {
+        let buyer_token_account =
+            get_associated_token_address(&buyer.pubkey(), &test_metadata.mint.pubkey());
+        let (escrow, _) = find_escrow_payment_address(ahkey, &buyer.pubkey());
+        let (clearing_trade_state, _) = find_trade_state_address(
+            &buyer.pubkey(),
+            ahkey,
+            &sell_acc.token_account,
+            &ah.treasury_mint,
+            &test_metadata.mint.pubkey(),
+            clearing_price,
+            1,
+        );
+        accounts.push(AccountMeta::new(buyer.pubkey(), *signs));
+        accounts.push(AccountMeta::new(*buyer_trade_state, false));
+        accounts.push(AccountMeta::new(escrow, false));
+        accounts.push(AccountMeta::new(buyer_token_account, false));
+        accounts.push(AccountMeta::new(clearing_trade_state, false));
+        if *signs {
+            signers.push(*buyer);
+        }
+    }
+
+    // Multi-unit listings are placed with Auction House for a single unit.
+    let (_, free_sts_bump) = find_trade_state_address(
+        &test_metadata.token.pubkey(),
+        ahkey,
+        &sell_acc.token_account,
+        &ah.treasury_mint,
+        &test_metadata.mint.pubkey(),
+        0,
+        1,
+    );
+    let (_, pas_bump) = find_program_as_signer_address();
+
+    let instruction = Instruction {
+        program_id: mpl_auctioneer::id(),
+        data: mpl_auctioneer::instruction::ExecuteMultiUnitSale {
+            free_trade_state_bump: free_sts_bump,
+            program_as_signer_bump: pas_bump,
+            auctioneer_authority_bump: aa_bump,
+            token_size,
+            winner_count: winners.len() as u8,
+        }
+        .data(),
+        accounts,
+    };
+
+    let compute_ix = ComputeBudgetInstruction::set_compute_unit_limit(700_000);
+
+    Transaction::new_signed_with_payer(
+        &[compute_ix, instruction],
+        Some(&test_metadata.token.pubkey()),
+        &signers,
+        context.last_blockhash,
+    )
+}
+
+#[tokio::test]
+async fn multi_unit_uniform_price_success() {
+    let mut context = auctioneer_program_test().start_with_context().await;
+    // Payer Wallet
+    let (ah, ahkey, _) = existing_auction_house_test_context(&mut context)
+        .await
+        .unwrap();
+    let test_metadata = Metadata::new();
+    airdrop(&mut context, &test_metadata.token.pubkey(), 10_000_000_000)
+        .await
+        .unwrap();
+    test_metadata
+        .create(
+            &mut context,
+            "Test".to_string(),
+            "TST".to_string(),
+            "uri".to_string(),
+            None,
+            10,
+            false,
+            2,
+        )
+        .await
+        .unwrap();
+    let ((sell_acc, listing_config_address), sell_tx) = sell_units(
+        &mut context,
+        &ahkey,
+        &ah,
+        &test_metadata,
+        2,
+        now() - 60,
+        now() + 60,
+        None,
+        None,
+        None,
+        None,
+        None,
+        ListingConfigV1Args {
+            multi_unit: Some(true),
+            ..Default::default()
+        },
+    );
+    context
+        .banks_client
+        .process_transaction(sell_tx)
+        .await
+        .unwrap();
+
+    // The first bid is outbid once both units have higher bids.
+    let mut bids = vec![];
+    for price in [ONE_SOL, 2 * ONE_SOL, 3 * ONE_SOL] {
+        let buyer = Keypair::new();
+        airdrop(&mut context, &buyer.pubkey(), 10_000_000_000)
+            .await
+            .unwrap();
+        let (bid_acc, buy_tx) = buy_unit(
+            &mut context,
+            &ahkey,
+            &ah,
+            &test_metadata,
+            &test_metadata.token.pubkey(),
+            &buyer,
+            &sell_acc.wallet,
+            &listing_config_address,
+            price,
+            2,
+        );
+        context
+            .banks_client
+            .process_transaction(buy_tx)
+            .await
+            .unwrap();
+        bids.push((buyer, bid_acc));
+    }
+
+    // A bid that does not beat the lowest winning bid is rejected.
+    let low_buyer = Keypair::new();
+    airdrop(&mut context, &low_buyer.pubkey(), 10_000_000_000)
+        .await
+        .unwrap();
+    let (_, low_buy_tx) = buy_unit(
+        &mut context,
+        &ahkey,
+        &ah,
+        &test_metadata,
+        &test_metadata.token.pubkey(),
+        &low_buyer,
+        &sell_acc.wallet,
+        &listing_config_address,
+        2 * ONE_SOL,
+        2,
+    );
+    let result = context
+        .banks_client
+        .process_transaction(low_buy_tx)
+        .await
+        .unwrap_err();
+    assert_error!(result, BID_TOO_LOW);
+
+    context.warp_to_slot(120 * 400).unwrap();
+
+    // The highest bidder must sign to buy at the clearing price.
+    let (low_winner, low_winner_bid) = &bids[1];
+    let (high_winner, high_winner_bid) = &bids[2];
+    let tx = execute_multi_unit_sale(
+        &mut context,
+        &ahkey,
+        &ah,
+        &test_metadata,
+        &sell_acc,
+        &listing_config_address,
+        &[
+            (low_winner, low_winner_bid.buyer_trade_state, false),
+            (high_winner, high_winner_bid.buyer_trade_state, false),
+        ],
+        2 * ONE_SOL,
+        2,
+    );
+    let result = context
+        .banks_client
+        .process_transaction(tx)
+        .await
+        .unwrap_err();
+    assert_error!(result, WINNER_MUST_SIGN);
+
+    let seller_before = context
+        .banks_client
+        .get_account(test_metadata.token.pubkey())
+        .await
+        .unwrap()
+        .unwrap();
+
+    let tx = execute_multi_unit_sale(
+        &mut context,
+        &ahkey,
+        &ah,
+        &test_metadata,
+        &sell_acc,
+        &listing_config_address,
+        &[
+            (low_winner, low_winner_bid.buyer_trade_state, false),
+            (high_winner, high_winner_bid.buyer_trade_state, true),
+        ],
+        2 * ONE_SOL,
+        2,
+    );
+    context.banks_client.process_transaction(tx).await.unwrap();
+
+    for buyer in [low_winner.pubkey(), high_winner.pubkey()] {
+        let buyer_token_account =
+            get_associated_token_address(&buyer, &test_metadata.mint.pubkey());
+        let buyer_token_after = Account::unpack_from_slice(
+            context
+                .banks_client
+                .get_account(buyer_token_account)
+                .await
+                .unwrap()
+                .unwrap()
+                .data
+                .as_slice(),
+        )
+        .unwrap();
+        assert_eq!(buyer_token_after.amount, 1);
+    }
+
+    // The highest bidder pays the clearing price and keeps the rest of their bid in escrow.
+    let (high_bid_escrow, _) = find_escrow_payment_address(&ahkey, &high_winner.pubkey());
+    let high_bid_escrow_after = context
+        .banks_client
+        .get_account(high_bid_escrow)
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(high_bid_escrow_after.lamports >= ONE_SOL);
+    assert!(high_bid_escrow_after.lamports < 2 * ONE_SOL);
+
+    // The seller is paid the clearing price for each unit, less fees and royalties.
+    let seller_after = context
+        .banks_client
+        .get_account(test_metadata.token.pubkey())
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(seller_after.lamports > seller_before.lamports + 3 * ONE_SOL);
+    assert!(seller_after.lamports < seller_before.lamports + 4 * ONE_SOL + ONE_SOL / 10);
+
+    // Every unit is sold, so the sell order is closed.
+    let seller_trade_state = context
+        .banks_client
+        .get_account(sell_acc.seller_trade_state)
+        .await
+        .unwrap();
+    assert!(seller_trade_state.is_none());
+
+    let listing_config_closed = context
+        .banks_client
+        .get_account(listing_config_address)
+        .await
+        .unwrap();
+    assert!(listing_config_closed.is_none());
+}
+
+#[tokio::test]
+async fn multi_unit_fewer_bids_than_units_success() {
+    let mut context = auctioneer_program_test().start_with_context().await;
+    // Payer Wallet
+    let (ah, ahkey, _) = existing_auction_house_test_context(&mut context)
+        .await
+        .unwrap();
+    let test_metadata = Metadata::new();
+    airdrop(&mut context, &test_metadata.token.pubkey(), 10_000_000_000)
+        .await
+        .unwrap();
+    test_metadata
+        .create(
+            &mut context,
+            "Test".to_string(),
+            "TST".to_string(),
+            "uri".to_string(),
+            None,
+            10,
+            false,
+            3,
+        )
+        .await
+        .unwrap();
+    let ((sell_acc, listing_config_address), sell_tx) = sell_units(
+        &mut context,
+        &ahkey,
+        &ah,
+        &test_metadata,
+        3,
+        now() - 60,
+        now() + 60,
+        None,
+        None,
+        None,
+        None,
+        None,
+        ListingConfigV1Args {
+            multi_unit: Some(true),
+            ..Default::default()
+        },
+    );
+    context
+        .banks_client
+        .process_transaction(sell_tx)
+        .await
+        .unwrap();
+
+    let buyer = Keypair::new();
+    airdrop(&mut context, &buyer.pubkey(), 10_000_000_000)
+        .await
+        .unwrap();
+    let (bid_acc, buy_tx) = buy_unit(
+        &mut context,
+        &ahkey,
+        &ah,
+        &test_metadata,
+        &test_metadata.token.pubkey(),
+        &buyer,
+        &sell_acc.wallet,
+        &listing_config_address,
+        ONE_SOL,
+        3,
+    );
+    context
+        .banks_client
+        .process_transaction(buy_tx)
+        .await
+        .unwrap();
+
+    context.warp_to_slot(120 * 400).unwrap();
+
+    let tx = execute_multi_unit_sale(
+        &mut context,
+        &ahkey,
+        &ah,
+        &test_metadata,
+        &sell_acc,
+        &listing_config_address,
+        &[(&buyer, bid_acc.buyer_trade_state, false)],
+        ONE_SOL,
+        3,
+    );
+    context.banks_client.process_transaction(tx).await.unwrap();
+
+    let buyer_token_account =
+        get_associated_token_address(&buyer.pubkey(), &test_metadata.mint.pubkey());
+    let buyer_token_after = Account::unpack_from_slice(
+        context
+            .banks_client
+            .get_account(buyer_token_account)
+            .await
+            .unwrap()
+            .unwrap()
+            .data
+            .as_slice(),
+    )
+    .unwrap();
+    assert_eq!(buyer_token_after.amount, 1);
+
+    // The sell order for the units without a bid is canceled and the delegate revoked.
+    let seller_trade_state = context
+        .banks_client
+        .get_account(sell_acc.seller_trade_state)
+        .await
+        .unwrap();
+    assert!(seller_trade_state.is_none());
+
+    let seller_token_after = Account::unpack_from_slice(
+        context
+            .banks_client
+            .get_account(sell_acc.token_account)
+            .await
+            .unwrap()
+            .unwrap()
+            .data
+            .as_slice(),
+    )
+    .unwrap();
+    assert_eq!(seller_token_after.amount, 2);
+    assert!(seller_token_after.delegate.is_none());
+
+    let listing_config_closed = context
+        .banks_client
+        .get_account(listing_config_address)
+        .await
+        .unwrap();
+    assert!(listing_config_closed.is_none());
+}
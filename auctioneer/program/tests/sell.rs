@@ -51,6 +51,8 @@ async fn sell_success() {
         None,
         None,
         None,
+        None,
+        None,
     );
 
     context
@@ -66,3 +68,56 @@ async fn sell_success() {
         .expect("Trade State Empty");
     assert_eq!(sts.data.len(), 1);
 }
+
+#[tokio::test]
+async fn sell_rejects_max_end_time_not_after_end_time() {
+    let mut context = auctioneer_program_test().start_with_context().await;
+    let (ah, ahkey, _) = existing_auction_house_test_context(&mut context)
+        .await
+        .unwrap();
+    let test_metadata = Metadata::new();
+    let owner_pubkey = &test_metadata.token.pubkey();
+    airdrop(&mut context, owner_pubkey, TEN_SOL).await.unwrap();
+    test_metadata
+        .create(
+            &mut context,
+            "Test".to_string(),
+            "TST".to_string(),
+            "uri".to_string(),
+            None,
+            10,
+            false,
+            1,
+        )
+        .await
+        .unwrap();
+    let start_time = (SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .expect("Time went backwards")
+        .as_secs()) as i64;
+    let end_time = start_time + 60;
+
+    for max_end_time in [0, end_time] {
+        let (_, sell_tx) = sell(
+            &mut context,
+            &ahkey,
+            &ah,
+            &test_metadata,
+            start_time,
+            end_time,
+            None,
+            None,
+            None,
+            None,
+            None,
+            Some(max_end_time),
+            None,
+        );
+        let result = context
+            .banks_client
+            .process_transaction(sell_tx)
+            .await
+            .unwrap_err();
+        assert_error!(result, INVALID_MAX_END_TIME);
+    }
+}
@@ -0,0 +1,399 @@
+#![cfg(feature = "test-bpf")]
+pub mod common;
+pub mod utils;
+
+use common::*;
+use utils::setup_functions::*;
+
+use anchor_lang::{InstructionData, ToAccountMetas};
+use mpl_testing_utils::{solana::airdrop, utils::Metadata};
+use solana_sdk::{compute_budget::ComputeBudgetInstruction, signer::Signer};
+
+use std::{assert_eq, time::SystemTime};
+
+use solana_program::{instruction::Instruction, system_program, sysvar};
+
+use solana_program::program_pack::Pack;
+
+use mpl_auction_house::pda::{
+    find_auctioneer_pda, find_escrow_payment_address, find_program_as_signer_address,
+    find_trade_state_address,
+};
+use mpl_auctioneer::{pda::find_auctioneer_authority_seeds, sell::config::ListingConfigV1Args};
+use solana_sdk::{pubkey::Pubkey, signature::Keypair, transaction::Transaction};
+use spl_associated_token_account::get_associated_token_address;
+use spl_token::state::Account;
+
+fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .expect("Time went backwards")
+        .as_secs() as i64
+}
+
+fn settle(
+    context: &mut ProgramTestContext,
+    ahkey: &Pubkey,
+    ah: &AuctionHouse,
+    test_metadata: &Metadata,
+    sell_acc: &mpl_auctioneer::accounts::AuctioneerSell,
+    listing_config: &Pubkey,
+    buyer: &Pubkey,
+    buyer_trade_state: &Pubkey,
+    cranker: &Keypair,
+) -> Transaction {
+    let (auctioneer_authority, aa_bump) = find_auctioneer_authority_seeds(ahkey);
+    let (auctioneer_pda, _) = find_auctioneer_pda(ahkey, &auctioneer_authority);
+    let (escrow, escrow_bump) = find_escrow_payment_address(ahkey, buyer);
+    let accounts = mpl_auctioneer::accounts::AuctioneerSettle {
+        auction_house_program: mpl_auction_house::id(),
+        cranker: cranker.pubkey(),
+        listing_config: *listing_config,
+        buyer: *buyer,
+        seller: test_metadata.token.pubkey(),
+        authority: ah.authority,
+        auction_house: *ahkey,
+        metadata: test_metadata.pubkey,
+        token_account: sell_acc.token_account,
+        seller_trade_state: sell_acc.seller_trade_state,
+        buyer_trade_state: *buyer_trade_state,
+        token_program: spl_token::id(),
+        free_trade_state: sell_acc.free_seller_trade_state,
+        seller_payment_receipt_account: test_metadata.token.pubkey(),
+        buyer_receipt_token_account: get_associated_token_address(
+            buyer,
+            &test_metadata.mint.pubkey(),
+        ),
+        buyer_payment_receipt_account: *buyer,
+        escrow_payment_account: escrow,
+        token_mint: test_metadata.mint.pubkey(),
+        auction_house_fee_account: ah.auction_house_fee_account,
+        auction_house_treasury: ah.auction_house_treasury,
+        treasury_mint: ah.treasury_mint,
+        program_as_signer: sell_acc.program_as_signer,
+        system_program: system_program::id(),
+        ata_program: spl_associated_token_account::id(),
+        rent: sysvar::rent::id(),
+        auctioneer_authority,
+        ah_auctioneer_pda: auctioneer_pda,
+    }
+    .to_account_metas(None);
+    let (_, free_sts_bump) = find_trade_state_address(
+        &test_metadata.token.pubkey(),
+        ahkey,
+        &sell_acc.token_account,
+        &ah.treasury_mint,
+        &test_metadata.mint.pubkey(),
+        0,
+        1,
+    );
+    let (_, pas_bump) = find_program_as_signer_address();
+
+    let instruction = Instruction {
+        program_id: mpl_auctioneer::id(),
+        data: mpl_auctioneer::instruction::Settle {
+            escrow_payment_bump: escrow_bump,
+            free_trade_state_bump: free_sts_bump,
+            program_as_signer_bump: pas_bump,
+            auctioneer_authority_bump: aa_bump,
+            token_size: 1,
+        }
+        .data(),
+        accounts,
+    };
+
+    let compute_ix = ComputeBudgetInstruction::set_compute_unit_limit(350_000);
+
+    Transaction::new_signed_with_payer(
+        &[compute_ix, instruction],
+        Some(&cranker.pubkey()),
+        &[cranker],
+        context.last_blockhash,
+    )
+}
+
+#[tokio::test]
+async fn settle_sold_by_buyer_success() {
+    let mut context = auctioneer_program_test().start_with_context().await;
+    // Payer Wallet
+    let (ah, ahkey, _) = existing_auction_house_test_context(&mut context)
+        .await
+        .unwrap();
+    let test_metadata = Metadata::new();
+    airdrop(&mut context, &test_metadata.token.pubkey(), 10_000_000_000)
+        .await
+        .unwrap();
+    test_metadata
+        .create(
+            &mut context,
+            "Test".to_string(),
+            "TST".to_string(),
+            "uri".to_string(),
+            None,
+            10,
+            false,
+            1,
+        )
+        .await
+        .unwrap();
+    let ((sell_acc, listing_config_address), sell_tx) = sell_units(
+        &mut context,
+        &ahkey,
+        &ah,
+        &test_metadata,
+        1,
+        now() - 60,
+        now() + 60,
+        None,
+        None,
+        None,
+        None,
+        None,
+        ListingConfigV1Args::default(),
+    );
+    context
+        .banks_client
+        .process_transaction(sell_tx)
+        .await
+        .unwrap();
+
+    let buyer = Keypair::new();
+    airdrop(&mut context, &buyer.pubkey(), 10_000_000_000)
+        .await
+        .unwrap();
+    let (bid_acc, buy_tx) = buy(
+        &mut context,
+        &ahkey,
+        &ah,
+        &test_metadata,
+        &test_metadata.token.pubkey(),
+        &buyer,
+        &sell_acc.wallet,
+        &listing_config_address,
+        ONE_SOL,
+    );
+    context
+        .banks_client
+        .process_transaction(buy_tx)
+        .await
+        .unwrap();
+
+    context.warp_to_slot(120 * 400).unwrap();
+
+    // Anyone can settle, but Auction House needs the buyer, the seller or its authority to pay fees.
+    let stranger = Keypair::new();
+    airdrop(&mut context, &stranger.pubkey(), ONE_SOL)
+        .await
+        .unwrap();
+    let tx = settle(
+        &mut context,
+        &ahkey,
+        &ah,
+        &test_metadata,
+        &sell_acc,
+        &listing_config_address,
+        &buyer.pubkey(),
+        &bid_acc.buyer_trade_state,
+        &stranger,
+    );
+    let result = context
+        .banks_client
+        .process_transaction(tx)
+        .await
+        .unwrap_err();
+    assert_error!(result, NO_PAYER_PRESENT);
+
+    let listing_config_account = context
+        .banks_client
+        .get_account(listing_config_address)
+        .await
+        .unwrap()
+        .unwrap();
+    let seller_before = context
+        .banks_client
+        .get_account(test_metadata.token.pubkey())
+        .await
+        .unwrap()
+        .unwrap();
+    // The buyer settles the sale without the seller.
+    let tx = settle(
+        &mut context,
+        &ahkey,
+        &ah,
+        &test_metadata,
+        &sell_acc,
+        &listing_config_address,
+        &buyer.pubkey(),
+        &bid_acc.buyer_trade_state,
+        &buyer,
+    );
+    context.banks_client.process_transaction(tx).await.unwrap();
+
+    let buyer_token_account =
+        get_associated_token_address(&buyer.pubkey(), &test_metadata.mint.pubkey());
+    let buyer_token_after = Account::unpack_from_slice(
+        context
+            .banks_client
+            .get_account(buyer_token_account)
+            .await
+            .unwrap()
+            .unwrap()
+            .data
+            .as_slice(),
+    )
+    .unwrap();
+    assert_eq!(buyer_token_after.amount, 1);
+
+    // The seller is paid the sale proceeds and the Listing Config rent.
+    let seller_after = context
+        .banks_client
+        .get_account(test_metadata.token.pubkey())
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(seller_after.lamports > seller_before.lamports + listing_config_account.lamports);
+
+    let listing_config_closed = context
+        .banks_client
+        .get_account(listing_config_address)
+        .await
+        .unwrap();
+    assert!(listing_config_closed.is_none());
+}
+
+#[tokio::test]
+async fn settle_no_bids_by_seller_success() {
+    let mut context = auctioneer_program_test().start_with_context().await;
+    // Payer Wallet
+    let (ah, ahkey, _) = existing_auction_house_test_context(&mut context)
+        .await
+        .unwrap();
+    let test_metadata = Metadata::new();
+    airdrop(&mut context, &test_metadata.token.pubkey(), 10_000_000_000)
+        .await
+        .unwrap();
+    test_metadata
+        .create(
+            &mut context,
+            "Test".to_string(),
+            "TST".to_string(),
+            "uri".to_string(),
+            None,
+            10,
+            false,
+            1,
+        )
+        .await
+        .unwrap();
+    let ((sell_acc, listing_config_address), sell_tx) = sell_units(
+        &mut context,
+        &ahkey,
+        &ah,
+        &test_metadata,
+        1,
+        now() - 60,
+        now() + 60,
+        None,
+        None,
+        None,
+        None,
+        None,
+        ListingConfigV1Args::default(),
+    );
+    context
+        .banks_client
+        .process_transaction(sell_tx)
+        .await
+        .unwrap();
+
+    context.warp_to_slot(120 * 400).unwrap();
+
+    // With no bid there is no buyer, so only the seller or the authority can cancel the listing.
+    let nobody = Keypair::new();
+    airdrop(&mut context, &nobody.pubkey(), ONE_SOL)
+        .await
+        .unwrap();
+    let (nobody_trade_state, _) = find_trade_state_address(
+        &nobody.pubkey(),
+        &ahkey,
+        &sell_acc.token_account,
+        &ah.treasury_mint,
+        &test_metadata.mint.pubkey(),
+        ONE_SOL,
+        1,
+    );
+    let tx = settle(
+        &mut context,
+        &ahkey,
+        &ah,
+        &test_metadata,
+        &sell_acc,
+        &listing_config_address,
+        &nobody.pubkey(),
+        &nobody_trade_state,
+        &nobody,
+    );
+    let result = context
+        .banks_client
+        .process_transaction(tx)
+        .await
+        .unwrap_err();
+    assert_error!(result, NO_VALID_SIGNER_PRESENT);
+
+    let seller_before = context
+        .banks_client
+        .get_account(test_metadata.token.pubkey())
+        .await
+        .unwrap()
+        .unwrap();
+
+    let tx = settle(
+        &mut context,
+        &ahkey,
+        &ah,
+        &test_metadata,
+        &sell_acc,
+        &listing_config_address,
+        &nobody.pubkey(),
+        &nobody_trade_state,
+        &test_metadata.token,
+    );
+    context.banks_client.process_transaction(tx).await.unwrap();
+
+    // The listing is canceled and the token is no longer delegated.
+    let seller_trade_state = context
+        .banks_client
+        .get_account(sell_acc.seller_trade_state)
+        .await
+        .unwrap();
+    assert!(seller_trade_state.is_none());
+    let token_account = Account::unpack_from_slice(
+        context
+            .banks_client
+            .get_account(sell_acc.token_account)
+            .await
+            .unwrap()
+            .unwrap()
+            .data
+            .as_slice(),
+    )
+    .unwrap();
+    assert_eq!(token_account.amount, 1);
+    assert!(token_account.delegate.is_none());
+
+    // The seller gets the Listing Config rent back.
+    let seller_after = context
+        .banks_client
+        .get_account(test_metadata.token.pubkey())
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(seller_after.lamports > seller_before.lamports);
+
+    let listing_config_closed = context
+        .banks_client
+        .get_account(listing_config_address)
+        .await
+        .unwrap();
+    assert!(listing_config_closed.is_none());
+}
@@ -1,6 +1,7 @@
 use std::io;
 
 use anchor_client::solana_sdk::{
+    instruction::AccountMeta,
     pubkey::Pubkey,
     signature::{Keypair, Signer},
     system_program, sysvar,
@@ -15,7 +16,7 @@ use mpl_auction_house::{
     },
     AuctionHouse,
 };
-use mpl_auctioneer::pda::*;
+use mpl_auctioneer::{pda::*, sell::config::ListingConfigV1Args};
 use mpl_testing_utils::{solana::airdrop, utils::Metadata};
 use std::result::Result as StdResult;
 
@@ -212,6 +213,91 @@ pub fn buy(
     seller: &Pubkey,
     listing_config: &Pubkey,
     sale_price: u64,
+) -> (mpl_auctioneer::accounts::AuctioneerBuy, Transaction) {
+    buy_with_remaining_accounts(
+        context,
+        ahkey,
+        ah,
+        test_metadata,
+        owner,
+        buyer,
+        seller,
+        listing_config,
+        sale_price,
+        vec![],
+    )
+}
+
+pub fn buy_with_remaining_accounts(
+    context: &mut ProgramTestContext,
+    ahkey: &Pubkey,
+    ah: &AuctionHouse,
+    test_metadata: &Metadata,
+    owner: &Pubkey,
+    buyer: &Keypair,
+    seller: &Pubkey,
+    listing_config: &Pubkey,
+    sale_price: u64,
+    remaining_accounts: Vec<AccountMeta>,
+) -> (mpl_auctioneer::accounts::AuctioneerBuy, Transaction) {
+    buy_with_token_size(
+        context,
+        ahkey,
+        ah,
+        test_metadata,
+        owner,
+        buyer,
+        seller,
+        listing_config,
+        sale_price,
+        1,
+        1,
+        remaining_accounts,
+    )
+}
+
+/// Bid for a single unit of a multi-unit listing of `token_size` tokens.
+pub fn buy_unit(
+    context: &mut ProgramTestContext,
+    ahkey: &Pubkey,
+    ah: &AuctionHouse,
+    test_metadata: &Metadata,
+    owner: &Pubkey,
+    buyer: &Keypair,
+    seller: &Pubkey,
+    listing_config: &Pubkey,
+    sale_price: u64,
+    token_size: u64,
+) -> (mpl_auctioneer::accounts::AuctioneerBuy, Transaction) {
+    buy_with_token_size(
+        context,
+        ahkey,
+        ah,
+        test_metadata,
+        owner,
+        buyer,
+        seller,
+        listing_config,
+        sale_price,
+        token_size,
+        1,
+        vec![],
+    )
+}
+
+pub fn buy_with_token_size(
+    context: &mut ProgramTestContext,
+    ahkey: &Pubkey,
+    ah: &AuctionHouse,
+    test_metadata: &Metadata,
+    owner: &Pubkey,
+    buyer: &Keypair,
+    seller: &Pubkey,
+    listing_config: &Pubkey,
+    sale_price: u64,
+    token_size: u64,
+    bid_token_size: u64,
+    remaining_accounts: Vec<AccountMeta>,
 ) -> (mpl_auctioneer::accounts::AuctioneerBuy, Transaction) {
     let seller_token_account = get_associated_token_address(owner, &test_metadata.mint.pubkey());
     let trade_state = find_trade_state_address(
@@ -221,7 +307,7 @@ pub fn buy(
         &ah.treasury_mint,
         &test_metadata.mint.pubkey(),
         sale_price,
-        1,
+        bid_token_size,
     );
     let (auctioneer_authority, aa_bump) = find_auctioneer_authority_seeds(ahkey);
     let (escrow, escrow_bump) = find_escrow_payment_address(ahkey, &buyer.pubkey());
@@ -249,13 +335,14 @@ pub fn buy(
         ah_auctioneer_pda: auctioneer_pda,
     };
 
-    let account_metas = accounts.to_account_metas(None);
+    let mut account_metas = accounts.to_account_metas(None);
+    account_metas.extend(remaining_accounts);
 
     let buy_ix = mpl_auctioneer::instruction::Buy {
         trade_state_bump: bts_bump,
         escrow_payment_bump: escrow_bump,
         auctioneer_authority_bump: aa_bump,
-        token_size: 1,
+        token_size,
         buyer_price: sale_price,
     };
     let data = buy_ix.data();
@@ -376,6 +463,8 @@ pub fn sell_mint(
     time_ext_period: Option<u32>,
     time_ext_delta: Option<u32>,
     allow_high_bid_cancel: Option<bool>,
+    max_end_time: Option<UnixTimestamp>,
+    bid_cooldown: Option<u32>,
 ) -> (
     (mpl_auctioneer::accounts::AuctioneerSell, Pubkey),
     Transaction,
@@ -433,12 +522,12 @@ pub fn sell_mint(
     };
     let account_metas = accounts.to_account_metas(None);
 
-    let data = mpl_auctioneer::instruction::Sell {
-        trade_state_bump: sts_bump,
-        free_trade_state_bump: free_sts_bump,
-        program_as_signer_bump: pas_bump,
-        auctioneer_authority_bump: aa_bump,
-        token_size: 1,
+    let data = sell_data(
+        sts_bump,
+        free_sts_bump,
+        pas_bump,
+        aa_bump,
+        1,
         start_time,
         end_time,
         reserve_price,
@@ -446,8 +535,12 @@ pub fn sell_mint(
         time_ext_period,
         time_ext_delta,
         allow_high_bid_cancel,
-    }
-    .data();
+        ListingConfigV1Args {
+            max_end_time,
+            bid_cooldown,
+            ..Default::default()
+        },
+    );
 
     let instruction = Instruction {
         program_id: mpl_auctioneer::id(),
@@ -466,6 +559,63 @@ pub fn sell_mint(
     )
 }
 
+/// Instruction data for `sell`, or `sell_v1` when any of the V1 listing settings is set.
+fn sell_data(
+    trade_state_bump: u8,
+    free_trade_state_bump: u8,
+    program_as_signer_bump: u8,
+    auctioneer_authority_bump: u8,
+    token_size: u64,
+    start_time: UnixTimestamp,
+    end_time: UnixTimestamp,
+    reserve_price: Option<u64>,
+    min_bid_increment: Option<u64>,
+    time_ext_period: Option<u32>,
+    time_ext_delta: Option<u32>,
+    allow_high_bid_cancel: Option<bool>,
+    listing_config_args: ListingConfigV1Args,
+) -> Vec<u8> {
+    let ListingConfigV1Args {
+        max_end_time,
+        bid_cooldown,
+        multi_unit,
+    } = &listing_config_args;
+    if max_end_time.is_none() && bid_cooldown.is_none() && multi_unit.is_none() {
+        mpl_auctioneer::instruction::Sell {
+            trade_state_bump,
+            free_trade_state_bump,
+            program_as_signer_bump,
+            auctioneer_authority_bump,
+            token_size,
+            start_time,
+            end_time,
+            reserve_price,
+            min_bid_increment,
+            time_ext_period,
+            time_ext_delta,
+            allow_high_bid_cancel,
+        }
+        .data()
+    } else {
+        mpl_auctioneer::instruction::SellV1 {
+            trade_state_bump,
+            free_trade_state_bump,
+            program_as_signer_bump,
+            auctioneer_authority_bump,
+            token_size,
+            start_time,
+            end_time,
+            reserve_price,
+            min_bid_increment,
+            time_ext_period,
+            time_ext_delta,
+            allow_high_bid_cancel,
+            listing_config_args,
+        }
+        .data()
+    }
+}
+
 pub fn sell(
     context: &mut ProgramTestContext,
     ahkey: &Pubkey,
@@ -478,19 +628,66 @@ pub fn sell(
     time_ext_period: Option<u32>,
     time_ext_delta: Option<u32>,
     allow_high_bid_cancel: Option<bool>,
+    max_end_time: Option<UnixTimestamp>,
+    bid_cooldown: Option<u32>,
+) -> (
+    (mpl_auctioneer::accounts::AuctioneerSell, Pubkey),
+    Transaction,
+) {
+    sell_units(
+        context,
+        ahkey,
+        ah,
+        test_metadata,
+        1,
+        start_time,
+        end_time,
+        reserve_price,
+        min_bid_increment,
+        time_ext_period,
+        time_ext_delta,
+        allow_high_bid_cancel,
+        ListingConfigV1Args {
+            max_end_time,
+            bid_cooldown,
+            ..Default::default()
+        },
+    )
+}
+
+pub fn sell_units(
+    context: &mut ProgramTestContext,
+    ahkey: &Pubkey,
+    ah: &AuctionHouse,
+    test_metadata: &Metadata,
+    token_size: u64,
+    start_time: UnixTimestamp,
+    end_time: UnixTimestamp,
+    reserve_price: Option<u64>,
+    min_bid_increment: Option<u64>,
+    time_ext_period: Option<u32>,
+    time_ext_delta: Option<u32>,
+    allow_high_bid_cancel: Option<bool>,
+    listing_config_args: ListingConfigV1Args,
 ) -> (
     (mpl_auctioneer::accounts::AuctioneerSell, Pubkey),
     Transaction,
 ) {
     let token =
         get_associated_token_address(&test_metadata.token.pubkey(), &test_metadata.mint.pubkey());
+    // Multi-unit listings are placed with Auction House for a single unit.
+    let trade_state_size = if listing_config_args.multi_unit == Some(true) {
+        1
+    } else {
+        token_size
+    };
     let (seller_trade_state, sts_bump) = find_auctioneer_trade_state_address(
         &test_metadata.token.pubkey(),
         ahkey,
         &token,
         &ah.treasury_mint,
         &test_metadata.mint.pubkey(),
-        1,
+        trade_state_size,
     );
 
     let (free_seller_trade_state, free_sts_bump) = find_trade_state_address(
@@ -500,7 +697,7 @@ pub fn sell(
         &ah.treasury_mint,
         &test_metadata.mint.pubkey(),
         0,
-        1,
+        trade_state_size,
     );
 
     let (listing_config_address, _list_bump) = find_listing_config_address(
@@ -509,7 +706,7 @@ pub fn sell(
         &token,
         &ah.treasury_mint,
         &test_metadata.mint.pubkey(),
-        1,
+        token_size,
     );
 
     let (pas, pas_bump) = find_program_as_signer_address();
@@ -536,12 +733,12 @@ pub fn sell(
     };
     let account_metas = accounts.to_account_metas(None);
 
-    let data = mpl_auctioneer::instruction::Sell {
-        trade_state_bump: sts_bump,
-        free_trade_state_bump: free_sts_bump,
-        program_as_signer_bump: pas_bump,
-        auctioneer_authority_bump: aa_bump,
-        token_size: 1,
+    let data = sell_data(
+        sts_bump,
+        free_sts_bump,
+        pas_bump,
+        aa_bump,
+        token_size,
         start_time,
         end_time,
         reserve_price,
@@ -549,8 +746,8 @@ pub fn sell(
         time_ext_period,
         time_ext_delta,
         allow_high_bid_cancel,
-    }
-    .data();
+        listing_config_args,
+    );
 
     let instruction = Instruction {
         program_id: mpl_auctioneer::id(),
@@ -569,6 +766,47 @@ pub fn sell(
     )
 }
 
+pub fn create_bid_history(
+    context: &mut ProgramTestContext,
+    ahkey: &Pubkey,
+    test_metadata: &Metadata,
+    listing_config: &Pubkey,
+    capacity: u8,
+) -> (mpl_auctioneer::accounts::CreateBidHistory, Transaction) {
+    let token =
+        get_associated_token_address(&test_metadata.token.pubkey(), &test_metadata.mint.pubkey());
+    let (bid_history, _) = find_bid_history_address(listing_config);
+
+    let accounts = mpl_auctioneer::accounts::CreateBidHistory {
+        wallet: test_metadata.token.pubkey(),
+        listing_config: *listing_config,
+        bid_history,
+        token_account: token,
+        auction_house: *ahkey,
+        system_program: system_program::id(),
+    };
+
+    let instruction = Instruction {
+        program_id: mpl_auctioneer::id(),
+        data: mpl_auctioneer::instruction::CreateBidHistory {
+            token_size: 1,
+            capacity,
+        }
+        .data(),
+        accounts: accounts.to_account_metas(None),
+    };
+
+    (
+        accounts,
+        Transaction::new_signed_with_payer(
+            &[instruction],
+            Some(&test_metadata.token.pubkey()),
+            &[&test_metadata.token],
+            context.last_blockhash,
+        ),
+    )
+}
+
 pub fn withdraw(
     context: &mut ProgramTestContext,
     buyer: &Keypair,
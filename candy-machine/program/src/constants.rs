@@ -5,20 +5,37 @@ use solana_program::pubkey::Pubkey;
 
 pub const EXPIRE_OFFSET: i64 = 10 * 60;
 pub const PREFIX: &str = "candy_machine";
+// seeds the PDA a mint authority program signs candy machine mints with
+pub const MINT_AUTHORITY_PREFIX: &str = "mint_authority";
 
 pub const BOT_FEE: u64 = 10000000;
 pub const FREEZE_FEE: u64 = 0; //100000; // 0.0001 SOL
 
 pub const MAX_FREEZE_TIME: i64 = 60 * 60 * 24 * 31; // 1 month
 
-pub const COLLECTIONS_FEATURE_INDEX: usize = 0;
-pub const FREEZE_FEATURE_INDEX: usize = 1;
-pub const FREEZE_LOCK_FEATURE_INDEX: usize = 2;
+pub const CANDY_MACHINE_VERSION: u8 = 1;
+
+// accounts from before versioning flag their features in the first uuid bytes instead
+pub const LEGACY_FEATURE_COUNT: usize = 6;
 
 pub const COLLECTION_PDA_SIZE: usize = 8 + 32 + 32;
 
+pub const MAX_MINT_PHASES: usize = 8;
+pub const MAX_PHASE_LABEL_LENGTH: usize = 32;
+pub const MAX_PAYMENT_OPTIONS: usize = 8;
+pub const MAX_REVENUE_SPLITS: usize = 5;
+pub const MAX_MINT_QUANTITY: u8 = 10;
+
 pub const CONFIG_LINE_SIZE: usize = 4 + MAX_NAME_LENGTH + 4 + MAX_URI_LENGTH;
 
+// written after the loaded-lines bitmask of candy machines that pick config lines from the
+// swap-remove index array instead of scanning the legacy taken-lines bitmask
+pub const CONFIG_INDICES_MARKER: u32 = u32::MAX;
+
+// written in place of the config line count of candy machines with compressed config lines,
+// the ConfigLineSettings and the actual count follow it
+pub const CONFIG_LINE_SETTINGS_MARKER: u32 = u32::MAX;
+
 pub const BLOCK_HASHES: Pubkey =
     solana_program::pubkey!("SysvarRecentB1ockHashes11111111111111111111");
 pub const GUMDROP_ID: Pubkey =
@@ -29,7 +46,8 @@ pub const A_TOKEN: Pubkey = solana_program::pubkey!("ATokenGPvbdGVxr1b2hvZbsiqW5
 pub const COMPUTE_BUDGET: Pubkey =
     solana_program::pubkey!("ComputeBudget111111111111111111111111111111");
 
-pub const CONFIG_ARRAY_START: usize = 8 + // key
+// config lines of accounts from before versioning start right after the candy machine data
+pub const LEGACY_CONFIG_ARRAY_START: usize = 8 + // key
     32 + // authority
     32 + //wallet
     33 + // token mint
@@ -48,7 +66,8 @@ pub const CONFIG_ARRAY_START: usize = 8 + // key
     4 + MAX_NAME_LENGTH + // name length,
     4 + MAX_URI_LENGTH + // uri length,
     32 + // hash
-    4 +  // max number of lines;
+    1 + // account version
+    3 + // unused, left over from the max number of lines, the last byte flags mint settings
     8 + // items redeemed
     1 + // whitelist option
     1 + // whitelist mint mode
@@ -56,3 +75,17 @@ pub const CONFIG_ARRAY_START: usize = 8 + // key
     9 + // discount price
     32 + // mint key for whitelist
     1 + 32 + 1; // gatekeeper
+
+// versioned accounts mark their version in a byte legacy accounts never wrote to, the candy
+// machine data can't grow over it
+pub const VERSION_OFFSET: usize = LEGACY_CONFIG_ARRAY_START - 3;
+
+// legacy accounts ran out of uuid feature bytes and flag mint settings in their last unused byte
+pub const LEGACY_MINT_SETTINGS_OFFSET: usize = LEGACY_CONFIG_ARRAY_START - 1;
+
+// the optional BotTax, candy machines without one tax every failure at BOT_FEE
+pub const BOT_TAX_OFFSET: usize = LEGACY_CONFIG_ARRAY_START;
+
+pub const CONFIG_ARRAY_START: usize = BOT_TAX_OFFSET +
+    44 + // bot tax
+    20; // reserved
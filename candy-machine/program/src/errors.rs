@@ -112,4 +112,138 @@ pub enum CandyError {
     CannotSwitchFromHiddenSettings,
     #[msg("Invalid Metadata Account")]
     InvalidMetadataAccount,
+    #[msg("Mint phases must be in order, not overlap and have valid labels")]
+    InvalidMintPhases,
+    #[msg("Mint phases account doesn't belong to this Candy Machine.")]
+    MintPhasesMismatch,
+    #[msg("Can't pay for a mint phase in another token while freeze is enabled.")]
+    NoPhasePaymentWithFreeze,
+    #[msg("The wallet has reached its mint limit")]
+    MintLimitReached,
+    #[msg("Mint counter address is invalid")]
+    MismatchedMintCounter,
+    #[msg("Mint limit must be greater than zero")]
+    InvalidMintLimit,
+    #[msg("Mint limit account doesn't belong to this Candy Machine.")]
+    MintLimitMismatch,
+    #[msg("Candy Machine doesn't use a merkle tree allowlist")]
+    NotMerkleAllowlist,
+    #[msg("Invalid allowlist merkle proof")]
+    InvalidAllowlistProof,
+    #[msg("Payment options must be unique and burn a non zero amount")]
+    InvalidPaymentOptions,
+    #[msg("Payment options account doesn't belong to this Candy Machine.")]
+    PaymentOptionsMismatch,
+    #[msg("Can't use payment options while freeze is enabled.")]
+    NoPaymentOptionsWithFreeze,
+    #[msg("Mint phase payments can't be combined with payment options.")]
+    PhasePaymentWithPaymentOptions,
+    #[msg("The payment doesn't match any of the Candy Machine payment options")]
+    InvalidPaymentOption,
+    #[msg("The wallet doesn't hold an NFT from the required collection")]
+    NotCollectionHolder,
+    #[msg("This NFT has already been used to mint")]
+    HolderNftAlreadyUsed,
+    #[msg("Holder mint record address is invalid")]
+    MismatchedHolderMintRecord,
+    #[msg("Randomness oracles can't be used with collections or freeze.")]
+    RandomnessNotSupported,
+    #[msg("Mint request address is invalid")]
+    MismatchedMintRequest,
+    #[msg("Randomness can only be revealed once, with the committed seed, in a later slot than the request")]
+    InvalidRandomnessReveal,
+    #[msg("The oracle hasn't revealed the randomness for this mint request yet")]
+    RandomnessNotRevealed,
+    #[msg("Mint settings account doesn't belong to this Candy Machine.")]
+    MintSettingsMismatch,
+    #[msg("Candy Machine already uses the config index array")]
+    ConfigIndicesAlreadyMigrated,
+    #[msg("Config line prefixes and suffix lengths must fit in the metadata name and URI")]
+    InvalidConfigLineSettings,
+    #[msg("Config line is longer than the config line settings allow")]
+    ConfigLineTooLong,
+    #[msg("Candy Machine account is too small for its config lines")]
+    CandyMachineTooSmall,
+    #[msg(
+        "Can't switch between sequential and random minting after items have begun to be minted"
+    )]
+    CannotChangeSequentialDuringMint,
+    #[msg("On-chain reveal requires hidden settings, retained authority and no collection")]
+    HiddenRevealNotSupported,
+    #[msg("Reveal lines must be uploaded in mint order, or from the start again")]
+    RevealLinesOutOfOrder,
+    #[msg("Reveal lines don't match the hidden settings hash")]
+    RevealHashMismatch,
+    #[msg("Not all reveal lines have been uploaded yet")]
+    RevealLinesIncomplete,
+    #[msg("This NFT isn't waiting for a reveal from this Candy Machine")]
+    NftNotRevealable,
+    #[msg("Programmable NFTs can't be minted with randomness or an on-chain reveal")]
+    ProgrammableNotSupported,
+    #[msg("Mint settings programmable NFT accounts are missing or invalid")]
+    InvalidProgrammableAccounts,
+    #[msg("Migrate the candy machine account to the current version first")]
+    CandyMachineNotMigrated,
+    #[msg("Candy machine account is already on the current version")]
+    CandyMachineAlreadyMigrated,
+    #[msg("Revenue splits need distinct recipients with shares adding up to 10000 basis points")]
+    InvalidRevenueSplits,
+    #[msg("Revenue splits account doesn't belong to this candy machine")]
+    RevenueSplitsMismatch,
+    #[msg("Revenue splits can't be used with freeze, which holds the mint proceeds")]
+    RevenueSplitsWithFreeze,
+    #[msg("Can't change the payment token while revenue splits are set")]
+    NoChangingTokenWithRevenueSplits,
+    #[msg("Refunds are only available once the freeze period ends without a sell-out")]
+    RefundNotAvailable,
+    #[msg("Can't remove freeze once NFTs have been minted with refunds")]
+    NoRemovingFreezeWithRefunds,
+    #[msg("Only the holder can thaw an NFT while refunds are open")]
+    RefundThawRequiresOwner,
+    #[msg("Minting ended without a sell-out, refunds are open")]
+    MintEndedWithRefunds,
+    #[msg("This NFT isn't frozen by the candy machine")]
+    NftNotRefundable,
+    #[msg("Can only mint between 1 and the max mint quantity at once")]
+    InvalidMintQuantity,
+    #[msg("Minting many NFTs at once doesn't support collections, freeze, editions, randomness oracles, programmable NFTs or payment options")]
+    MintManyNotSupported,
+    #[msg("Candy machine has to be sold out")]
+    CandyMachineNotSoldOut,
+    #[msg("Config data of this candy machine was closed")]
+    ConfigDataClosed,
+    #[msg("Mint requests of the randomness oracle still need the config data")]
+    ConfigDataInUse,
+    #[msg("The recipient has to sign to expire its gateway token")]
+    RecipientMustSign,
+    #[msg("Mints have to come from the mint authority program, signed by its PDA")]
+    InvalidMintAuthority,
+    #[msg("Mint authority programs can't be used with collections")]
+    MintAuthorityNotSupported,
+    #[msg("The candy machine creator has to hold the master edition token")]
+    InvalidMasterEdition,
+    #[msg("Edition settings account doesn't belong to this candy machine")]
+    EditionSettingsMismatch,
+    #[msg("Editions can't be minted with randomness oracles, hidden reveals or programmable NFTs")]
+    EditionsNotSupported,
+    #[msg("Candy machine has to be sold out or past its end settings")]
+    CandyMachineNotEnded,
+    #[msg("Allowlist root account doesn't belong to this Candy Machine.")]
+    AllowlistRootMismatch,
+    #[msg("Payment options can't be combined with mint phases or revenue splits.")]
+    PaymentOptionsNotSupported,
+    #[msg("The hash of the request slot is no longer in the slot hashes")]
+    RequestSlotHashExpired,
+    #[msg("Hidden mint record address is invalid")]
+    MismatchedHiddenMintRecord,
+    #[msg("Refund freezes need every NFT to be minted at the candy machine price")]
+    FreezeRefundPriceMismatch,
+    #[msg("Bot tax destination must be passed writable as the last remaining account")]
+    BotTaxDestinationMismatch,
+    #[msg("Candy machines paid in tokens can't pay the bot tax to the wallet")]
+    BotTaxWalletWithToken,
+    #[msg("The master edition can't print enough editions for the items available")]
+    EditionSupplyTooLow,
+    #[msg("Candy machine data would overwrite the account version")]
+    CandyMachineDataTooLong,
 }
@@ -1,5 +1,6 @@
 pub mod constants;
 pub mod errors;
+pub mod merkle_proof;
 pub mod processor;
 pub mod state;
 pub mod utils;
@@ -19,7 +20,15 @@ pub mod candy_machine {
         ctx: Context<InitializeCandyMachine>,
         data: CandyMachineData,
     ) -> Result<()> {
-        handle_initialize_candy_machine(ctx, data)
+        handle_initialize_candy_machine(ctx, data, None)
+    }
+
+    pub fn initialize_compressed_candy_machine(
+        ctx: Context<InitializeCandyMachine>,
+        data: CandyMachineData,
+        config_line_settings: ConfigLineSettings,
+    ) -> Result<()> {
+        handle_initialize_candy_machine(ctx, data, Some(config_line_settings))
     }
 
     pub fn update_candy_machine(
@@ -36,6 +45,14 @@ pub mod candy_machine {
         handle_update_authority(ctx, new_authority)
     }
 
+    pub fn update_bot_tax(ctx: Context<UpdateCandyMachine>, bot_tax: Option<BotTax>) -> Result<()> {
+        handle_update_bot_tax(ctx, bot_tax)
+    }
+
+    pub fn update_sequential(ctx: Context<UpdateCandyMachine>, sequential: bool) -> Result<()> {
+        handle_update_sequential(ctx, sequential)
+    }
+
     pub fn add_config_lines(
         ctx: Context<AddConfigLines>,
         index: u32,
@@ -44,6 +61,20 @@ pub mod candy_machine {
         handle_add_config_lines(ctx, index, config_lines)
     }
 
+    pub fn migrate_candy_machine_version(ctx: Context<MigrateCandyMachineVersion>) -> Result<()> {
+        handle_migrate_candy_machine_version(ctx)
+    }
+
+    pub fn migrate_config_indices(ctx: Context<MigrateConfigIndices>) -> Result<()> {
+        handle_migrate_config_indices(ctx)
+    }
+
+    pub fn close_config_data<'info>(
+        ctx: Context<'_, '_, '_, 'info, CloseConfigData<'info>>,
+    ) -> Result<()> {
+        handle_close_config_data(ctx)
+    }
+
     pub fn set_collection(ctx: Context<SetCollection>) -> Result<()> {
         handle_set_collection(ctx)
     }
@@ -59,6 +90,21 @@ pub mod candy_machine {
         handle_mint_nft(ctx, creator_bump)
     }
 
+    pub fn mint_nft_to<'info>(
+        ctx: Context<'_, '_, '_, 'info, MintNFT<'info>>,
+        creator_bump: u8,
+    ) -> Result<()> {
+        handle_mint_nft_to(ctx, creator_bump)
+    }
+
+    pub fn mint_many<'info>(
+        ctx: Context<'_, '_, '_, 'info, MintNFT<'info>>,
+        creator_bump: u8,
+        quantity: u8,
+    ) -> Result<()> {
+        handle_mint_many(ctx, creator_bump, quantity)
+    }
+
     pub fn set_collection_during_mint(ctx: Context<SetCollectionDuringMint>) -> Result<()> {
         handle_set_collection_during_mint(ctx)
     }
@@ -70,18 +116,147 @@ pub mod candy_machine {
     }
 
     pub fn set_freeze(ctx: Context<SetFreeze>, freeze_time: i64) -> Result<()> {
-        handle_set_freeze(ctx, freeze_time)
+        handle_set_freeze(ctx, freeze_time, false)
+    }
+
+    /// Set the freeze like `set_freeze`, also letting holders burn their NFTs for a refund of
+    /// the candy machine price if the freeze ends without a sell-out.
+    pub fn set_refundable_freeze(ctx: Context<SetFreeze>, freeze_time: i64) -> Result<()> {
+        handle_set_freeze(ctx, freeze_time, true)
     }
 
     pub fn remove_freeze(ctx: Context<RemoveFreeze>) -> Result<()> {
         handle_remove_freeze(ctx)
     }
 
-    pub fn thaw_nft(ctx: Context<ThawNFT>) -> Result<()> {
+    pub fn thaw_nft<'info>(ctx: Context<'_, '_, '_, 'info, ThawNFT<'info>>) -> Result<()> {
         handle_thaw_nft(ctx)
     }
 
+    pub fn thaw_nfts<'info>(ctx: Context<'_, '_, '_, 'info, ThawNFTs<'info>>) -> Result<()> {
+        handle_thaw_nfts(ctx)
+    }
+
+    pub fn refund_nft<'info>(ctx: Context<'_, '_, '_, 'info, RefundNFT<'info>>) -> Result<()> {
+        handle_refund_nft(ctx)
+    }
+
     pub fn unlock_funds<'info>(ctx: Context<'_, '_, '_, 'info, UnlockFunds<'info>>) -> Result<()> {
         handle_unlock_funds(ctx)
     }
+
+    pub fn set_mint_phases<'info>(
+        ctx: Context<'_, '_, '_, 'info, SetMintPhases<'info>>,
+        phases: Vec<MintPhase>,
+    ) -> Result<()> {
+        handle_set_mint_phases(ctx, phases)
+    }
+
+    pub fn remove_mint_phases(ctx: Context<RemoveMintPhases>) -> Result<()> {
+        handle_remove_mint_phases(ctx)
+    }
+
+    pub fn set_allowlist_root(ctx: Context<SetAllowlistRoot>, root: [u8; 32]) -> Result<()> {
+        handle_set_allowlist_root(ctx, root)
+    }
+
+    pub fn remove_allowlist_root(ctx: Context<RemoveAllowlistRoot>) -> Result<()> {
+        handle_remove_allowlist_root(ctx)
+    }
+
+    pub fn prove_allowlist(
+        ctx: Context<ProveAllowlist>,
+        proof: Vec<[u8; 32]>,
+        index: u64,
+        allocation: u64,
+    ) -> Result<()> {
+        handle_prove_allowlist(ctx, proof, index, allocation)
+    }
+
+    pub fn set_payment_options<'info>(
+        ctx: Context<'_, '_, '_, 'info, SetPaymentOptions<'info>>,
+        options: Vec<PaymentOption>,
+        required: bool,
+    ) -> Result<()> {
+        handle_set_payment_options(ctx, options, required)
+    }
+
+    pub fn remove_payment_options(ctx: Context<RemovePaymentOptions>) -> Result<()> {
+        handle_remove_payment_options(ctx)
+    }
+
+    pub fn set_mint_limit(ctx: Context<SetMintLimit>, limit: u16) -> Result<()> {
+        handle_set_mint_limit(ctx, limit)
+    }
+
+    pub fn remove_mint_limit(ctx: Context<RemoveMintLimit>) -> Result<()> {
+        handle_remove_mint_limit(ctx)
+    }
+
+    pub fn close_mint_counter(ctx: Context<CloseMintCounter>) -> Result<()> {
+        handle_close_mint_counter(ctx)
+    }
+
+    pub fn set_revenue_splits(
+        ctx: Context<SetRevenueSplits>,
+        splits: Vec<RevenueSplit>,
+    ) -> Result<()> {
+        handle_set_revenue_splits(ctx, splits)
+    }
+
+    pub fn remove_revenue_splits(ctx: Context<RemoveRevenueSplits>) -> Result<()> {
+        handle_remove_revenue_splits(ctx)
+    }
+
+    pub fn set_edition_settings(ctx: Context<SetEditionSettings>) -> Result<()> {
+        handle_set_edition_settings(ctx)
+    }
+
+    pub fn remove_edition_settings(ctx: Context<RemoveEditionSettings>) -> Result<()> {
+        handle_remove_edition_settings(ctx)
+    }
+
+    pub fn set_mint_settings(
+        ctx: Context<SetMintSettings>,
+        randomness_oracle: Option<RandomnessOracle>,
+        reveal_hash: Option<[u8; 32]>,
+        programmable: Option<ProgrammableSettings>,
+        mint_authority_program: Option<Pubkey>,
+    ) -> Result<()> {
+        handle_set_mint_settings(
+            ctx,
+            randomness_oracle,
+            reveal_hash,
+            programmable,
+            mint_authority_program,
+        )
+    }
+
+    pub fn remove_mint_settings(ctx: Context<RemoveMintSettings>) -> Result<()> {
+        handle_remove_mint_settings(ctx)
+    }
+
+    pub fn reveal_mint_randomness(
+        ctx: Context<RevealMintRandomness>,
+        seed: [u8; 32],
+        next_commitment: [u8; 32],
+    ) -> Result<()> {
+        handle_reveal_mint_randomness(ctx, seed, next_commitment)
+    }
+
+    pub fn add_reveal_lines(
+        ctx: Context<AddRevealLines>,
+        index: u32,
+        config_lines: Vec<ConfigLine>,
+    ) -> Result<()> {
+        handle_add_reveal_lines(ctx, index, config_lines)
+    }
+
+    pub fn reveal_nft(ctx: Context<RevealNft>, creator_bump: u8) -> Result<()> {
+        handle_reveal_nft(ctx, creator_bump)
+    }
+
+    pub fn fulfill_mint(ctx: Context<FulfillMint>, creator_bump: u8) -> Result<()> {
+        handle_fulfill_mint(ctx, creator_bump)
+    }
 }
@@ -5,8 +5,9 @@ use arrayref::array_ref;
 use mpl_token_metadata::state::{MAX_NAME_LENGTH, MAX_URI_LENGTH};
 
 use crate::{
-    constants::{CONFIG_ARRAY_START, CONFIG_LINE_SIZE},
-    CandyError, CandyMachine, ConfigLine,
+    get_config_array_start, get_config_bit_mask_start, get_config_count_start,
+    get_config_line_settings, get_config_line_size, is_config_data_closed, CandyError,
+    CandyMachine, ConfigLine,
 };
 
 /// Add multiple config lines to the candy machine.
@@ -24,9 +25,14 @@ pub fn handle_add_config_lines(
 ) -> Result<()> {
     let candy_machine = &mut ctx.accounts.candy_machine;
     let account = candy_machine.to_account_info();
-    let current_count = get_config_count(&account.data.borrow_mut())?;
+    if is_config_data_closed(&account.data.borrow()) {
+        return err!(CandyError::ConfigDataClosed);
+    }
+    let config_start = get_config_array_start(&account.data.borrow());
+    let line_settings = get_config_line_settings(&account.data.borrow())?;
+    let count_start = get_config_count_start(config_start, line_settings.as_ref());
+    let current_count = get_config_count(&account.data.borrow_mut(), count_start)?;
     let mut data = account.data.borrow_mut();
-    let mut fixed_config_lines = Vec::with_capacity(config_lines.len());
 
     // no risk overflow because you literally cannot store this many in an account
     // going beyond u32 only happens with the hidden settings candies
@@ -45,33 +51,62 @@ pub fn handle_add_config_lines(
     if candy_machine.data.hidden_settings.is_some() {
         return err!(CandyError::HiddenSettingsConfigsDoNotHaveConfigLines);
     }
-    for line in &config_lines {
-        let array_of_zeroes = vec![0u8; MAX_NAME_LENGTH - line.name.len()];
-        let name = line.name.clone() + std::str::from_utf8(&array_of_zeroes).unwrap();
-
-        let array_of_zeroes = vec![0u8; MAX_URI_LENGTH - line.uri.len()];
-        let uri = line.uri.clone() + std::str::from_utf8(&array_of_zeroes).unwrap();
-        fixed_config_lines.push(ConfigLine { name, uri })
-    }
 
-    let as_vec = fixed_config_lines.try_to_vec()?;
-    // remove unneeded u32 because we're just gonna edit the u32 at the front
-    let serialized: &[u8] = &as_vec.as_slice()[4..];
+    let line_size = get_config_line_size(line_settings.as_ref());
+    let serialized = match &line_settings {
+        // compressed config lines only hold the suffixes, padded to their max length
+        Some(settings) => {
+            let mut serialized = Vec::with_capacity(config_lines.len() * line_size);
+            for line in &config_lines {
+                if line.name.len() > settings.name_length as usize
+                    || line.uri.len() > settings.uri_length as usize
+                {
+                    return err!(CandyError::ConfigLineTooLong);
+                }
+                serialized.extend_from_slice(line.name.as_bytes());
+                serialized.resize(
+                    serialized.len() + settings.name_length as usize - line.name.len(),
+                    0,
+                );
+                serialized.extend_from_slice(line.uri.as_bytes());
+                serialized.resize(
+                    serialized.len() + settings.uri_length as usize - line.uri.len(),
+                    0,
+                );
+            }
+            serialized
+        }
+        None => {
+            let mut fixed_config_lines = Vec::with_capacity(config_lines.len());
+            for line in &config_lines {
+                let array_of_zeroes = vec![0u8; MAX_NAME_LENGTH - line.name.len()];
+                let name = line.name.clone() + std::str::from_utf8(&array_of_zeroes).unwrap();
+
+                let array_of_zeroes = vec![0u8; MAX_URI_LENGTH - line.uri.len()];
+                let uri = line.uri.clone() + std::str::from_utf8(&array_of_zeroes).unwrap();
+                fixed_config_lines.push(ConfigLine { name, uri })
+            }
+
+            let as_vec = fixed_config_lines.try_to_vec()?;
+            // remove unneeded u32 because we're just gonna edit the u32 at the front
+            as_vec[4..].to_vec()
+        }
+    };
 
-    let position = CONFIG_ARRAY_START + 4 + (index as usize) * CONFIG_LINE_SIZE;
+    let position = count_start + 4 + (index as usize) * line_size;
 
-    let array_slice: &mut [u8] =
-        &mut data[position..position + fixed_config_lines.len() * CONFIG_LINE_SIZE];
+    let array_slice: &mut [u8] = &mut data[position..position + config_lines.len() * line_size];
 
-    array_slice.copy_from_slice(serialized);
+    array_slice.copy_from_slice(&serialized);
 
-    let bit_mask_vec_start = CONFIG_ARRAY_START
-        + 4
-        + (candy_machine.data.items_available as usize) * CONFIG_LINE_SIZE
-        + 4;
+    let bit_mask_vec_start = get_config_bit_mask_start(
+        config_start,
+        candy_machine.data.items_available as usize,
+        line_settings.as_ref(),
+    );
 
     let mut new_count = current_count;
-    for i in 0..fixed_config_lines.len() {
+    for i in 0..config_lines.len() {
         let position = (index as usize)
             .checked_add(i)
             .ok_or(CandyError::NumericalOverflowError)?;
@@ -106,12 +141,11 @@ pub fn handle_add_config_lines(
     }
 
     // plug in new count.
-    data[CONFIG_ARRAY_START..CONFIG_ARRAY_START + 4]
-        .copy_from_slice(&(new_count as u32).to_le_bytes());
+    data[count_start..count_start + 4].copy_from_slice(&(new_count as u32).to_le_bytes());
 
     Ok(())
 }
 
-pub fn get_config_count(data: &RefMut<&mut [u8]>) -> Result<usize> {
-    Ok(u32::from_le_bytes(*array_ref![data, CONFIG_ARRAY_START, 4]) as usize)
+pub fn get_config_count(data: &RefMut<&mut [u8]>, count_start: usize) -> Result<usize> {
+    Ok(u32::from_le_bytes(*array_ref![data, count_start, 4]) as usize)
 }
@@ -0,0 +1,5 @@
+pub mod remove_allowlist_root;
+pub mod set_allowlist_root;
+
+pub use remove_allowlist_root::*;
+pub use set_allowlist_root::*;
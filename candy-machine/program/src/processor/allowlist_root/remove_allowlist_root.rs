@@ -0,0 +1,18 @@
+use anchor_lang::prelude::*;
+
+use crate::{AllowlistRoot, CandyMachine};
+
+/// Closes the allowlist root pda, proofs against the old root can't be used once a new one is set
+#[derive(Accounts)]
+pub struct RemoveAllowlistRoot<'info> {
+    #[account(has_one = authority)]
+    candy_machine: Account<'info, CandyMachine>,
+    #[account(mut)]
+    authority: Signer<'info>,
+    #[account(mut, close = authority, seeds = [AllowlistRoot::PREFIX.as_bytes(), candy_machine.to_account_info().key.as_ref()], bump)]
+    allowlist_root: Account<'info, AllowlistRoot>,
+}
+
+pub fn handle_remove_allowlist_root(_ctx: Context<RemoveAllowlistRoot>) -> Result<()> {
+    Ok(())
+}
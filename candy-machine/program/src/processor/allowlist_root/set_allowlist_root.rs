@@ -0,0 +1,28 @@
+use anchor_lang::prelude::*;
+
+use crate::{AllowlistRoot, CandyError, CandyMachine, WhitelistMintMode};
+
+/// Set the allowlist root PDA for a candy machine with a merkle tree whitelist
+#[derive(Accounts)]
+pub struct SetAllowlistRoot<'info> {
+    #[account(has_one = authority)]
+    candy_machine: Account<'info, CandyMachine>,
+    #[account(mut)]
+    authority: Signer<'info>,
+    #[account(init, seeds = [AllowlistRoot::PREFIX.as_bytes(), candy_machine.to_account_info().key.as_ref()], bump, space = AllowlistRoot::SIZE, payer = authority)]
+    allowlist_root: Account<'info, AllowlistRoot>,
+    system_program: Program<'info, System>,
+}
+
+pub fn handle_set_allowlist_root(ctx: Context<SetAllowlistRoot>, root: [u8; 32]) -> Result<()> {
+    let candy_machine = &ctx.accounts.candy_machine;
+    match &candy_machine.data.whitelist_mint_settings {
+        Some(ws) if ws.mode == WhitelistMintMode::MerkleTree => (),
+        _ => return err!(CandyError::NotMerkleAllowlist),
+    }
+
+    let allowlist_root = &mut ctx.accounts.allowlist_root;
+    allowlist_root.candy_machine = candy_machine.key();
+    allowlist_root.root = root;
+    Ok(())
+}
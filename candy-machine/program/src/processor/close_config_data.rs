@@ -0,0 +1,62 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    cmp_pubkeys, get_config_array_start, is_config_data_closed, is_feature_active, CandyError,
+    CandyFeatures, CandyMachine, MintSettings,
+};
+
+/// Shrink a sold out candy machine down to its header, returning the rent of the config data.
+#[derive(Accounts)]
+pub struct CloseConfigData<'info> {
+    #[account(mut, has_one = authority)]
+    candy_machine: Account<'info, CandyMachine>,
+    #[account(mut)]
+    authority: Signer<'info>,
+    // > Only needed if the candy machine has mint settings
+    // mint_settings
+}
+
+pub fn handle_close_config_data<'info>(
+    ctx: Context<'_, '_, '_, 'info, CloseConfigData<'info>>,
+) -> Result<()> {
+    let candy_machine = &ctx.accounts.candy_machine;
+    if candy_machine.data.hidden_settings.is_some() {
+        return err!(CandyError::HiddenSettingsConfigsDoNotHaveConfigLines);
+    }
+    if candy_machine.items_redeemed < candy_machine.data.items_available {
+        return err!(CandyError::CandyMachineNotSoldOut);
+    }
+
+    // mint requests count as redeemed before the oracle reveals which line they get
+    if is_feature_active(candy_machine, CandyFeatures::MINT_SETTINGS)? {
+        let mint_settings_info = ctx
+            .remaining_accounts
+            .get(0)
+            .ok_or(CandyError::IncorrectRemainingAccountsLen)?;
+        let mint_settings: Account<MintSettings> = Account::try_from(mint_settings_info)?;
+        if !cmp_pubkeys(&mint_settings.candy_machine, &candy_machine.key()) {
+            return err!(CandyError::MintSettingsMismatch);
+        }
+        if mint_settings.randomness_oracle.is_some() {
+            return err!(CandyError::ConfigDataInUse);
+        }
+    }
+
+    let account = candy_machine.to_account_info();
+    if is_config_data_closed(&account.data.borrow()) {
+        return err!(CandyError::ConfigDataClosed);
+    }
+
+    // the header keeps the candy machine data, mint stats and feature flags
+    let new_len = get_config_array_start(&account.data.borrow());
+    account.realloc(new_len, false)?;
+
+    let authority = ctx.accounts.authority.to_account_info();
+    let excess = account
+        .lamports()
+        .saturating_sub(Rent::get()?.minimum_balance(new_len));
+    **account.try_borrow_mut_lamports()? -= excess;
+    **authority.try_borrow_mut_lamports()? += excess;
+
+    Ok(())
+}
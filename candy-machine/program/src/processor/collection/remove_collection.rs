@@ -3,8 +3,7 @@ use mpl_token_metadata::{instruction::revoke_collection_authority, state::Metada
 use solana_program::program::invoke;
 
 use crate::{
-    cmp_pubkeys, constants::COLLECTIONS_FEATURE_INDEX, remove_feature_flag, CandyError,
-    CandyMachine, CollectionPDA,
+    cmp_pubkeys, remove_feature_flag, CandyError, CandyFeatures, CandyMachine, CollectionPDA,
 };
 
 /// Set the collection PDA for the candy machine
@@ -68,6 +67,6 @@ pub fn handle_remove_collection(ctx: Context<RemoveCollection>) -> Result<()> {
         ),
         revoke_collection_infos.as_slice(),
     )?;
-    remove_feature_flag(&mut candy_machine.data.uuid, COLLECTIONS_FEATURE_INDEX);
+    remove_feature_flag(candy_machine, CandyFeatures::COLLECTIONS)?;
     Ok(())
 }
@@ -6,9 +6,8 @@ use mpl_token_metadata::{
 use solana_program::program::invoke;
 
 use crate::{
-    assert_master_edition, cmp_pubkeys,
-    constants::{COLLECTIONS_FEATURE_INDEX, COLLECTION_PDA_SIZE},
-    set_feature_flag, CandyError, CandyMachine, CollectionPDA,
+    assert_master_edition, cmp_pubkeys, constants::COLLECTION_PDA_SIZE, set_feature_flag,
+    CandyError, CandyFeatures, CandyMachine, CollectionPDA,
 };
 
 /// Set the collection PDA for the candy machine
@@ -113,6 +112,6 @@ pub fn handle_set_collection(ctx: Context<SetCollection>) -> Result<()> {
     collection_pda_object.mint = mint.key();
     collection_pda_object.candy_machine = candy_machine.key();
     collection_pda_object.try_serialize(&mut data_ref)?;
-    set_feature_flag(&mut candy_machine.data.uuid, COLLECTIONS_FEATURE_INDEX);
+    set_feature_flag(candy_machine, CandyFeatures::COLLECTIONS)?;
     Ok(())
 }
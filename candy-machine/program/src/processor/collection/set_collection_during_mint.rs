@@ -64,7 +64,10 @@ pub fn handle_set_collection_during_mint(ctx: Context<SetCollectionDuringMint>)
     }
 
     let discriminator = &previous_instruction.data[0..8];
-    if discriminator != [211, 57, 6, 167, 15, 219, 35, 251] {
+    // mint_nft or mint_nft_to
+    if discriminator != [211, 57, 6, 167, 15, 219, 35, 251]
+        && discriminator != [148, 67, 217, 30, 234, 68, 159, 215]
+    {
         msg!("Transaction had ix with data {:?}", discriminator);
         return Ok(());
     }
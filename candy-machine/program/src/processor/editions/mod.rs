@@ -0,0 +1,5 @@
+pub mod remove_edition_settings;
+pub mod set_edition_settings;
+
+pub use remove_edition_settings::*;
+pub use set_edition_settings::*;
@@ -0,0 +1,56 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::Token;
+
+use crate::{
+    assert_is_ata, constants::PREFIX, remove_feature_flag, spl_token_transfer, CandyFeatures,
+    CandyMachine, EditionSettings, TokenTransferParams,
+};
+
+/// Removes the edition settings from the candy machine, closes the edition settings pda and
+/// returns the master edition token to the authority
+#[derive(Accounts)]
+pub struct RemoveEditionSettings<'info> {
+    #[account(mut, has_one = authority)]
+    candy_machine: Account<'info, CandyMachine>,
+    #[account(mut)]
+    authority: Signer<'info>,
+    #[account(mut, close = authority, seeds = [EditionSettings::PREFIX.as_bytes(), candy_machine.to_account_info().key.as_ref()], bump)]
+    edition_settings: Account<'info, EditionSettings>,
+    /// CHECK: account constraints checked in account trait
+    #[account(seeds=[PREFIX.as_bytes(), candy_machine.key().as_ref()], bump)]
+    candy_machine_creator: UncheckedAccount<'info>,
+    /// CHECK: account checked in program
+    #[account(mut)]
+    master_token_account: UncheckedAccount<'info>,
+    /// CHECK: account checked in CPI
+    #[account(mut)]
+    destination: UncheckedAccount<'info>,
+    token_program: Program<'info, Token>,
+}
+
+pub fn handle_remove_edition_settings(ctx: Context<RemoveEditionSettings>) -> Result<()> {
+    let candy_machine = &mut ctx.accounts.candy_machine;
+    let master_token_account = ctx.accounts.master_token_account.to_account_info();
+    assert_is_ata(
+        &master_token_account,
+        &ctx.accounts.candy_machine_creator.key(),
+        &ctx.accounts.edition_settings.master_mint,
+    )?;
+
+    let cm_key = candy_machine.key();
+    spl_token_transfer(TokenTransferParams {
+        source: master_token_account,
+        destination: ctx.accounts.destination.to_account_info(),
+        authority: ctx.accounts.candy_machine_creator.to_account_info(),
+        authority_signer_seeds: &[
+            PREFIX.as_bytes(),
+            cm_key.as_ref(),
+            &[*ctx.bumps.get("candy_machine_creator").unwrap()],
+        ],
+        token_program: ctx.accounts.token_program.to_account_info(),
+        amount: 1,
+    })?;
+
+    remove_feature_flag(candy_machine, CandyFeatures::EDITIONS)?;
+    Ok(())
+}
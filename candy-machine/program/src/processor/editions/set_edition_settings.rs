@@ -0,0 +1,79 @@
+use anchor_lang::prelude::*;
+use mpl_token_metadata::state::{
+    MasterEditionV2, TokenMetadataAccount, EDITION, PREFIX as METADATA_PREFIX,
+};
+
+use crate::{
+    assert_is_ata, assert_owned_by, cmp_pubkeys, constants::PREFIX, set_feature_flag, CandyError,
+    CandyFeatures, CandyMachine, EditionSettings,
+};
+
+/// Set the edition settings PDA for the candy machine. Editions are printed one at a time, so
+/// mint many and randomness oracles can't be used while they are set.
+#[derive(Accounts)]
+pub struct SetEditionSettings<'info> {
+    #[account(mut, has_one = authority)]
+    candy_machine: Account<'info, CandyMachine>,
+    #[account(mut)]
+    authority: Signer<'info>,
+    #[account(init, seeds = [EditionSettings::PREFIX.as_bytes(), candy_machine.to_account_info().key.as_ref()], bump, space = EditionSettings::SIZE, payer = authority)]
+    edition_settings: Account<'info, EditionSettings>,
+    /// CHECK: account constraints checked in account trait
+    #[account(seeds=[PREFIX.as_bytes(), candy_machine.key().as_ref()], bump)]
+    candy_machine_creator: UncheckedAccount<'info>,
+    /// CHECK: checked against the master edition
+    master_mint: UncheckedAccount<'info>,
+    /// CHECK: account checked in program
+    master_edition: UncheckedAccount<'info>,
+    /// CHECK: account checked in program
+    master_token_account: UncheckedAccount<'info>,
+    system_program: Program<'info, System>,
+}
+
+pub fn handle_set_edition_settings(ctx: Context<SetEditionSettings>) -> Result<()> {
+    let master_mint = ctx.accounts.master_mint.key();
+    let master_edition_info = ctx.accounts.master_edition.to_account_info();
+    let (expected_master_edition, _) = Pubkey::find_program_address(
+        &[
+            METADATA_PREFIX.as_bytes(),
+            mpl_token_metadata::id().as_ref(),
+            master_mint.as_ref(),
+            EDITION.as_bytes(),
+        ],
+        &mpl_token_metadata::id(),
+    );
+    if !cmp_pubkeys(master_edition_info.key, &expected_master_edition) {
+        return err!(CandyError::InvalidMasterEdition);
+    }
+    assert_owned_by(&master_edition_info, &mpl_token_metadata::id())?;
+    let master_edition = MasterEditionV2::from_account_info(&master_edition_info)?;
+
+    // the authority hands the master edition token to the candy machine creator beforehand
+    let master_token = assert_is_ata(
+        &ctx.accounts.master_token_account.to_account_info(),
+        &ctx.accounts.candy_machine_creator.key(),
+        &master_mint,
+    )?;
+    if master_token.amount != 1 {
+        return err!(CandyError::InvalidMasterEdition);
+    }
+
+    let candy_machine = &mut ctx.accounts.candy_machine;
+    // every item left in the candy machine has to be printable from the master edition
+    if let Some(max_supply) = master_edition.max_supply {
+        let items_left = candy_machine
+            .data
+            .items_available
+            .saturating_sub(candy_machine.items_redeemed);
+        if items_left > max_supply.saturating_sub(master_edition.supply) {
+            return err!(CandyError::EditionSupplyTooLow);
+        }
+    }
+
+    let edition_settings = &mut ctx.accounts.edition_settings;
+    edition_settings.candy_machine = candy_machine.key();
+    edition_settings.master_mint = master_mint;
+
+    set_feature_flag(candy_machine, CandyFeatures::EDITIONS)?;
+    Ok(())
+}
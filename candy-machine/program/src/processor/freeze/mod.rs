@@ -1,9 +1,13 @@
+pub mod refund_nft;
 pub mod remove_freeze;
 pub mod set_freeze;
 pub mod thaw_nft;
+pub mod thaw_nfts;
 pub mod unlock_funds;
 
+pub use refund_nft::*;
 pub use remove_freeze::*;
 pub use set_freeze::*;
 pub use thaw_nft::*;
+pub use thaw_nfts::*;
 pub use unlock_funds::*;
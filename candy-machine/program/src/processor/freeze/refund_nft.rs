@@ -0,0 +1,167 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{Mint, Token, TokenAccount};
+use mpl_token_metadata::{
+    instruction::{burn_nft, thaw_delegated_account},
+    state::{Key, Metadata, TokenStandard},
+};
+use solana_program::{
+    program::{invoke, invoke_signed},
+    program_option::COption,
+};
+
+use crate::{
+    assert_is_ata, assert_owned_by, spl_token_transfer, CandyError, CandyMachine, FreezePDA,
+    TokenTransferParams,
+};
+
+/// Burn a frozen NFT for a refund once the freeze period ends without a sell-out
+#[derive(Accounts)]
+pub struct RefundNFT<'info> {
+    #[account(mut, seeds = [FreezePDA::PREFIX.as_bytes(), candy_machine.key().as_ref()], bump, has_one = candy_machine)]
+    freeze_pda: Account<'info, FreezePDA>,
+    candy_machine: Account<'info, CandyMachine>,
+    #[account(mut, has_one = mint, has_one = owner)]
+    token_account: Account<'info, TokenAccount>,
+    #[account(mut)]
+    owner: Signer<'info>,
+    #[account(mut)]
+    mint: Account<'info, Mint>,
+    /// CHECK: account checked in CPI
+    #[account(mut)]
+    metadata: UncheckedAccount<'info>,
+    /// CHECK: account checked in CPI
+    #[account(mut)]
+    edition: UncheckedAccount<'info>,
+    token_program: Program<'info, Token>,
+    /// CHECK: checked in account constraints
+    #[account(address = mpl_token_metadata::id())]
+    token_metadata_program: UncheckedAccount<'info>,
+    // > Only needed if candy machine has a mint set
+    // freeze_ata (writable)
+    // destination_ata (writable)
+    // > Only needed if the NFT is a verified member of a collection
+    // collection_metadata (writable)
+}
+
+pub fn handle_refund_nft<'info>(ctx: Context<'_, '_, '_, 'info, RefundNFT<'info>>) -> Result<()> {
+    let freeze_pda = &mut ctx.accounts.freeze_pda;
+    let candy_machine = &ctx.accounts.candy_machine;
+    let current_timestamp = Clock::get()?.unix_timestamp;
+    if !freeze_pda.refund_eligible(current_timestamp, candy_machine) {
+        return err!(CandyError::RefundNotAvailable);
+    }
+
+    // only NFTs still frozen by this candy machine paid into the freeze PDA
+    let token_account = &ctx.accounts.token_account;
+    if !token_account.is_frozen() || token_account.delegate != COption::Some(freeze_pda.key()) {
+        return err!(CandyError::NftNotRefundable);
+    }
+    let metadata_info = &ctx.accounts.metadata;
+    assert_owned_by(metadata_info, &mpl_token_metadata::id())?;
+    let metadata = {
+        let data = metadata_info.try_borrow_data()?;
+        if data.is_empty() || data[0] != Key::MetadataV1 as u8 {
+            return err!(CandyError::InvalidMetadataAccount);
+        }
+        Metadata::deserialize(&mut data.as_ref())?
+    };
+    if metadata.token_standard == Some(TokenStandard::ProgrammableNonFungible) {
+        return err!(CandyError::ProgrammableNotSupported);
+    }
+
+    let mint = &ctx.accounts.mint;
+    let edition = &ctx.accounts.edition;
+    let owner = &ctx.accounts.owner;
+    let token_program = &ctx.accounts.token_program;
+    let token_metadata_program = &ctx.accounts.token_metadata_program;
+    let candy_key = candy_machine.key();
+    let freeze_seeds = [
+        FreezePDA::PREFIX.as_bytes(),
+        candy_key.as_ref(),
+        &[*ctx.bumps.get("freeze_pda").unwrap()],
+    ];
+    invoke_signed(
+        &thaw_delegated_account(
+            mpl_token_metadata::ID,
+            freeze_pda.key(),
+            token_account.key(),
+            edition.key(),
+            mint.key(),
+        ),
+        &[
+            freeze_pda.to_account_info(),
+            token_account.to_account_info(),
+            edition.to_account_info(),
+            mint.to_account_info(),
+            token_program.to_account_info(),
+            token_metadata_program.to_account_info(),
+        ],
+        &[&freeze_seeds],
+    )?;
+
+    let token_accounts_count = if candy_machine.token_mint.is_some() {
+        2
+    } else {
+        0
+    };
+    let collection_metadata = ctx.remaining_accounts.get(token_accounts_count);
+    let mut burn_accounts = vec![
+        ctx.accounts.metadata.to_account_info(),
+        owner.to_account_info(),
+        mint.to_account_info(),
+        token_account.to_account_info(),
+        edition.to_account_info(),
+        token_program.to_account_info(),
+        token_metadata_program.to_account_info(),
+    ];
+    if let Some(collection_metadata) = collection_metadata {
+        burn_accounts.push(collection_metadata.clone());
+    }
+    invoke(
+        &burn_nft(
+            mpl_token_metadata::id(),
+            ctx.accounts.metadata.key(),
+            owner.key(),
+            mint.key(),
+            token_account.key(),
+            edition.key(),
+            spl_token::id(),
+            collection_metadata.map(|collection_metadata| collection_metadata.key()),
+        ),
+        &burn_accounts,
+    )?;
+
+    let refund = freeze_pda.refund_price;
+    freeze_pda.frozen_count = freeze_pda.frozen_count.saturating_sub(1);
+    freeze_pda.escrowed_count = freeze_pda
+        .escrowed_count
+        .checked_sub(1)
+        .ok_or(CandyError::NftNotRefundable)?;
+    if let Some(token_mint) = &candy_machine.token_mint {
+        if ctx.remaining_accounts.len() < 2 {
+            return err!(CandyError::MissingRemoveFreezeTokenAccounts);
+        }
+        let freeze_ata_info = &ctx.remaining_accounts[0];
+        assert_is_ata(freeze_ata_info, &freeze_pda.key(), token_mint)?;
+        spl_token_transfer(TokenTransferParams {
+            source: freeze_ata_info.clone(),
+            destination: ctx.remaining_accounts[1].clone(),
+            authority: freeze_pda.to_account_info(),
+            authority_signer_seeds: &freeze_seeds,
+            token_program: token_program.to_account_info(),
+            amount: refund,
+        })?;
+    } else {
+        // the freeze PDA has to stay rent exempt until it is closed
+        let freeze_info = freeze_pda.to_account_info();
+        let rent_exempt = Rent::get()?.minimum_balance(freeze_info.data_len());
+        let remaining = freeze_info
+            .lamports()
+            .checked_sub(refund)
+            .filter(|remaining| *remaining >= rent_exempt)
+            .ok_or(CandyError::NotEnoughSOL)?;
+        **freeze_info.try_borrow_mut_lamports()? = remaining;
+        **owner.try_borrow_mut_lamports()? += refund;
+    }
+    Ok(())
+}
@@ -1,9 +1,6 @@
 use anchor_lang::{prelude::*, AccountsClose};
 
-use crate::{
-    constants::{FREEZE_FEATURE_INDEX, FREEZE_LOCK_FEATURE_INDEX},
-    remove_feature_flag, CandyError, CandyMachine, FreezePDA,
-};
+use crate::{remove_feature_flag, CandyError, CandyFeatures, CandyMachine, FreezePDA};
 
 /// Removes the freeze flag from candy machine without closing the freeze pda unless no NFTs have been minted
 #[derive(Accounts)]
@@ -19,8 +16,12 @@ pub struct RemoveFreeze<'info> {
 pub fn handle_remove_freeze(ctx: Context<RemoveFreeze>) -> Result<()> {
     let candy_machine = &mut ctx.accounts.candy_machine;
     let freeze_pda = &mut ctx.accounts.freeze_pda;
+    // thawing early would let every NFT out of the freeze before refunds can open
+    if freeze_pda.refund && freeze_pda.frozen_count > 0 {
+        return err!(CandyError::NoRemovingFreezeWithRefunds);
+    }
     freeze_pda.allow_thaw = true;
-    remove_feature_flag(&mut candy_machine.data.uuid, FREEZE_FEATURE_INDEX);
+    remove_feature_flag(candy_machine, CandyFeatures::FREEZE)?;
 
     // Closes the account to enable editing if minting hasn't started
     if candy_machine
@@ -28,7 +29,7 @@ pub fn handle_remove_freeze(ctx: Context<RemoveFreeze>) -> Result<()> {
         .is_ok()
     {
         freeze_pda.close(ctx.accounts.authority.to_account_info())?;
-        remove_feature_flag(&mut candy_machine.data.uuid, FREEZE_LOCK_FEATURE_INDEX);
+        remove_feature_flag(candy_machine, CandyFeatures::FREEZE_LOCK)?;
     }
     Ok(())
 }
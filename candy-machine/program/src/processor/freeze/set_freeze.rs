@@ -1,9 +1,8 @@
 use anchor_lang::prelude::*;
 
 use crate::{
-    assert_is_ata,
-    constants::{FREEZE_FEATURE_INDEX, FREEZE_LOCK_FEATURE_INDEX, MAX_FREEZE_TIME},
-    set_feature_flag, CandyError, CandyMachine, FreezePDA,
+    assert_is_ata, constants::MAX_FREEZE_TIME, is_feature_active, set_feature_flag, CandyError,
+    CandyFeatures, CandyMachine, FreezePDA,
 };
 
 /// Set the Freeze PDA for the candy machine
@@ -21,14 +20,34 @@ pub struct SetFreeze<'info> {
     // freeze_ata
 }
 
-pub fn handle_set_freeze(ctx: Context<SetFreeze>, freeze_time: i64) -> Result<()> {
+pub fn handle_set_freeze(ctx: Context<SetFreeze>, freeze_time: i64, refund: bool) -> Result<()> {
     let candy_machine = &mut ctx.accounts.candy_machine;
     candy_machine.assert_not_minted(error!(CandyError::NoChangingFreezeDuringMint))?;
+    if is_feature_active(candy_machine, CandyFeatures::PAYMENT_OPTIONS)? {
+        return err!(CandyError::NoPaymentOptionsWithFreeze);
+    }
+    if is_feature_active(candy_machine, CandyFeatures::REVENUE_SPLITS)? {
+        return err!(CandyError::RevenueSplitsWithFreeze);
+    }
     let freeze_pda = &mut ctx.accounts.freeze_pda;
     if freeze_time > MAX_FREEZE_TIME {
         return err!(CandyError::EnteredFreezeIsMoreThanMaxFreeze);
     }
-    freeze_pda.init(candy_machine.key(), None, freeze_time);
+    // every refund returns the same price, so mint phases and discounts can't change it
+    let refund_price = if refund {
+        let discounted = candy_machine
+            .data
+            .whitelist_mint_settings
+            .as_ref()
+            .map_or(false, |ws| ws.discount_price.is_some());
+        if discounted || is_feature_active(candy_machine, CandyFeatures::MINT_PHASES)? {
+            return err!(CandyError::FreezeRefundPriceMismatch);
+        }
+        Some(candy_machine.data.price)
+    } else {
+        None
+    };
+    freeze_pda.init(candy_machine.key(), None, freeze_time, refund_price);
 
     if let Some(mint_pubkey) = candy_machine.token_mint {
         let freeze_ata = ctx
@@ -38,7 +57,7 @@ pub fn handle_set_freeze(ctx: Context<SetFreeze>, freeze_time: i64) -> Result<()
         assert_is_ata(freeze_ata, freeze_pda.to_account_info().key, &mint_pubkey)
             .map_err(|_| CandyError::IncorrectFreezeAta)?;
     }
-    set_feature_flag(&mut candy_machine.data.uuid, FREEZE_FEATURE_INDEX);
-    set_feature_flag(&mut candy_machine.data.uuid, FREEZE_LOCK_FEATURE_INDEX);
+    set_feature_flag(candy_machine, CandyFeatures::FREEZE)?;
+    set_feature_flag(candy_machine, CandyFeatures::FREEZE_LOCK)?;
     Ok(())
 }
@@ -3,11 +3,17 @@ use anchor_lang::{
     system_program::{transfer, Transfer},
 };
 use anchor_spl::token::{Mint, Token, TokenAccount};
-use mpl_token_metadata::instruction::thaw_delegated_account;
+use mpl_token_metadata::{
+    instruction::{
+        builders::{RevokeBuilder, UnlockBuilder},
+        thaw_delegated_account, InstructionBuilder, RevokeArgs, UnlockArgs,
+    },
+    state::{Key, TokenRecord, TokenState},
+};
 use solana_program::program::{invoke, invoke_signed};
 use spl_token::instruction::revoke;
 
-use crate::{cmp_pubkeys, CandyError, CandyMachine, FreezePDA};
+use crate::{assert_owned_by, cmp_pubkeys, CandyError, CandyMachine, FreezePDA};
 
 /// Set the collection PDA for the candy machine
 #[derive(Accounts)]
@@ -31,9 +37,15 @@ pub struct ThawNFT<'info> {
     #[account(address = mpl_token_metadata::id())]
     token_metadata_program: UncheckedAccount<'info>,
     system_program: Program<'info, System>,
+    // > Only needed if the NFT is programmable
+    // metadata (writable)
+    // token_record (writable)
+    // instruction_sysvar_account
+    // authorization_rules_program // Only needed if the NFT has a rule set
+    // authorization_rules // Only needed if the NFT has a rule set
 }
 
-pub fn handle_thaw_nft(ctx: Context<ThawNFT>) -> Result<()> {
+pub fn handle_thaw_nft<'info>(ctx: Context<'_, '_, '_, 'info, ThawNFT<'info>>) -> Result<()> {
     let freeze_pda = &mut ctx.accounts.freeze_pda;
     let candy_machine = &mut ctx.accounts.candy_machine;
     let current_timestamp = Clock::get()?.unix_timestamp;
@@ -43,6 +55,12 @@ pub fn handle_thaw_nft(ctx: Context<ThawNFT>) -> Result<()> {
     } else {
         let candy_struct: Account<CandyMachine> =
             Account::try_from(&candy_machine.to_account_info())?;
+        // holders pick between keeping and refunding their NFTs, nobody else can decide for them
+        if freeze_pda.refund_eligible(current_timestamp, &candy_struct)
+            && !cmp_pubkeys(ctx.accounts.payer.key, ctx.accounts.owner.key)
+        {
+            return err!(CandyError::RefundThawRequiresOwner);
+        }
         freeze_pda.thaw_eligible(current_timestamp, &candy_struct)
     };
     msg!("Can thaw: {}", can_thaw);
@@ -61,26 +79,83 @@ pub fn handle_thaw_nft(ctx: Context<ThawNFT>) -> Result<()> {
         candy_machine.key.as_ref(),
         &[*ctx.bumps.get("freeze_pda").unwrap()],
     ];
-    if token_account.is_frozen() {
+    // programmable NFT token accounts are always frozen, their token record tells if it is locked
+    let programmable_accounts = ctx.remaining_accounts.get(0..3);
+    let is_frozen = match programmable_accounts {
+        Some(programmable_accounts) => {
+            let token_record_info = &programmable_accounts[1];
+            assert_owned_by(token_record_info, &mpl_token_metadata::id())?;
+            let data = token_record_info.try_borrow_data()?;
+            if data.is_empty() || data[0] != Key::TokenRecord as u8 {
+                return err!(CandyError::InvalidProgrammableAccounts);
+            }
+            TokenRecord::deserialize(&mut data.as_ref())?.state == TokenState::Locked
+        }
+        None => token_account.is_frozen(),
+    };
+    let mut account_infos = vec![
+        freeze_pda.to_account_info(),
+        token_account.to_account_info(),
+        owner.to_account_info(),
+        mint.to_account_info(),
+        edition.to_account_info(),
+        payer.to_account_info(),
+        token_program.to_account_info(),
+        token_metadata_program.to_account_info(),
+        ctx.accounts.system_program.to_account_info(),
+    ];
+    account_infos.extend(ctx.remaining_accounts.iter().cloned());
+    if is_frozen {
         msg!("Token account is frozen! Now attempting to thaw!");
-        invoke_signed(
-            &thaw_delegated_account(
-                mpl_token_metadata::ID,
-                freeze_pda.key(),
-                token_account.key(),
-                edition.key(),
-                mint.key(),
-            ),
-            &[
-                freeze_pda.to_account_info(),
-                token_account.to_account_info(),
-                edition.to_account_info(),
-                mint.to_account_info(),
-                token_program.to_account_info(),
-                token_metadata_program.to_account_info(),
-            ],
-            &[&freeze_seeds],
-        )?;
+        match programmable_accounts {
+            Some(programmable_accounts) => {
+                let mut unlock_builder = UnlockBuilder::new();
+                unlock_builder
+                    .authority(freeze_pda.key())
+                    .token_owner(owner.key())
+                    .token(token_account.key())
+                    .mint(mint.key())
+                    .metadata(programmable_accounts[0].key())
+                    .edition(edition.key())
+                    .token_record(programmable_accounts[1].key())
+                    .payer(payer.key())
+                    .system_program(ctx.accounts.system_program.key())
+                    .sysvar_instructions(programmable_accounts[2].key())
+                    .spl_token_program(token_program.key());
+                if let Some(authorization_rules) = ctx.remaining_accounts.get(3..5) {
+                    unlock_builder
+                        .authorization_rules_program(authorization_rules[0].key())
+                        .authorization_rules(authorization_rules[1].key());
+                }
+                let unlock_ix = unlock_builder
+                    .build(UnlockArgs::V1 {
+                        authorization_data: None,
+                    })
+                    .unwrap()
+                    .instruction();
+                invoke_signed(&unlock_ix, &account_infos, &[&freeze_seeds])?;
+            }
+            None => {
+                invoke_signed(
+                    &thaw_delegated_account(
+                        mpl_token_metadata::ID,
+                        freeze_pda.key(),
+                        token_account.key(),
+                        edition.key(),
+                        mint.key(),
+                    ),
+                    &[
+                        freeze_pda.to_account_info(),
+                        token_account.to_account_info(),
+                        edition.to_account_info(),
+                        mint.to_account_info(),
+                        token_program.to_account_info(),
+                        token_metadata_program.to_account_info(),
+                    ],
+                    &[&freeze_seeds],
+                )?;
+            }
+        }
         if freeze_pda.freeze_fee > 0 && freeze_pda.frozen_count > 0 {
             transfer(
                 CpiContext::new(
@@ -97,15 +172,46 @@ pub fn handle_thaw_nft(ctx: Context<ThawNFT>) -> Result<()> {
         // Just an extra precaution to allow unfreezing if something unexpected were to
         // happen to the freeze count to allow everyone to still unfreeze
         freeze_pda.frozen_count = freeze_pda.frozen_count.saturating_sub(1);
+        // a thawed NFT can't be refunded anymore, so its price no longer has to be held back
+        freeze_pda.escrowed_count = freeze_pda.escrowed_count.saturating_sub(1);
     } else {
         msg!("Token account is not frozen!");
     }
     if cmp_pubkeys(&payer.key(), &owner.key()) {
         msg!("Revoking authority");
-        invoke(
-            &revoke(&spl_token::ID, &token_account.key(), &payer.key(), &[])?,
-            &[token_account.to_account_info(), payer.to_account_info()],
-        )?;
+        match programmable_accounts {
+            Some(programmable_accounts) => {
+                let mut revoke_builder = RevokeBuilder::new();
+                revoke_builder
+                    .delegate(freeze_pda.key())
+                    .metadata(programmable_accounts[0].key())
+                    .master_edition(edition.key())
+                    .token_record(programmable_accounts[1].key())
+                    .mint(mint.key())
+                    .token(token_account.key())
+                    .authority(payer.key())
+                    .payer(payer.key())
+                    .system_program(ctx.accounts.system_program.key())
+                    .sysvar_instructions(programmable_accounts[2].key())
+                    .spl_token_program(token_program.key());
+                if let Some(authorization_rules) = ctx.remaining_accounts.get(3..5) {
+                    revoke_builder
+                        .authorization_rules_program(authorization_rules[0].key())
+                        .authorization_rules(authorization_rules[1].key());
+                }
+                let revoke_ix = revoke_builder
+                    .build(RevokeArgs::UtilityV1)
+                    .unwrap()
+                    .instruction();
+                invoke(&revoke_ix, &account_infos)?;
+            }
+            None => {
+                invoke(
+                    &revoke(&spl_token::ID, &token_account.key(), &payer.key(), &[])?,
+                    &[token_account.to_account_info(), payer.to_account_info()],
+                )?;
+            }
+        }
     } else {
         msg!("Cannot revoke delegate authority: token account owner is not signer. Re-run as owner to revoke or just call revoke manually.");
     }
@@ -0,0 +1,115 @@
+use anchor_lang::{
+    prelude::*,
+    system_program::{transfer, Transfer},
+};
+use anchor_spl::token::Token;
+use mpl_token_metadata::instruction::thaw_delegated_account;
+use solana_program::{program::invoke_signed, program_option::COption};
+
+use crate::{
+    assert_initialized, assert_keys_equal, assert_owned_by, CandyError, CandyMachine, FreezePDA,
+};
+
+/// Thaw many frozen NFTs at once so the funds can be unlocked sooner
+#[derive(Accounts)]
+pub struct ThawNFTs<'info> {
+    #[account(mut, seeds = [FreezePDA::PREFIX.as_bytes(), candy_machine.key().as_ref()], bump, has_one = candy_machine)]
+    freeze_pda: Account<'info, FreezePDA>,
+    /// CHECK: account could be empty so must be unchecked. Checked in freeze_pda constraint.
+    #[account(mut)]
+    candy_machine: UncheckedAccount<'info>,
+    #[account(mut)]
+    payer: Signer<'info>,
+    token_program: Program<'info, Token>,
+    /// CHECK: checked in account constraints
+    #[account(address = mpl_token_metadata::id())]
+    token_metadata_program: UncheckedAccount<'info>,
+    system_program: Program<'info, System>,
+    // > For each NFT, programmable NFTs have to be thawed with thaw_nft
+    // token_account (writable)
+    // mint
+    // edition
+}
+
+pub fn handle_thaw_nfts<'info>(ctx: Context<'_, '_, '_, 'info, ThawNFTs<'info>>) -> Result<()> {
+    let freeze_pda = &mut ctx.accounts.freeze_pda;
+    let candy_machine = &ctx.accounts.candy_machine;
+    if ctx.remaining_accounts.is_empty() || ctx.remaining_accounts.len() % 3 != 0 {
+        return err!(CandyError::IncorrectRemainingAccountsLen);
+    }
+    let current_timestamp = Clock::get()?.unix_timestamp;
+    let can_thaw = if candy_machine.data_is_empty() {
+        true
+    } else {
+        let candy_struct: Account<CandyMachine> =
+            Account::try_from(&candy_machine.to_account_info())?;
+        if freeze_pda.refund_eligible(current_timestamp, &candy_struct) {
+            return err!(CandyError::RefundThawRequiresOwner);
+        }
+        freeze_pda.thaw_eligible(current_timestamp, &candy_struct)
+    };
+    if !can_thaw {
+        return err!(CandyError::InvalidThawNft);
+    }
+
+    let freeze_seeds = [
+        FreezePDA::PREFIX.as_bytes(),
+        candy_machine.key.as_ref(),
+        &[*ctx.bumps.get("freeze_pda").unwrap()],
+    ];
+    let mut thawed_count: u64 = 0;
+    for nft_accounts in ctx.remaining_accounts.chunks(3) {
+        let token_account_info = &nft_accounts[0];
+        let mint_info = &nft_accounts[1];
+        let edition_info = &nft_accounts[2];
+        assert_owned_by(token_account_info, &spl_token::id())?;
+        let token_account: spl_token::state::Account = assert_initialized(token_account_info)?;
+        assert_keys_equal(&token_account.mint, mint_info.key)?;
+        // NFTs thawed in the meantime are skipped so they don't fail the whole batch
+        if !token_account.is_frozen() || token_account.delegate != COption::Some(freeze_pda.key()) {
+            continue;
+        }
+
+        invoke_signed(
+            &thaw_delegated_account(
+                mpl_token_metadata::ID,
+                freeze_pda.key(),
+                token_account_info.key(),
+                edition_info.key(),
+                mint_info.key(),
+            ),
+            &[
+                freeze_pda.to_account_info(),
+                token_account_info.clone(),
+                edition_info.clone(),
+                mint_info.clone(),
+                ctx.accounts.token_program.to_account_info(),
+                ctx.accounts.token_metadata_program.to_account_info(),
+            ],
+            &[&freeze_seeds],
+        )?;
+        thawed_count += 1;
+    }
+    msg!("Thawed {} NFTs", thawed_count);
+
+    let fee_count = thawed_count.min(freeze_pda.frozen_count);
+    if freeze_pda.freeze_fee > 0 && fee_count > 0 {
+        transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                Transfer {
+                    from: freeze_pda.to_account_info(),
+                    to: ctx.accounts.payer.to_account_info(),
+                },
+            ),
+            freeze_pda
+                .freeze_fee
+                .checked_mul(fee_count)
+                .ok_or(CandyError::NumericalOverflowError)?,
+        )?;
+    }
+    freeze_pda.frozen_count = freeze_pda.frozen_count.saturating_sub(thawed_count);
+    // thawed NFTs can't be refunded anymore, so their price no longer has to be held back
+    freeze_pda.escrowed_count = freeze_pda.escrowed_count.saturating_sub(thawed_count);
+    Ok(())
+}
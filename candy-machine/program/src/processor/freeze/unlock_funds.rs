@@ -1,14 +1,13 @@
-use anchor_lang::prelude::*;
+use anchor_lang::{prelude::*, AccountsClose};
 use anchor_spl::token::{close_account, CloseAccount, Token};
 
 use crate::{
-    assert_is_ata,
-    constants::{FREEZE_FEATURE_INDEX, FREEZE_LOCK_FEATURE_INDEX},
-    remove_feature_flag, spl_token_transfer, CandyError, CandyMachine, FreezePDA,
-    TokenTransferParams,
+    assert_is_ata, remove_feature_flag, spl_token_transfer, CandyError, CandyFeatures,
+    CandyMachine, FreezePDA, TokenTransferParams,
 };
 
-/// Unlocks the funds from mint stuck in the FreezePDA
+/// Unlocks the funds from mint stuck in the FreezePDA, while refunds are open only the funds
+/// the frozen NFTs can't reclaim
 #[derive(Accounts)]
 pub struct UnlockFunds<'info> {
     #[account(mut, has_one = authority, has_one = wallet)]
@@ -18,7 +17,7 @@ pub struct UnlockFunds<'info> {
     wallet: UncheckedAccount<'info>,
     #[account(mut)]
     authority: Signer<'info>,
-    #[account(mut, close = wallet, seeds = [FreezePDA::PREFIX.as_bytes(), candy_machine.to_account_info().key.as_ref()], bump)]
+    #[account(mut, seeds = [FreezePDA::PREFIX.as_bytes(), candy_machine.to_account_info().key.as_ref()], bump)]
     freeze_pda: Account<'info, FreezePDA>,
     system_program: Program<'info, System>,
     // > Only needed if candy machine has a mint set
@@ -35,11 +34,14 @@ pub fn handle_unlock_funds<'info>(
     let candy_machine = &mut ctx.accounts.candy_machine;
     let freeze_pda = &mut ctx.accounts.freeze_pda;
     let authority = &mut ctx.accounts.authority;
-    if freeze_pda.frozen_count > 0 {
+    let current_timestamp = Clock::get()?.unix_timestamp;
+    let refunds_pending = freeze_pda.frozen_count > 0;
+    if refunds_pending && !freeze_pda.refund_eligible(current_timestamp, candy_machine) {
         return err!(CandyError::NoUnlockWithNFTsStillFrozen);
     }
+    let refund_reserve = freeze_pda.refund_reserve()?;
 
-    if !freeze_pda.allow_thaw {
+    if !refunds_pending && !freeze_pda.allow_thaw {
         freeze_pda.allow_thaw = true;
     }
     if let Some(mint) = &candy_machine.token_mint {
@@ -71,21 +73,42 @@ pub fn handle_unlock_funds<'info>(
             authority: freeze_pda.to_account_info(),
             authority_signer_seeds: &freeze_seeds,
             token_program: token_program.to_account_info(),
-            amount: freeze_ata.amount,
+            amount: freeze_ata.amount.saturating_sub(refund_reserve),
         })?;
 
-        close_account(CpiContext::new_with_signer(
-            token_program.to_account_info(),
-            CloseAccount {
-                account: freeze_ata_info.to_account_info(),
-                destination: authority.to_account_info(),
-                authority: freeze_pda.to_account_info(),
-            },
-            &[&freeze_seeds],
-        ))?;
+        if !refunds_pending {
+            close_account(CpiContext::new_with_signer(
+                token_program.to_account_info(),
+                CloseAccount {
+                    account: freeze_ata_info.to_account_info(),
+                    destination: authority.to_account_info(),
+                    authority: freeze_pda.to_account_info(),
+                },
+                &[&freeze_seeds],
+            ))?;
+        }
+    } else if refunds_pending {
+        // the freeze PDA keeps its rent, the refunds and the thaw fees of the frozen NFTs
+        let freeze_info = freeze_pda.to_account_info();
+        let thaw_fees = freeze_pda
+            .freeze_fee
+            .checked_mul(freeze_pda.frozen_count)
+            .ok_or(CandyError::NumericalOverflowError)?;
+        let available = freeze_info
+            .lamports()
+            .saturating_sub(Rent::get()?.minimum_balance(freeze_info.data_len()))
+            .saturating_sub(refund_reserve)
+            .saturating_sub(thaw_fees);
+        **freeze_info.try_borrow_mut_lamports()? -= available;
+        **ctx.accounts.wallet.try_borrow_mut_lamports()? += available;
+    }
+
+    if refunds_pending {
+        return Ok(());
     }
 
-    remove_feature_flag(&mut candy_machine.data.uuid, FREEZE_FEATURE_INDEX);
-    remove_feature_flag(&mut candy_machine.data.uuid, FREEZE_LOCK_FEATURE_INDEX);
+    freeze_pda.close(ctx.accounts.wallet.to_account_info())?;
+    remove_feature_flag(candy_machine, CandyFeatures::FREEZE)?;
+    remove_feature_flag(candy_machine, CandyFeatures::FREEZE_LOCK)?;
     Ok(())
 }
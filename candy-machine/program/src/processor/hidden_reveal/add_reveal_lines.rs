@@ -0,0 +1,104 @@
+use anchor_lang::prelude::*;
+use mpl_token_metadata::state::{MAX_NAME_LENGTH, MAX_URI_LENGTH};
+
+use crate::{
+    constants::CONFIG_LINE_SIZE, create_pda_account, resize_account, CandyError, CandyMachine,
+    ConfigLine, HiddenReveal, MintSettings,
+};
+
+/// Upload the config lines revealing a hidden settings candy machine, in mint order.
+#[derive(Accounts)]
+pub struct AddRevealLines<'info> {
+    #[account(has_one = authority)]
+    candy_machine: Account<'info, CandyMachine>,
+    #[account(mut)]
+    authority: Signer<'info>,
+    #[account(seeds = [MintSettings::PREFIX.as_bytes(), candy_machine.key().as_ref()], bump, has_one = candy_machine)]
+    mint_settings: Account<'info, MintSettings>,
+    /// CHECK: created on the first upload, seeds checked in account trait
+    #[account(mut, seeds = [HiddenReveal::PREFIX.as_bytes(), candy_machine.key().as_ref()], bump)]
+    hidden_reveal: UncheckedAccount<'info>,
+    system_program: Program<'info, System>,
+}
+
+pub fn handle_add_reveal_lines(
+    ctx: Context<AddRevealLines>,
+    index: u32,
+    config_lines: Vec<ConfigLine>,
+) -> Result<()> {
+    let candy_machine = &ctx.accounts.candy_machine;
+    let reveal_hash = match ctx.accounts.mint_settings.reveal_hash {
+        Some(reveal_hash) if candy_machine.data.hidden_settings.is_some() => reveal_hash,
+        _ => return err!(CandyError::HiddenRevealNotSupported),
+    };
+    let hidden_reveal_info = ctx.accounts.hidden_reveal.to_account_info();
+    let authority = ctx.accounts.authority.to_account_info();
+    let system_program = ctx.accounts.system_program.to_account_info();
+
+    let cm_key = candy_machine.key();
+    let mut hidden_reveal = if hidden_reveal_info.data_is_empty() {
+        create_pda_account(
+            &hidden_reveal_info,
+            &authority,
+            &system_program,
+            &[HiddenReveal::PREFIX.as_bytes(), cm_key.as_ref()],
+            HiddenReveal::SIZE,
+            CandyError::DerivedKeyInvalid,
+        )?;
+        HiddenReveal {
+            candy_machine: cm_key,
+            ..Default::default()
+        }
+    } else {
+        HiddenReveal::try_deserialize(&mut hidden_reveal_info.try_borrow_data()?.as_ref())?
+    };
+
+    // the hash chain can't be rewound, so wrong lines are fixed by uploading from the start
+    if index == 0 {
+        hidden_reveal.hash = [0; 32];
+        hidden_reveal.lines_uploaded = 0;
+    } else if index != hidden_reveal.lines_uploaded {
+        return err!(CandyError::RevealLinesOutOfOrder);
+    }
+    let total = index
+        .checked_add(config_lines.len() as u32)
+        .ok_or(CandyError::NumericalOverflowError)?;
+    if total as u64 > candy_machine.data.items_available {
+        return err!(CandyError::IndexGreaterThanLength);
+    }
+
+    let required_len = HiddenReveal::SIZE + total as usize * CONFIG_LINE_SIZE;
+    if hidden_reveal_info.data_len() < required_len {
+        resize_account(
+            &hidden_reveal_info,
+            &authority,
+            &system_program,
+            required_len,
+        )?;
+    }
+
+    let mut data = hidden_reveal_info.try_borrow_mut_data()?;
+    let mut position = HiddenReveal::SIZE + index as usize * CONFIG_LINE_SIZE;
+    for line in config_lines {
+        if line.name.len() > MAX_NAME_LENGTH || line.uri.len() > MAX_URI_LENGTH {
+            return err!(CandyError::ConfigLineTooLong);
+        }
+        hidden_reveal.hash = HiddenReveal::chain_hash(&hidden_reveal.hash, &line)?;
+
+        // padded like the config lines of the candy machine so they can be found by mint number
+        let padded = ConfigLine {
+            name: format!("{:\0<1$}", line.name, MAX_NAME_LENGTH),
+            uri: format!("{:\0<1$}", line.uri, MAX_URI_LENGTH),
+        };
+        data[position..position + CONFIG_LINE_SIZE].copy_from_slice(&padded.try_to_vec()?);
+        position += CONFIG_LINE_SIZE;
+    }
+    hidden_reveal.lines_uploaded = total;
+
+    if total as u64 == candy_machine.data.items_available && hidden_reveal.hash != reveal_hash {
+        return err!(CandyError::RevealHashMismatch);
+    }
+
+    hidden_reveal.try_serialize(&mut *data)?;
+    Ok(())
+}
@@ -0,0 +1,5 @@
+pub mod add_reveal_lines;
+pub mod reveal_nft;
+
+pub use add_reveal_lines::*;
+pub use reveal_nft::*;
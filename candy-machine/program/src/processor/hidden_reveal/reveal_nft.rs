@@ -0,0 +1,121 @@
+use anchor_lang::prelude::*;
+use mpl_token_metadata::{
+    instruction::update_metadata_accounts_v2,
+    state::{DataV2, Metadata, TokenMetadataAccount},
+};
+use solana_program::program::invoke_signed;
+
+use crate::{
+    assert_owned_by, cmp_pubkeys,
+    constants::{CONFIG_LINE_SIZE, PREFIX},
+    CandyError, CandyMachine, ConfigLine, HiddenMintRecord, HiddenReveal,
+};
+
+/// Permissionlessly update a hidden settings NFT to the config line of its mint number.
+#[derive(Accounts)]
+#[instruction(creator_bump: u8)]
+pub struct RevealNft<'info> {
+    candy_machine: Account<'info, CandyMachine>,
+    #[account(seeds = [HiddenReveal::PREFIX.as_bytes(), candy_machine.key().as_ref()], bump, has_one = candy_machine)]
+    hidden_reveal: Account<'info, HiddenReveal>,
+    #[account(mut, has_one = candy_machine, has_one = minter, close = minter)]
+    hidden_mint_record: Account<'info, HiddenMintRecord>,
+    /// CHECK: gets the rent of the hidden mint record back, checked against the record
+    #[account(mut)]
+    minter: UncheckedAccount<'info>,
+    /// CHECK: account constraints checked in account trait
+    #[account(seeds = [PREFIX.as_bytes(), candy_machine.key().as_ref()], bump = creator_bump)]
+    candy_machine_creator: UncheckedAccount<'info>,
+    /// CHECK: account checked in handler and CPI
+    #[account(mut)]
+    metadata: UncheckedAccount<'info>,
+    /// CHECK: account constraints checked in account trait
+    #[account(address = mpl_token_metadata::id())]
+    token_metadata_program: UncheckedAccount<'info>,
+}
+
+pub fn handle_reveal_nft(ctx: Context<RevealNft>, creator_bump: u8) -> Result<()> {
+    let candy_machine = &ctx.accounts.candy_machine;
+    if candy_machine.data.hidden_settings.is_none() {
+        return err!(CandyError::HiddenRevealNotSupported);
+    }
+    // the last upload only goes through if the lines match the hidden settings hash
+    if ctx.accounts.hidden_reveal.lines_uploaded as u64 != candy_machine.data.items_available {
+        return err!(CandyError::RevealLinesIncomplete);
+    }
+
+    let metadata_info = ctx.accounts.metadata.to_account_info();
+    assert_owned_by(&metadata_info, &mpl_token_metadata::id())?;
+    let metadata = Metadata::from_account_info(&metadata_info)?;
+
+    // the creator PDA is the verified first creator of every NFT this candy machine minted,
+    // and keeps the update authority of the NFTs waiting for a reveal
+    let candy_machine_creator = ctx.accounts.candy_machine_creator.to_account_info();
+    let minted_here = matches!(
+        metadata.data.creators.as_ref().and_then(|creators| creators.first()),
+        Some(creator) if creator.verified && cmp_pubkeys(&creator.address, candy_machine_creator.key)
+    );
+    if !minted_here || !cmp_pubkeys(&metadata.update_authority, candy_machine_creator.key) {
+        return err!(CandyError::NftNotRevealable);
+    }
+
+    let hidden_mint_record = &ctx.accounts.hidden_mint_record;
+    if !cmp_pubkeys(&hidden_mint_record.mint, &metadata.mint)
+        || hidden_mint_record.mint_number >= candy_machine.data.items_available
+    {
+        return err!(CandyError::NftNotRevealable);
+    }
+    let mint_number = hidden_mint_record.mint_number as usize;
+
+    let config_line = {
+        let data = ctx.accounts.hidden_reveal.to_account_info();
+        let data = data.data.borrow();
+        let position = HiddenReveal::SIZE + mint_number * CONFIG_LINE_SIZE;
+        ConfigLine::deserialize(&mut &data[position..position + CONFIG_LINE_SIZE])?
+    };
+
+    // the NFT goes to the update authority it would have had without the hidden reveal
+    let update_authority = if candy_machine.data.retain_authority {
+        candy_machine.authority
+    } else {
+        hidden_mint_record.update_authority
+    };
+
+    let cm_key = candy_machine.key();
+    let authority_seeds = [PREFIX.as_bytes(), cm_key.as_ref(), &[creator_bump]];
+    invoke_signed(
+        &update_metadata_accounts_v2(
+            ctx.accounts.token_metadata_program.key(),
+            metadata_info.key(),
+            candy_machine_creator.key(),
+            Some(update_authority),
+            Some(DataV2 {
+                name: config_line.name.trim_end_matches(char::from(0)).to_string(),
+                symbol: metadata
+                    .data
+                    .symbol
+                    .trim_end_matches(char::from(0))
+                    .to_string(),
+                uri: config_line.uri.trim_end_matches(char::from(0)).to_string(),
+                seller_fee_basis_points: metadata.data.seller_fee_basis_points,
+                creators: metadata.data.creators,
+                collection: metadata.collection,
+                uses: metadata.uses,
+            }),
+            None,
+            if !candy_machine.data.is_mutable {
+                Some(false)
+            } else {
+                None
+            },
+        ),
+        &[
+            ctx.accounts.token_metadata_program.to_account_info(),
+            metadata_info,
+            candy_machine_creator,
+        ],
+        &[&authority_seeds],
+    )?;
+
+    Ok(())
+}
@@ -1,19 +1,24 @@
 use anchor_lang::{prelude::*, Discriminator};
-use mpl_token_metadata::state::{MAX_CREATOR_LIMIT, MAX_SYMBOL_LENGTH};
+use mpl_token_metadata::state::{
+    MAX_CREATOR_LIMIT, MAX_NAME_LENGTH, MAX_SYMBOL_LENGTH, MAX_URI_LENGTH,
+};
 use spl_token::state::Mint;
 
 use crate::{
-    assert_initialized, assert_owned_by, cmp_pubkeys,
-    constants::{CONFIG_ARRAY_START, CONFIG_LINE_SIZE},
-    CandyError, CandyMachine, CandyMachineData,
+    assert_candy_machine_fits, assert_initialized, assert_owned_by, cmp_pubkeys,
+    constants::{
+        CANDY_MACHINE_VERSION, CONFIG_ARRAY_START, CONFIG_INDICES_MARKER,
+        CONFIG_LINE_SETTINGS_MARKER, VERSION_OFFSET,
+    },
+    get_config_bit_mask_start, get_config_indices_start, CandyError, CandyMachine,
+    CandyMachineData, ConfigLineSettings,
 };
 
 /// Create a new candy machine.
 #[derive(Accounts)]
-#[instruction(data: CandyMachineData)]
 pub struct InitializeCandyMachine<'info> {
-    /// CHECK: account constraints checked in account trait
-    #[account(zero, rent_exempt = skip, constraint = candy_machine.to_account_info().owner == program_id && candy_machine.to_account_info().data_len() >= get_space_for_candy(data)?)]
+    /// CHECK: account constraints checked in account trait, space checked in the handler
+    #[account(zero, rent_exempt = skip, constraint = candy_machine.to_account_info().owner == program_id)]
     candy_machine: UncheckedAccount<'info>,
     /// CHECK: wallet can be any account and is not written to or read
     wallet: UncheckedAccount<'info>,
@@ -27,6 +32,7 @@ pub struct InitializeCandyMachine<'info> {
 pub fn handle_initialize_candy_machine(
     ctx: Context<InitializeCandyMachine>,
     data: CandyMachineData,
+    config_line_settings: Option<ConfigLineSettings>,
 ) -> Result<()> {
     let candy_machine_account = &mut ctx.accounts.candy_machine;
 
@@ -34,12 +40,26 @@ pub fn handle_initialize_candy_machine(
         return err!(CandyError::UuidMustBeExactly6Length);
     }
 
+    if let Some(settings) = &config_line_settings {
+        if data.hidden_settings.is_some() {
+            return err!(CandyError::HiddenSettingsConfigsDoNotHaveConfigLines);
+        }
+        settings.assert_valid()?;
+    }
+    if candy_machine_account.data_len() < get_space_for_candy(&data, config_line_settings.as_ref())?
+    {
+        return err!(CandyError::CandyMachineTooSmall);
+    }
+
     let mut candy_machine = CandyMachine {
         data,
         authority: ctx.accounts.authority.key(),
         wallet: ctx.accounts.wallet.key(),
         token_mint: None,
         items_redeemed: 0,
+        version: CANDY_MACHINE_VERSION,
+        features: 0,
+        reserved: [0; 16],
     };
 
     candy_machine.data.uuid = "000000".to_string();
@@ -71,6 +91,7 @@ pub fn handle_initialize_candy_machine(
     if candy_machine.data.creators.len() > MAX_CREATOR_LIMIT - 1 {
         return err!(CandyError::TooManyCreators);
     }
+    assert_candy_machine_fits(&candy_machine)?;
 
     let mut new_data = CandyMachine::discriminator().try_to_vec().unwrap();
     new_data.append(&mut candy_machine.try_to_vec().unwrap());
@@ -79,13 +100,30 @@ pub fn handle_initialize_candy_machine(
     for i in 0..new_data.len() {
         data[i] = new_data[i];
     }
+    data[VERSION_OFFSET] = CANDY_MACHINE_VERSION;
 
     // only if we are not using hidden settings we will have space for
     // the config lines
     if candy_machine.data.hidden_settings.is_none() {
-        let vec_start = CONFIG_ARRAY_START
-            + 4
-            + (candy_machine.data.items_available as usize) * CONFIG_LINE_SIZE;
+        if let Some(settings) = &config_line_settings {
+            // prefixes are padded so the config lines start at a fixed offset
+            let padded = ConfigLineSettings {
+                prefix_name: format!("{:\0<1$}", settings.prefix_name, MAX_NAME_LENGTH),
+                prefix_uri: format!("{:\0<1$}", settings.prefix_uri, MAX_URI_LENGTH),
+                ..settings.clone()
+            };
+            let mut settings_data = CONFIG_LINE_SETTINGS_MARKER.to_le_bytes().to_vec();
+            settings_data.append(&mut padded.try_to_vec()?);
+            data[CONFIG_ARRAY_START..CONFIG_ARRAY_START + settings_data.len()]
+                .copy_from_slice(&settings_data);
+        }
+
+        let items_available = candy_machine.data.items_available as usize;
+        let vec_start = get_config_bit_mask_start(
+            CONFIG_ARRAY_START,
+            items_available,
+            config_line_settings.as_ref(),
+        ) - 4;
         let as_bytes = (candy_machine
             .data
             .items_available
@@ -95,24 +133,33 @@ pub fn handle_initialize_candy_machine(
         for i in 0..4 {
             data[vec_start + i] = as_bytes[i]
         }
+        // zeroed index slots already stand for their own position
+        let indices_start = get_config_indices_start(
+            CONFIG_ARRAY_START,
+            items_available,
+            config_line_settings.as_ref(),
+        )?;
+        data[indices_start..indices_start + 4]
+            .copy_from_slice(&CONFIG_INDICES_MARKER.to_le_bytes());
     }
 
     Ok(())
 }
 
-fn get_space_for_candy(data: CandyMachineData) -> Result<usize> {
+pub fn get_space_for_candy(
+    data: &CandyMachineData,
+    config_line_settings: Option<&ConfigLineSettings>,
+) -> Result<usize> {
     let num = if data.hidden_settings.is_some() {
         CONFIG_ARRAY_START
     } else {
-        CONFIG_ARRAY_START
-            + 4
-            + (data.items_available as usize) * CONFIG_LINE_SIZE
-            + 8
-            + 2 * ((data
-                .items_available
-                .checked_div(8)
-                .ok_or(CandyError::NumericalOverflowError)?
-                + 1) as usize)
+        // config lines, loaded-lines bitmask, then the config index array
+        get_config_indices_start(
+            CONFIG_ARRAY_START,
+            data.items_available as usize,
+            config_line_settings,
+        )? + 8
+            + 4 * (data.items_available as usize)
     };
 
     Ok(num)
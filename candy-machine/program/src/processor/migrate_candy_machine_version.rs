@@ -0,0 +1,56 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    assert_candy_machine_fits, clear_legacy_features,
+    constants::{
+        CANDY_MACHINE_VERSION, CONFIG_ARRAY_START, LEGACY_CONFIG_ARRAY_START,
+        LEGACY_MINT_SETTINGS_OFFSET, VERSION_OFFSET,
+    },
+    get_features, is_legacy_candy_machine, resize_account, CandyError, CandyMachine,
+};
+
+/// Move the feature flags of a legacy candy machine out of its uuid into the versioned account.
+#[derive(Accounts)]
+pub struct MigrateCandyMachineVersion<'info> {
+    #[account(mut, has_one = authority)]
+    candy_machine: Account<'info, CandyMachine>,
+    authority: Signer<'info>,
+    #[account(mut)]
+    payer: Signer<'info>,
+    system_program: Program<'info, System>,
+}
+
+pub fn handle_migrate_candy_machine_version(
+    ctx: Context<MigrateCandyMachineVersion>,
+) -> Result<()> {
+    let candy_machine = &mut ctx.accounts.candy_machine;
+    let account = candy_machine.to_account_info();
+    if !is_legacy_candy_machine(&account.data.borrow()) {
+        return err!(CandyError::CandyMachineAlreadyMigrated);
+    }
+
+    // the trailing fields held whatever bytes followed the legacy data
+    let features = get_features(candy_machine)?;
+    candy_machine.version = CANDY_MACHINE_VERSION;
+    candy_machine.features = features.bits();
+    candy_machine.reserved = [0; 16];
+    candy_machine.data.uuid = clear_legacy_features(&candy_machine.data.uuid);
+    assert_candy_machine_fits(candy_machine)?;
+
+    let old_len = account.data_len();
+    resize_account(
+        &account,
+        &ctx.accounts.payer.to_account_info(),
+        &ctx.accounts.system_program.to_account_info(),
+        old_len + CONFIG_ARRAY_START - LEGACY_CONFIG_ARRAY_START,
+    )?;
+
+    // config data moves back to make room for the bot tax
+    let mut data = account.data.borrow_mut();
+    data.copy_within(LEGACY_CONFIG_ARRAY_START..old_len, CONFIG_ARRAY_START);
+    data[LEGACY_CONFIG_ARRAY_START..CONFIG_ARRAY_START].fill(0);
+    data[LEGACY_MINT_SETTINGS_OFFSET] = 0;
+    data[VERSION_OFFSET] = CANDY_MACHINE_VERSION;
+
+    Ok(())
+}
@@ -0,0 +1,89 @@
+use anchor_lang::prelude::*;
+use solana_program::entrypoint::MAX_PERMITTED_DATA_INCREASE;
+
+use crate::{
+    constants::{CONFIG_ARRAY_START, CONFIG_INDICES_MARKER},
+    get_config_indices_start, get_config_line_settings, get_space_for_candy, is_config_data_closed,
+    is_legacy_candy_machine, resize_account, uses_config_indices, CandyError, CandyMachine,
+};
+
+/// Move a candy machine from the taken-lines bitmask to the swap-remove config index array.
+#[derive(Accounts)]
+pub struct MigrateConfigIndices<'info> {
+    #[account(mut, has_one = authority)]
+    candy_machine: Account<'info, CandyMachine>,
+    authority: Signer<'info>,
+    #[account(mut)]
+    payer: Signer<'info>,
+    system_program: Program<'info, System>,
+}
+
+pub fn handle_migrate_config_indices(ctx: Context<MigrateConfigIndices>) -> Result<()> {
+    let candy_machine = &ctx.accounts.candy_machine;
+    if candy_machine.data.hidden_settings.is_some() {
+        return err!(CandyError::HiddenSettingsConfigsDoNotHaveConfigLines);
+    }
+
+    let items_available = candy_machine.data.items_available as usize;
+    let account = candy_machine.to_account_info();
+    // the index array is sized for the versioned layout, so the version migration comes first
+    if is_legacy_candy_machine(&account.data.borrow()) {
+        return err!(CandyError::CandyMachineNotMigrated);
+    }
+    if is_config_data_closed(&account.data.borrow()) {
+        return err!(CandyError::ConfigDataClosed);
+    }
+    // compressed config lines were introduced with the index array, so only full ones migrate
+    let data = account.data.borrow();
+    if get_config_line_settings(&data)?.is_some()
+        || uses_config_indices(&data, items_available, None)?
+    {
+        return err!(CandyError::ConfigIndicesAlreadyMigrated);
+    }
+    drop(data);
+
+    // accounts can only grow by MAX_PERMITTED_DATA_INCREASE per instruction, so large
+    // candy machines call this again until the index array fits
+    let required_len = get_space_for_candy(&candy_machine.data, None)?;
+    if account.data_len() < required_len {
+        let new_len = required_len.min(account.data_len() + MAX_PERMITTED_DATA_INCREASE);
+        resize_account(
+            &account,
+            &ctx.accounts.payer.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            new_len,
+        )?;
+        if new_len < required_len {
+            msg!("Resized to {} of {} bytes", new_len, required_len);
+            return Ok(());
+        }
+    }
+
+    let mut data = account.data.borrow_mut();
+    let indices_start = get_config_indices_start(CONFIG_ARRAY_START, items_available, None)?;
+    // the legacy taken-lines bitmask overlaps the index array, so copy it out first
+    let taken_mask_start = indices_start + 3;
+    let taken_mask = data[taken_mask_start
+        ..taken_mask_start
+            + items_available
+                .checked_div(8)
+                .ok_or(CandyError::NumericalOverflowError)?
+            + 1]
+        .to_vec();
+
+    let slots_start = indices_start + 8;
+    let mut remaining = 0;
+    for index in 0..items_available {
+        let mask = u8::pow(2, 7 - (index % 8) as u32);
+        if taken_mask[index / 8] & mask == 0 {
+            let position = slots_start + remaining * 4;
+            data[position..position + 4].copy_from_slice(&(index as u32 + 1).to_le_bytes());
+            remaining += 1;
+        }
+    }
+    let taken = (items_available - remaining) as u32;
+    data[indices_start..indices_start + 4].copy_from_slice(&CONFIG_INDICES_MARKER.to_le_bytes());
+    data[indices_start + 4..indices_start + 8].copy_from_slice(&taken.to_le_bytes());
+
+    Ok(())
+}
@@ -1,14 +1,20 @@
-use std::{cell::RefMut, ops::Deref};
+use std::cell::RefMut;
 
 use anchor_lang::prelude::*;
 use anchor_spl::token::Token;
 use arrayref::array_ref;
 use mpl_token_metadata::{
     instruction::{
+        builders::{CreateBuilder, DelegateBuilder, LockBuilder, MintBuilder, UpdateBuilder},
         create_master_edition_v3, create_metadata_accounts_v3, freeze_delegated_account,
-        update_metadata_accounts_v2,
+        mint_new_edition_from_master_edition_via_token, update_metadata_accounts_v2,
+        CollectionDetailsToggle, CollectionToggle, CreateArgs, DelegateArgs, InstructionBuilder,
+        LockArgs, MintArgs, RuleSetToggle, UpdateArgs, UsesToggle,
+    },
+    state::{
+        AssetData, MasterEditionV2, PrintSupply, TokenMetadataAccount, TokenStandard,
+        MAX_NAME_LENGTH, MAX_URI_LENGTH,
     },
-    state::{MAX_NAME_LENGTH, MAX_URI_LENGTH},
 };
 use solana_gateway::{
     state::{GatewayTokenAccess, InPlaceGatewayToken},
@@ -16,21 +22,25 @@ use solana_gateway::{
 };
 use solana_program::{
     clock::Clock,
+    keccak,
     program::{invoke, invoke_signed},
     serialize_utils::{read_pubkey, read_u16},
     system_instruction, sysvar,
     sysvar::{instructions::get_instruction_relative, SysvarId},
 };
-use spl_token::instruction::approve;
+use spl_token::instruction::{approve, set_authority, AuthorityType};
 
 use crate::{
     constants::{
-        A_TOKEN, BLOCK_HASHES, BOT_FEE, COLLECTIONS_FEATURE_INDEX, COMPUTE_BUDGET,
-        CONFIG_ARRAY_START, CONFIG_LINE_SIZE, CUPCAKE_ID, EXPIRE_OFFSET, FREEZE_FEATURE_INDEX,
-        GUMDROP_ID, PREFIX,
+        A_TOKEN, BLOCK_HASHES, COMPUTE_BUDGET, CONFIG_ARRAY_START, CONFIG_INDICES_MARKER,
+        CONFIG_LINE_SETTINGS_MARKER, CONFIG_LINE_SIZE, CUPCAKE_ID, EXPIRE_OFFSET, GUMDROP_ID,
+        LEGACY_CONFIG_ARRAY_START, MAX_MINT_QUANTITY, MINT_AUTHORITY_PREFIX, PREFIX,
     },
     utils::*,
-    CandyError, CandyMachine, CandyMachineData, ConfigLine, EndSettingType, FreezePDA,
+    AllowlistProof, AllowlistRoot, BotTaxDestination, CandyError, CandyFeatures, CandyMachine,
+    CandyMachineData, ConfigLine, ConfigLineSettings, EditionSettings, EndSettingType, FreezePDA,
+    HiddenMintRecord, HolderMintRecord, MintCounter, MintLimitPDA, MintPhase, MintPhases,
+    MintRequest, MintSettings, PaymentOptions, PhaseGate, ProgrammableSettings, RevenueSplits,
     WhitelistMintMode, WhitelistMintSettings,
 };
 
@@ -83,7 +93,9 @@ pub struct MintNFT<'info> {
     // gateway program
     // network_expire_feature
     // > Only needed if candy machine has whitelist_mint_settings
-    // whitelist_token_account
+    // whitelist_token_account (allowlist_proof in MerkleTree mode)
+    // > Only needed if candy machine has whitelist_mint_settings and mode is MerkleTree
+    // allowlist_root
     // > Only needed if candy machine has whitelist_mint_settings and mode is BurnEveryTime
     // whitelist_token_mint
     // whitelist_burn_authority
@@ -94,12 +106,114 @@ pub struct MintNFT<'info> {
     // freeze_pda (writable)
     // nft_token_account (writable)
     // freeze_ata (writable) // Only needed if spl token mint is enabled
+    // > Only needed if the candy machine has mint settings
+    // mint_settings
+    // mint_request (writable) // Only needed if the settings have a randomness oracle
+    // > Only needed if the mint settings mint programmable NFTs
+    // nft_token_account (writable) // created if empty, the mint must have no supply
+    // token_record (writable)
+    // associated_token_program
+    // authorization_rules_program // Only needed if the settings have a rule set
+    // authorization_rules // Only needed if the settings have a rule set
+    // mint_authority (signer) // Only needed if the settings have a mint authority program
+    // > Only needed if the candy machine has a mint limit
+    // mint_limit
+    // mint_counter (writable)
+    // > Only needed if the candy machine has mint phases
+    // mint_phases
+    // > Only needed if the live phase has a wallet limit
+    // mint_counter (writable)
+    // > Only needed if the live phase has a whitelist token gate
+    // whitelist_token_account (writable)
+    // whitelist_token_mint (writable) // Only needed if the gate burns the token
+    // > Only needed if the live phase has a collection holder gate
+    // held_token_account
+    // held_metadata
+    // holder_mint_record (writable) // Only needed if each held NFT can only mint once
+    // > Only needed if the live phase has a payment
+    // payment_token_account (writable)
+    // payment_destination (writable)
+    // > Only needed if the candy machine has revenue splits
+    // revenue_splits
+    // split recipients (writable) // one per split, in split order
+    // > Only needed if the candy machine prints editions
+    // edition_settings
+    // master_metadata
+    // master_edition (writable)
+    // master_token_account
+    // edition_marker (writable)
+    // > Only needed if the candy machine has payment options
+    // payment_options
+    // > Only needed to pay with a payment option, see `pay_with_payment_option`
+    // payment option accounts (writable)
+    // > Only needed by `mint_many`, for every NFT after the first
+    // mint (writable)
+    // metadata (writable)
+    // master_edition (writable)
+    // > Only needed by `mint_nft_to`
+    // recipient
 }
 
 pub fn handle_mint_nft<'info>(
     ctx: Context<'_, '_, '_, 'info, MintNFT<'info>>,
     creator_bump: u8,
 ) -> Result<()> {
+    mint_candy_nfts(ctx, creator_bump, 1, false)
+}
+
+/// Mints a new NFT to a recipient other than the payer, which still pays the price and rent.
+///
+/// Takes the same accounts as `mint_nft`, followed by the recipient wallet. Limits and gates
+/// apply to the recipient, which has to sign when they burn or expire its tokens or the NFT
+/// is frozen.
+pub fn handle_mint_nft_to<'info>(
+    ctx: Context<'_, '_, '_, 'info, MintNFT<'info>>,
+    creator_bump: u8,
+) -> Result<()> {
+    mint_candy_nfts(ctx, creator_bump, 1, true)
+}
+
+/// Mints `quantity` NFTs to the payer for `quantity` times the price, charged once.
+///
+/// Takes the same accounts as `mint_nft`, followed by the mint, metadata and master edition of
+/// every NFT after the first.
+pub fn handle_mint_many<'info>(
+    ctx: Context<'_, '_, '_, 'info, MintNFT<'info>>,
+    creator_bump: u8,
+    quantity: u8,
+) -> Result<()> {
+    if quantity == 0 || quantity > MAX_MINT_QUANTITY {
+        return err!(CandyError::InvalidMintQuantity);
+    }
+    mint_candy_nfts(ctx, creator_bump, quantity, false)
+}
+
+fn mint_candy_nfts<'info>(
+    ctx: Context<'_, '_, '_, 'info, MintNFT<'info>>,
+    creator_bump: u8,
+    quantity: u8,
+    to_recipient: bool,
+) -> Result<()> {
+    let bot_tax = get_bot_tax(&ctx.accounts.candy_machine)?;
+    // An address taking the bot tax comes after all of the other accounts.
+    let (remaining_accounts, bot_tax_destination) = match bot_tax.destination {
+        BotTaxDestination::CandyMachine => (
+            ctx.remaining_accounts,
+            ctx.accounts.candy_machine.to_account_info(),
+        ),
+        BotTaxDestination::Wallet => (
+            ctx.remaining_accounts,
+            ctx.accounts.wallet.to_account_info(),
+        ),
+        BotTaxDestination::Address(address) => match ctx.remaining_accounts.split_last() {
+            Some((destination, remaining_accounts))
+                if cmp_pubkeys(destination.key, &address) && destination.is_writable =>
+            {
+                (remaining_accounts, destination.clone())
+            }
+            _ => return err!(CandyError::BotTaxDestinationMismatch),
+        },
+    };
     let candy_machine = &mut ctx.accounts.candy_machine;
     let candy_machine_creator = &ctx.accounts.candy_machine_creator;
     // Note this is the wallet of the Candy machine
@@ -112,19 +226,51 @@ pub fn handle_mint_nft<'info>(
     let instruction_sysvar_account = &ctx.accounts.instruction_sysvar_account;
     let instruction_sysvar_account_info = instruction_sysvar_account.to_account_info();
     let instruction_sysvar = instruction_sysvar_account_info.data.borrow();
-    let current_ix = get_instruction_relative(0, &instruction_sysvar_account_info).unwrap();
+    let current_ix = get_instruction_relative(0, &instruction_sysvar_account_info)?;
     // We must ensure the metadata cannot be passed in with data in it, this must remain the first check before any bot taxes
     if !ctx.accounts.metadata.data_is_empty() {
         return err!(CandyError::MetadataAccountMustBeEmpty);
     }
+    // The recipient comes after all of the other accounts but the bot tax destination.
+    let (remaining_accounts, recipient) = if to_recipient {
+        match remaining_accounts.split_last() {
+            Some((recipient, remaining_accounts)) => (remaining_accounts, recipient.clone()),
+            None => return err!(CandyError::IncorrectRemainingAccountsLen),
+        }
+    } else {
+        (remaining_accounts, payer.to_account_info())
+    };
+    // The accounts of every NFT after the first come after all of the other accounts.
+    let extra_nft_accounts_len = 3 * (quantity as usize - 1);
+    if remaining_accounts.len() < extra_nft_accounts_len {
+        return err!(CandyError::IncorrectRemainingAccountsLen);
+    }
+    let (remaining_accounts, extra_nft_accounts) =
+        remaining_accounts.split_at(remaining_accounts.len() - extra_nft_accounts_len);
 
-    if get_expected_remaining_accounts_count(candy_machine) > ctx.remaining_accounts.len() {
+    for nft_accounts in extra_nft_accounts.chunks(3) {
+        if !nft_accounts[1].data_is_empty() {
+            return err!(CandyError::MetadataAccountMustBeEmpty);
+        }
+    }
+
+    let features = get_features(candy_machine)?;
+    if quantity > 1
+        && (features.contains(CandyFeatures::COLLECTIONS)
+            || features.contains(CandyFeatures::FREEZE)
+            || features.contains(CandyFeatures::EDITIONS))
+    {
+        return err!(CandyError::MintManyNotSupported);
+    }
+    if get_expected_remaining_accounts_count(candy_machine, features, None, None)
+        > remaining_accounts.len()
+    {
         punish_bots(
             CandyError::IncorrectRemainingAccountsLen,
             payer.to_account_info(),
-            ctx.accounts.candy_machine.to_account_info(),
+            bot_tax_destination.clone(),
             ctx.accounts.system_program.to_account_info(),
-            BOT_FEE,
+            &bot_tax,
         )?;
         return Ok(());
     }
@@ -133,12 +279,35 @@ pub fn handle_mint_nft<'info>(
         punish_bots(
             CandyError::CandyMachineEmpty,
             payer.to_account_info(),
-            ctx.accounts.candy_machine.to_account_info(),
+            bot_tax_destination.clone(),
             ctx.accounts.system_program.to_account_info(),
-            BOT_FEE,
+            &bot_tax,
         )?;
         return Ok(());
     }
+    if candy_machine.items_redeemed + u64::from(quantity) > candy_machine.data.items_available {
+        return err!(CandyError::CandyMachineEmpty);
+    }
+
+    // The mint settings pick who can call the candy machine, so they are read up front.
+    let mint_settings = if features.contains(CandyFeatures::MINT_SETTINGS) {
+        let mint_settings_info = remaining_accounts
+            .get(get_feature_accounts_index(candy_machine, features))
+            .ok_or(CandyError::IncorrectRemainingAccountsLen)?;
+        let mint_settings: Account<MintSettings> = Account::try_from(mint_settings_info)?;
+        if !cmp_pubkeys(&mint_settings.candy_machine, &candy_machine.key()) {
+            return err!(CandyError::MintSettingsMismatch);
+        }
+        Some(mint_settings)
+    } else {
+        None
+    };
+    let mint_authority_program = mint_settings
+        .as_ref()
+        .and_then(|mint_settings| mint_settings.mint_authority_program);
+    if mint_authority_program.is_some() && features.contains(CandyFeatures::COLLECTIONS) {
+        return err!(CandyError::MintAuthorityNotSupported);
+    }
 
     if cmp_pubkeys(&recent_slothashes.key(), &BLOCK_HASHES) {
         msg!("recent_blockhashes is deprecated and will break soon");
@@ -149,16 +318,21 @@ pub fn handle_mint_nft<'info>(
         return err!(CandyError::IncorrectSlotHashesPubkey);
     }
     // Restrict Who can call Candy Machine via CPI
-    if !cmp_pubkeys(&current_ix.program_id, &crate::id())
-        && !cmp_pubkeys(&current_ix.program_id, &GUMDROP_ID)
-        && !cmp_pubkeys(&current_ix.program_id, &CUPCAKE_ID)
-    {
+    let allowed_caller = match &mint_authority_program {
+        Some(mint_authority_program) => cmp_pubkeys(&current_ix.program_id, mint_authority_program),
+        None => {
+            cmp_pubkeys(&current_ix.program_id, &crate::id())
+                || cmp_pubkeys(&current_ix.program_id, &GUMDROP_ID)
+                || cmp_pubkeys(&current_ix.program_id, &CUPCAKE_ID)
+        }
+    };
+    if !allowed_caller {
         punish_bots(
             CandyError::SuspiciousTransaction,
             payer.to_account_info(),
-            ctx.accounts.candy_machine.to_account_info(),
+            bot_tax_destination.clone(),
             ctx.accounts.system_program.to_account_info(),
-            BOT_FEE,
+            &bot_tax,
         )?;
         return Ok(());
     }
@@ -177,13 +351,13 @@ pub fn handle_mint_nft<'info>(
             }
         }
         Err(_) => {
-            if is_feature_active(&candy_machine.data.uuid, COLLECTIONS_FEATURE_INDEX) {
+            if features.contains(CandyFeatures::COLLECTIONS) {
                 punish_bots(
                     CandyError::MissingSetCollectionDuringMint,
                     payer.to_account_info(),
-                    ctx.accounts.candy_machine.to_account_info(),
+                    bot_tax_destination.clone(),
                     ctx.accounts.system_program.to_account_info(),
-                    BOT_FEE,
+                    &bot_tax,
                 )?;
                 return Ok(());
             }
@@ -203,6 +377,9 @@ pub fn handle_mint_nft<'info>(
         let program_id = read_pubkey(&mut current, &instruction_sysvar).unwrap();
 
         if !cmp_pubkeys(&program_id, &crate::id())
+            && mint_authority_program.map_or(true, |mint_authority_program| {
+                !cmp_pubkeys(&program_id, &mint_authority_program)
+            })
             && !cmp_pubkeys(&program_id, &spl_token::id())
             && !cmp_pubkeys(&program_id, &solana_program::system_program::ID)
             && !cmp_pubkeys(&program_id, &A_TOKEN)
@@ -212,9 +389,9 @@ pub fn handle_mint_nft<'info>(
             punish_bots(
                 CandyError::SuspiciousTransaction,
                 payer.to_account_info(),
-                ctx.accounts.candy_machine.to_account_info(),
+                bot_tax_destination.clone(),
                 ctx.accounts.system_program.to_account_info(),
-                BOT_FEE,
+                &bot_tax,
             )?;
             return Ok(());
         }
@@ -230,9 +407,9 @@ pub fn handle_mint_nft<'info>(
                     punish_bots(
                         CandyError::CandyMachineNotLive,
                         payer.to_account_info(),
-                        ctx.accounts.candy_machine.to_account_info(),
+                        bot_tax_destination.clone(),
                         ctx.accounts.system_program.to_account_info(),
-                        BOT_FEE,
+                        &bot_tax,
                     )?;
                     return Ok(());
                 }
@@ -243,20 +420,85 @@ pub fn handle_mint_nft<'info>(
                         punish_bots(
                             CandyError::CandyMachineEmpty,
                             payer.to_account_info(),
-                            ctx.accounts.candy_machine.to_account_info(),
+                            bot_tax_destination.clone(),
                             ctx.accounts.system_program.to_account_info(),
-                            BOT_FEE,
+                            &bot_tax,
                         )?;
                         return Ok(());
                     }
                     return err!(CandyError::CandyMachineEmpty);
                 }
+                if candy_machine.items_redeemed + u64::from(quantity) > es.number {
+                    return err!(CandyError::CandyMachineEmpty);
+                }
             }
         }
     }
+
+    // The live phase stands in for the go live date and the whitelist presale, so it is
+    // resolved before any of them.
+    let mint_phases = if features.contains(CandyFeatures::MINT_PHASES) {
+        let mint_phases_index = get_feature_accounts_index(candy_machine, features)
+            + mint_settings
+                .as_ref()
+                .map_or(0, |mint_settings| 1 + mint_settings.accounts_len())
+            + if features.contains(CandyFeatures::MINT_LIMIT) {
+                2
+            } else {
+                0
+            };
+        let mint_phases_info = remaining_accounts
+            .get(mint_phases_index)
+            .ok_or(CandyError::IncorrectRemainingAccountsLen)?;
+        let mint_phases: Account<MintPhases> = Account::try_from(mint_phases_info)?;
+        if !cmp_pubkeys(&mint_phases.candy_machine, &candy_machine.key()) {
+            return err!(CandyError::MintPhasesMismatch);
+        }
+        Some(mint_phases)
+    } else {
+        None
+    };
+    let active_phase = mint_phases
+        .as_ref()
+        .and_then(|mint_phases| mint_phases.active_phase(clock.unix_timestamp));
+    // The authority can mint outside of the phases at the candy machine price.
+    if mint_phases.is_some()
+        && active_phase.is_none()
+        && !cmp_pubkeys(&payer.key(), &candy_machine.authority)
+    {
+        punish_bots(
+            CandyError::CandyMachineNotLive,
+            payer.to_account_info(),
+            bot_tax_destination.clone(),
+            ctx.accounts.system_program.to_account_info(),
+            &bot_tax,
+        )?;
+        return Ok(());
+    }
+    // The accounts of the mint settings and of the live phase are known from here on.
+    if get_expected_remaining_accounts_count(
+        candy_machine,
+        features,
+        mint_settings.as_deref(),
+        active_phase.map(|(_, phase)| phase),
+    ) > remaining_accounts.len()
+    {
+        punish_bots(
+            CandyError::IncorrectRemainingAccountsLen,
+            payer.to_account_info(),
+            bot_tax_destination.clone(),
+            ctx.accounts.system_program.to_account_info(),
+            &bot_tax,
+        )?;
+        return Ok(());
+    }
+
+    // Burns and counters are only written once every check that taxes the payer has passed,
+    // as taxing them succeeds and commits anything written before.
+    let mut pending_updates = Vec::new();
     let mut remaining_accounts_counter: usize = 0;
     if let Some(gatekeeper) = &candy_machine.data.gatekeeper {
-        let gateway_token_info = &ctx.remaining_accounts[remaining_accounts_counter];
+        let gateway_token_info = &remaining_accounts[remaining_accounts_counter];
 
         remaining_accounts_counter += 1;
 
@@ -282,15 +524,19 @@ pub fn handle_mint_nft<'info>(
             };
 
         if gatekeeper.expire_on_use {
-            let gateway_app = &ctx.remaining_accounts[remaining_accounts_counter];
+            // Expiring the token is an honest mistake without the recipient's signature.
+            if !recipient.is_signer {
+                return err!(CandyError::RecipientMustSign);
+            }
+            let gateway_app = &remaining_accounts[remaining_accounts_counter];
             remaining_accounts_counter += 1;
-            let network_expire_feature = &ctx.remaining_accounts[remaining_accounts_counter];
+            let network_expire_feature = &remaining_accounts[remaining_accounts_counter];
             remaining_accounts_counter += 1;
 
             if Gateway::verify_and_expire_token_with_eval(
                 gateway_app.clone(),
                 gateway_token_info.clone(),
-                payer.deref().clone(),
+                recipient.clone(),
                 &gatekeeper.gatekeeper_network,
                 network_expire_feature.clone(),
                 eval_function,
@@ -300,15 +546,15 @@ pub fn handle_mint_nft<'info>(
                 punish_bots(
                     CandyError::GatewayProgramError,
                     payer.to_account_info(),
-                    ctx.accounts.candy_machine.to_account_info(),
+                    bot_tax_destination.clone(),
                     ctx.accounts.system_program.to_account_info(),
-                    BOT_FEE,
+                    &bot_tax,
                 )?;
                 return Ok(());
             }
         } else if Gateway::verify_gateway_token_with_eval(
             gateway_token_info,
-            &payer.key(),
+            recipient.key,
             &gatekeeper.gatekeeper_network,
             None,
             eval_function,
@@ -318,63 +564,69 @@ pub fn handle_mint_nft<'info>(
             punish_bots(
                 CandyError::GatewayProgramError,
                 payer.to_account_info(),
-                ctx.accounts.candy_machine.to_account_info(),
+                bot_tax_destination.clone(),
                 ctx.accounts.system_program.to_account_info(),
-                BOT_FEE,
+                &bot_tax,
             )?;
             return Ok(());
         }
     }
 
     if let Some(ws) = &candy_machine.data.whitelist_mint_settings {
-        let whitelist_token_account = &ctx.remaining_accounts[remaining_accounts_counter];
+        let whitelist_token_account = &remaining_accounts[remaining_accounts_counter];
         remaining_accounts_counter += 1;
+        // A merkle allowlist proof counts as holding a whitelist token for every
+        // NFT left in its allocation.
+        let mut allowlist_proof = None;
+        let whitelist_token_amount = if ws.mode == WhitelistMintMode::MerkleTree {
+            let allowlist_root_info = &remaining_accounts[remaining_accounts_counter];
+            remaining_accounts_counter += 1;
+            let allowlist_root: Account<AllowlistRoot> = Account::try_from(allowlist_root_info)?;
+            if !cmp_pubkeys(&allowlist_root.candy_machine, &candy_machine.key()) {
+                return err!(CandyError::AllowlistRootMismatch);
+            }
+            allowlist_proof = assert_allowlist_proof(
+                whitelist_token_account,
+                &candy_machine.key(),
+                recipient.key,
+                &allowlist_root.root,
+            )
+            .ok();
+            allowlist_proof
+                .as_ref()
+                .map(|proof| proof.allocation.saturating_sub(proof.minted))
+        } else {
+            assert_is_ata(whitelist_token_account, recipient.key, &ws.mint)
+                .ok()
+                .map(|wta| wta.amount)
+        };
         // If the user has not actually made this account,
         // this explodes and we just check normal dates.
-        // If they have, we check amount, if it's > 0 we let them use the logic
-        // if 0, check normal dates.
-        match assert_is_ata(whitelist_token_account, &payer.key(), &ws.mint) {
-            Ok(wta) => {
-                if wta.amount > 0 {
-                    match candy_machine.data.go_live_date {
-                        None => {
-                            if !cmp_pubkeys(&ctx.accounts.payer.key(), &candy_machine.authority)
-                                && !ws.presale
-                            {
-                                punish_bots(
-                                    CandyError::CandyMachineNotLive,
-                                    payer.to_account_info(),
-                                    ctx.accounts.candy_machine.to_account_info(),
-                                    ctx.accounts.system_program.to_account_info(),
-                                    BOT_FEE,
-                                )?;
-                                return Ok(());
-                            }
-                        }
-                        Some(val) => {
-                            if clock.unix_timestamp < val
-                                && !cmp_pubkeys(&ctx.accounts.payer.key(), &candy_machine.authority)
-                                && !ws.presale
-                            {
-                                punish_bots(
-                                    CandyError::CandyMachineNotLive,
-                                    payer.to_account_info(),
-                                    ctx.accounts.candy_machine.to_account_info(),
-                                    ctx.accounts.system_program.to_account_info(),
-                                    BOT_FEE,
-                                )?;
-                                return Ok(());
-                            }
-                        }
+        // If they have, we check amount, if it covers every NFT minted we let them use the logic
+        // otherwise, check normal dates.
+        match whitelist_token_amount {
+            Some(amount) => {
+                if amount >= u64::from(quantity) {
+                    if mint_phases.is_none()
+                        && !ws.presale
+                        && assert_valid_go_live(payer, &clock, candy_machine).is_err()
+                    {
+                        punish_bots(
+                            CandyError::CandyMachineNotLive,
+                            payer.to_account_info(),
+                            bot_tax_destination.clone(),
+                            ctx.accounts.system_program.to_account_info(),
+                            &bot_tax,
+                        )?;
+                        return Ok(());
                     }
 
                     if ws.mode == WhitelistMintMode::BurnEveryTime {
-                        let whitelist_token_mint =
-                            &ctx.remaining_accounts[remaining_accounts_counter];
+                        let whitelist_token_mint = &remaining_accounts[remaining_accounts_counter];
                         remaining_accounts_counter += 1;
 
                         let whitelist_burn_authority =
-                            &ctx.remaining_accounts[remaining_accounts_counter];
+                            &remaining_accounts[remaining_accounts_counter];
                         remaining_accounts_counter += 1;
 
                         let key_check = assert_keys_equal(&whitelist_token_mint.key(), &ws.mint);
@@ -383,48 +635,57 @@ pub fn handle_mint_nft<'info>(
                             punish_bots(
                                 CandyError::IncorrectOwner,
                                 payer.to_account_info(),
-                                ctx.accounts.candy_machine.to_account_info(),
+                                bot_tax_destination.clone(),
                                 ctx.accounts.system_program.to_account_info(),
-                                BOT_FEE,
+                                &bot_tax,
                             )?;
                             return Ok(());
                         }
 
-                        spl_token_burn(TokenBurnParams {
+                        pending_updates.push(PendingMintUpdate::BurnWhitelistToken {
                             mint: whitelist_token_mint.clone(),
                             source: whitelist_token_account.clone(),
-                            amount: 1,
                             authority: whitelist_burn_authority.clone(),
-                            authority_signer_seeds: None,
-                            token_program: token_program.to_account_info(),
-                        })?;
+                        });
+                    }
+
+                    if let Some(mut allowlist_proof) = allowlist_proof {
+                        allowlist_proof.minted += u64::from(quantity);
+                        if allowlist_proof.minted > allowlist_proof.allocation {
+                            return err!(CandyError::MintLimitReached);
+                        }
+                        pending_updates.push(PendingMintUpdate::RecordAllowlistMint {
+                            allowlist_proof_info: whitelist_token_account.clone(),
+                            allowlist_proof,
+                        });
                     }
 
                     if let Some(dp) = ws.discount_price {
                         price = dp;
                     }
                 } else {
-                    if wta.amount == 0 && ws.discount_price.is_none() && !ws.presale {
+                    if ws.discount_price.is_none() && !ws.presale {
                         // A non-presale whitelist with no discount price is a forced whitelist
                         // If a pre-sale has no discount, its no issue, because the "discount"
                         // is minting first - a presale whitelist always has an open post sale.
                         punish_bots(
                             CandyError::NoWhitelistToken,
                             payer.to_account_info(),
-                            ctx.accounts.candy_machine.to_account_info(),
+                            bot_tax_destination.clone(),
                             ctx.accounts.system_program.to_account_info(),
-                            BOT_FEE,
+                            &bot_tax,
                         )?;
                         return Ok(());
                     }
-                    let go_live = assert_valid_go_live(payer, &clock, candy_machine);
-                    if go_live.is_err() {
+                    if mint_phases.is_none()
+                        && assert_valid_go_live(payer, &clock, candy_machine).is_err()
+                    {
                         punish_bots(
                             CandyError::CandyMachineNotLive,
                             payer.to_account_info(),
-                            ctx.accounts.candy_machine.to_account_info(),
+                            bot_tax_destination.clone(),
                             ctx.accounts.system_program.to_account_info(),
-                            BOT_FEE,
+                            &bot_tax,
                         )?;
                         return Ok(());
                     }
@@ -433,7 +694,7 @@ pub fn handle_mint_nft<'info>(
                     }
                 }
             }
-            Err(_) => {
+            None => {
                 if ws.discount_price.is_none() && !ws.presale {
                     // A non-presale whitelist with no discount price is a forced whitelist
                     // If a pre-sale has no discount, its no issue, because the "discount"
@@ -441,58 +702,479 @@ pub fn handle_mint_nft<'info>(
                     punish_bots(
                         CandyError::NoWhitelistToken,
                         payer.to_account_info(),
-                        ctx.accounts.candy_machine.to_account_info(),
+                        bot_tax_destination.clone(),
                         ctx.accounts.system_program.to_account_info(),
-                        BOT_FEE,
+                        &bot_tax,
                     )?;
                     return Ok(());
                 }
                 if ws.mode == WhitelistMintMode::BurnEveryTime {
                     remaining_accounts_counter += 2;
                 }
-                let go_live = assert_valid_go_live(payer, &clock, candy_machine);
-                if go_live.is_err() {
+                if mint_phases.is_none()
+                    && assert_valid_go_live(payer, &clock, candy_machine).is_err()
+                {
                     punish_bots(
                         CandyError::CandyMachineNotLive,
                         payer.to_account_info(),
-                        ctx.accounts.candy_machine.to_account_info(),
+                        bot_tax_destination.clone(),
                         ctx.accounts.system_program.to_account_info(),
-                        BOT_FEE,
+                        &bot_tax,
                     )?;
                     return Ok(());
                 }
             }
         }
     } else {
-        // no whitelist means normal datecheck
-        let go_live = assert_valid_go_live(payer, &clock, candy_machine);
-        if go_live.is_err() {
+        // no whitelist means normal datecheck, unless the live phase replaces it
+        if mint_phases.is_none() && assert_valid_go_live(payer, &clock, candy_machine).is_err() {
             punish_bots(
                 CandyError::CandyMachineNotLive,
                 payer.to_account_info(),
-                ctx.accounts.candy_machine.to_account_info(),
+                bot_tax_destination.clone(),
+                ctx.accounts.system_program.to_account_info(),
+                &bot_tax,
+            )?;
+            return Ok(());
+        }
+    }
+
+    // Accounts of the optional features come after all of the accounts above,
+    // with the variable accounts of each feature right after its own accounts.
+    let mut feature_accounts_counter = get_feature_accounts_index(candy_machine, features);
+    let mut mint_request_info = None;
+    let mut hidden_reveal = false;
+    let mut hidden_mint_record_info = None;
+    let mut programmable_nft = None;
+    if let Some(mint_settings) = &mint_settings {
+        feature_accounts_counter += 1;
+
+        if let Some(randomness_oracle) = &mint_settings.randomness_oracle {
+            if quantity > 1 {
+                return err!(CandyError::MintManyNotSupported);
+            }
+            if features.contains(CandyFeatures::COLLECTIONS)
+                || features.contains(CandyFeatures::FREEZE)
+            {
+                return err!(CandyError::RandomnessNotSupported);
+            }
+            match remaining_accounts.get(feature_accounts_counter) {
+                Some(account_info) => {
+                    mint_request_info = Some((account_info, randomness_oracle.commitment))
+                }
+                None => {
+                    punish_bots(
+                        CandyError::IncorrectRemainingAccountsLen,
+                        payer.to_account_info(),
+                        bot_tax_destination.clone(),
+                        ctx.accounts.system_program.to_account_info(),
+                        &bot_tax,
+                    )?;
+                    return Ok(());
+                }
+            }
+            feature_accounts_counter += 1;
+        }
+
+        if mint_settings.reveal_hash.is_some() {
+            // verifying the collection needs the final update authority
+            if features.contains(CandyFeatures::COLLECTIONS) {
+                return err!(CandyError::HiddenRevealNotSupported);
+            }
+            if quantity > 1 {
+                return err!(CandyError::MintManyNotSupported);
+            }
+            match remaining_accounts.get(feature_accounts_counter) {
+                Some(account_info) => hidden_mint_record_info = Some(account_info),
+                None => {
+                    punish_bots(
+                        CandyError::IncorrectRemainingAccountsLen,
+                        payer.to_account_info(),
+                        bot_tax_destination.clone(),
+                        ctx.accounts.system_program.to_account_info(),
+                        &bot_tax,
+                    )?;
+                    return Ok(());
+                }
+            }
+            feature_accounts_counter += 1;
+            hidden_reveal = true;
+        }
+
+        if let Some(programmable) = &mint_settings.programmable {
+            if quantity > 1 {
+                return err!(CandyError::MintManyNotSupported);
+            }
+            let accounts_len = if programmable.rule_set.is_some() {
+                5
+            } else {
+                3
+            };
+            let accounts = match remaining_accounts
+                .get(feature_accounts_counter..feature_accounts_counter + accounts_len)
+            {
+                Some(accounts) => accounts,
+                None => {
+                    punish_bots(
+                        CandyError::IncorrectRemainingAccountsLen,
+                        payer.to_account_info(),
+                        bot_tax_destination.clone(),
+                        ctx.accounts.system_program.to_account_info(),
+                        &bot_tax,
+                    )?;
+                    return Ok(());
+                }
+            };
+            feature_accounts_counter += accounts_len;
+            programmable_nft = Some((
+                programmable.clone(),
+                get_programmable_nft_accounts(
+                    programmable,
+                    accounts,
+                    &recipient,
+                    &ctx.accounts.mint.key(),
+                    &instruction_sysvar_account_info,
+                )?,
+            ));
+        }
+
+        if let Some(mint_authority_program) = &mint_settings.mint_authority_program {
+            // The mint authority program signs with its PDA for the candy machine.
+            let cm_key = candy_machine.key();
+            let (expected_mint_authority, _) = Pubkey::find_program_address(
+                &[MINT_AUTHORITY_PREFIX.as_bytes(), cm_key.as_ref()],
+                mint_authority_program,
+            );
+            match remaining_accounts.get(feature_accounts_counter) {
+                Some(mint_authority)
+                    if mint_authority.is_signer
+                        && cmp_pubkeys(mint_authority.key, &expected_mint_authority) => {}
+                _ => {
+                    punish_bots(
+                        CandyError::InvalidMintAuthority,
+                        payer.to_account_info(),
+                        bot_tax_destination.clone(),
+                        ctx.accounts.system_program.to_account_info(),
+                        &bot_tax,
+                    )?;
+                    return Ok(());
+                }
+            }
+            feature_accounts_counter += 1;
+        }
+    }
+
+    if features.contains(CandyFeatures::MINT_LIMIT) {
+        let mint_limit_info = remaining_accounts
+            .get(feature_accounts_counter)
+            .ok_or(CandyError::IncorrectRemainingAccountsLen)?;
+        feature_accounts_counter += 1;
+        let mint_counter_info = remaining_accounts
+            .get(feature_accounts_counter)
+            .ok_or(CandyError::IncorrectRemainingAccountsLen)?;
+        feature_accounts_counter += 1;
+        let mint_limit: Account<MintLimitPDA> = Account::try_from(mint_limit_info)?;
+        if !cmp_pubkeys(&mint_limit.candy_machine, &candy_machine.key()) {
+            return err!(CandyError::MintLimitMismatch);
+        }
+
+        // Hitting the limit is an honest mistake, so it fails without the bot tax.
+        pending_updates.push(PendingMintUpdate::IncrementMintCounter {
+            mint_counter_info: mint_counter_info.clone(),
+            seeds: vec![
+                MintCounter::PREFIX.as_bytes().to_vec(),
+                candy_machine.key().to_bytes().to_vec(),
+                recipient.key.to_bytes().to_vec(),
+            ],
+            limit: mint_limit.limit,
+        });
+    }
+
+    let mut phase_payment = None;
+    if mint_phases.is_some() {
+        feature_accounts_counter += 1;
+
+        if let Some((phase_index, phase)) = active_phase {
+            price = phase.price;
+
+            if let Some(wallet_limit) = phase.wallet_limit {
+                let mint_counter_info = match remaining_accounts.get(feature_accounts_counter) {
+                    Some(account_info) => account_info,
+                    None => {
+                        punish_bots(
+                            CandyError::IncorrectRemainingAccountsLen,
+                            payer.to_account_info(),
+                            bot_tax_destination.clone(),
+                            ctx.accounts.system_program.to_account_info(),
+                            &bot_tax,
+                        )?;
+                        return Ok(());
+                    }
+                };
+                feature_accounts_counter += 1;
+                pending_updates.push(PendingMintUpdate::IncrementMintCounter {
+                    mint_counter_info: mint_counter_info.clone(),
+                    seeds: vec![
+                        MintCounter::PREFIX.as_bytes().to_vec(),
+                        candy_machine.key().to_bytes().to_vec(),
+                        recipient.key.to_bytes().to_vec(),
+                        vec![phase_index],
+                    ],
+                    limit: wallet_limit,
+                });
+            }
+
+            match &phase.gate {
+                Some(PhaseGate::WhitelistToken { mint, burn }) => {
+                    let whitelist_token_account = remaining_accounts.get(feature_accounts_counter);
+                    feature_accounts_counter += 1;
+                    let whitelist_token = whitelist_token_account.and_then(|account_info| {
+                        assert_is_ata(account_info, recipient.key, mint).ok()
+                    });
+                    let whitelist_token_account = match (whitelist_token_account, whitelist_token) {
+                        // one token per NFT minted, burned or not
+                        (Some(account_info), Some(wta)) if wta.amount >= u64::from(quantity) => {
+                            account_info
+                        }
+                        _ => {
+                            punish_bots(
+                                CandyError::NoWhitelistToken,
+                                payer.to_account_info(),
+                                bot_tax_destination.clone(),
+                                ctx.accounts.system_program.to_account_info(),
+                                &bot_tax,
+                            )?;
+                            return Ok(());
+                        }
+                    };
+
+                    if *burn {
+                        let whitelist_token_mint = remaining_accounts.get(feature_accounts_counter);
+                        feature_accounts_counter += 1;
+                        let whitelist_token_mint = match whitelist_token_mint {
+                            Some(account_info) if cmp_pubkeys(account_info.key, mint) => {
+                                account_info
+                            }
+                            _ => {
+                                punish_bots(
+                                    CandyError::IncorrectOwner,
+                                    payer.to_account_info(),
+                                    bot_tax_destination.clone(),
+                                    ctx.accounts.system_program.to_account_info(),
+                                    &bot_tax,
+                                )?;
+                                return Ok(());
+                            }
+                        };
+
+                        pending_updates.push(PendingMintUpdate::BurnWhitelistToken {
+                            mint: whitelist_token_mint.clone(),
+                            source: whitelist_token_account.clone(),
+                            authority: recipient.clone(),
+                        });
+                    }
+                }
+                Some(PhaseGate::CollectionHolder {
+                    collection,
+                    once_per_nft,
+                }) => {
+                    let held_token_account = remaining_accounts.get(feature_accounts_counter);
+                    let held_metadata = remaining_accounts.get(feature_accounts_counter + 1);
+                    feature_accounts_counter += 2;
+                    let held_mint = match (held_token_account, held_metadata) {
+                        (Some(held_token_account), Some(held_metadata)) => {
+                            assert_collection_holder(
+                                held_token_account,
+                                held_metadata,
+                                recipient.key,
+                                collection,
+                            )
+                            .ok()
+                        }
+                        _ => None,
+                    };
+                    let held_mint = match held_mint {
+                        Some(held_mint) => held_mint,
+                        None => {
+                            punish_bots(
+                                CandyError::NotCollectionHolder,
+                                payer.to_account_info(),
+                                bot_tax_destination.clone(),
+                                ctx.accounts.system_program.to_account_info(),
+                                &bot_tax,
+                            )?;
+                            return Ok(());
+                        }
+                    };
+
+                    if *once_per_nft {
+                        // one held NFT only pays for one of the NFTs minted
+                        if quantity > 1 {
+                            return err!(CandyError::MintManyNotSupported);
+                        }
+                        let holder_mint_record_info = remaining_accounts
+                            .get(feature_accounts_counter)
+                            .ok_or(CandyError::IncorrectRemainingAccountsLen)?;
+                        feature_accounts_counter += 1;
+                        // Reusing an NFT is an honest mistake, so it fails without the bot tax.
+                        if !holder_mint_record_info.data_is_empty() {
+                            return err!(CandyError::HolderNftAlreadyUsed);
+                        }
+                        pending_updates.push(PendingMintUpdate::RecordHolderMint {
+                            holder_mint_record_info: holder_mint_record_info.clone(),
+                            held_mint,
+                        });
+                    }
+                }
+                None => (),
+            }
+
+            if let Some(payment) = &phase.payment {
+                let payment_token_account = remaining_accounts.get(feature_accounts_counter);
+                let payment_destination = remaining_accounts.get(feature_accounts_counter + 1);
+                match (payment_token_account, payment_destination) {
+                    (Some(payment_token_account), Some(payment_destination)) => {
+                        feature_accounts_counter += 2;
+                        assert_keys_equal(payment_destination.key, &payment.destination)?;
+                        phase_payment =
+                            Some((payment_token_account, payment_destination, payment.mint));
+                    }
+                    _ => {
+                        punish_bots(
+                            CandyError::IncorrectRemainingAccountsLen,
+                            payer.to_account_info(),
+                            bot_tax_destination.clone(),
+                            ctx.accounts.system_program.to_account_info(),
+                            &bot_tax,
+                        )?;
+                        return Ok(());
+                    }
+                }
+            }
+        }
+    }
+
+    // Recipients sharing the candy machine price, in split order after the splits account.
+    let mut revenue_splits = None;
+    if features.contains(CandyFeatures::REVENUE_SPLITS) {
+        let revenue_splits_info = remaining_accounts
+            .get(feature_accounts_counter)
+            .ok_or(CandyError::IncorrectRemainingAccountsLen)?;
+        feature_accounts_counter += 1;
+        let splits: Account<RevenueSplits> = Account::try_from(revenue_splits_info)?;
+        if !cmp_pubkeys(&splits.candy_machine, &candy_machine.key()) {
+            return err!(CandyError::RevenueSplitsMismatch);
+        }
+        let recipients = remaining_accounts
+            .get(feature_accounts_counter..feature_accounts_counter + splits.splits.len())
+            .ok_or(CandyError::IncorrectRemainingAccountsLen)?;
+        feature_accounts_counter += splits.splits.len();
+        for (split, recipient) in splits.splits.iter().zip(recipients) {
+            assert_keys_equal(recipient.key, &split.address)?;
+        }
+        revenue_splits = Some((splits, recipients));
+    }
+
+    // Prints of the master edition held by the candy machine creator, instead of config lines.
+    let mut edition_accounts = None;
+    if features.contains(CandyFeatures::EDITIONS) {
+        if mint_request_info.is_some() || hidden_reveal || programmable_nft.is_some() {
+            return err!(CandyError::EditionsNotSupported);
+        }
+        let edition_settings_info = remaining_accounts
+            .get(feature_accounts_counter)
+            .ok_or(CandyError::IncorrectRemainingAccountsLen)?;
+        feature_accounts_counter += 1;
+        let edition_settings: Account<EditionSettings> = Account::try_from(edition_settings_info)?;
+        if !cmp_pubkeys(&edition_settings.candy_machine, &candy_machine.key()) {
+            return err!(CandyError::EditionSettingsMismatch);
+        }
+        let accounts = remaining_accounts
+            .get(feature_accounts_counter..feature_accounts_counter + 4)
+            .ok_or(CandyError::IncorrectRemainingAccountsLen)?;
+        feature_accounts_counter += 4;
+        edition_accounts = Some((edition_settings.master_mint, accounts));
+    }
+
+    let mut payment_option_paid = false;
+    if features.contains(CandyFeatures::PAYMENT_OPTIONS) {
+        let payment_options_info = remaining_accounts
+            .get(feature_accounts_counter)
+            .ok_or(CandyError::IncorrectRemainingAccountsLen)?;
+        feature_accounts_counter += 1;
+        let payment_options: Account<PaymentOptions> = Account::try_from(payment_options_info)?;
+        if !cmp_pubkeys(&payment_options.candy_machine, &candy_machine.key()) {
+            return err!(CandyError::PaymentOptionsMismatch);
+        }
+
+        // Passing the accounts of a payment option picks it over the candy machine price.
+        if feature_accounts_counter < remaining_accounts.len() {
+            if features.contains(CandyFeatures::FREEZE) {
+                return err!(CandyError::NoPaymentOptionsWithFreeze);
+            }
+            if features.intersects(CandyFeatures::MINT_PHASES | CandyFeatures::REVENUE_SPLITS) {
+                return err!(CandyError::PaymentOptionsNotSupported);
+            }
+            if quantity > 1 {
+                return err!(CandyError::MintManyNotSupported);
+            }
+            pay_with_payment_option(
+                &payment_options,
+                &remaining_accounts[feature_accounts_counter..],
+                &payer.to_account_info(),
+                &token_program.to_account_info(),
+                &ctx.accounts.token_metadata_program.to_account_info(),
+            )?;
+            payment_option_paid = true;
+        } else if payment_options.required && !cmp_pubkeys(&payer.key(), &candy_machine.authority) {
+            punish_bots(
+                CandyError::InvalidPaymentOption,
+                payer.to_account_info(),
+                bot_tax_destination.clone(),
                 ctx.accounts.system_program.to_account_info(),
-                BOT_FEE,
+                &bot_tax,
             )?;
             return Ok(());
         }
     }
 
+    // No check taxes the payer from here on.
+    apply_pending_mint_updates(
+        pending_updates,
+        &candy_machine.key(),
+        recipient.key,
+        quantity,
+        &payer.to_account_info(),
+        &ctx.accounts.system_program.to_account_info(),
+        &token_program.to_account_info(),
+    )?;
+
+    // Every NFT minted is paid for at once.
+    let price = price
+        .checked_mul(quantity.into())
+        .ok_or(CandyError::NumericalOverflowError)?;
+
     let (wallet_to_use, freeze_pda): (&AccountInfo, Option<Account<FreezePDA>>) =
-        if is_feature_active(&candy_machine.data.uuid, FREEZE_FEATURE_INDEX) {
+        if features.contains(CandyFeatures::FREEZE) {
             if let Some(mint) = candy_machine.token_mint {
-                let freeze_pda_info = &ctx.remaining_accounts[remaining_accounts_counter + 2];
-                let freeze_ata = &ctx.remaining_accounts[remaining_accounts_counter + 2 + 2];
+                let freeze_pda_info = &remaining_accounts[remaining_accounts_counter + 2];
+                let freeze_ata = &remaining_accounts[remaining_accounts_counter + 2 + 2];
                 assert_is_ata(freeze_ata, freeze_pda_info.key, &mint)?;
                 let freeze_pda: Account<FreezePDA> = Account::try_from(freeze_pda_info)?;
+                if freeze_pda.refund_eligible(clock.unix_timestamp, candy_machine) {
+                    return err!(CandyError::MintEndedWithRefunds);
+                }
                 if freeze_pda.thaw_eligible(clock.unix_timestamp, candy_machine) {
                     (wallet, None)
                 } else {
                     (freeze_ata, Some(freeze_pda))
                 }
             } else {
-                let freeze_pda_info = &ctx.remaining_accounts[remaining_accounts_counter];
+                let freeze_pda_info = &remaining_accounts[remaining_accounts_counter];
                 let freeze_pda: Account<FreezePDA> = Account::try_from(freeze_pda_info)?;
+                if freeze_pda.refund_eligible(clock.unix_timestamp, candy_machine) {
+                    return err!(CandyError::MintEndedWithRefunds);
+                }
                 if freeze_pda.thaw_eligible(clock.unix_timestamp, candy_machine) {
                     (wallet, None)
                 } else {
@@ -502,51 +1184,168 @@ pub fn handle_mint_nft<'info>(
         } else {
             (wallet, None)
         };
+    if let Some(freeze_pda) = &freeze_pda {
+        // refunds return the same price to every holder, whatever price paid into the freeze
+        if freeze_pda.refund && !payment_option_paid && price != freeze_pda.refund_price {
+            return err!(CandyError::FreezeRefundPriceMismatch);
+        }
+    }
 
-    if let Some(mint) = candy_machine.token_mint {
-        let token_account_info = &ctx.remaining_accounts[remaining_accounts_counter];
-        remaining_accounts_counter += 1;
-        let transfer_authority_info = &ctx.remaining_accounts[remaining_accounts_counter];
-        remaining_accounts_counter += 1;
+    if payment_option_paid {
+        if candy_machine.token_mint.is_some() {
+            // The candy machine payment accounts are passed in but unused.
+            remaining_accounts_counter += 2;
+        }
+    } else if let Some((payment_token_account_info, payment_destination_info, payment_mint)) =
+        phase_payment
+    {
+        if freeze_pda.is_some() {
+            return err!(CandyError::NoPhasePaymentWithFreeze);
+        }
+        if candy_machine.token_mint.is_some() {
+            // The candy machine payment accounts are passed in but unused.
+            remaining_accounts_counter += 2;
+        }
 
-        let token_account = assert_is_ata(token_account_info, &payer.key(), &mint)?;
+        let payment_token_account =
+            assert_is_ata(payment_token_account_info, &payer.key(), &payment_mint)?;
 
-        if token_account.amount < price {
+        if payment_token_account.amount < price {
             return err!(CandyError::NotEnoughTokens);
         }
 
         spl_token_transfer(TokenTransferParams {
-            source: token_account_info.clone(),
-            destination: wallet_to_use.to_account_info(),
-            authority: transfer_authority_info.clone(),
+            source: payment_token_account_info.clone(),
+            destination: payment_destination_info.clone(),
+            authority: payer.to_account_info(),
             authority_signer_seeds: &[],
             token_program: token_program.to_account_info(),
             amount: price,
         })?;
     } else {
-        if ctx.accounts.payer.lamports() < price {
-            return err!(CandyError::NotEnoughSOL);
-        }
-        invoke(
-            &system_instruction::transfer(&ctx.accounts.payer.key(), &wallet_to_use.key(), price),
-            &[
-                ctx.accounts.payer.to_account_info(),
-                wallet_to_use.to_account_info(),
-                ctx.accounts.system_program.to_account_info(),
-            ],
-        )?;
-    }
+        // Revenue splits can't be set with freeze, so they always replace the wallet.
+        let payouts: Vec<(&AccountInfo, u64)> = match &revenue_splits {
+            Some((splits, recipients)) => recipients.iter().zip(splits.amounts(price)?).collect(),
+            None => vec![(wallet_to_use, price)],
+        };
 
-    let data = recent_slothashes.data.borrow();
+        if let Some(mint) = candy_machine.token_mint {
+            let token_account_info = &remaining_accounts[remaining_accounts_counter];
+            remaining_accounts_counter += 1;
+            let transfer_authority_info = &remaining_accounts[remaining_accounts_counter];
+            remaining_accounts_counter += 1;
+
+            let token_account = assert_is_ata(token_account_info, &payer.key(), &mint)?;
+
+            if token_account.amount < price {
+                return err!(CandyError::NotEnoughTokens);
+            }
+
+            for (destination, amount) in payouts {
+                spl_token_transfer(TokenTransferParams {
+                    source: token_account_info.clone(),
+                    destination: destination.to_account_info(),
+                    authority: transfer_authority_info.clone(),
+                    authority_signer_seeds: &[],
+                    token_program: token_program.to_account_info(),
+                    amount,
+                })?;
+            }
+        } else {
+            if ctx.accounts.payer.lamports() < price {
+                return err!(CandyError::NotEnoughSOL);
+            }
+            for (destination, amount) in payouts {
+                invoke(
+                    &system_instruction::transfer(
+                        &ctx.accounts.payer.key(),
+                        &destination.key(),
+                        amount,
+                    ),
+                    &[
+                        ctx.accounts.payer.to_account_info(),
+                        destination.to_account_info(),
+                        ctx.accounts.system_program.to_account_info(),
+                    ],
+                )?;
+            }
+        }
+    }
+
+    if let Some((mint_request_info, commitment)) = mint_request_info {
+        // The config line is only picked once the oracle reveals the randomness in a later slot.
+        let cm_key = candy_machine.key();
+        let mint_key = ctx.accounts.mint.key();
+        create_pda_account(
+            mint_request_info,
+            &payer.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            &[
+                MintRequest::PREFIX.as_bytes(),
+                cm_key.as_ref(),
+                mint_key.as_ref(),
+            ],
+            MintRequest::SIZE,
+            CandyError::MismatchedMintRequest,
+        )?;
+
+        // Hand the mint over to the candy machine so anyone can fulfill the request.
+        let mint_info = ctx.accounts.mint.to_account_info();
+        let mint_authority_info = ctx.accounts.mint_authority.to_account_info();
+        let nft_mint: spl_token::state::Mint = assert_initialized(&mint_info)?;
+        let mut authority_types = vec![AuthorityType::MintTokens];
+        if nft_mint.freeze_authority.is_some() {
+            authority_types.push(AuthorityType::FreezeAccount);
+        }
+        for authority_type in authority_types {
+            invoke(
+                &set_authority(
+                    &spl_token::id(),
+                    mint_info.key,
+                    Some(&candy_machine_creator.key()),
+                    authority_type,
+                    mint_authority_info.key,
+                    &[],
+                )?,
+                &[
+                    mint_info.clone(),
+                    mint_authority_info.clone(),
+                    token_program.to_account_info(),
+                ],
+            )?;
+        }
+
+        MintRequest {
+            candy_machine: cm_key,
+            minter: *recipient.key,
+            mint: mint_key,
+            update_authority: if hidden_reveal {
+                candy_machine_creator.key()
+            } else if candy_machine.data.retain_authority {
+                candy_machine.authority
+            } else {
+                ctx.accounts.update_authority.key()
+            },
+            mint_number: candy_machine.items_redeemed,
+            request_slot: clock.slot,
+            commitment,
+            randomness: None,
+        }
+        .try_serialize(&mut *mint_request_info.try_borrow_mut_data()?)?;
+
+        candy_machine.items_redeemed = candy_machine
+            .items_redeemed
+            .checked_add(1)
+            .ok_or(CandyError::NumericalOverflowError)?;
+        return Ok(());
+    }
+
+    let data = recent_slothashes.data.borrow();
     let most_recent = array_ref![data, 12, 8];
 
     let index = u64::from_le_bytes(*most_recent);
-    let modded: usize = index
-        .checked_rem(candy_machine.data.items_available)
-        .ok_or(CandyError::NumericalOverflowError)? as usize;
-
-    let config_line = get_config_line(candy_machine, modded, candy_machine.items_redeemed)?;
 
+    let mint_number = candy_machine.items_redeemed;
     candy_machine.items_redeemed = candy_machine
         .items_redeemed
         .checked_add(1)
@@ -554,54 +1353,378 @@ pub fn handle_mint_nft<'info>(
 
     let cm_key = candy_machine.key();
     let authority_seeds = [PREFIX.as_bytes(), cm_key.as_ref(), &[creator_bump]];
+    // NFTs revealed on-chain stay with the creator PDA until `reveal_nft`
+    let update_authority = if hidden_reveal {
+        candy_machine_creator.key()
+    } else if candy_machine.data.retain_authority {
+        candy_machine.authority
+    } else {
+        ctx.accounts.update_authority.key()
+    };
+    let candy_nft_accounts = CandyNftAccounts {
+        metadata: &ctx.accounts.metadata.to_account_info(),
+        mint: &ctx.accounts.mint.to_account_info(),
+        mint_authority: &ctx.accounts.mint_authority.to_account_info(),
+        payer: &ctx.accounts.payer.to_account_info(),
+        master_edition: &ctx.accounts.master_edition.to_account_info(),
+        candy_machine_creator: &candy_machine_creator.to_account_info(),
+        token_metadata_program: &ctx.accounts.token_metadata_program.to_account_info(),
+        token_program: &ctx.accounts.token_program.to_account_info(),
+        system_program: &ctx.accounts.system_program.to_account_info(),
+        rent: &ctx.accounts.rent.to_account_info(),
+    };
+    match (&edition_accounts, &programmable_nft) {
+        (Some((master_mint, edition_accounts)), _) => print_candy_edition(
+            candy_nft_accounts,
+            master_mint,
+            edition_accounts,
+            update_authority,
+            &authority_seeds,
+        )?,
+        (None, Some((programmable, programmable_accounts))) => create_programmable_candy_nft(
+            candy_machine,
+            candy_nft_accounts,
+            programmable_accounts,
+            programmable.rule_set,
+            get_config_line(candy_machine, index, mint_number)?,
+            update_authority,
+            &authority_seeds,
+        )?,
+        (None, None) => create_candy_nft(
+            candy_machine,
+            candy_nft_accounts,
+            get_config_line(candy_machine, index, mint_number)?,
+            update_authority,
+            &authority_seeds,
+        )?,
+    }
 
-    let mut creators: Vec<mpl_token_metadata::state::Creator> =
-        vec![mpl_token_metadata::state::Creator {
-            address: candy_machine_creator.key(),
-            verified: true,
-            share: 0,
-        }];
+    if let Some(hidden_mint_record_info) = hidden_mint_record_info {
+        // `reveal_nft` finds the config line of the NFT by its mint number
+        let mint_key = ctx.accounts.mint.key();
+        create_pda_account(
+            hidden_mint_record_info,
+            &payer.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            &[
+                HiddenMintRecord::PREFIX.as_bytes(),
+                cm_key.as_ref(),
+                mint_key.as_ref(),
+            ],
+            HiddenMintRecord::SIZE,
+            CandyError::MismatchedHiddenMintRecord,
+        )?;
+        HiddenMintRecord {
+            candy_machine: cm_key,
+            mint: mint_key,
+            minter: payer.key(),
+            update_authority: ctx.accounts.update_authority.key(),
+            mint_number,
+        }
+        .try_serialize(&mut *hidden_mint_record_info.try_borrow_mut_data()?)?;
+    }
 
-    for c in &candy_machine.data.creators {
-        creators.push(mpl_token_metadata::state::Creator {
-            address: c.address,
-            verified: false,
-            share: c.share,
-        });
+    for (position, nft_accounts) in extra_nft_accounts.chunks(3).enumerate() {
+        let extra_index = get_extra_index(&data, position);
+        let config_line =
+            get_config_line(candy_machine, extra_index, candy_machine.items_redeemed)?;
+
+        candy_machine.items_redeemed = candy_machine
+            .items_redeemed
+            .checked_add(1)
+            .ok_or(CandyError::NumericalOverflowError)?;
+
+        create_candy_nft(
+            candy_machine,
+            CandyNftAccounts {
+                metadata: &nft_accounts[1],
+                mint: &nft_accounts[0],
+                mint_authority: &ctx.accounts.mint_authority.to_account_info(),
+                payer: &ctx.accounts.payer.to_account_info(),
+                master_edition: &nft_accounts[2],
+                candy_machine_creator: &candy_machine_creator.to_account_info(),
+                token_metadata_program: &ctx.accounts.token_metadata_program.to_account_info(),
+                token_program: &ctx.accounts.token_program.to_account_info(),
+                system_program: &ctx.accounts.system_program.to_account_info(),
+                rent: &ctx.accounts.rent.to_account_info(),
+            },
+            config_line,
+            update_authority,
+            &authority_seeds,
+        )?;
     }
 
+    if let Some(mut freeze_pda) = freeze_pda {
+        msg!("About to freeze nft");
+        let mint_pubkey = ctx.accounts.mint.key();
+        let candy_pubkey = ctx.accounts.candy_machine.key();
+        // counter incremented here since we sorta incremented it in our hearts during the wallet_to_use block.
+        remaining_accounts_counter += 1;
+        let nft_token_account_info = &remaining_accounts[remaining_accounts_counter];
+        // If we add more extra accounts later on we need to uncomment the following line out.
+        // remaining_accounts_counter += 1;
+
+        assert_is_ata(nft_token_account_info, recipient.key, &mint_pubkey)?;
+        let seeds: &[&[u8]] = &[FreezePDA::PREFIX.as_bytes(), candy_pubkey.as_ref()];
+        let (expected_freeze_key, freeze_bump) = Pubkey::find_program_address(seeds, &crate::id());
+        assert_keys_equal(&expected_freeze_key, &freeze_pda.key())?;
+        // redundant check
+        freeze_pda.assert_from_candy(&candy_pubkey)?;
+
+        freeze_pda.frozen_count += 1;
+        // payment options pay their own destinations, leaving nothing to refund
+        if freeze_pda.refund && !payment_option_paid {
+            freeze_pda.escrowed_count += 1;
+        }
+
+        if freeze_pda.freeze_fee > 0 {
+            invoke(
+                &system_instruction::transfer(
+                    &ctx.accounts.payer.key(),
+                    &freeze_pda.key(),
+                    freeze_pda.freeze_fee,
+                ),
+                &[
+                    ctx.accounts.payer.to_account_info(),
+                    freeze_pda.to_account_info(),
+                    ctx.accounts.system_program.to_account_info(),
+                ],
+            )?;
+        }
+
+        if freeze_pda.mint_start.is_none() {
+            freeze_pda.mint_start = Some(clock.unix_timestamp);
+        }
+
+        let freeze_seeds = [
+            FreezePDA::PREFIX.as_bytes(),
+            candy_pubkey.as_ref(),
+            &[freeze_bump],
+        ];
+        if let Some((_, programmable_accounts)) = &programmable_nft {
+            lock_programmable_nft(
+                &freeze_pda.to_account_info(),
+                &recipient,
+                &ctx.accounts.payer.to_account_info(),
+                &ctx.accounts.metadata.to_account_info(),
+                &ctx.accounts.master_edition.to_account_info(),
+                &ctx.accounts.mint.to_account_info(),
+                programmable_accounts,
+                &ctx.accounts.token_metadata_program.to_account_info(),
+                &ctx.accounts.token_program.to_account_info(),
+                &ctx.accounts.system_program.to_account_info(),
+                &freeze_seeds,
+            )?;
+        } else {
+            let mut freeze_ix = freeze_delegated_account(
+                mpl_token_metadata::ID,
+                freeze_pda.key(),
+                nft_token_account_info.key(),
+                ctx.accounts.master_edition.key(),
+                ctx.accounts.mint.key(),
+            );
+            // token metadata ix is sorta bad, so this line fixes it to enable freeze without marking signer as mutable
+            freeze_ix.accounts[0] = AccountMeta::new_readonly(freeze_pda.key(), true);
+
+            invoke(
+                &approve(
+                    &spl_token::ID,
+                    &nft_token_account_info.key(),
+                    &freeze_pda.key(),
+                    recipient.key,
+                    &[],
+                    1,
+                )?,
+                &[
+                    nft_token_account_info.to_account_info(),
+                    freeze_pda.to_account_info(),
+                    recipient.clone(),
+                ],
+            )?;
+            invoke_signed(
+                &freeze_ix,
+                &[
+                    freeze_pda.to_account_info(),
+                    nft_token_account_info.to_account_info(),
+                    ctx.accounts.master_edition.to_account_info(),
+                    ctx.accounts.mint.to_account_info(),
+                ],
+                &[&freeze_seeds],
+            )?;
+        }
+        freeze_pda.exit(&crate::id())?;
+    }
+
+    Ok(())
+}
+
+/// A write of the mint gates and limits, held back until no check can tax the payer.
+enum PendingMintUpdate<'info> {
+    BurnWhitelistToken {
+        mint: AccountInfo<'info>,
+        source: AccountInfo<'info>,
+        authority: AccountInfo<'info>,
+    },
+    IncrementMintCounter {
+        mint_counter_info: AccountInfo<'info>,
+        seeds: Vec<Vec<u8>>,
+        limit: u16,
+    },
+    RecordAllowlistMint {
+        allowlist_proof_info: AccountInfo<'info>,
+        allowlist_proof: AllowlistProof,
+    },
+    RecordHolderMint {
+        holder_mint_record_info: AccountInfo<'info>,
+        held_mint: Pubkey,
+    },
+}
+
+fn apply_pending_mint_updates<'info>(
+    pending_updates: Vec<PendingMintUpdate<'info>>,
+    candy_machine: &Pubkey,
+    recipient: &Pubkey,
+    quantity: u8,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    token_program: &AccountInfo<'info>,
+) -> Result<()> {
+    for pending_update in pending_updates {
+        match pending_update {
+            PendingMintUpdate::BurnWhitelistToken {
+                mint,
+                source,
+                authority,
+            } => {
+                spl_token_burn(TokenBurnParams {
+                    mint,
+                    source,
+                    amount: quantity.into(),
+                    authority,
+                    authority_signer_seeds: None,
+                    token_program: token_program.clone(),
+                })?;
+            }
+            PendingMintUpdate::IncrementMintCounter {
+                mint_counter_info,
+                seeds,
+                limit,
+            } => {
+                let seeds: Vec<&[u8]> = seeds.iter().map(|seed| seed.as_slice()).collect();
+                increment_mint_counter(
+                    &mint_counter_info,
+                    candy_machine,
+                    recipient,
+                    payer,
+                    system_program,
+                    &seeds,
+                    limit,
+                    quantity.into(),
+                )?;
+            }
+            PendingMintUpdate::RecordAllowlistMint {
+                allowlist_proof_info,
+                allowlist_proof,
+            } => {
+                allowlist_proof.try_serialize(&mut *allowlist_proof_info.try_borrow_mut_data()?)?;
+            }
+            PendingMintUpdate::RecordHolderMint {
+                holder_mint_record_info,
+                held_mint,
+            } => {
+                create_pda_account(
+                    &holder_mint_record_info,
+                    payer,
+                    system_program,
+                    &[
+                        HolderMintRecord::PREFIX.as_bytes(),
+                        candy_machine.as_ref(),
+                        held_mint.as_ref(),
+                    ],
+                    HolderMintRecord::SIZE,
+                    CandyError::MismatchedHolderMintRecord,
+                )?;
+                HolderMintRecord {
+                    candy_machine: *candy_machine,
+                    held_mint,
+                }
+                .try_serialize(&mut *holder_mint_record_info.try_borrow_mut_data()?)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Accounts needed to create the metadata and master edition of a candy machine NFT.
+pub struct CandyNftAccounts<'a, 'info> {
+    pub metadata: &'a AccountInfo<'info>,
+    pub mint: &'a AccountInfo<'info>,
+    pub mint_authority: &'a AccountInfo<'info>,
+    pub payer: &'a AccountInfo<'info>,
+    pub master_edition: &'a AccountInfo<'info>,
+    pub candy_machine_creator: &'a AccountInfo<'info>,
+    pub token_metadata_program: &'a AccountInfo<'info>,
+    pub token_program: &'a AccountInfo<'info>,
+    pub system_program: &'a AccountInfo<'info>,
+    pub rent: &'a AccountInfo<'info>,
+}
+
+/// Creates the metadata and master edition of an NFT minted from `config_line`,
+/// signed by the candy machine creator with `authority_seeds`.
+pub fn create_candy_nft(
+    candy_machine: &CandyMachine,
+    accounts: CandyNftAccounts,
+    config_line: ConfigLine,
+    update_authority: Pubkey,
+    authority_seeds: &[&[u8]],
+) -> Result<()> {
+    let CandyNftAccounts {
+        metadata,
+        mint,
+        mint_authority,
+        payer,
+        master_edition,
+        candy_machine_creator,
+        token_metadata_program,
+        token_program,
+        system_program,
+        rent,
+    } = accounts;
+
+    let creators = get_candy_nft_creators(candy_machine, &candy_machine_creator.key());
+
     let metadata_infos = vec![
-        ctx.accounts.metadata.to_account_info(),
-        ctx.accounts.mint.to_account_info(),
-        ctx.accounts.mint_authority.to_account_info(),
-        ctx.accounts.payer.to_account_info(),
-        ctx.accounts.token_metadata_program.to_account_info(),
-        ctx.accounts.token_program.to_account_info(),
-        ctx.accounts.system_program.to_account_info(),
-        ctx.accounts.rent.to_account_info(),
-        candy_machine_creator.to_account_info(),
+        metadata.clone(),
+        mint.clone(),
+        mint_authority.clone(),
+        payer.clone(),
+        token_metadata_program.clone(),
+        token_program.clone(),
+        system_program.clone(),
+        rent.clone(),
+        candy_machine_creator.clone(),
     ];
 
     let master_edition_infos = vec![
-        ctx.accounts.master_edition.to_account_info(),
-        ctx.accounts.mint.to_account_info(),
-        ctx.accounts.mint_authority.to_account_info(),
-        ctx.accounts.payer.to_account_info(),
-        ctx.accounts.metadata.to_account_info(),
-        ctx.accounts.token_metadata_program.to_account_info(),
-        ctx.accounts.token_program.to_account_info(),
-        ctx.accounts.system_program.to_account_info(),
-        ctx.accounts.rent.to_account_info(),
-        candy_machine_creator.to_account_info(),
+        master_edition.clone(),
+        mint.clone(),
+        mint_authority.clone(),
+        payer.clone(),
+        metadata.clone(),
+        token_metadata_program.clone(),
+        token_program.clone(),
+        system_program.clone(),
+        rent.clone(),
+        candy_machine_creator.clone(),
     ];
 
     invoke_signed(
         &create_metadata_accounts_v3(
-            ctx.accounts.token_metadata_program.key(),
-            ctx.accounts.metadata.key(),
-            ctx.accounts.mint.key(),
-            ctx.accounts.mint_authority.key(),
-            ctx.accounts.payer.key(),
+            token_metadata_program.key(),
+            metadata.key(),
+            mint.key(),
+            mint_authority.key(),
+            payer.key(),
             candy_machine_creator.key(),
             config_line.name,
             candy_machine.data.symbol.clone(),
@@ -615,131 +1738,457 @@ pub fn handle_mint_nft<'info>(
             None,
         ),
         metadata_infos.as_slice(),
-        &[&authority_seeds],
+        &[authority_seeds],
     )?;
     invoke_signed(
         &create_master_edition_v3(
-            ctx.accounts.token_metadata_program.key(),
-            ctx.accounts.master_edition.key(),
-            ctx.accounts.mint.key(),
+            token_metadata_program.key(),
+            master_edition.key(),
+            mint.key(),
             candy_machine_creator.key(),
-            ctx.accounts.mint_authority.key(),
-            ctx.accounts.metadata.key(),
-            ctx.accounts.payer.key(),
+            mint_authority.key(),
+            metadata.key(),
+            payer.key(),
             Some(candy_machine.data.max_supply),
         ),
         master_edition_infos.as_slice(),
-        &[&authority_seeds],
+        &[authority_seeds],
     )?;
 
-    let mut new_update_authority = Some(candy_machine.authority);
-
-    if !candy_machine.data.retain_authority {
-        new_update_authority = Some(ctx.accounts.update_authority.key());
-    }
     invoke_signed(
         &update_metadata_accounts_v2(
-            ctx.accounts.token_metadata_program.key(),
-            ctx.accounts.metadata.key(),
+            token_metadata_program.key(),
+            metadata.key(),
             candy_machine_creator.key(),
-            new_update_authority,
+            Some(update_authority),
             None,
             Some(true),
-            if !candy_machine.data.is_mutable {
+            // NFTs waiting for an on-chain reveal are made immutable once revealed
+            if !candy_machine.data.is_mutable && update_authority != candy_machine_creator.key() {
                 Some(false)
             } else {
                 None
             },
         ),
         &[
-            ctx.accounts.token_metadata_program.to_account_info(),
-            ctx.accounts.metadata.to_account_info(),
-            candy_machine_creator.to_account_info(),
+            token_metadata_program.clone(),
+            metadata.clone(),
+            candy_machine_creator.clone(),
         ],
-        &[&authority_seeds],
+        &[authority_seeds],
     )?;
+    Ok(())
+}
 
-    if let Some(mut freeze_pda) = freeze_pda {
-        msg!("About to freeze nft");
-        let mint_pubkey = ctx.accounts.mint.key();
-        let candy_pubkey = ctx.accounts.candy_machine.key();
-        // counter incremented here since we sorta incremented it in our hearts during the wallet_to_use block.
-        remaining_accounts_counter += 1;
-        let nft_token_account_info = &ctx.remaining_accounts[remaining_accounts_counter];
-        // If we add more extra accounts later on we need to uncomment the following line out.
-        // remaining_accounts_counter += 1;
+/// Prints the next edition of the master edition held by the candy machine creator, which
+/// signs with `authority_seeds`.
+///
+/// `edition_accounts` are the master metadata, master edition, master token account and the
+/// edition marker of the next edition.
+pub fn print_candy_edition(
+    accounts: CandyNftAccounts,
+    master_mint: &Pubkey,
+    edition_accounts: &[AccountInfo],
+    update_authority: Pubkey,
+    authority_seeds: &[&[u8]],
+) -> Result<()> {
+    let CandyNftAccounts {
+        metadata,
+        mint,
+        mint_authority,
+        payer,
+        master_edition: new_edition,
+        candy_machine_creator,
+        token_metadata_program,
+        token_program,
+        system_program,
+        rent,
+    } = accounts;
+    let master_metadata = &edition_accounts[0];
+    let master_edition = &edition_accounts[1];
+    let master_token_account = &edition_accounts[2];
+    let edition_marker = &edition_accounts[3];
 
-        assert_is_ata(nft_token_account_info, &payer.key(), &mint_pubkey)?;
-        let seeds: &[&[u8]] = &[FreezePDA::PREFIX.as_bytes(), candy_pubkey.as_ref()];
-        let (expected_freeze_key, freeze_bump) = Pubkey::find_program_address(seeds, &crate::id());
-        assert_keys_equal(&expected_freeze_key, &freeze_pda.key())?;
-        // redundant check
-        freeze_pda.assert_from_candy(&candy_pubkey)?;
+    let edition = MasterEditionV2::from_account_info(master_edition)?
+        .supply
+        .checked_add(1)
+        .ok_or(CandyError::NumericalOverflowError)?;
 
-        freeze_pda.frozen_count += 1;
+    // the print starts out with the candy machine creator as update authority, like new NFTs
+    invoke_signed(
+        &mint_new_edition_from_master_edition_via_token(
+            token_metadata_program.key(),
+            metadata.key(),
+            new_edition.key(),
+            master_edition.key(),
+            mint.key(),
+            mint_authority.key(),
+            payer.key(),
+            candy_machine_creator.key(),
+            master_token_account.key(),
+            candy_machine_creator.key(),
+            master_metadata.key(),
+            *master_mint,
+            edition,
+        ),
+        &[
+            metadata.clone(),
+            new_edition.clone(),
+            master_edition.clone(),
+            mint.clone(),
+            edition_marker.clone(),
+            mint_authority.clone(),
+            payer.clone(),
+            candy_machine_creator.clone(),
+            master_token_account.clone(),
+            master_metadata.clone(),
+            token_program.clone(),
+            system_program.clone(),
+            rent.clone(),
+        ],
+        &[authority_seeds],
+    )?;
 
-        if freeze_pda.freeze_fee > 0 {
-            invoke(
-                &system_instruction::transfer(
-                    &ctx.accounts.payer.key(),
-                    &freeze_pda.key(),
-                    freeze_pda.freeze_fee,
-                ),
-                &[
-                    ctx.accounts.payer.to_account_info(),
-                    freeze_pda.to_account_info(),
-                    ctx.accounts.system_program.to_account_info(),
-                ],
-            )?;
+    invoke_signed(
+        &update_metadata_accounts_v2(
+            token_metadata_program.key(),
+            metadata.key(),
+            candy_machine_creator.key(),
+            Some(update_authority),
+            None,
+            Some(true),
+            None,
+        ),
+        &[
+            token_metadata_program.clone(),
+            metadata.clone(),
+            candy_machine_creator.clone(),
+        ],
+        &[authority_seeds],
+    )?;
+    Ok(())
+}
+
+/// Creators of a candy machine NFT, led by the verified candy machine creator.
+fn get_candy_nft_creators(
+    candy_machine: &CandyMachine,
+    candy_machine_creator: &Pubkey,
+) -> Vec<mpl_token_metadata::state::Creator> {
+    let mut creators: Vec<mpl_token_metadata::state::Creator> =
+        vec![mpl_token_metadata::state::Creator {
+            address: *candy_machine_creator,
+            verified: true,
+            share: 0,
+        }];
+
+    for c in &candy_machine.data.creators {
+        creators.push(mpl_token_metadata::state::Creator {
+            address: c.address,
+            verified: false,
+            share: c.share,
+        });
+    }
+    creators
+}
+
+/// Extra accounts needed to mint a programmable NFT.
+pub struct ProgrammableNftAccounts<'a, 'info> {
+    pub token_owner: &'a AccountInfo<'info>,
+    pub token: &'a AccountInfo<'info>,
+    pub token_record: &'a AccountInfo<'info>,
+    pub associated_token_program: &'a AccountInfo<'info>,
+    pub instruction_sysvar: &'a AccountInfo<'info>,
+    /// Authorization rules program and rule set, if the NFT has one
+    pub authorization_rules: Option<(&'a AccountInfo<'info>, &'a AccountInfo<'info>)>,
+}
+
+impl<'a, 'info> ProgrammableNftAccounts<'a, 'info> {
+    fn account_infos(&self) -> Vec<AccountInfo<'info>> {
+        let mut infos = vec![
+            self.token_owner.clone(),
+            self.token.clone(),
+            self.token_record.clone(),
+            self.associated_token_program.clone(),
+            self.instruction_sysvar.clone(),
+        ];
+        if let Some((authorization_rules_program, authorization_rules)) = self.authorization_rules {
+            infos.push(authorization_rules_program.clone());
+            infos.push(authorization_rules.clone());
         }
+        infos
+    }
+}
 
-        if freeze_pda.mint_start.is_none() {
-            freeze_pda.mint_start = Some(clock.unix_timestamp);
+/// Checks the programmable NFT accounts passed after the mint settings.
+pub fn get_programmable_nft_accounts<'a, 'info>(
+    programmable: &ProgrammableSettings,
+    accounts: &'a [AccountInfo<'info>],
+    owner: &'a AccountInfo<'info>,
+    mint: &Pubkey,
+    instruction_sysvar: &'a AccountInfo<'info>,
+) -> Result<ProgrammableNftAccounts<'a, 'info>> {
+    let token = &accounts[0];
+    let associated_token_program = &accounts[2];
+    if !cmp_pubkeys(
+        token.key,
+        &spl_associated_token_account::get_associated_token_address(owner.key, mint),
+    ) || !cmp_pubkeys(associated_token_program.key, &A_TOKEN)
+    {
+        return err!(CandyError::InvalidProgrammableAccounts);
+    }
+    let authorization_rules = match programmable.rule_set {
+        Some(rule_set) => {
+            if !cmp_pubkeys(accounts[4].key, &rule_set) {
+                return err!(CandyError::InvalidProgrammableAccounts);
+            }
+            Some((&accounts[3], &accounts[4]))
         }
+        None => None,
+    };
+    Ok(ProgrammableNftAccounts {
+        token_owner: owner,
+        token,
+        token_record: &accounts[1],
+        associated_token_program,
+        instruction_sysvar,
+        authorization_rules,
+    })
+}
 
-        let freeze_seeds = [
-            FreezePDA::PREFIX.as_bytes(),
-            candy_pubkey.as_ref(),
-            &[freeze_bump],
-        ];
-        let mut freeze_ix = freeze_delegated_account(
-            mpl_token_metadata::ID,
-            freeze_pda.key(),
-            nft_token_account_info.key(),
-            ctx.accounts.master_edition.key(),
-            ctx.accounts.mint.key(),
-        );
-        // token metadata ix is sorta bad, so this line fixes it to enable freeze without marking signer as mutable
-        freeze_ix.accounts[0] = AccountMeta::new_readonly(freeze_pda.key(), true);
-
-        invoke(
-            &approve(
-                &spl_token::ID,
-                &nft_token_account_info.key(),
-                &freeze_pda.key(),
-                &payer.key(),
-                &[],
-                1,
-            )?,
-            &[
-                nft_token_account_info.to_account_info(),
-                freeze_pda.to_account_info(),
-                payer.to_account_info(),
-            ],
-        )?;
-        invoke_signed(
-            &freeze_ix,
-            &[
-                freeze_pda.to_account_info(),
-                nft_token_account_info.to_account_info(),
-                ctx.accounts.master_edition.to_account_info(),
-                ctx.accounts.mint.to_account_info(),
-            ],
-            &[&freeze_seeds],
-        )?;
-        freeze_pda.exit(&crate::id())?;
+/// Creates the metadata and master edition of a programmable NFT minted from `config_line`
+/// and mints its token to the token owner, signed by the candy machine creator with `authority_seeds`.
+pub fn create_programmable_candy_nft<'info>(
+    candy_machine: &CandyMachine,
+    accounts: CandyNftAccounts<'_, 'info>,
+    programmable_accounts: &ProgrammableNftAccounts<'_, 'info>,
+    rule_set: Option<Pubkey>,
+    config_line: ConfigLine,
+    update_authority: Pubkey,
+    authority_seeds: &[&[u8]],
+) -> Result<()> {
+    let CandyNftAccounts {
+        metadata,
+        mint,
+        mint_authority,
+        payer,
+        master_edition,
+        candy_machine_creator,
+        token_metadata_program,
+        token_program,
+        system_program,
+        rent: _,
+    } = accounts;
+    let ProgrammableNftAccounts {
+        token_owner,
+        token,
+        token_record,
+        associated_token_program,
+        instruction_sysvar,
+        authorization_rules,
+    } = programmable_accounts;
+
+    let mut account_infos = vec![
+        metadata.clone(),
+        mint.clone(),
+        mint_authority.clone(),
+        payer.clone(),
+        master_edition.clone(),
+        candy_machine_creator.clone(),
+        token_metadata_program.clone(),
+        token_program.clone(),
+        system_program.clone(),
+    ];
+    account_infos.extend(programmable_accounts.account_infos());
+
+    // the candy machine creator can only be verified while it is the update authority
+    let create_ix = CreateBuilder::new()
+        .metadata(metadata.key())
+        .master_edition(master_edition.key())
+        .mint(mint.key())
+        .authority(mint_authority.key())
+        .payer(payer.key())
+        .update_authority(candy_machine_creator.key())
+        .system_program(system_program.key())
+        .sysvar_instructions(instruction_sysvar.key())
+        .spl_token_program(token_program.key())
+        .initialize_mint(false)
+        .update_authority_as_signer(true)
+        .build(CreateArgs::V1 {
+            asset_data: AssetData {
+                name: config_line.name,
+                symbol: candy_machine.data.symbol.clone(),
+                uri: config_line.uri,
+                seller_fee_basis_points: candy_machine.data.seller_fee_basis_points,
+                creators: Some(get_candy_nft_creators(
+                    candy_machine,
+                    &candy_machine_creator.key(),
+                )),
+                primary_sale_happened: true,
+                is_mutable: true,
+                token_standard: TokenStandard::ProgrammableNonFungible,
+                collection: None,
+                uses: None,
+                collection_details: None,
+                rule_set,
+            },
+            decimals: Some(0),
+            print_supply: Some(if candy_machine.data.max_supply == 0 {
+                PrintSupply::Zero
+            } else {
+                PrintSupply::Limited(candy_machine.data.max_supply)
+            }),
+        })
+        .unwrap()
+        .instruction();
+    invoke_signed(&create_ix, &account_infos, &[authority_seeds])?;
+
+    // the master edition holds the mint authority now, so the update authority mints
+    let mut mint_builder = MintBuilder::new();
+    mint_builder
+        .token(token.key())
+        .token_owner(token_owner.key())
+        .metadata(metadata.key())
+        .master_edition(master_edition.key())
+        .token_record(token_record.key())
+        .mint(mint.key())
+        .authority(candy_machine_creator.key())
+        .payer(payer.key())
+        .system_program(system_program.key())
+        .sysvar_instructions(instruction_sysvar.key())
+        .spl_token_program(token_program.key())
+        .spl_ata_program(associated_token_program.key());
+    if let Some((authorization_rules_program, authorization_rules)) = authorization_rules {
+        mint_builder
+            .authorization_rules_program(authorization_rules_program.key())
+            .authorization_rules(authorization_rules.key());
+    }
+    let mint_ix = mint_builder
+        .build(MintArgs::V1 {
+            amount: 1,
+            authorization_data: None,
+        })
+        .unwrap()
+        .instruction();
+    invoke_signed(&mint_ix, &account_infos, &[authority_seeds])?;
+
+    let update_ix = UpdateBuilder::new()
+        .authority(candy_machine_creator.key())
+        .metadata(metadata.key())
+        .mint(mint.key())
+        .edition(master_edition.key())
+        .payer(payer.key())
+        .system_program(system_program.key())
+        .sysvar_instructions(instruction_sysvar.key())
+        .build(UpdateArgs::V1 {
+            new_update_authority: Some(update_authority),
+            data: None,
+            primary_sale_happened: None,
+            is_mutable: if candy_machine.data.is_mutable {
+                None
+            } else {
+                Some(false)
+            },
+            collection: CollectionToggle::None,
+            collection_details: CollectionDetailsToggle::None,
+            uses: UsesToggle::None,
+            rule_set: RuleSetToggle::None,
+            authorization_data: None,
+        })
+        .unwrap()
+        .instruction();
+    invoke_signed(&update_ix, &account_infos, &[authority_seeds])?;
+    Ok(())
+}
+
+/// Locks a programmable NFT with the freeze PDA as its utility delegate. Programmable NFT
+/// token accounts are always frozen, so the token record holds the freeze instead.
+#[allow(clippy::too_many_arguments)]
+fn lock_programmable_nft<'info>(
+    freeze_pda: &AccountInfo<'info>,
+    owner: &AccountInfo<'info>,
+    payer: &AccountInfo<'info>,
+    metadata: &AccountInfo<'info>,
+    master_edition: &AccountInfo<'info>,
+    mint: &AccountInfo<'info>,
+    programmable_accounts: &ProgrammableNftAccounts<'_, 'info>,
+    token_metadata_program: &AccountInfo<'info>,
+    token_program: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    freeze_seeds: &[&[u8]],
+) -> Result<()> {
+    let ProgrammableNftAccounts {
+        token,
+        token_record,
+        instruction_sysvar,
+        authorization_rules,
+        ..
+    } = programmable_accounts;
+
+    let mut account_infos = vec![
+        freeze_pda.clone(),
+        owner.clone(),
+        payer.clone(),
+        metadata.clone(),
+        master_edition.clone(),
+        mint.clone(),
+        token_metadata_program.clone(),
+        token_program.clone(),
+        system_program.clone(),
+    ];
+    account_infos.extend(programmable_accounts.account_infos());
+
+    let mut delegate_builder = DelegateBuilder::new();
+    delegate_builder
+        .delegate(freeze_pda.key())
+        .metadata(metadata.key())
+        .master_edition(master_edition.key())
+        .token_record(token_record.key())
+        .mint(mint.key())
+        .token(token.key())
+        .authority(owner.key())
+        .payer(payer.key())
+        .system_program(system_program.key())
+        .sysvar_instructions(instruction_sysvar.key())
+        .spl_token_program(token_program.key());
+    if let Some((authorization_rules_program, authorization_rules)) = authorization_rules {
+        delegate_builder
+            .authorization_rules_program(authorization_rules_program.key())
+            .authorization_rules(authorization_rules.key());
     }
+    let delegate_ix = delegate_builder
+        .build(DelegateArgs::UtilityV1 {
+            amount: 1,
+            authorization_data: None,
+        })
+        .unwrap()
+        .instruction();
+    invoke(&delegate_ix, &account_infos)?;
 
+    let mut lock_builder = LockBuilder::new();
+    lock_builder
+        .authority(freeze_pda.key())
+        .token_owner(owner.key())
+        .token(token.key())
+        .mint(mint.key())
+        .metadata(metadata.key())
+        .edition(master_edition.key())
+        .token_record(token_record.key())
+        .payer(payer.key())
+        .system_program(system_program.key())
+        .sysvar_instructions(instruction_sysvar.key())
+        .spl_token_program(token_program.key());
+    if let Some((authorization_rules_program, authorization_rules)) = authorization_rules {
+        lock_builder
+            .authorization_rules_program(authorization_rules_program.key())
+            .authorization_rules(authorization_rules.key());
+    }
+    let lock_ix = lock_builder
+        .build(LockArgs::V1 {
+            authorization_data: None,
+        })
+        .unwrap()
+        .instruction();
+    invoke_signed(&lock_ix, &account_infos, &[freeze_seeds])?;
     Ok(())
 }
 
@@ -752,7 +2201,7 @@ pub fn get_good_index(
     let mut index_to_use = index;
     let mut taken = 1;
     let mut found = false;
-    let bit_mask_vec_start = CONFIG_ARRAY_START
+    let bit_mask_vec_start = get_config_array_start(arr)
         + 4
         + (items_available) * CONFIG_LINE_SIZE
         + 4
@@ -815,9 +2264,151 @@ pub fn get_good_index(
     Ok((index_to_use, found))
 }
 
+/// Start of the config data, legacy candy machines have no bot tax in front of it.
+pub fn get_config_array_start(arr: &[u8]) -> usize {
+    if is_legacy_candy_machine(arr) {
+        LEGACY_CONFIG_ARRAY_START
+    } else {
+        CONFIG_ARRAY_START
+    }
+}
+
+/// Whether `close_config_data` shrank the account down to the candy machine header.
+pub fn is_config_data_closed(arr: &[u8]) -> bool {
+    arr.len() <= get_config_array_start(arr)
+}
+
+/// Reads the settings of compressed config lines, stored in front of the config line count.
+pub fn get_config_line_settings(arr: &[u8]) -> Result<Option<ConfigLineSettings>> {
+    let config_start = get_config_array_start(arr);
+    if arr.len() < config_start + 4
+        || u32::from_le_bytes(*array_ref![arr, config_start, 4]) != CONFIG_LINE_SETTINGS_MARKER
+    {
+        return Ok(None);
+    }
+    let mut settings = ConfigLineSettings::deserialize(&mut &arr[config_start + 4..])?;
+    settings.prefix_name = settings
+        .prefix_name
+        .trim_end_matches(char::from(0))
+        .to_string();
+    settings.prefix_uri = settings
+        .prefix_uri
+        .trim_end_matches(char::from(0))
+        .to_string();
+    Ok(Some(settings))
+}
+
+/// Start of the config line count, which the config lines follow.
+pub fn get_config_count_start(
+    config_start: usize,
+    line_settings: Option<&ConfigLineSettings>,
+) -> usize {
+    match line_settings {
+        Some(_) => config_start + 4 + ConfigLineSettings::SIZE,
+        None => config_start,
+    }
+}
+
+pub fn get_config_line_size(line_settings: Option<&ConfigLineSettings>) -> usize {
+    line_settings.map_or(CONFIG_LINE_SIZE, ConfigLineSettings::line_size)
+}
+
+/// Start of the loaded-lines bitmask, right after the config lines and the bitmask length.
+pub fn get_config_bit_mask_start(
+    config_start: usize,
+    items_available: usize,
+    line_settings: Option<&ConfigLineSettings>,
+) -> usize {
+    get_config_count_start(config_start, line_settings)
+        + 4
+        + items_available * get_config_line_size(line_settings)
+        + 4
+}
+
+/// Start of the config index array header, right after the loaded-lines bitmask.
+pub fn get_config_indices_start(
+    config_start: usize,
+    items_available: usize,
+    line_settings: Option<&ConfigLineSettings>,
+) -> Result<usize> {
+    Ok(
+        get_config_bit_mask_start(config_start, items_available, line_settings)
+            + items_available
+                .checked_div(8)
+                .ok_or(CandyError::NumericalOverflowError)?
+            + 1,
+    )
+}
+
+/// Whether the candy machine picks config lines from the swap-remove index array
+/// rather than the legacy taken-lines bitmask.
+pub fn uses_config_indices(
+    arr: &[u8],
+    items_available: usize,
+    line_settings: Option<&ConfigLineSettings>,
+) -> Result<bool> {
+    let start =
+        get_config_indices_start(get_config_array_start(arr), items_available, line_settings)?;
+    if arr.len() < start + 4 {
+        return Ok(false);
+    }
+    Ok(u32::from_le_bytes(*array_ref![arr, start, 4]) == CONFIG_INDICES_MARKER)
+}
+
+/// Takes a remaining config line index in constant compute.
+///
+/// The array holds the remaining indices in its first `items_available - taken` slots. The
+/// picked slot is overwritten with the last remaining one, a Fisher-Yates shuffle done one
+/// step per mint. Zeroed slots stand for their own position so new candy machines don't
+/// have to fill the array, other slots store the index plus one.
+pub fn take_config_index(
+    arr: &mut [u8],
+    items_available: usize,
+    line_settings: Option<&ConfigLineSettings>,
+    random: u64,
+) -> Result<usize> {
+    let start =
+        get_config_indices_start(get_config_array_start(arr), items_available, line_settings)?;
+    let taken = u32::from_le_bytes(*array_ref![arr, start + 4, 4]) as usize;
+    let remaining = items_available
+        .checked_sub(taken)
+        .ok_or(CandyError::NumericalOverflowError)?;
+    if remaining == 0 {
+        return err!(CandyError::CannotFindUsableConfigLine);
+    }
+
+    let slots_start = start + 8;
+    let read_slot = |arr: &[u8], slot: usize| -> usize {
+        match u32::from_le_bytes(*array_ref![arr, slots_start + slot * 4, 4]) {
+            0 => slot,
+            stored => stored as usize - 1,
+        }
+    };
+    let picked_slot = random
+        .checked_rem(remaining as u64)
+        .ok_or(CandyError::NumericalOverflowError)? as usize;
+    let index = read_slot(arr, picked_slot);
+    let last = read_slot(arr, remaining - 1);
+
+    let position = slots_start + picked_slot * 4;
+    arr[position..position + 4].copy_from_slice(&(last as u32 + 1).to_le_bytes());
+    arr[start + 4..start + 8].copy_from_slice(&(taken as u32 + 1).to_le_bytes());
+
+    Ok(index)
+}
+
+/// Pseudo-random index of the extra NFT at `position` of a mint many, the most recent slot
+/// hash mixed with the position so each NFT draws its own config line.
+fn get_extra_index(slot_hashes_data: &[u8], position: usize) -> u64 {
+    let hash = keccak::hashv(&[&slot_hashes_data[16..48], &(position as u64).to_le_bytes()]);
+    u64::from_le_bytes(*array_ref![hash.0, 0, 8])
+}
+
+/// Takes a config line for a mint, `random` picks among the lines not minted yet unless the
+/// candy machine mints sequentially.
 pub fn get_config_line(
     a: &Account<'_, CandyMachine>,
-    index: usize,
+    random: u64,
     mint_number: u64,
 ) -> Result<ConfigLine> {
     if let Some(hs) = &a.data.hidden_settings {
@@ -826,53 +2417,78 @@ pub fn get_config_line(
             uri: hs.uri.clone(),
         });
     }
+    let is_sequential = is_feature_active(a, CandyFeatures::SEQUENTIAL)?;
     let a_info = a.to_account_info();
 
     let mut arr = a_info.data.borrow_mut();
+    let items_available = a.data.items_available as usize;
+    let line_settings = get_config_line_settings(&arr)?;
 
-    let (mut index_to_use, good) =
-        get_good_index(&mut arr, a.data.items_available as usize, index, true)?;
-    if !good {
-        let (index_to_use_new, good_new) =
-            get_good_index(&mut arr, a.data.items_available as usize, index, false)?;
-        index_to_use = index_to_use_new;
-        if !good_new {
-            return err!(CandyError::CannotFindUsableConfigLine);
+    let index_to_use = if is_sequential {
+        // lines are minted in order, so neither the index array nor the bitmask is needed
+        mint_number as usize
+    } else if uses_config_indices(&arr, items_available, line_settings.as_ref())? {
+        take_config_index(&mut arr, items_available, line_settings.as_ref(), random)?
+    } else {
+        let index = random
+            .checked_rem(a.data.items_available)
+            .ok_or(CandyError::NumericalOverflowError)? as usize;
+        let (mut index_to_use, good) = get_good_index(&mut arr, items_available, index, true)?;
+        if !good {
+            let (index_to_use_new, good_new) =
+                get_good_index(&mut arr, items_available, index, false)?;
+            index_to_use = index_to_use_new;
+            if !good_new {
+                return err!(CandyError::CannotFindUsableConfigLine);
+            }
         }
-    }
-
-    if arr[CONFIG_ARRAY_START + 4 + index_to_use * (CONFIG_LINE_SIZE)] == 1 {
-        return err!(CandyError::CannotFindUsableConfigLine);
-    }
-
-    let data_array = &mut arr[CONFIG_ARRAY_START + 4 + index_to_use * (CONFIG_LINE_SIZE)
-        ..CONFIG_ARRAY_START + 4 + (index_to_use + 1) * (CONFIG_LINE_SIZE)];
+        index_to_use
+    };
 
-    let mut name_vec = Vec::with_capacity(MAX_NAME_LENGTH);
-    let mut uri_vec = Vec::with_capacity(MAX_URI_LENGTH);
+    let line_size = get_config_line_size(line_settings.as_ref());
+    let line_start = get_config_count_start(get_config_array_start(&arr), line_settings.as_ref())
+        + 4
+        + index_to_use * line_size;
+    let data_array = &arr[line_start..line_start + line_size];
 
-    #[allow(clippy::needless_range_loop)]
-    for i in 4..4 + MAX_NAME_LENGTH {
-        if data_array[i] == 0 {
-            break;
+    // compressed config lines are unprefixed suffixes, full ones borsh strings padded with zeroes
+    let (prefix_name, prefix_uri, name_range, uri_range) = match &line_settings {
+        Some(settings) => (
+            settings.prefix_name.as_str(),
+            settings.prefix_uri.as_str(),
+            0..settings.name_length as usize,
+            settings.name_length as usize..line_size,
+        ),
+        None => {
+            if data_array[0] == 1 {
+                return err!(CandyError::CannotFindUsableConfigLine);
+            }
+            (
+                "",
+                "",
+                4..4 + MAX_NAME_LENGTH,
+                8 + MAX_NAME_LENGTH..8 + MAX_NAME_LENGTH + MAX_URI_LENGTH,
+            )
         }
-        name_vec.push(data_array[i])
-    }
+    };
 
-    #[allow(clippy::needless_range_loop)]
-    for i in 8 + MAX_NAME_LENGTH..8 + MAX_NAME_LENGTH + MAX_URI_LENGTH {
-        if data_array[i] == 0 {
-            break;
-        }
-        uri_vec.push(data_array[i])
-    }
+    let name_vec: Vec<u8> = data_array[name_range]
+        .iter()
+        .take_while(|byte| **byte != 0)
+        .copied()
+        .collect();
+    let uri_vec: Vec<u8> = data_array[uri_range]
+        .iter()
+        .take_while(|byte| **byte != 0)
+        .copied()
+        .collect();
     let config_line: ConfigLine = ConfigLine {
         name: match String::from_utf8(name_vec) {
-            Ok(val) => val,
+            Ok(val) => prefix_name.to_string() + &val,
             Err(_) => return err!(CandyError::InvalidString),
         },
         uri: match String::from_utf8(uri_vec) {
-            Ok(val) => val,
+            Ok(val) => prefix_uri.to_string() + &val,
             Err(_) => return err!(CandyError::InvalidString),
         },
     };
@@ -880,7 +2496,8 @@ pub fn get_config_line(
     Ok(config_line)
 }
 
-pub fn get_expected_remaining_accounts_count(candy: &CandyMachine) -> usize {
+/// Index of the first remaining account used by the optional features, which come last.
+pub fn get_feature_accounts_index(candy: &CandyMachine, features: CandyFeatures) -> usize {
     let mut expected_count = 0;
     if let Some(gatekeeper) = &candy.data.gatekeeper {
         expected_count += 1;
@@ -890,15 +2507,17 @@ pub fn get_expected_remaining_accounts_count(candy: &CandyMachine) -> usize {
     }
     if let Some(whitelist) = &candy.data.whitelist_mint_settings {
         expected_count += 1;
-        if whitelist.mode == WhitelistMintMode::BurnEveryTime {
-            expected_count += 2;
+        match whitelist.mode {
+            WhitelistMintMode::BurnEveryTime => expected_count += 2,
+            WhitelistMintMode::MerkleTree => expected_count += 1,
+            WhitelistMintMode::NeverBurn => (),
         }
     }
     if candy.token_mint.is_some() {
         expected_count += 2;
     }
 
-    if is_feature_active(&candy.data.uuid, FREEZE_FEATURE_INDEX) {
+    if features.contains(CandyFeatures::FREEZE) {
         expected_count += 2;
         if candy.token_mint.is_some() {
             expected_count += 1;
@@ -906,3 +2525,40 @@ pub fn get_expected_remaining_accounts_count(candy: &CandyMachine) -> usize {
     }
     expected_count
 }
+
+/// Number of remaining accounts a mint needs, counting the accounts of the mint settings and of
+/// the live phase once they are read. The revenue split recipients are checked against the
+/// splits account itself.
+pub fn get_expected_remaining_accounts_count(
+    candy: &CandyMachine,
+    features: CandyFeatures,
+    mint_settings: Option<&MintSettings>,
+    active_phase: Option<&MintPhase>,
+) -> usize {
+    let mut expected_count = get_feature_accounts_index(candy, features);
+    if features.contains(CandyFeatures::MINT_SETTINGS) {
+        expected_count += 1 + mint_settings.map_or(0, |mint_settings| mint_settings.accounts_len());
+    }
+    if features.contains(CandyFeatures::MINT_LIMIT) {
+        expected_count += 2;
+    }
+
+    if features.contains(CandyFeatures::MINT_PHASES) {
+        expected_count += 1 + active_phase.map_or(0, |phase| phase.accounts_len());
+    }
+
+    if features.contains(CandyFeatures::REVENUE_SPLITS) {
+        expected_count += 1;
+    }
+
+    if features.contains(CandyFeatures::EDITIONS) {
+        // the edition settings, then the master metadata, master edition, master token account
+        // and edition marker
+        expected_count += 5;
+    }
+
+    if features.contains(CandyFeatures::PAYMENT_OPTIONS) {
+        expected_count += 1;
+    }
+    expected_count
+}
@@ -0,0 +1,33 @@
+use anchor_lang::prelude::*;
+
+use crate::{CandyError, CandyMachine, EndSettingType, MintCounter};
+
+/// Closes a wallet's mint counter once minting has ended, returning the rent to the wallet
+#[derive(Accounts)]
+pub struct CloseMintCounter<'info> {
+    #[account(has_one = authority)]
+    candy_machine: Account<'info, CandyMachine>,
+    authority: Signer<'info>,
+    #[account(mut, close = wallet, has_one = candy_machine, has_one = wallet)]
+    mint_counter: Account<'info, MintCounter>,
+    /// CHECK: checked against the mint counter
+    #[account(mut)]
+    wallet: UncheckedAccount<'info>,
+}
+
+pub fn handle_close_mint_counter(ctx: Context<CloseMintCounter>) -> Result<()> {
+    let candy_machine = &ctx.accounts.candy_machine;
+    // Closing a counter while minting is still possible would reset the wallet's limit.
+    let sold_out = candy_machine.items_redeemed >= candy_machine.data.items_available;
+    let ended = match &candy_machine.data.end_settings {
+        Some(es) => match es.end_setting_type {
+            EndSettingType::Date => Clock::get()?.unix_timestamp > es.number as i64,
+            EndSettingType::Amount => candy_machine.items_redeemed >= es.number,
+        },
+        None => false,
+    };
+    if !sold_out && !ended {
+        return err!(CandyError::CandyMachineNotEnded);
+    }
+    Ok(())
+}
@@ -0,0 +1,7 @@
+pub mod close_mint_counter;
+pub mod remove_mint_limit;
+pub mod set_mint_limit;
+
+pub use close_mint_counter::*;
+pub use remove_mint_limit::*;
+pub use set_mint_limit::*;
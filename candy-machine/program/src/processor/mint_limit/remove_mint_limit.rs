@@ -0,0 +1,20 @@
+use anchor_lang::prelude::*;
+
+use crate::{remove_feature_flag, CandyFeatures, CandyMachine, MintLimitPDA};
+
+/// Removes the mint limit from the candy machine and closes the mint limit pda
+#[derive(Accounts)]
+pub struct RemoveMintLimit<'info> {
+    #[account(mut, has_one = authority)]
+    candy_machine: Account<'info, CandyMachine>,
+    #[account(mut)]
+    authority: Signer<'info>,
+    #[account(mut, close = authority, seeds = [MintLimitPDA::PREFIX.as_bytes(), candy_machine.to_account_info().key.as_ref()], bump)]
+    mint_limit: Account<'info, MintLimitPDA>,
+}
+
+pub fn handle_remove_mint_limit(ctx: Context<RemoveMintLimit>) -> Result<()> {
+    let candy_machine = &mut ctx.accounts.candy_machine;
+    remove_feature_flag(candy_machine, CandyFeatures::MINT_LIMIT)?;
+    Ok(())
+}
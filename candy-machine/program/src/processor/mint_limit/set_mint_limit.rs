@@ -0,0 +1,28 @@
+use anchor_lang::prelude::*;
+
+use crate::{set_feature_flag, CandyError, CandyFeatures, CandyMachine, MintLimitPDA};
+
+/// Set the mint limit PDA capping how many NFTs a wallet can mint from the candy machine
+#[derive(Accounts)]
+pub struct SetMintLimit<'info> {
+    #[account(mut, has_one = authority)]
+    candy_machine: Account<'info, CandyMachine>,
+    #[account(mut)]
+    authority: Signer<'info>,
+    #[account(init, seeds = [MintLimitPDA::PREFIX.as_bytes(), candy_machine.to_account_info().key.as_ref()], bump, space = MintLimitPDA::SIZE, payer = authority)]
+    mint_limit: Account<'info, MintLimitPDA>,
+    system_program: Program<'info, System>,
+}
+
+pub fn handle_set_mint_limit(ctx: Context<SetMintLimit>, limit: u16) -> Result<()> {
+    if limit == 0 {
+        return err!(CandyError::InvalidMintLimit);
+    }
+    let candy_machine = &mut ctx.accounts.candy_machine;
+    let mint_limit = &mut ctx.accounts.mint_limit;
+    mint_limit.candy_machine = candy_machine.key();
+    mint_limit.limit = limit;
+
+    set_feature_flag(candy_machine, CandyFeatures::MINT_LIMIT)?;
+    Ok(())
+}
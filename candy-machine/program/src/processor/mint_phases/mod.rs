@@ -0,0 +1,5 @@
+pub mod remove_mint_phases;
+pub mod set_mint_phases;
+
+pub use remove_mint_phases::*;
+pub use set_mint_phases::*;
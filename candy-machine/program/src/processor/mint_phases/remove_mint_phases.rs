@@ -0,0 +1,20 @@
+use anchor_lang::prelude::*;
+
+use crate::{remove_feature_flag, CandyFeatures, CandyMachine, MintPhases};
+
+/// Removes the mint phases from the candy machine and closes the mint phases pda
+#[derive(Accounts)]
+pub struct RemoveMintPhases<'info> {
+    #[account(mut, has_one = authority)]
+    candy_machine: Account<'info, CandyMachine>,
+    #[account(mut)]
+    authority: Signer<'info>,
+    #[account(mut, close = authority, seeds = [MintPhases::PREFIX.as_bytes(), candy_machine.to_account_info().key.as_ref()], bump)]
+    mint_phases: Account<'info, MintPhases>,
+}
+
+pub fn handle_remove_mint_phases(ctx: Context<RemoveMintPhases>) -> Result<()> {
+    let candy_machine = &mut ctx.accounts.candy_machine;
+    remove_feature_flag(candy_machine, CandyFeatures::MINT_PHASES)?;
+    Ok(())
+}
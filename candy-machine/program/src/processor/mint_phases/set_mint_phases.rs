@@ -0,0 +1,60 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    assert_initialized, assert_owned_by, is_feature_active, set_feature_flag, CandyError,
+    CandyFeatures, CandyMachine, MintPhase, MintPhases,
+};
+
+/// Set the mint phases PDA for the candy machine
+#[derive(Accounts)]
+#[instruction(phases: Vec<MintPhase>)]
+pub struct SetMintPhases<'info> {
+    #[account(mut, has_one = authority)]
+    candy_machine: Account<'info, CandyMachine>,
+    #[account(mut)]
+    authority: Signer<'info>,
+    #[account(init, seeds = [MintPhases::PREFIX.as_bytes(), candy_machine.to_account_info().key.as_ref()], bump, space = MintPhases::size(&phases), payer = authority)]
+    mint_phases: Account<'info, MintPhases>,
+    system_program: Program<'info, System>,
+    // > Only needed for each phase with a payment, in phase order
+    // payment destination token account
+}
+
+pub fn handle_set_mint_phases<'info>(
+    ctx: Context<'_, '_, '_, 'info, SetMintPhases<'info>>,
+    phases: Vec<MintPhase>,
+) -> Result<()> {
+    let candy_machine = &mut ctx.accounts.candy_machine;
+    if is_feature_active(candy_machine, CandyFeatures::PAYMENT_OPTIONS)? {
+        return err!(CandyError::PaymentOptionsNotSupported);
+    }
+    MintPhases::assert_valid(&phases)?;
+
+    let mut remaining_accounts_counter: usize = 0;
+    for payment in phases.iter().filter_map(|phase| phase.payment.as_ref()) {
+        if is_feature_active(candy_machine, CandyFeatures::FREEZE)? {
+            return err!(CandyError::NoPhasePaymentWithFreeze);
+        }
+        let destination_info = ctx
+            .remaining_accounts
+            .get(remaining_accounts_counter)
+            .ok_or(CandyError::IncorrectRemainingAccountsLen)?;
+        remaining_accounts_counter += 1;
+
+        if destination_info.key() != payment.destination {
+            return err!(CandyError::PublicKeyMismatch);
+        }
+        assert_owned_by(destination_info, &spl_token::id())?;
+        let destination: spl_token::state::Account = assert_initialized(destination_info)?;
+        if destination.mint != payment.mint {
+            return err!(CandyError::MintMismatch);
+        }
+    }
+
+    let mint_phases = &mut ctx.accounts.mint_phases;
+    mint_phases.candy_machine = candy_machine.key();
+    mint_phases.phases = phases;
+
+    set_feature_flag(candy_machine, CandyFeatures::MINT_PHASES)?;
+    Ok(())
+}
@@ -0,0 +1,5 @@
+pub mod remove_mint_settings;
+pub mod set_mint_settings;
+
+pub use remove_mint_settings::*;
+pub use set_mint_settings::*;
@@ -0,0 +1,20 @@
+use anchor_lang::prelude::*;
+
+use crate::{remove_feature_flag, CandyFeatures, CandyMachine, MintSettings};
+
+/// Removes the mint settings from the candy machine and closes the mint settings pda
+#[derive(Accounts)]
+pub struct RemoveMintSettings<'info> {
+    #[account(mut, has_one = authority)]
+    candy_machine: Account<'info, CandyMachine>,
+    #[account(mut)]
+    authority: Signer<'info>,
+    #[account(mut, close = authority, seeds = [MintSettings::PREFIX.as_bytes(), candy_machine.to_account_info().key.as_ref()], bump)]
+    mint_settings: Account<'info, MintSettings>,
+}
+
+pub fn handle_remove_mint_settings(ctx: Context<RemoveMintSettings>) -> Result<()> {
+    let candy_machine = &mut ctx.accounts.candy_machine;
+    remove_feature_flag(candy_machine, CandyFeatures::MINT_SETTINGS)?;
+    Ok(())
+}
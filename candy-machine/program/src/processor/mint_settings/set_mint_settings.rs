@@ -0,0 +1,65 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    is_feature_active, set_feature_flag, CandyError, CandyFeatures, CandyMachine, MintSettings,
+    ProgrammableSettings, RandomnessOracle,
+};
+
+/// Set the mint settings PDA for the candy machine
+#[derive(Accounts)]
+pub struct SetMintSettings<'info> {
+    #[account(mut, has_one = authority)]
+    candy_machine: Account<'info, CandyMachine>,
+    #[account(mut)]
+    authority: Signer<'info>,
+    #[account(init, seeds = [MintSettings::PREFIX.as_bytes(), candy_machine.to_account_info().key.as_ref()], bump, space = MintSettings::SIZE, payer = authority)]
+    mint_settings: Account<'info, MintSettings>,
+    system_program: Program<'info, System>,
+}
+
+pub fn handle_set_mint_settings(
+    ctx: Context<SetMintSettings>,
+    randomness_oracle: Option<RandomnessOracle>,
+    reveal_hash: Option<[u8; 32]>,
+    programmable: Option<ProgrammableSettings>,
+    mint_authority_program: Option<Pubkey>,
+) -> Result<()> {
+    let candy_machine = &mut ctx.accounts.candy_machine;
+    let hidden_reveal = reveal_hash.is_some();
+    if randomness_oracle.is_some()
+        && (is_feature_active(candy_machine, CandyFeatures::COLLECTIONS)?
+            || is_feature_active(candy_machine, CandyFeatures::FREEZE)?)
+    {
+        return err!(CandyError::RandomnessNotSupported);
+    }
+    if hidden_reveal
+        && (candy_machine.data.hidden_settings.is_none()
+            || is_feature_active(candy_machine, CandyFeatures::COLLECTIONS)?)
+    {
+        return err!(CandyError::HiddenRevealNotSupported);
+    }
+    // hidden settings lines don't need randomness, and the mint number is recorded on mint
+    if hidden_reveal && randomness_oracle.is_some() {
+        return err!(CandyError::HiddenRevealNotSupported);
+    }
+    // mint requests mint the token before its metadata exists and reveals use the legacy
+    // metadata update, neither of which programmable NFTs allow
+    if programmable.is_some() && (randomness_oracle.is_some() || hidden_reveal) {
+        return err!(CandyError::ProgrammableNotSupported);
+    }
+    // collections are set by a candy machine instruction right after the mint
+    if mint_authority_program.is_some()
+        && is_feature_active(candy_machine, CandyFeatures::COLLECTIONS)?
+    {
+        return err!(CandyError::MintAuthorityNotSupported);
+    }
+    let mint_settings = &mut ctx.accounts.mint_settings;
+    mint_settings.candy_machine = candy_machine.key();
+    mint_settings.randomness_oracle = randomness_oracle;
+    mint_settings.reveal_hash = reveal_hash;
+    mint_settings.programmable = programmable;
+    mint_settings.mint_authority_program = mint_authority_program;
+
+    set_feature_flag(candy_machine, CandyFeatures::MINT_SETTINGS)?;
+    Ok(())
+}
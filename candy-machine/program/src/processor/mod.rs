@@ -1,15 +1,41 @@
 pub mod add_config_lines;
+pub mod allowlist_root;
+pub mod close_config_data;
 pub mod collection;
+pub mod editions;
 pub mod freeze;
+pub mod hidden_reveal;
 pub mod initialize;
+pub mod migrate_candy_machine_version;
+pub mod migrate_config_indices;
 pub mod mint;
+pub mod mint_limit;
+pub mod mint_phases;
+pub mod mint_settings;
+pub mod payment_options;
+pub mod prove_allowlist;
+pub mod randomness;
+pub mod revenue_splits;
 pub mod update;
 pub mod withdraw;
 
 pub use add_config_lines::*;
+pub use allowlist_root::*;
+pub use close_config_data::*;
 pub use collection::*;
+pub use editions::*;
 pub use freeze::*;
+pub use hidden_reveal::*;
 pub use initialize::*;
+pub use migrate_candy_machine_version::*;
+pub use migrate_config_indices::*;
 pub use mint::*;
+pub use mint_limit::*;
+pub use mint_phases::*;
+pub use mint_settings::*;
+pub use payment_options::*;
+pub use prove_allowlist::*;
+pub use randomness::*;
+pub use revenue_splits::*;
 pub use update::*;
 pub use withdraw::*;
@@ -0,0 +1,5 @@
+pub mod remove_payment_options;
+pub mod set_payment_options;
+
+pub use remove_payment_options::*;
+pub use set_payment_options::*;
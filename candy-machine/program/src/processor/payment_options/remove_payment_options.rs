@@ -0,0 +1,20 @@
+use anchor_lang::prelude::*;
+
+use crate::{remove_feature_flag, CandyFeatures, CandyMachine, PaymentOptions};
+
+/// Removes the payment options from the candy machine and closes the payment options pda
+#[derive(Accounts)]
+pub struct RemovePaymentOptions<'info> {
+    #[account(mut, has_one = authority)]
+    candy_machine: Account<'info, CandyMachine>,
+    #[account(mut)]
+    authority: Signer<'info>,
+    #[account(mut, close = authority, seeds = [PaymentOptions::PREFIX.as_bytes(), candy_machine.to_account_info().key.as_ref()], bump)]
+    payment_options: Account<'info, PaymentOptions>,
+}
+
+pub fn handle_remove_payment_options(ctx: Context<RemovePaymentOptions>) -> Result<()> {
+    let candy_machine = &mut ctx.accounts.candy_machine;
+    remove_feature_flag(candy_machine, CandyFeatures::PAYMENT_OPTIONS)?;
+    Ok(())
+}
@@ -0,0 +1,71 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    assert_initialized, assert_owned_by, is_feature_active, set_feature_flag, CandyError,
+    CandyFeatures, CandyMachine, PaymentOption, PaymentOptions,
+};
+
+/// Set the payment options PDA for the candy machine
+#[derive(Accounts)]
+#[instruction(options: Vec<PaymentOption>)]
+pub struct SetPaymentOptions<'info> {
+    #[account(mut, has_one = authority)]
+    candy_machine: Account<'info, CandyMachine>,
+    #[account(mut)]
+    authority: Signer<'info>,
+    #[account(init, seeds = [PaymentOptions::PREFIX.as_bytes(), candy_machine.to_account_info().key.as_ref()], bump, space = PaymentOptions::size(&options), payer = authority)]
+    payment_options: Account<'info, PaymentOptions>,
+    system_program: Program<'info, System>,
+    // > Only needed for each token option, in option order
+    // destination token account
+}
+
+pub fn handle_set_payment_options<'info>(
+    ctx: Context<'_, '_, '_, 'info, SetPaymentOptions<'info>>,
+    options: Vec<PaymentOption>,
+    required: bool,
+) -> Result<()> {
+    let candy_machine = &mut ctx.accounts.candy_machine;
+    if is_feature_active(candy_machine, CandyFeatures::FREEZE)? {
+        return err!(CandyError::NoPaymentOptionsWithFreeze);
+    }
+    PaymentOptions::assert_valid(&options)?;
+
+    // A payment option replaces the price, which phases and revenue splits are built around.
+    if is_feature_active(candy_machine, CandyFeatures::MINT_PHASES)?
+        || is_feature_active(candy_machine, CandyFeatures::REVENUE_SPLITS)?
+    {
+        return err!(CandyError::PaymentOptionsNotSupported);
+    }
+
+    let mut remaining_accounts_counter: usize = 0;
+    for option in &options {
+        if let PaymentOption::Token {
+            mint, destination, ..
+        } = option
+        {
+            let destination_info = ctx
+                .remaining_accounts
+                .get(remaining_accounts_counter)
+                .ok_or(CandyError::IncorrectRemainingAccountsLen)?;
+            remaining_accounts_counter += 1;
+
+            if destination_info.key() != *destination {
+                return err!(CandyError::PublicKeyMismatch);
+            }
+            assert_owned_by(destination_info, &spl_token::id())?;
+            let destination: spl_token::state::Account = assert_initialized(destination_info)?;
+            if destination.mint != *mint {
+                return err!(CandyError::MintMismatch);
+            }
+        }
+    }
+
+    let payment_options = &mut ctx.accounts.payment_options;
+    payment_options.candy_machine = candy_machine.key();
+    payment_options.required = required;
+    payment_options.options = options;
+
+    set_feature_flag(candy_machine, CandyFeatures::PAYMENT_OPTIONS)?;
+    Ok(())
+}
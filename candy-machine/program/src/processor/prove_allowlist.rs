@@ -0,0 +1,56 @@
+use anchor_lang::prelude::*;
+
+use crate::{merkle_proof, AllowlistProof, AllowlistRoot, CandyError, CandyMachine};
+
+/// Verify a wallet against the allowlist merkle root, recording the proof for minting
+#[derive(Accounts)]
+pub struct ProveAllowlist<'info> {
+    candy_machine: Account<'info, CandyMachine>,
+    #[account(mut)]
+    payer: Signer<'info>,
+    #[account(seeds = [AllowlistRoot::PREFIX.as_bytes(), candy_machine.to_account_info().key.as_ref()], bump)]
+    allowlist_root: Account<'info, AllowlistRoot>,
+    // The root is part of the proof seeds so a new root needs a new proof.
+    #[account(
+        init,
+        seeds = [
+            AllowlistProof::PREFIX.as_bytes(),
+            candy_machine.to_account_info().key.as_ref(),
+            payer.key.as_ref(),
+            allowlist_root.root.as_ref(),
+        ],
+        bump,
+        space = AllowlistProof::SIZE,
+        payer = payer
+    )]
+    allowlist_proof: Account<'info, AllowlistProof>,
+    system_program: Program<'info, System>,
+}
+
+pub fn handle_prove_allowlist(
+    ctx: Context<ProveAllowlist>,
+    proof: Vec<[u8; 32]>,
+    index: u64,
+    allocation: u64,
+) -> Result<()> {
+    let candy_machine = &ctx.accounts.candy_machine;
+    let root = ctx.accounts.allowlist_root.root;
+
+    let leaf = AllowlistProof::leaf(
+        index,
+        ctx.accounts.payer.key,
+        &candy_machine.key(),
+        allocation,
+    );
+    if !merkle_proof::verify(proof, root, leaf) {
+        return err!(CandyError::InvalidAllowlistProof);
+    }
+
+    let allowlist_proof = &mut ctx.accounts.allowlist_proof;
+    allowlist_proof.candy_machine = candy_machine.key();
+    allowlist_proof.wallet = ctx.accounts.payer.key();
+    allowlist_proof.root = root;
+    allowlist_proof.allocation = allocation;
+    allowlist_proof.minted = 0;
+    Ok(())
+}
@@ -0,0 +1,77 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::Token;
+use arrayref::array_ref;
+
+use crate::{
+    constants::PREFIX, create_candy_nft, get_config_line, CandyError, CandyMachine,
+    CandyNftAccounts, MintRequest,
+};
+
+/// Mint the NFT of a mint request once the oracle has revealed its randomness.
+#[derive(Accounts)]
+#[instruction(creator_bump: u8)]
+pub struct FulfillMint<'info> {
+    #[account(mut)]
+    candy_machine: Box<Account<'info, CandyMachine>>,
+    /// CHECK: account constraints checked in account trait
+    #[account(seeds=[PREFIX.as_bytes(), candy_machine.key().as_ref()], bump=creator_bump)]
+    candy_machine_creator: UncheckedAccount<'info>,
+    #[account(mut, close = minter, has_one = candy_machine, has_one = minter, has_one = mint)]
+    mint_request: Account<'info, MintRequest>,
+    /// CHECK: checked against the mint request
+    #[account(mut)]
+    minter: UncheckedAccount<'info>,
+    #[account(mut)]
+    payer: Signer<'info>,
+    // With the following accounts we aren't using anchor macros because they are CPI'd
+    // through to token-metadata which will do all the validations we need on them.
+    /// CHECK: account checked in CPI
+    #[account(mut)]
+    metadata: UncheckedAccount<'info>,
+    /// CHECK: checked against the mint request
+    #[account(mut)]
+    mint: UncheckedAccount<'info>,
+    /// CHECK: account checked in CPI
+    #[account(mut)]
+    master_edition: UncheckedAccount<'info>,
+    /// CHECK: account checked in CPI
+    #[account(address = mpl_token_metadata::id())]
+    token_metadata_program: UncheckedAccount<'info>,
+    token_program: Program<'info, Token>,
+    system_program: Program<'info, System>,
+    rent: Sysvar<'info, Rent>,
+}
+
+pub fn handle_fulfill_mint(ctx: Context<FulfillMint>, creator_bump: u8) -> Result<()> {
+    let candy_machine = &ctx.accounts.candy_machine;
+    let mint_request = &ctx.accounts.mint_request;
+    let randomness = mint_request
+        .randomness
+        .ok_or(CandyError::RandomnessNotRevealed)?;
+
+    let index = u64::from_le_bytes(*array_ref![randomness, 0, 8]);
+    let config_line = get_config_line(candy_machine, index, mint_request.mint_number)?;
+
+    // The candy machine creator took over the mint when the mint was requested.
+    let candy_machine_creator = ctx.accounts.candy_machine_creator.to_account_info();
+    let cm_key = candy_machine.key();
+    let authority_seeds = [PREFIX.as_bytes(), cm_key.as_ref(), &[creator_bump]];
+    create_candy_nft(
+        candy_machine,
+        CandyNftAccounts {
+            metadata: &ctx.accounts.metadata.to_account_info(),
+            mint: &ctx.accounts.mint.to_account_info(),
+            mint_authority: &candy_machine_creator,
+            payer: &ctx.accounts.payer.to_account_info(),
+            master_edition: &ctx.accounts.master_edition.to_account_info(),
+            candy_machine_creator: &candy_machine_creator,
+            token_metadata_program: &ctx.accounts.token_metadata_program.to_account_info(),
+            token_program: &ctx.accounts.token_program.to_account_info(),
+            system_program: &ctx.accounts.system_program.to_account_info(),
+            rent: &ctx.accounts.rent.to_account_info(),
+        },
+        config_line,
+        mint_request.update_authority,
+        &authority_seeds,
+    )
+}
@@ -0,0 +1,5 @@
+pub mod fulfill_mint;
+pub mod reveal_mint_randomness;
+
+pub use fulfill_mint::*;
+pub use reveal_mint_randomness::*;
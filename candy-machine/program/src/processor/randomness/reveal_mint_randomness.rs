@@ -0,0 +1,53 @@
+use anchor_lang::{prelude::*, solana_program::keccak};
+use solana_program::sysvar::slot_hashes;
+
+use crate::{get_slot_hash, CandyError, CandyMachine, MintRequest, MintSettings};
+
+/// Oracle reveals the seed it committed to, which picks the config line of a mint request
+/// together with the hash of the request slot.
+#[derive(Accounts)]
+pub struct RevealMintRandomness<'info> {
+    candy_machine: Account<'info, CandyMachine>,
+    #[account(
+        mut,
+        seeds = [MintSettings::PREFIX.as_bytes(), candy_machine.to_account_info().key.as_ref()],
+        bump,
+        constraint = mint_settings.randomness_oracle.as_ref().map(|randomness_oracle| randomness_oracle.oracle) == Some(oracle.key()) @ CandyError::IncorrectOwner
+    )]
+    mint_settings: Account<'info, MintSettings>,
+    oracle: Signer<'info>,
+    #[account(mut, has_one = candy_machine)]
+    mint_request: Account<'info, MintRequest>,
+    /// CHECK: address checked
+    #[account(address = slot_hashes::id())]
+    recent_slothashes: UncheckedAccount<'info>,
+}
+
+pub fn handle_reveal_mint_randomness(
+    ctx: Context<RevealMintRandomness>,
+    seed: [u8; 32],
+    next_commitment: [u8; 32],
+) -> Result<()> {
+    let mint_request = &mut ctx.accounts.mint_request;
+    // The hash of the request slot only exists once the slot is over.
+    if mint_request.randomness.is_some()
+        || Clock::get()?.slot <= mint_request.request_slot
+        || keccak::hash(&seed).0 != mint_request.commitment
+    {
+        return err!(CandyError::InvalidRandomnessReveal);
+    }
+    let request_slot_hash = get_slot_hash(
+        &ctx.accounts.recent_slothashes.data.borrow(),
+        mint_request.request_slot,
+    )
+    .ok_or(CandyError::RequestSlotHashExpired)?;
+    mint_request.randomness = Some(mint_request.randomness(&seed, &request_slot_hash));
+
+    // Later requests are made under the next commitment once this seed is public.
+    if let Some(randomness_oracle) = &mut ctx.accounts.mint_settings.randomness_oracle {
+        if randomness_oracle.commitment == mint_request.commitment {
+            randomness_oracle.commitment = next_commitment;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,5 @@
+pub mod remove_revenue_splits;
+pub mod set_revenue_splits;
+
+pub use remove_revenue_splits::*;
+pub use set_revenue_splits::*;
@@ -0,0 +1,20 @@
+use anchor_lang::prelude::*;
+
+use crate::{remove_feature_flag, CandyFeatures, CandyMachine, RevenueSplits};
+
+/// Removes the revenue splits from the candy machine and closes the revenue splits pda
+#[derive(Accounts)]
+pub struct RemoveRevenueSplits<'info> {
+    #[account(mut, has_one = authority)]
+    candy_machine: Account<'info, CandyMachine>,
+    #[account(mut)]
+    authority: Signer<'info>,
+    #[account(mut, close = authority, seeds = [RevenueSplits::PREFIX.as_bytes(), candy_machine.to_account_info().key.as_ref()], bump)]
+    revenue_splits: Account<'info, RevenueSplits>,
+}
+
+pub fn handle_remove_revenue_splits(ctx: Context<RemoveRevenueSplits>) -> Result<()> {
+    let candy_machine = &mut ctx.accounts.candy_machine;
+    remove_feature_flag(candy_machine, CandyFeatures::REVENUE_SPLITS)?;
+    Ok(())
+}
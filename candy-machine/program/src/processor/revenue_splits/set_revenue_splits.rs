@@ -0,0 +1,58 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    assert_initialized, assert_owned_by, is_feature_active, set_feature_flag, CandyError,
+    CandyFeatures, CandyMachine, RevenueSplit, RevenueSplits,
+};
+
+/// Set the revenue splits PDA for the candy machine
+#[derive(Accounts)]
+#[instruction(splits: Vec<RevenueSplit>)]
+pub struct SetRevenueSplits<'info> {
+    #[account(mut, has_one = authority)]
+    candy_machine: Account<'info, CandyMachine>,
+    #[account(mut)]
+    authority: Signer<'info>,
+    #[account(init, seeds = [RevenueSplits::PREFIX.as_bytes(), candy_machine.to_account_info().key.as_ref()], bump, space = RevenueSplits::size(&splits), payer = authority)]
+    revenue_splits: Account<'info, RevenueSplits>,
+    system_program: Program<'info, System>,
+    // > Only needed if spl token mint is enabled, for each split in split order
+    // recipient token account
+}
+
+pub fn handle_set_revenue_splits(
+    ctx: Context<SetRevenueSplits>,
+    splits: Vec<RevenueSplit>,
+) -> Result<()> {
+    let candy_machine = &mut ctx.accounts.candy_machine;
+    if is_feature_active(candy_machine, CandyFeatures::FREEZE)? {
+        return err!(CandyError::RevenueSplitsWithFreeze);
+    }
+    if is_feature_active(candy_machine, CandyFeatures::PAYMENT_OPTIONS)? {
+        return err!(CandyError::PaymentOptionsNotSupported);
+    }
+    RevenueSplits::assert_valid(&splits)?;
+
+    if let Some(mint) = candy_machine.token_mint {
+        if ctx.remaining_accounts.len() != splits.len() {
+            return err!(CandyError::IncorrectRemainingAccountsLen);
+        }
+        for (split, recipient_info) in splits.iter().zip(ctx.remaining_accounts) {
+            if recipient_info.key() != split.address {
+                return err!(CandyError::PublicKeyMismatch);
+            }
+            assert_owned_by(recipient_info, &spl_token::id())?;
+            let recipient: spl_token::state::Account = assert_initialized(recipient_info)?;
+            if recipient.mint != mint {
+                return err!(CandyError::MintMismatch);
+            }
+        }
+    }
+
+    let revenue_splits = &mut ctx.accounts.revenue_splits;
+    revenue_splits.candy_machine = candy_machine.key();
+    revenue_splits.splits = splits;
+
+    set_feature_flag(candy_machine, CandyFeatures::REVENUE_SPLITS)?;
+    Ok(())
+}
@@ -1,8 +1,9 @@
 use anchor_lang::prelude::*;
 
 use crate::{
-    constants::{COLLECTIONS_FEATURE_INDEX, FREEZE_FEATURE_INDEX},
-    is_feature_active, CandyError, CandyMachine, CandyMachineData,
+    assert_candy_machine_fits, get_bot_tax, get_features, is_feature_active, remove_feature_flag,
+    set_feature_flag, write_bot_tax, BotTax, BotTaxDestination, CandyError, CandyFeatures,
+    CandyMachine, CandyMachineData,
 };
 
 /// Update the candy machine state.
@@ -28,7 +29,7 @@ pub fn handle_update_authority(
 
     // Do not allow changing update authority if collections is active
     if let Some(new_auth) = new_authority {
-        if is_feature_active(&candy_machine.data.uuid, COLLECTIONS_FEATURE_INDEX) {
+        if is_feature_active(candy_machine, CandyFeatures::COLLECTIONS)? {
             return err!(CandyError::NoChangingAuthorityWithCollection);
         } else {
             candy_machine.authority = new_auth;
@@ -37,6 +38,35 @@ pub fn handle_update_authority(
     Ok(())
 }
 
+// None goes back to taxing every failure at the default fee
+pub fn handle_update_bot_tax(
+    ctx: Context<UpdateCandyMachine>,
+    bot_tax: Option<BotTax>,
+) -> Result<()> {
+    let candy_machine = &ctx.accounts.candy_machine;
+    // the wallet of a candy machine paid in tokens is a token account
+    if candy_machine.token_mint.is_some()
+        && matches!(&bot_tax, Some(bot_tax) if bot_tax.destination == BotTaxDestination::Wallet)
+    {
+        return err!(CandyError::BotTaxWalletWithToken);
+    }
+    write_bot_tax(candy_machine, bot_tax)
+}
+
+// switching the order once lines have been minted would skip or repeat some of them
+pub fn handle_update_sequential(ctx: Context<UpdateCandyMachine>, sequential: bool) -> Result<()> {
+    let candy_machine = &mut ctx.accounts.candy_machine;
+    if is_feature_active(candy_machine, CandyFeatures::SEQUENTIAL)? == sequential {
+        return Ok(());
+    }
+    candy_machine.assert_not_minted(error!(CandyError::CannotChangeSequentialDuringMint))?;
+    if sequential {
+        set_feature_flag(candy_machine, CandyFeatures::SEQUENTIAL)
+    } else {
+        remove_feature_flag(candy_machine, CandyFeatures::SEQUENTIAL)
+    }
+}
+
 // updates without modifying UUID
 pub fn handle_update_candy_machine(
     ctx: Context<UpdateCandyMachine>,
@@ -65,11 +95,24 @@ pub fn handle_update_candy_machine(
     }
 
     let old_uuid = candy_machine.data.uuid.clone();
-    if is_feature_active(&old_uuid, FREEZE_FEATURE_INDEX) && candy_machine.token_mint != token_mint
+    let features = get_features(candy_machine)?;
+    // the edition settings checked the items available against the master edition supply
+    if features.contains(CandyFeatures::EDITIONS)
+        && data.items_available != candy_machine.data.items_available
     {
+        return err!(CandyError::CannotChangeNumberOfLines);
+    }
+    if features.contains(CandyFeatures::FREEZE) && candy_machine.token_mint != token_mint {
         return err!(CandyError::NoChangingTokenWithFreeze);
     }
-    if is_feature_active(&old_uuid, COLLECTIONS_FEATURE_INDEX) && !data.retain_authority {
+    if features.contains(CandyFeatures::REVENUE_SPLITS) && candy_machine.token_mint != token_mint {
+        return err!(CandyError::NoChangingTokenWithRevenueSplits);
+    }
+    if token_mint.is_some() && get_bot_tax(candy_machine)?.destination == BotTaxDestination::Wallet
+    {
+        return err!(CandyError::BotTaxWalletWithToken);
+    }
+    if features.contains(CandyFeatures::COLLECTIONS) && !data.retain_authority {
         return err!(CandyError::CandyCollectionRequiresRetainAuthority);
     }
 
@@ -77,6 +120,7 @@ pub fn handle_update_candy_machine(
     candy_machine.data = data;
     candy_machine.data.uuid = old_uuid;
     candy_machine.token_mint = token_mint;
+    assert_candy_machine_fits(candy_machine)?;
 
     Ok(())
 }
@@ -1,9 +1,7 @@
 use anchor_lang::{prelude::*, AccountsClose};
 
 use crate::{
-    cmp_pubkeys,
-    constants::{FREEZE_FEATURE_INDEX, FREEZE_LOCK_FEATURE_INDEX},
-    is_feature_active, CandyError, CandyMachine, CollectionPDA,
+    cmp_pubkeys, is_feature_active, CandyError, CandyFeatures, CandyMachine, CollectionPDA,
 };
 
 /// Withdraw SOL from candy machine account.
@@ -22,10 +20,10 @@ pub fn handle_withdraw_funds<'info>(
 ) -> Result<()> {
     let authority = &ctx.accounts.authority;
     let candy_machine = &ctx.accounts.candy_machine;
-    if is_feature_active(&candy_machine.data.uuid, FREEZE_FEATURE_INDEX) {
+    if is_feature_active(candy_machine, CandyFeatures::FREEZE)? {
         return err!(CandyError::NoWithdrawWithFreeze);
     }
-    if is_feature_active(&candy_machine.data.uuid, FREEZE_LOCK_FEATURE_INDEX) {
+    if is_feature_active(candy_machine, CandyFeatures::FREEZE_LOCK)? {
         return err!(CandyError::NoWithdrawWithFrozenFunds);
     }
 
@@ -1,5 +1,13 @@
-use crate::{constants::FREEZE_FEE, CandyError};
-use anchor_lang::prelude::*;
+use crate::{
+    constants::{
+        BOT_FEE, FREEZE_FEE, MAX_MINT_PHASES, MAX_PAYMENT_OPTIONS, MAX_PHASE_LABEL_LENGTH,
+        MAX_REVENUE_SPLITS,
+    },
+    CandyError,
+};
+use anchor_lang::{prelude::*, solana_program::keccak};
+use bitflags::bitflags;
+use mpl_token_metadata::state::{MAX_NAME_LENGTH, MAX_URI_LENGTH};
 
 /// Candy machine state and config data.
 #[account]
@@ -10,10 +18,58 @@ pub struct CandyMachine {
     pub token_mint: Option<Pubkey>,
     pub items_redeemed: u64,
     pub data: CandyMachineData,
+    /// Account version, only meaningful once the version marker is set, see `is_legacy_candy_machine`
+    pub version: u8,
+    /// [`CandyFeatures`] bits, legacy accounts flag their features in the uuid instead
+    pub features: u64,
+    pub reserved: [u8; 16],
+    // accounts from before versioning end with the data, the fields above read whatever bytes follow it
+    // the version marker sits in the spare bytes in front of the config data and the optional BotTax right after them
+    // compressed config lines start with a u32 CONFIG_LINE_SETTINGS_MARKER and the ConfigLineSettings
     // there's a borsh vec u32 denoting how many actual lines of data there are currently (eventually equals items available)
     // There is actually lines and lines of data after this but we explicitly never want them deserialized.
     // here there is a borsh vec u32 indicating number of bytes in bitmask array.
     // here there is a number of bytes equal to ceil(max_number_of_lines/8) and it is a bit mask used to figure out when to increment borsh vec u32
+    // here there is a u32 CONFIG_INDICES_MARKER and a u32 count of config lines taken by mints
+    // here there are max_number_of_lines u32 slots, the swap-remove array of lines left to mint
+}
+
+bitflags! {
+    /// Optional features of a candy machine, bit `n` matches uuid byte `n` of legacy accounts.
+    #[derive(Default)]
+    pub struct CandyFeatures: u64 {
+        const COLLECTIONS = 1 << 0;
+        const FREEZE = 1 << 1;
+        const FREEZE_LOCK = 1 << 2;
+        const MINT_PHASES = 1 << 3;
+        const MINT_LIMIT = 1 << 4;
+        const PAYMENT_OPTIONS = 1 << 5;
+        /// Flagged in the last unused byte of legacy accounts instead of the uuid
+        const MINT_SETTINGS = 1 << 6;
+        const REVENUE_SPLITS = 1 << 7;
+        /// Prints editions of a master edition instead of config lines, one per mint and
+        /// without randomness oracles
+        const EDITIONS = 1 << 8;
+        /// Config lines are minted in order instead of pseudo-randomly
+        const SEQUENTIAL = 1 << 9;
+    }
+}
+
+bitflags! {
+    /// Categories of failed mints, taxed instead of failing the transaction when set.
+    pub struct BotTaxFailures: u16 {
+        /// Missing or wrong accounts
+        const ACCOUNTS = 1 << 0;
+        const SOLD_OUT = 1 << 1;
+        /// Minting before go live, after the end settings or outside a mint phase
+        const NOT_LIVE = 1 << 2;
+        /// Missing whitelist token or collection NFT
+        const WHITELIST = 1 << 3;
+        const GATEWAY = 1 << 4;
+        /// Unexpected instructions or callers in the transaction
+        const SUSPICIOUS = 1 << 5;
+        const PAYMENT = 1 << 6;
+    }
 }
 
 /// Collection PDA account
@@ -39,20 +95,37 @@ pub struct FreezePDA {
     pub mint_start: Option<i64>, // 1 + 8
     pub freeze_time: i64,        // 8
     pub freeze_fee: u64,         // 8
+    /// If true holders can burn their frozen NFTs for a refund when the freeze period ends
+    /// before the candy machine sells out
+    pub refund: bool, // 1
+    /// Price of every NFT minted while refunds are on, refunds return it in full
+    pub refund_price: u64, // 8
+    /// Frozen NFTs that paid the refund price into the freeze PDA
+    pub escrowed_count: u64, // 8
 }
 
 impl FreezePDA {
+    // the refund fields fit in the spare bytes, so older freeze PDAs read as without refunds
     pub const SIZE: usize = 8 + 32 + 32 + 1 + 8 + 1 + 8 + 8 + 8;
 
     pub const PREFIX: &'static str = "freeze";
 
-    pub fn init(&mut self, candy_machine: Pubkey, mint_start: Option<i64>, freeze_time: i64) {
+    pub fn init(
+        &mut self,
+        candy_machine: Pubkey,
+        mint_start: Option<i64>,
+        freeze_time: i64,
+        refund_price: Option<u64>,
+    ) {
         self.candy_machine = candy_machine;
         self.allow_thaw = false;
         self.frozen_count = 0;
         self.mint_start = mint_start;
         self.freeze_time = freeze_time;
         self.freeze_fee = FREEZE_FEE;
+        self.refund = refund_price.is_some();
+        self.refund_price = refund_price.unwrap_or(0);
+        self.escrowed_count = 0;
     }
 
     pub fn thaw_eligible(&self, current_timestamp: i64, candy_machine: &CandyMachine) -> bool {
@@ -66,6 +139,25 @@ impl FreezePDA {
         false
     }
 
+    /// Refunds open once the freeze period ends without a sell-out, minting ends with them.
+    pub fn refund_eligible(&self, current_timestamp: i64, candy_machine: &CandyMachine) -> bool {
+        if !self.refund || candy_machine.items_redeemed >= candy_machine.data.items_available {
+            return false;
+        }
+        match self.mint_start {
+            Some(start_timestamp) => current_timestamp >= start_timestamp + self.freeze_time,
+            None => false,
+        }
+    }
+
+    /// Funds the freeze PDA has to keep to refund every escrowed NFT.
+    pub fn refund_reserve(&self) -> Result<u64> {
+        Ok(self
+            .refund_price
+            .checked_mul(self.escrowed_count)
+            .ok_or(CandyError::NumericalOverflowError)?)
+    }
+
     pub fn assert_from_candy(&self, candy_machine: &Pubkey) -> Result<()> {
         if &self.candy_machine != candy_machine {
             return err!(CandyError::FreezePDAMismatch);
@@ -74,6 +166,528 @@ impl FreezePDA {
     }
 }
 
+/// Mint phases PDA account
+#[account]
+#[derive(Default, Debug)]
+pub struct MintPhases {
+    pub candy_machine: Pubkey,
+    pub phases: Vec<MintPhase>,
+}
+
+impl MintPhases {
+    pub const PREFIX: &'static str = "mint_phases";
+
+    pub fn size(phases: &[MintPhase]) -> usize {
+        8 + 32 + 4 + phases.len() * MintPhase::SIZE
+    }
+
+    /// Phases must be in chronological order and not overlap; only the last phase may be open ended.
+    pub fn assert_valid(phases: &[MintPhase]) -> Result<()> {
+        if phases.is_empty() || phases.len() > MAX_MINT_PHASES {
+            return err!(CandyError::InvalidMintPhases);
+        }
+        for (index, phase) in phases.iter().enumerate() {
+            if phase.label.len() > MAX_PHASE_LABEL_LENGTH {
+                return err!(CandyError::InvalidMintPhases);
+            }
+            match (phase.end_time, phases.get(index + 1)) {
+                (Some(end_time), _) if end_time <= phase.start_time => {
+                    return err!(CandyError::InvalidMintPhases);
+                }
+                (Some(end_time), Some(next)) if end_time > next.start_time => {
+                    return err!(CandyError::InvalidMintPhases);
+                }
+                (None, Some(_)) => return err!(CandyError::InvalidMintPhases),
+                _ => (),
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the phase live at `current_timestamp` and its index.
+    pub fn active_phase(&self, current_timestamp: i64) -> Option<(u8, &MintPhase)> {
+        self.phases
+            .iter()
+            .enumerate()
+            .find(|(_, phase)| phase.is_active(current_timestamp))
+            .map(|(index, phase)| (index as u8, phase))
+    }
+}
+
+/// A window of the mint with its own price, payment and gating rules.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct MintPhase {
+    pub label: String,
+    pub start_time: i64,
+    pub end_time: Option<i64>,
+    /// Replaces the candy machine price during the phase
+    pub price: u64,
+    /// If [`Some`] the price is paid in this SPL token instead of the candy machine currency
+    pub payment: Option<PhasePayment>,
+    /// If [`Some`] caps how many NFTs a wallet can mint during the phase
+    pub wallet_limit: Option<u16>,
+    /// If [`Some`] only minters passing the gate can mint during the phase
+    pub gate: Option<PhaseGate>,
+}
+
+impl MintPhase {
+    pub const SIZE: usize = 4 + MAX_PHASE_LABEL_LENGTH + // label
+        8 + // start time
+        9 + // end time
+        8 + // price
+        1 + 32 + 32 + // payment
+        3 + // wallet limit
+        1 + 1 + 32 + 1; // gate
+
+    pub fn is_active(&self, current_timestamp: i64) -> bool {
+        current_timestamp >= self.start_time
+            && self
+                .end_time
+                .map_or(true, |end_time| current_timestamp < end_time)
+    }
+
+    /// Number of accounts the phase takes in a mint while it is live.
+    pub fn accounts_len(&self) -> usize {
+        let mut accounts_len = 0;
+        if self.wallet_limit.is_some() {
+            accounts_len += 1;
+        }
+        match &self.gate {
+            Some(PhaseGate::WhitelistToken { burn, .. }) => {
+                accounts_len += if *burn { 2 } else { 1 };
+            }
+            Some(PhaseGate::CollectionHolder { once_per_nft, .. }) => {
+                accounts_len += if *once_per_nft { 3 } else { 2 };
+            }
+            None => (),
+        }
+        if self.payment.is_some() {
+            accounts_len += 2;
+        }
+        accounts_len
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct PhasePayment {
+    pub mint: Pubkey,
+    /// Token account receiving the payment
+    pub destination: Pubkey,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub enum PhaseGate {
+    /// Requires holding a token of the mint for every NFT minted, burning them if `burn` is set.
+    WhitelistToken { mint: Pubkey, burn: bool },
+    /// Requires holding an NFT verified in the `collection`, with each held NFT
+    /// only usable for a single mint if `once_per_nft` is set.
+    CollectionHolder {
+        collection: Pubkey,
+        once_per_nft: bool,
+    },
+}
+
+/// Payment options PDA account
+#[account]
+#[derive(Default, Debug)]
+pub struct PaymentOptions {
+    pub candy_machine: Pubkey,
+    /// If true minters must pay with one of the options instead of the candy machine price
+    pub required: bool,
+    pub options: Vec<PaymentOption>,
+}
+
+impl PaymentOptions {
+    pub const PREFIX: &'static str = "payment_options";
+
+    pub fn size(options: &[PaymentOption]) -> usize {
+        8 + 32 + 1 + 4 + options.len() * PaymentOption::SIZE
+    }
+
+    /// Each token can only be used by one option and burns must burn something.
+    pub fn assert_valid(options: &[PaymentOption]) -> Result<()> {
+        if options.is_empty() || options.len() > MAX_PAYMENT_OPTIONS {
+            return err!(CandyError::InvalidPaymentOptions);
+        }
+        for (index, option) in options.iter().enumerate() {
+            let duplicate = options[index + 1..]
+                .iter()
+                .any(|other| option.key() == other.key());
+            if duplicate || matches!(option, PaymentOption::BurnToken { amount: 0, .. }) {
+                return err!(CandyError::InvalidPaymentOptions);
+            }
+        }
+        Ok(())
+    }
+
+    /// Finds the option paying with `mint`, or burning an NFT from `collection`.
+    pub fn find(&self, mint: &Pubkey, collection: Option<&Pubkey>) -> Option<&PaymentOption> {
+        self.options.iter().find(|option| match option {
+            PaymentOption::Token { mint: key, .. } | PaymentOption::BurnToken { mint: key, .. } => {
+                key == mint
+            }
+            PaymentOption::BurnNft { collection: key } => Some(key) == collection,
+        })
+    }
+}
+
+/// An alternative way to pay for a mint.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub enum PaymentOption {
+    /// Transfers `price` tokens of `mint` to the `destination` token account
+    Token {
+        mint: Pubkey,
+        price: u64,
+        destination: Pubkey,
+    },
+    /// Burns `amount` tokens of `mint`
+    BurnToken { mint: Pubkey, amount: u64 },
+    /// Burns an NFT from the verified `collection`
+    BurnNft { collection: Pubkey },
+}
+
+impl PaymentOption {
+    pub const SIZE: usize = 1 + 32 + 8 + 32;
+
+    fn key(&self) -> &Pubkey {
+        match self {
+            PaymentOption::Token { mint, .. } | PaymentOption::BurnToken { mint, .. } => mint,
+            PaymentOption::BurnNft { collection } => collection,
+        }
+    }
+}
+
+/// Mint limit PDA account
+#[account]
+#[derive(Default, Debug)]
+pub struct MintLimitPDA {
+    pub candy_machine: Pubkey, // 32
+    pub limit: u16,            // 2
+}
+
+impl MintLimitPDA {
+    pub const SIZE: usize = 8 + 32 + 2;
+
+    pub const PREFIX: &'static str = "mint_limit";
+}
+
+/// Revenue splits PDA account
+#[account]
+#[derive(Default, Debug)]
+pub struct RevenueSplits {
+    pub candy_machine: Pubkey,
+    pub splits: Vec<RevenueSplit>,
+}
+
+impl RevenueSplits {
+    pub const PREFIX: &'static str = "revenue_splits";
+
+    pub fn size(splits: &[RevenueSplit]) -> usize {
+        8 + 32 + 4 + splits.len() * RevenueSplit::SIZE
+    }
+
+    /// Each recipient is listed once and the shares add up to the whole price.
+    pub fn assert_valid(splits: &[RevenueSplit]) -> Result<()> {
+        if splits.is_empty() || splits.len() > MAX_REVENUE_SPLITS {
+            return err!(CandyError::InvalidRevenueSplits);
+        }
+        let mut total: u16 = 0;
+        for (index, split) in splits.iter().enumerate() {
+            let duplicate = splits[index + 1..]
+                .iter()
+                .any(|other| other.address == split.address);
+            if duplicate || split.basis_points == 0 {
+                return err!(CandyError::InvalidRevenueSplits);
+            }
+            total = total
+                .checked_add(split.basis_points)
+                .ok_or(CandyError::InvalidRevenueSplits)?;
+        }
+        if total != 10000 {
+            return err!(CandyError::InvalidRevenueSplits);
+        }
+        Ok(())
+    }
+
+    /// Amounts of `price` paid to each recipient, in split order. The rounding remainder
+    /// goes to the last recipient so the whole price is paid out.
+    pub fn amounts(&self, price: u64) -> Result<Vec<u64>> {
+        let mut amounts = Vec::with_capacity(self.splits.len());
+        let mut remaining = price;
+        for (index, split) in self.splits.iter().enumerate() {
+            let amount = if index == self.splits.len() - 1 {
+                remaining
+            } else {
+                (price as u128)
+                    .checked_mul(split.basis_points as u128)
+                    .and_then(|amount| amount.checked_div(10000))
+                    .ok_or(CandyError::NumericalOverflowError)? as u64
+            };
+            remaining = remaining
+                .checked_sub(amount)
+                .ok_or(CandyError::NumericalOverflowError)?;
+            amounts.push(amount);
+        }
+        Ok(amounts)
+    }
+}
+
+/// A share of the mint price.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct RevenueSplit {
+    /// Wallet paid in SOL, or token account paid in `token_mint` tokens
+    pub address: Pubkey,
+    pub basis_points: u16,
+}
+
+impl RevenueSplit {
+    pub const SIZE: usize = 32 + 2;
+}
+
+/// Where the bot tax is paid to.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BotTaxDestination {
+    CandyMachine,
+    /// The treasury wallet, only for candy machines paid in SOL
+    Wallet,
+    /// Any account, passed as the last remaining account of every mint
+    Address(Pubkey),
+}
+
+/// Bot tax charged on failed mints, stored in the candy machine account after the features.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct BotTax {
+    pub lamports: u64,
+    pub destination: BotTaxDestination,
+    /// `BotTaxFailures` bits, the other failures return their error instead
+    pub taxed_failures: u16,
+}
+
+impl BotTax {
+    // option + lamports + destination + taxed failures
+    pub const SIZE: usize = 1 + 8 + 1 + 32 + 2;
+}
+
+impl Default for BotTax {
+    fn default() -> Self {
+        BotTax {
+            lamports: BOT_FEE,
+            destination: BotTaxDestination::CandyMachine,
+            taxed_failures: BotTaxFailures::all().bits(),
+        }
+    }
+}
+
+/// Edition settings PDA account, mints print editions of the master edition the candy machine
+/// creator holds instead of taking config lines
+#[account]
+#[derive(Default, Debug)]
+pub struct EditionSettings {
+    pub candy_machine: Pubkey, // 32
+    pub master_mint: Pubkey,   // 32
+}
+
+impl EditionSettings {
+    pub const SIZE: usize = 8 + 32 + 32;
+
+    pub const PREFIX: &'static str = "edition_settings";
+}
+
+/// Mint settings PDA account
+#[account]
+#[derive(Default, Debug)]
+pub struct MintSettings {
+    pub candy_machine: Pubkey, // 32
+    /// If [`Some`] mints are requested and then fulfilled with randomness from this oracle
+    pub randomness_oracle: Option<RandomnessOracle>, // 1 + 64
+    /// If [`Some`] hidden settings NFTs stay updatable by the candy machine until revealed
+    /// on-chain to config lines chaining to this hash, see [`HiddenReveal::chain_hash`]. Their
+    /// mint numbers are kept in a [`HiddenMintRecord`] created on mint
+    pub reveal_hash: Option<[u8; 32]>, // 1 + 32
+    /// If [`Some`] mints programmable NFTs instead of regular ones
+    pub programmable: Option<ProgrammableSettings>, // 1 + 1 + 32
+    /// If [`Some`] only CPIs from this program, signed by its `mint_authority` PDA, can mint
+    pub mint_authority_program: Option<Pubkey>, // 1 + 32
+}
+
+impl MintSettings {
+    pub const SIZE: usize = 8 + 32 + 1 + 64 + 1 + 32 + 1 + 1 + 32 + 1 + 32;
+
+    pub const PREFIX: &'static str = "mint_settings";
+
+    /// Number of accounts the settings take in a mint, after the settings account itself.
+    pub fn accounts_len(&self) -> usize {
+        let mut accounts_len = 0;
+        if self.randomness_oracle.is_some() {
+            accounts_len += 1;
+        }
+        if self.reveal_hash.is_some() {
+            accounts_len += 1;
+        }
+        if let Some(programmable) = &self.programmable {
+            accounts_len += if programmable.rule_set.is_some() {
+                5
+            } else {
+                3
+            };
+        }
+        if self.mint_authority_program.is_some() {
+            accounts_len += 1;
+        }
+        accounts_len
+    }
+}
+
+/// Mint request PDA account holding a mint until the oracle reveals its randomness
+#[account]
+#[derive(Default, Debug)]
+pub struct MintRequest {
+    pub candy_machine: Pubkey,    // 32
+    pub minter: Pubkey,           // 32
+    pub mint: Pubkey,             // 32
+    pub update_authority: Pubkey, // 32
+    pub mint_number: u64,         // 8
+    pub request_slot: u64,        // 8
+    /// Oracle commitment at the time of the request, opened by the seed of the reveal
+    pub commitment: [u8; 32], // 32
+    pub randomness: Option<[u8; 32]>, // 1 + 32
+}
+
+impl MintRequest {
+    pub const SIZE: usize = 8 + 32 + 32 + 32 + 32 + 8 + 8 + 32 + 1 + 32;
+
+    pub const PREFIX: &'static str = "mint_request";
+
+    /// keccak(seed, hash of the request slot, mint), so neither the oracle, which committed to
+    /// the seed before the request, nor the minter, who can't know the slot hash, picks the line.
+    pub fn randomness(&self, seed: &[u8; 32], request_slot_hash: &[u8; 32]) -> [u8; 32] {
+        keccak::hashv(&[seed, request_slot_hash, self.mint.as_ref()]).0
+    }
+}
+
+/// Config lines revealing the NFTs of a hidden settings candy machine on-chain
+#[account]
+#[derive(Default, Debug)]
+pub struct HiddenReveal {
+    pub candy_machine: Pubkey, // 32
+    /// Hash chained over the config lines uploaded so far, must end up as the reveal hash
+    pub hash: [u8; 32], // 32
+    pub lines_uploaded: u32,   // 4
+                               // the config lines follow in mint order, CONFIG_LINE_SIZE bytes each like config lines
+                               // stored in the candy machine
+}
+
+impl HiddenReveal {
+    pub const SIZE: usize = 8 + 32 + 32 + 4;
+
+    pub const PREFIX: &'static str = "hidden_reveal";
+
+    /// keccak(previous hash, borsh config line), starting from zeroes.
+    ///
+    /// The borsh config line is the unpadded name and then the unpadded URI, each as a little
+    /// endian u32 length followed by its bytes. The hash after the last line in mint order is
+    /// the reveal hash of the mint settings.
+    pub fn chain_hash(hash: &[u8; 32], config_line: &ConfigLine) -> Result<[u8; 32]> {
+        Ok(keccak::hashv(&[hash, &config_line.try_to_vec()?]).0)
+    }
+}
+
+/// Hidden mint record PDA account holding the mint number of an NFT waiting for its reveal
+#[account]
+#[derive(Default, Debug)]
+pub struct HiddenMintRecord {
+    pub candy_machine: Pubkey, // 32
+    pub mint: Pubkey,          // 32
+    /// Paid for the record and gets its rent back on reveal
+    pub minter: Pubkey, // 32
+    /// Update authority of the revealed NFT when the candy machine doesn't retain authority
+    pub update_authority: Pubkey, // 32
+    pub mint_number: u64,      // 8
+}
+
+impl HiddenMintRecord {
+    pub const SIZE: usize = 8 + 32 + 32 + 32 + 32 + 8;
+
+    pub const PREFIX: &'static str = "hidden_mint";
+}
+
+/// Holder mint record PDA account marking a held NFT as used for a holder gated mint
+#[account]
+#[derive(Default, Debug)]
+pub struct HolderMintRecord {
+    pub candy_machine: Pubkey, // 32
+    pub held_mint: Pubkey,     // 32
+}
+
+impl HolderMintRecord {
+    pub const SIZE: usize = 8 + 32 + 32;
+
+    pub const PREFIX: &'static str = "holder_mint";
+}
+
+/// Mint counter PDA account tracking how many NFTs a wallet has minted
+#[account]
+#[derive(Default, Debug)]
+pub struct MintCounter {
+    pub candy_machine: Pubkey, // 32
+    pub wallet: Pubkey,        // 32
+    pub count: u16,            // 2
+}
+
+impl MintCounter {
+    pub const SIZE: usize = 8 + 32 + 32 + 2;
+
+    pub const PREFIX: &'static str = "mint_counter";
+}
+
+/// Allowlist root PDA account holding the merkle root of a [`WhitelistMintMode::MerkleTree`] whitelist
+#[account]
+#[derive(Default, Debug)]
+pub struct AllowlistRoot {
+    pub candy_machine: Pubkey, // 32
+    pub root: [u8; 32],        // 32
+}
+
+impl AllowlistRoot {
+    pub const SIZE: usize = 8 + 32 + 32;
+
+    pub const PREFIX: &'static str = "allowlist_root";
+}
+
+/// Allowlist proof PDA account recording a wallet proven against the allowlist merkle root
+#[account]
+#[derive(Default, Debug)]
+pub struct AllowlistProof {
+    pub candy_machine: Pubkey, // 32
+    pub wallet: Pubkey,        // 32
+    pub root: [u8; 32],        // 32
+    pub allocation: u64,       // 8
+    pub minted: u64,           // 8
+}
+
+impl AllowlistProof {
+    pub const SIZE: usize = 8 + 32 + 32 + 32 + 8 + 8;
+
+    pub const PREFIX: &'static str = "allowlist_proof";
+
+    /// Leaf of the allowlist merkle tree, encoded like gumdrop candy machine claims so gumdrop
+    /// tooling can build the tree with the candy machine and the allocation as the amount.
+    pub fn leaf(index: u64, wallet: &Pubkey, candy_machine: &Pubkey, allocation: u64) -> [u8; 32] {
+        keccak::hashv(&[
+            &[0x00],
+            &index.to_le_bytes(),
+            &wallet.to_bytes(),
+            &candy_machine.to_bytes(),
+            &allocation.to_le_bytes(),
+        ])
+        .0
+    }
+
+    pub fn has_allocation_left(&self) -> bool {
+        self.minted < self.allocation
+    }
+}
+
 /// Candy machine settings data.
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Default, Debug)]
 pub struct CandyMachineData {
@@ -114,6 +728,35 @@ pub struct ConfigLine {
     pub uri: String,
 }
 
+/// Compressed config lines only store the name and URI suffixes after these shared prefixes.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Default, Debug, PartialEq, Eq)]
+pub struct ConfigLineSettings {
+    pub prefix_name: String,
+    /// Length of the name suffix stored in each config line
+    pub name_length: u32,
+    pub prefix_uri: String,
+    /// Length of the URI suffix stored in each config line
+    pub uri_length: u32,
+}
+
+impl ConfigLineSettings {
+    // stored after a CONFIG_LINE_SETTINGS_MARKER with prefixes padded to their max length
+    pub const SIZE: usize = 4 + MAX_NAME_LENGTH + 4 + 4 + MAX_URI_LENGTH + 4;
+
+    pub fn assert_valid(&self) -> Result<()> {
+        if self.prefix_name.len() + self.name_length as usize > MAX_NAME_LENGTH
+            || self.prefix_uri.len() + self.uri_length as usize > MAX_URI_LENGTH
+        {
+            return err!(CandyError::InvalidConfigLineSettings);
+        }
+        Ok(())
+    }
+
+    pub fn line_size(&self) -> usize {
+        self.name_length as usize + self.uri_length as usize
+    }
+}
+
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
 pub struct EndSettings {
     pub end_setting_type: EndSettingType,
@@ -143,9 +786,26 @@ pub struct HiddenSettings {
     pub hash: [u8; 32],
 }
 
+/// Oracle revealing the randomness of mint requests.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Default, Debug)]
+pub struct RandomnessOracle {
+    pub oracle: Pubkey,
+    /// keccak of the seed the oracle reveals for the requests made while it is current
+    pub commitment: [u8; 32],
+}
+
+/// Programmable NFT settings, enforced by token metadata on every transfer and delegation.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Default, Debug)]
+pub struct ProgrammableSettings {
+    /// Authorization rule set the NFTs are minted with
+    pub rule_set: Option<Pubkey>,
+}
+
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
 pub struct WhitelistMintSettings {
     pub mode: WhitelistMintMode,
+    /// The whitelist token mint, unused in [`WhitelistMintMode::MerkleTree`] mode where the root is
+    /// held by the [`AllowlistRoot`]
     pub mint: Pubkey,
     pub presale: bool,
     pub discount_price: Option<u64>,
@@ -158,6 +818,8 @@ pub enum WhitelistMintMode {
     // Holds the validation window
     BurnEveryTime,
     NeverBurn,
+    /// Wallets prove they are in the allowlist merkle tree instead of holding a token
+    MerkleTree,
 }
 
 /// Configurations options for the gatekeeper.
@@ -169,3 +831,194 @@ pub struct GatekeeperConfig {
     /// The gatekeeper network must support this if true.
     pub expire_on_use: bool,
 }
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    fn phase(start_time: i64, end_time: Option<i64>) -> MintPhase {
+        MintPhase {
+            label: "phase".to_string(),
+            start_time,
+            end_time,
+            price: 1,
+            payment: None,
+            wallet_limit: None,
+            gate: None,
+        }
+    }
+
+    #[test]
+    fn mint_phases_validation() {
+        assert!(MintPhases::assert_valid(&[]).is_err());
+        assert!(MintPhases::assert_valid(&[phase(10, None)]).is_ok());
+        assert!(MintPhases::assert_valid(&[phase(10, Some(20)), phase(20, None)]).is_ok());
+        // overlapping phases
+        assert!(MintPhases::assert_valid(&[phase(10, Some(30)), phase(20, None)]).is_err());
+        // only the last phase can be open ended
+        assert!(MintPhases::assert_valid(&[phase(10, None), phase(20, None)]).is_err());
+        // phases must end after they start
+        assert!(MintPhases::assert_valid(&[phase(10, Some(10))]).is_err());
+    }
+
+    #[test]
+    fn active_mint_phase() {
+        let mint_phases = MintPhases {
+            candy_machine: Pubkey::new_unique(),
+            phases: vec![phase(10, Some(20)), phase(30, None)],
+        };
+        assert!(mint_phases.active_phase(5).is_none());
+        assert_eq!(mint_phases.active_phase(10).unwrap().0, 0);
+        assert!(mint_phases.active_phase(25).is_none());
+        assert_eq!(mint_phases.active_phase(100).unwrap().0, 1);
+    }
+
+    #[test]
+    fn allowlist_proof_leaves() {
+        let candy_machine = Pubkey::new_unique();
+        let wallet = Pubkey::new_unique();
+        let other_wallet = Pubkey::new_unique();
+        let leaf = AllowlistProof::leaf(0, &wallet, &candy_machine, 2);
+        let other_leaf = AllowlistProof::leaf(1, &other_wallet, &candy_machine, 1);
+        let root = if leaf <= other_leaf {
+            keccak::hashv(&[&[0x01], &leaf, &other_leaf]).0
+        } else {
+            keccak::hashv(&[&[0x01], &other_leaf, &leaf]).0
+        };
+
+        assert!(crate::merkle_proof::verify(vec![other_leaf], root, leaf));
+        assert!(crate::merkle_proof::verify(vec![leaf], root, other_leaf));
+        // the index, candy machine and allocation are part of the leaf
+        for wrong_leaf in [
+            AllowlistProof::leaf(1, &wallet, &candy_machine, 2),
+            AllowlistProof::leaf(0, &wallet, &Pubkey::new_unique(), 2),
+            AllowlistProof::leaf(0, &wallet, &candy_machine, 3),
+        ] {
+            assert!(!crate::merkle_proof::verify(
+                vec![other_leaf],
+                root,
+                wrong_leaf
+            ));
+        }
+
+        let mut allowlist_proof = AllowlistProof {
+            allocation: 2,
+            minted: 1,
+            ..Default::default()
+        };
+        assert!(allowlist_proof.has_allocation_left());
+        allowlist_proof.minted = 2;
+        assert!(!allowlist_proof.has_allocation_left());
+    }
+
+    #[test]
+    fn payment_options_validation() {
+        let mint = Pubkey::new_unique();
+        let collection = Pubkey::new_unique();
+        let token = PaymentOption::Token {
+            mint,
+            price: 5,
+            destination: Pubkey::new_unique(),
+        };
+        let burn_nft = PaymentOption::BurnNft { collection };
+
+        assert!(PaymentOptions::assert_valid(&[]).is_err());
+        assert!(PaymentOptions::assert_valid(&[token.clone(), burn_nft.clone()]).is_ok());
+        // one option per mint
+        assert!(PaymentOptions::assert_valid(&[
+            token.clone(),
+            PaymentOption::BurnToken { mint, amount: 1 }
+        ])
+        .is_err());
+        assert!(PaymentOptions::assert_valid(&[PaymentOption::BurnToken {
+            mint: Pubkey::new_unique(),
+            amount: 0
+        }])
+        .is_err());
+
+        let payment_options = PaymentOptions {
+            candy_machine: Pubkey::new_unique(),
+            required: true,
+            options: vec![token.clone(), burn_nft.clone()],
+        };
+        assert_eq!(payment_options.find(&mint, None), Some(&token));
+        assert_eq!(
+            payment_options.find(&Pubkey::new_unique(), Some(&collection)),
+            Some(&burn_nft)
+        );
+        assert_eq!(payment_options.find(&Pubkey::new_unique(), None), None);
+    }
+
+    #[test]
+    fn freeze_refunds() {
+        let mut candy_machine = CandyMachine::default();
+        candy_machine.data.items_available = 10;
+        candy_machine.items_redeemed = 3;
+        let mut freeze_pda = FreezePDA::default();
+        freeze_pda.init(Pubkey::new_unique(), Some(100), 50, Some(10));
+        freeze_pda.escrowed_count = 3;
+
+        assert!(!freeze_pda.refund_eligible(149, &candy_machine));
+        assert!(freeze_pda.refund_eligible(150, &candy_machine));
+        assert_eq!(freeze_pda.refund_price, 10);
+        assert_eq!(
+            freeze_pda.refund_reserve().unwrap(),
+            freeze_pda.refund_price * freeze_pda.escrowed_count
+        );
+
+        candy_machine.items_redeemed = 10;
+        assert!(!freeze_pda.refund_eligible(150, &candy_machine));
+        freeze_pda.refund = false;
+        candy_machine.items_redeemed = 3;
+        assert!(!freeze_pda.refund_eligible(150, &candy_machine));
+    }
+
+    #[test]
+    fn revenue_splits_amounts() {
+        let split = |basis_points| RevenueSplit {
+            address: Pubkey::new_unique(),
+            basis_points,
+        };
+
+        assert!(RevenueSplits::assert_valid(&[]).is_err());
+        assert!(RevenueSplits::assert_valid(&[split(5000), split(4000)]).is_err());
+        assert!(RevenueSplits::assert_valid(&[split(10000), split(0)]).is_err());
+        let duplicate = split(5000);
+        assert!(RevenueSplits::assert_valid(&[duplicate.clone(), duplicate]).is_err());
+
+        let revenue_splits = RevenueSplits {
+            candy_machine: Pubkey::new_unique(),
+            splits: vec![split(3333), split(3333), split(3334)],
+        };
+        assert!(RevenueSplits::assert_valid(&revenue_splits.splits).is_ok());
+        assert_eq!(revenue_splits.amounts(100).unwrap(), vec![33, 33, 34]);
+        assert_eq!(revenue_splits.amounts(0).unwrap(), vec![0, 0, 0]);
+        assert_eq!(
+            revenue_splits
+                .amounts(u64::MAX)
+                .unwrap()
+                .iter()
+                .sum::<u64>(),
+            u64::MAX
+        );
+    }
+
+    #[test]
+    fn config_line_settings_validation() {
+        let settings = ConfigLineSettings {
+            prefix_name: "Item #".to_string(),
+            name_length: 5,
+            prefix_uri: "https://arweave.net/".to_string(),
+            uri_length: 43,
+        };
+        assert!(settings.assert_valid().is_ok());
+        assert_eq!(settings.line_size(), 48);
+
+        // prefix and suffix must fit in the metadata name
+        let settings = ConfigLineSettings {
+            name_length: (MAX_NAME_LENGTH - 5) as u32,
+            ..settings
+        };
+        assert!(settings.assert_valid().is_err());
+    }
+}
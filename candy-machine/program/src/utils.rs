@@ -1,7 +1,10 @@
 use anchor_lang::prelude::*;
+use arrayref::array_ref;
 use mpl_token_metadata::{
     error::MetadataError,
-    state::{MasterEditionV2, Metadata, TokenStandard},
+    instruction::burn_nft,
+    state::{MasterEditionV2, Metadata, TokenMetadataAccount, TokenStandard},
+    utils::create_or_allocate_account_raw,
 };
 use solana_program::{
     account_info::AccountInfo,
@@ -14,7 +17,10 @@ use solana_program::{
 };
 use spl_associated_token_account::get_associated_token_address;
 
-use crate::{constants::*, CandyError, CandyMachine};
+use crate::{
+    constants::*, AllowlistProof, BotTax, BotTaxFailures, CandyError, CandyFeatures, CandyMachine,
+    MintCounter, PaymentOption, PaymentOptions,
+};
 
 pub fn assert_initialized<T: Pack + IsInitialized>(account_info: &AccountInfo) -> Result<T> {
     let account: T = T::unpack_unchecked(&account_info.data.borrow())?;
@@ -29,6 +35,27 @@ pub fn cmp_pubkeys(a: &Pubkey, b: &Pubkey) -> bool {
     sol_memcmp(a.as_ref(), b.as_ref(), PUBKEY_BYTES) == 0
 }
 
+/// Finds the hash of `slot` in the raw slot hashes sysvar, whose entries are sorted by
+/// descending slot.
+pub fn get_slot_hash(slot_hashes_data: &[u8], slot: u64) -> Option<[u8; 32]> {
+    const ENTRY_SIZE: usize = 8 + 32;
+    let len = u64::from_le_bytes(*array_ref![slot_hashes_data, 0, 8]) as usize;
+    let (mut low, mut high) = (0, len.min((slot_hashes_data.len() - 8) / ENTRY_SIZE));
+    while low < high {
+        let middle = (low + high) / 2;
+        let entry = 8 + middle * ENTRY_SIZE;
+        let entry_slot = u64::from_le_bytes(*array_ref![slot_hashes_data, entry, 8]);
+        if entry_slot == slot {
+            return Some(*array_ref![slot_hashes_data, entry + 8, 32]);
+        } else if entry_slot > slot {
+            low = middle + 1;
+        } else {
+            high = middle;
+        }
+    }
+    None
+}
+
 pub fn assert_valid_go_live<'info>(
     payer: &Signer<'info>,
     clock: &Clock,
@@ -178,52 +205,170 @@ pub fn spl_token_burn(params: TokenBurnParams<'_, '_>) -> Result<()> {
     result.map_err(|_| CandyError::TokenBurnFailed.into())
 }
 
-pub fn is_feature_active(uuid: &str, feature_index: usize) -> bool {
+/// Whether a candy machine is from before versioning. The fields after the data of those accounts
+/// hold whatever bytes followed it, so versioned accounts also set the byte at `VERSION_OFFSET`.
+pub fn is_legacy_candy_machine(data: &[u8]) -> bool {
+    data[VERSION_OFFSET] == 0
+}
+
+/// Account version of a candy machine, 0 for accounts from before versioning.
+pub fn get_candy_machine_version(candy_machine: &CandyMachine, data: &[u8]) -> u8 {
+    if is_legacy_candy_machine(data) {
+        return 0;
+    }
+    candy_machine.version
+}
+
+/// Features of a candy machine, read from the uuid and the mint settings byte of a legacy account.
+pub fn get_candy_machine_features(candy_machine: &CandyMachine, data: &[u8]) -> CandyFeatures {
+    if is_legacy_candy_machine(data) {
+        let mut features = get_legacy_features(&candy_machine.data.uuid);
+        features.set(
+            CandyFeatures::MINT_SETTINGS,
+            data[LEGACY_MINT_SETTINGS_OFFSET] != 0,
+        );
+        return features;
+    }
+    CandyFeatures::from_bits_truncate(candy_machine.features)
+}
+
+/// The candy machine is written from the start of the account and must end before the version
+/// marker, its trailing fields would otherwise spill over the marker and the config data.
+pub fn assert_candy_machine_fits(candy_machine: &CandyMachine) -> Result<()> {
+    if 8 + candy_machine.try_to_vec()?.len() > VERSION_OFFSET {
+        return err!(CandyError::CandyMachineDataTooLong);
+    }
+    Ok(())
+}
+
+pub fn get_features(candy_machine: &Account<CandyMachine>) -> Result<CandyFeatures> {
+    let account = candy_machine.to_account_info();
+    let data = account.try_borrow_data()?;
+    Ok(get_candy_machine_features(candy_machine, &data))
+}
+
+pub fn is_feature_active(
+    candy_machine: &Account<CandyMachine>,
+    feature: CandyFeatures,
+) -> Result<bool> {
+    Ok(get_features(candy_machine)?.contains(feature))
+}
+
+pub fn set_feature_flag(
+    candy_machine: &mut Account<CandyMachine>,
+    feature: CandyFeatures,
+) -> Result<()> {
+    let mut features = get_features(candy_machine)?;
+    features.insert(feature);
+    write_features(candy_machine, features)
+}
+
+pub fn remove_feature_flag(
+    candy_machine: &mut Account<CandyMachine>,
+    feature: CandyFeatures,
+) -> Result<()> {
+    let mut features = get_features(candy_machine)?;
+    features.remove(feature);
+    write_features(candy_machine, features)
+}
+
+// legacy accounts have nowhere to store the flags, so they have to be migrated first
+fn write_features(
+    candy_machine: &mut Account<CandyMachine>,
+    features: CandyFeatures,
+) -> Result<()> {
+    if is_legacy_candy_machine(&candy_machine.to_account_info().try_borrow_data()?) {
+        return err!(CandyError::CandyMachineNotMigrated);
+    }
+    candy_machine.features = features.bits();
+    Ok(())
+}
+
+/// Features legacy accounts flagged by writing `#` over the first uuid bytes.
+pub fn get_legacy_features(uuid: &str) -> CandyFeatures {
     let uuid_bytes = uuid.as_bytes();
-    if feature_index == COLLECTIONS_FEATURE_INDEX && uuid_bytes[feature_index] == b'1' {
-        is_valid_uuid(uuid)
-    } else {
-        uuid_bytes[feature_index] == b'#'
+    let mut features = CandyFeatures::empty();
+    for (index, byte) in uuid_bytes.iter().take(LEGACY_FEATURE_COUNT).enumerate() {
+        let active = if index == 0 && *byte == b'1' {
+            is_valid_uuid(uuid)
+        } else {
+            *byte == b'#'
+        };
+        if active {
+            features |= CandyFeatures::from_bits_truncate(1 << index);
+        }
     }
+    features
 }
 
 fn is_valid_uuid(uuid: &str) -> bool {
     !uuid.bytes().any(|b| b != b'1' && b != b'0' && b != b'#')
 }
 
-// string is 6 bytes long, can be any valid utf8 char coming in.
-// feature_index is between 0 and 5, inclusive.
-pub fn set_feature_flag(uuid: &mut str, feature_index: usize) {
-    if feature_index > 5 {
-        return;
+/// Uuid with the legacy feature flags zeroed, once they have been moved to the feature flags.
+pub fn clear_legacy_features(uuid: &str) -> String {
+    let features = get_legacy_features(uuid);
+    let mut uuid_bytes = uuid.as_bytes().to_vec();
+    for (index, byte) in uuid_bytes.iter_mut().take(LEGACY_FEATURE_COUNT).enumerate() {
+        if features.contains(CandyFeatures::from_bits_truncate(1 << index)) {
+            *byte = b'0';
+        }
     }
+    // only ascii flag bytes were replaced, so the uuid is still valid utf8
+    String::from_utf8(uuid_bytes).unwrap()
+}
 
-    // its safe because the char boundaries for the normalized string are all 1 byte. trust me bro
-    unsafe {
-        uuid.as_bytes_mut()[feature_index] = b'#';
+/// Bot tax of a candy machine, the default one for candy machines that never set theirs.
+pub fn get_bot_tax(candy_machine: &Account<CandyMachine>) -> Result<BotTax> {
+    let account = candy_machine.to_account_info();
+    let data = account.try_borrow_data()?;
+    if is_legacy_candy_machine(&data) {
+        return Ok(BotTax::default());
     }
+    let mut bot_tax_data = &data[BOT_TAX_OFFSET..BOT_TAX_OFFSET + BotTax::SIZE];
+    Ok(Option::<BotTax>::deserialize(&mut bot_tax_data)?.unwrap_or_default())
 }
 
-// string is 6 bytes long, can be any valid utf8 char coming in.
-// feature_index is between 0 and 5, inclusive.
-pub fn remove_feature_flag(uuid: &mut str, feature_index: usize) {
-    if feature_index > 5 {
-        return;
+pub fn write_bot_tax(candy_machine: &Account<CandyMachine>, bot_tax: Option<BotTax>) -> Result<()> {
+    let account = candy_machine.to_account_info();
+    let mut data = account.try_borrow_mut_data()?;
+    if is_legacy_candy_machine(&data) {
+        return err!(CandyError::CandyMachineNotMigrated);
     }
+    let mut bot_tax_data = &mut data[BOT_TAX_OFFSET..BOT_TAX_OFFSET + BotTax::SIZE];
+    bot_tax_data.fill(0);
+    bot_tax.serialize(&mut bot_tax_data)?;
+    Ok(())
+}
 
-    // its safe because the char boundaries for the normalized string are all 1 byte. trust me bro
-    unsafe {
-        uuid.as_bytes_mut()[feature_index] = b'0';
+/// Category of the failures `punish_bots` handles.
+pub fn get_bot_tax_failure(error: CandyError) -> BotTaxFailures {
+    match error {
+        CandyError::CandyMachineEmpty => BotTaxFailures::SOLD_OUT,
+        CandyError::CandyMachineNotLive => BotTaxFailures::NOT_LIVE,
+        CandyError::NoWhitelistToken | CandyError::NotCollectionHolder => BotTaxFailures::WHITELIST,
+        CandyError::GatewayProgramError => BotTaxFailures::GATEWAY,
+        CandyError::SuspiciousTransaction
+        | CandyError::MissingSetCollectionDuringMint
+        | CandyError::InvalidMintAuthority => BotTaxFailures::SUSPICIOUS,
+        CandyError::InvalidPaymentOption => BotTaxFailures::PAYMENT,
+        _ => BotTaxFailures::ACCOUNTS,
     }
 }
 
+/// Charges the bot tax for `error`, or fails with it when its category isn't taxed.
 pub fn punish_bots<'a>(
     error: CandyError,
     bot_account: AccountInfo<'a>,
     payment_account: AccountInfo<'a>,
     system_program: AccountInfo<'a>,
-    fee: u64,
+    bot_tax: &BotTax,
 ) -> Result<()> {
+    let taxed_failures = BotTaxFailures::from_bits_truncate(bot_tax.taxed_failures);
+    if !taxed_failures.contains(get_bot_tax_failure(error)) {
+        return Err(error.into());
+    }
+    let fee = bot_tax.lamports;
     msg!(
         "{}, Candy Machine Botting is taxed at {:?} lamports",
         error.to_string(),
@@ -238,6 +383,253 @@ pub fn punish_bots<'a>(
     Ok(())
 }
 
+/// Creates a program owned PDA for `seeds`, failing if `account_info` isn't that PDA. The account is
+/// funded, allocated and assigned separately, so lamports sent to the address beforehand can't block
+/// its creation.
+pub fn create_pda_account<'info>(
+    account_info: &AccountInfo<'info>,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    seeds: &[&[u8]],
+    size: usize,
+    mismatch_error: CandyError,
+) -> Result<()> {
+    let (expected_key, bump) = Pubkey::find_program_address(seeds, &crate::id());
+    if !cmp_pubkeys(&expected_key, account_info.key) {
+        return Err(mismatch_error.into());
+    }
+    let bump_seed = [bump];
+    let mut signer_seeds = seeds.to_vec();
+    signer_seeds.push(&bump_seed);
+    create_or_allocate_account_raw(
+        crate::id(),
+        account_info,
+        system_program,
+        payer,
+        size,
+        &signer_seeds,
+    )?;
+    Ok(())
+}
+
+/// Grows a program owned account to `new_len`, topping up its rent from `payer`.
+pub fn resize_account<'info>(
+    account_info: &AccountInfo<'info>,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    new_len: usize,
+) -> Result<()> {
+    let rent_due = Rent::get()?
+        .minimum_balance(new_len)
+        .saturating_sub(account_info.lamports());
+    if rent_due > 0 {
+        invoke(
+            &system_instruction::transfer(payer.key, account_info.key, rent_due),
+            &[payer.clone(), account_info.clone(), system_program.clone()],
+        )?;
+    }
+    account_info.realloc(new_len, false)?;
+    Ok(())
+}
+
+/// Adds `quantity` to the mint counter PDA derived from `seeds`, creating it with `payer` on the
+/// wallet's first mint, and fails if that takes the wallet past `limit` NFTs.
+#[allow(clippy::too_many_arguments)]
+pub fn increment_mint_counter<'info>(
+    mint_counter_info: &AccountInfo<'info>,
+    candy_machine: &Pubkey,
+    wallet: &Pubkey,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    seeds: &[&[u8]],
+    limit: u16,
+    quantity: u16,
+) -> Result<()> {
+    let mut mint_counter = if mint_counter_info.data_is_empty() {
+        create_pda_account(
+            mint_counter_info,
+            payer,
+            system_program,
+            seeds,
+            MintCounter::SIZE,
+            CandyError::MismatchedMintCounter,
+        )?;
+        MintCounter {
+            candy_machine: *candy_machine,
+            wallet: *wallet,
+            count: 0,
+        }
+    } else {
+        let (expected_key, _) = Pubkey::find_program_address(seeds, &crate::id());
+        if !cmp_pubkeys(&expected_key, mint_counter_info.key) {
+            return err!(CandyError::MismatchedMintCounter);
+        }
+        MintCounter::try_deserialize(&mut mint_counter_info.try_borrow_data()?.as_ref())?
+    };
+
+    mint_counter.count = mint_counter
+        .count
+        .checked_add(quantity)
+        .ok_or(CandyError::NumericalOverflowError)?;
+    if mint_counter.count > limit {
+        return err!(CandyError::MintLimitReached);
+    }
+    mint_counter.try_serialize(&mut *mint_counter_info.try_borrow_mut_data()?)?;
+    Ok(())
+}
+
+/// Checks `wallet` holds an NFT verified in `collection`, returning the held mint.
+pub fn assert_collection_holder(
+    token_account_info: &AccountInfo,
+    metadata_info: &AccountInfo,
+    wallet: &Pubkey,
+    collection: &Pubkey,
+) -> Result<Pubkey> {
+    assert_owned_by(token_account_info, &spl_token::id())?;
+    let token_account: spl_token::state::Account = assert_initialized(token_account_info)?;
+    if !cmp_pubkeys(&token_account.owner, wallet) || token_account.amount < 1 {
+        return err!(CandyError::NotCollectionHolder);
+    }
+
+    assert_owned_by(metadata_info, &mpl_token_metadata::id())?;
+    let metadata = Metadata::from_account_info(metadata_info)?;
+    if !cmp_pubkeys(&metadata.mint, &token_account.mint) {
+        return err!(CandyError::MintMismatch);
+    }
+    match &metadata.collection {
+        Some(c) if c.verified && cmp_pubkeys(&c.key, collection) => Ok(token_account.mint),
+        _ => err!(CandyError::NotCollectionHolder),
+    }
+}
+
+/// Loads a wallet's allowlist proof, failing if it was made for another candy machine,
+/// wallet or root, or its allocation is used up.
+pub fn assert_allowlist_proof(
+    allowlist_proof_info: &AccountInfo,
+    candy_machine: &Pubkey,
+    wallet: &Pubkey,
+    root: &[u8; 32],
+) -> Result<AllowlistProof> {
+    assert_owned_by(allowlist_proof_info, &crate::id())?;
+    let allowlist_proof =
+        AllowlistProof::try_deserialize(&mut allowlist_proof_info.try_borrow_data()?.as_ref())?;
+    if !cmp_pubkeys(&allowlist_proof.candy_machine, candy_machine)
+        || !cmp_pubkeys(&allowlist_proof.wallet, wallet)
+        || allowlist_proof.root != *root
+        || !allowlist_proof.has_allocation_left()
+    {
+        return err!(CandyError::InvalidAllowlistProof);
+    }
+    Ok(allowlist_proof)
+}
+
+/// Pays for a mint with one of the payment options, picked by the mint of the payer's token account.
+///
+/// Token and burn token options take `[token_account, destination or mint]`, burning an NFT takes
+/// `[token_account, mint, metadata, edition, collection_metadata]`.
+pub fn pay_with_payment_option<'info>(
+    payment_options: &PaymentOptions,
+    accounts: &[AccountInfo<'info>],
+    payer: &AccountInfo<'info>,
+    token_program: &AccountInfo<'info>,
+    token_metadata_program: &AccountInfo<'info>,
+) -> Result<()> {
+    let token_account_info = accounts
+        .get(0)
+        .ok_or(CandyError::IncorrectRemainingAccountsLen)?;
+    assert_owned_by(token_account_info, &spl_token::id())?;
+    let token_account: spl_token::state::Account = assert_initialized(token_account_info)?;
+    if !cmp_pubkeys(&token_account.owner, payer.key) {
+        return err!(CandyError::IncorrectOwner);
+    }
+
+    match payment_options.find(&token_account.mint, None) {
+        Some(PaymentOption::Token {
+            price, destination, ..
+        }) => {
+            let destination_info = accounts
+                .get(1)
+                .ok_or(CandyError::IncorrectRemainingAccountsLen)?;
+            assert_keys_equal(destination_info.key, destination)?;
+            if token_account.amount < *price {
+                return err!(CandyError::NotEnoughTokens);
+            }
+            spl_token_transfer(TokenTransferParams {
+                source: token_account_info.clone(),
+                destination: destination_info.clone(),
+                authority: payer.clone(),
+                authority_signer_seeds: &[],
+                token_program: token_program.clone(),
+                amount: *price,
+            })
+        }
+        Some(PaymentOption::BurnToken { amount, .. }) => {
+            let mint_info = accounts
+                .get(1)
+                .ok_or(CandyError::IncorrectRemainingAccountsLen)?;
+            assert_keys_equal(mint_info.key, &token_account.mint)?;
+            if token_account.amount < *amount {
+                return err!(CandyError::NotEnoughTokens);
+            }
+            spl_token_burn(TokenBurnParams {
+                mint: mint_info.clone(),
+                source: token_account_info.clone(),
+                amount: *amount,
+                authority: payer.clone(),
+                authority_signer_seeds: None,
+                token_program: token_program.clone(),
+            })
+        }
+        _ => {
+            if accounts.len() < 5 {
+                return err!(CandyError::IncorrectRemainingAccountsLen);
+            }
+            let mint_info = &accounts[1];
+            let metadata_info = &accounts[2];
+            let edition_info = &accounts[3];
+            let collection_metadata_info = &accounts[4];
+            assert_keys_equal(mint_info.key, &token_account.mint)?;
+            assert_owned_by(metadata_info, &mpl_token_metadata::id())?;
+            let metadata = Metadata::from_account_info(metadata_info)?;
+            if !cmp_pubkeys(&metadata.mint, mint_info.key) {
+                return err!(CandyError::MintMismatch);
+            }
+            let collection = match &metadata.collection {
+                Some(collection) if collection.verified => collection.key,
+                _ => return err!(CandyError::InvalidPaymentOption),
+            };
+            match payment_options.find(&token_account.mint, Some(&collection)) {
+                Some(PaymentOption::BurnNft { .. }) => (),
+                _ => return err!(CandyError::InvalidPaymentOption),
+            }
+
+            invoke(
+                &burn_nft(
+                    mpl_token_metadata::id(),
+                    *metadata_info.key,
+                    *payer.key,
+                    *mint_info.key,
+                    *token_account_info.key,
+                    *edition_info.key,
+                    spl_token::id(),
+                    Some(*collection_metadata_info.key),
+                ),
+                &[
+                    metadata_info.clone(),
+                    payer.clone(),
+                    mint_info.clone(),
+                    token_account_info.clone(),
+                    edition_info.clone(),
+                    token_program.clone(),
+                    collection_metadata_info.clone(),
+                    token_metadata_program.clone(),
+                ],
+            )?;
+            Ok(())
+        }
+    }
+}
+
 pub fn assert_master_edition(
     collection_data: &Metadata,
     edition_account_info: &AccountInfo,
@@ -262,53 +654,138 @@ pub fn assert_master_edition(
 
 #[cfg(test)]
 pub mod tests {
-    use std::{assert_eq, println};
+    use std::assert_eq;
 
-    use crate::constants::COLLECTIONS_FEATURE_INDEX;
+    use mpl_token_metadata::state::{
+        MAX_CREATOR_LIMIT, MAX_NAME_LENGTH, MAX_SYMBOL_LENGTH, MAX_URI_LENGTH,
+    };
 
     use super::*;
+    use crate::{
+        Creator, EndSettingType, EndSettings, GatekeeperConfig, HiddenSettings, WhitelistMintMode,
+        WhitelistMintSettings,
+    };
+
+    #[test]
+    fn legacy_features_decoded() {
+        assert_eq!(get_legacy_features("ABCDEF"), CandyFeatures::empty());
+        assert_eq!(get_legacy_features("01H333"), CandyFeatures::empty());
+        assert_eq!(get_legacy_features("0#H333"), CandyFeatures::FREEZE);
+        assert_eq!(
+            get_legacy_features("0##333"),
+            CandyFeatures::FREEZE | CandyFeatures::FREEZE_LOCK
+        );
+        assert_eq!(
+            get_legacy_features("######"),
+            CandyFeatures::all() - CandyFeatures::MINT_SETTINGS
+        );
+        assert_eq!(get_legacy_features("#"), CandyFeatures::COLLECTIONS);
+
+        assert!(!get_legacy_features("1ABCDE").contains(CandyFeatures::COLLECTIONS));
+        assert!(get_legacy_features("100000").contains(CandyFeatures::COLLECTIONS));
+        assert!(get_legacy_features("1##000").contains(CandyFeatures::COLLECTIONS));
+    }
 
     #[test]
-    fn feature_flag_working() {
-        let mut uuid = String::from("ABCDEF");
-        println!(
-            "Should be 65: {}",
-            uuid.as_bytes()[COLLECTIONS_FEATURE_INDEX]
+    fn candy_machine_features() {
+        let mut candy_machine = CandyMachine::default();
+        candy_machine.data.uuid = "0#0000".to_string();
+        candy_machine.version = CANDY_MACHINE_VERSION;
+        candy_machine.features = CandyFeatures::MINT_LIMIT.bits();
+        let mut data = vec![0; CONFIG_ARRAY_START];
+        assert_eq!(get_candy_machine_version(&candy_machine, &data), 0);
+        assert_eq!(
+            get_candy_machine_features(&candy_machine, &data),
+            CandyFeatures::FREEZE
+        );
+        data[LEGACY_MINT_SETTINGS_OFFSET] = 1;
+        assert_eq!(
+            get_candy_machine_features(&candy_machine, &data),
+            CandyFeatures::FREEZE | CandyFeatures::MINT_SETTINGS
+        );
+
+        data[VERSION_OFFSET] = CANDY_MACHINE_VERSION;
+        assert_eq!(
+            get_candy_machine_version(&candy_machine, &data),
+            CANDY_MACHINE_VERSION
+        );
+        assert_eq!(
+            get_candy_machine_features(&candy_machine, &data),
+            CandyFeatures::MINT_LIMIT
         );
-        remove_feature_flag(&mut uuid, COLLECTIONS_FEATURE_INDEX);
-        assert_eq!(uuid, "0BCDEF");
-
-        uuid = String::from("01H333");
-        assert!(!is_feature_active(&uuid, FREEZE_FEATURE_INDEX));
-        assert_eq!(uuid, "01H333");
-        set_feature_flag(&mut uuid, FREEZE_FEATURE_INDEX);
-        assert_eq!(uuid, "0#H333");
-        assert!(is_feature_active(&uuid, FREEZE_FEATURE_INDEX));
-
-        remove_feature_flag(&mut uuid, FREEZE_FEATURE_INDEX);
-        assert!(!is_feature_active(&uuid, FREEZE_FEATURE_INDEX));
-        println!("Should be 00H333: {}", uuid);
-
-        set_feature_flag(&mut uuid, FREEZE_LOCK_FEATURE_INDEX);
-        assert!(is_feature_active(&uuid, FREEZE_LOCK_FEATURE_INDEX));
-        assert_eq!(uuid, "00#333");
-
-        remove_feature_flag(&mut uuid, COLLECTIONS_FEATURE_INDEX);
-        assert_eq!(uuid, "00#333");
-        set_feature_flag(&mut uuid, FREEZE_FEATURE_INDEX);
-        assert!(is_feature_active(&uuid, FREEZE_FEATURE_INDEX));
-        assert_eq!(uuid, "0##333");
-        set_feature_flag(&mut uuid, COLLECTIONS_FEATURE_INDEX);
-        assert!(is_feature_active(&uuid, COLLECTIONS_FEATURE_INDEX));
-        assert_eq!(uuid, "###333");
-
-        uuid = String::from("1ABCDE");
-        assert!(!is_feature_active(&uuid, COLLECTIONS_FEATURE_INDEX));
-        uuid = String::from("100000");
-        assert!(is_feature_active(&uuid, COLLECTIONS_FEATURE_INDEX));
-
-        uuid = String::from("1##000");
-        assert!(is_feature_active(&uuid, COLLECTIONS_FEATURE_INDEX));
+    }
+
+    #[test]
+    fn largest_candy_machine_fits_before_version() {
+        let mut candy_machine = CandyMachine {
+            token_mint: Some(Pubkey::default()),
+            ..CandyMachine::default()
+        };
+        let data = &mut candy_machine.data;
+        data.uuid = "000000".to_string();
+        data.symbol = "\0".repeat(MAX_SYMBOL_LENGTH);
+        data.go_live_date = Some(0);
+        data.end_settings = Some(EndSettings {
+            end_setting_type: EndSettingType::Date,
+            number: 0,
+        });
+        data.creators = vec![
+            Creator {
+                address: Pubkey::default(),
+                verified: false,
+                share: 25,
+            };
+            MAX_CREATOR_LIMIT - 1
+        ];
+        data.hidden_settings = Some(HiddenSettings {
+            name: "a".repeat(MAX_NAME_LENGTH),
+            uri: "a".repeat(MAX_URI_LENGTH),
+            hash: [0; 32],
+        });
+        data.whitelist_mint_settings = Some(WhitelistMintSettings {
+            mode: WhitelistMintMode::BurnEveryTime,
+            mint: Pubkey::default(),
+            presale: false,
+            discount_price: Some(0),
+        });
+        data.gatekeeper = Some(GatekeeperConfig {
+            gatekeeper_network: Pubkey::default(),
+            expire_on_use: false,
+        });
+        assert_candy_machine_fits(&candy_machine).unwrap();
+
+        candy_machine
+            .data
+            .creators
+            .push(candy_machine.data.creators[0].clone());
+        assert!(assert_candy_machine_fits(&candy_machine).is_err());
+    }
+
+    #[test]
+    fn legacy_features_cleared() {
+        assert_eq!(clear_legacy_features("0##333"), "000333");
+        assert_eq!(clear_legacy_features("1##000"), "000000");
+        assert_eq!(clear_legacy_features("1ABCDE"), "1ABCDE");
+        assert_eq!(clear_legacy_features("######"), "000000");
+    }
+
+    #[test]
+    fn bot_tax_fits_reserved_space() {
+        let bot_tax = Some(BotTax::default()).try_to_vec().unwrap();
+        assert_eq!(bot_tax.len(), BotTax::SIZE);
+    }
+
+    #[test]
+    fn slot_hash_lookup() {
+        let mut data = 3u64.to_le_bytes().to_vec();
+        for slot in [9u64, 7, 4] {
+            data.extend_from_slice(&slot.to_le_bytes());
+            data.extend_from_slice(&[slot as u8; 32]);
+        }
+        assert_eq!(get_slot_hash(&data, 7), Some([7; 32]));
+        assert_eq!(get_slot_hash(&data, 4), Some([4; 32]));
+        assert_eq!(get_slot_hash(&data, 8), None);
+        assert_eq!(get_slot_hash(&data, 3), None);
     }
 
     #[test]
@@ -0,0 +1,157 @@
+#![cfg(feature = "test-bpf")]
+#![allow(dead_code)]
+
+use anchor_client::solana_sdk::transaction::Transaction;
+use mpl_candy_machine::{
+    constants::BOT_FEE, AllowlistProof, WhitelistMintMode, WhitelistMintSettings,
+};
+use solana_program::{
+    instruction::{AccountMeta, InstructionError},
+    keccak,
+    pubkey::Pubkey,
+};
+use solana_program_test::*;
+use solana_sdk::{signature::Keypair, signer::Signer, transaction::TransactionError};
+
+use crate::{
+    core::helpers::{airdrop, get_balance, prepare_nft, update_blockhash},
+    utils::{
+        auto_config, candy_machine_program_test,
+        helpers::{find_allowlist_proof, find_allowlist_root, find_candy_creator, sol, test_start},
+        mint_nft_ix, prove_allowlist, set_allowlist_root, CandyManager,
+    },
+};
+
+pub mod core;
+pub mod utils;
+
+async fn mint_with_proof(
+    context: &mut ProgramTestContext,
+    candy_manager: &CandyManager,
+    root: &[u8; 32],
+) -> Result<(), BanksClientError> {
+    let candy_machine = candy_manager.candy_machine.pubkey();
+    let (candy_machine_creator, creator_bump) = find_candy_creator(&candy_machine);
+    let nft_info = prepare_nft(context, &candy_manager.minter).await;
+    let mut ix = mint_nft_ix(
+        &candy_machine,
+        &candy_machine_creator,
+        creator_bump,
+        &candy_manager.wallet,
+        &candy_manager.authority.pubkey(),
+        &candy_manager.minter,
+        &nft_info,
+        candy_manager.token_info.clone(),
+        candy_manager.whitelist_info.clone(),
+        candy_manager.collection_info.clone(),
+        candy_manager.gateway_info.clone(),
+        candy_manager.freeze_info.clone(),
+    );
+    // The merkle tree whitelist takes the proof and the root in place of the whitelist token
+    ix[0].accounts.push(AccountMeta::new(
+        find_allowlist_proof(&candy_machine, &candy_manager.minter.pubkey(), root).0,
+        false,
+    ));
+    ix[0].accounts.push(AccountMeta::new_readonly(
+        find_allowlist_root(&candy_machine).0,
+        false,
+    ));
+
+    update_blockhash(context).await?;
+    let tx = Transaction::new_signed_with_payer(
+        ix.as_slice(),
+        Some(&candy_manager.minter.pubkey()),
+        &[&candy_manager.minter],
+        context.last_blockhash,
+    );
+    context.banks_client.process_transaction(tx).await
+}
+
+#[tokio::test]
+async fn mint_with_allowlist_proof() {
+    test_start("Mint With Allowlist Proof");
+    let mut context = candy_machine_program_test().start_with_context().await;
+    let context = &mut context;
+    let mut candy_manager = CandyManager::init(context, None, false, None, None, None).await;
+
+    airdrop(context, &candy_manager.minter.pubkey(), sol(10.0))
+        .await
+        .unwrap();
+
+    // Only wallets in the allowlist can mint
+    let mut candy_data = auto_config(&candy_manager, Some(0), true, true, None, None);
+    candy_data.whitelist_mint_settings = Some(WhitelistMintSettings {
+        mode: WhitelistMintMode::MerkleTree,
+        mint: Pubkey::default(),
+        presale: false,
+        discount_price: None,
+    });
+    candy_manager
+        .create(context, candy_data.clone())
+        .await
+        .unwrap();
+    candy_manager.fill_config_lines(context).await.unwrap();
+    let candy_machine = candy_manager.candy_machine.pubkey();
+
+    // Tree of the minter allowed a single mint and another wallet, as gumdrop would build it
+    let other_wallet = Keypair::new().pubkey();
+    let leaf = AllowlistProof::leaf(0, &candy_manager.minter.pubkey(), &candy_machine, 1);
+    let other_leaf = AllowlistProof::leaf(1, &other_wallet, &candy_machine, 1);
+    let root = if leaf <= other_leaf {
+        keccak::hashv(&[&[0x01], &leaf, &other_leaf]).0
+    } else {
+        keccak::hashv(&[&[0x01], &other_leaf, &leaf]).0
+    };
+    set_allowlist_root(context, &candy_machine, &candy_manager.authority, root)
+        .await
+        .unwrap();
+
+    // The allocation is part of the leaf
+    let err = match prove_allowlist(
+        context,
+        &candy_machine,
+        &candy_manager.minter,
+        &root,
+        vec![other_leaf],
+        0,
+        2,
+    )
+    .await
+    .unwrap_err()
+    {
+        BanksClientError::TransactionError(TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(err_num),
+        )) => err_num,
+        _ => 0,
+    };
+    assert_eq!(err, 6061);
+
+    prove_allowlist(
+        context,
+        &candy_machine,
+        &candy_manager.minter,
+        &root,
+        vec![other_leaf],
+        0,
+        1,
+    )
+    .await
+    .unwrap();
+
+    mint_with_proof(context, &candy_manager, &root)
+        .await
+        .unwrap();
+    assert_eq!(candy_manager.get_candy(context).await.items_redeemed, 1);
+
+    // With its allocation used up the minter is taxed like a wallet without a whitelist token
+    let candy_balance = get_balance(context, &candy_machine).await;
+    mint_with_proof(context, &candy_manager, &root)
+        .await
+        .unwrap();
+    assert_eq!(candy_manager.get_candy(context).await.items_redeemed, 1);
+    assert_eq!(
+        get_balance(context, &candy_machine).await,
+        candy_balance + BOT_FEE
+    );
+}
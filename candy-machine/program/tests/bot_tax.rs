@@ -0,0 +1,247 @@
+#![cfg(feature = "test-bpf")]
+#![allow(dead_code)]
+
+use anchor_client::solana_sdk::transaction::Transaction;
+use mpl_candy_machine::{constants::BOT_FEE, BotTax, BotTaxDestination, BotTaxFailures};
+use solana_program::instruction::{AccountMeta, InstructionError};
+use solana_program_test::*;
+use solana_sdk::{signature::Keypair, signer::Signer, transaction::TransactionError};
+
+use crate::{
+    core::helpers::{airdrop, get_balance, prepare_nft, update_blockhash},
+    utils::{
+        auto_config, candy_machine_program_test,
+        helpers::{find_candy_creator, sol, test_start},
+        mint_nft_ix, update_bot_tax, CandyManager,
+    },
+};
+
+pub mod core;
+pub mod utils;
+
+async fn mint(
+    context: &mut ProgramTestContext,
+    candy_manager: &CandyManager,
+) -> Result<(), BanksClientError> {
+    mint_with_accounts(context, candy_manager, vec![]).await
+}
+
+async fn mint_with_accounts(
+    context: &mut ProgramTestContext,
+    candy_manager: &CandyManager,
+    extra_accounts: Vec<AccountMeta>,
+) -> Result<(), BanksClientError> {
+    let candy_machine = candy_manager.candy_machine.pubkey();
+    let (candy_machine_creator, creator_bump) = find_candy_creator(&candy_machine);
+    let nft_info = prepare_nft(context, &candy_manager.minter).await;
+    let mut ix = mint_nft_ix(
+        &candy_machine,
+        &candy_machine_creator,
+        creator_bump,
+        &candy_manager.wallet,
+        &candy_manager.authority.pubkey(),
+        &candy_manager.minter,
+        &nft_info,
+        candy_manager.token_info.clone(),
+        candy_manager.whitelist_info.clone(),
+        candy_manager.collection_info.clone(),
+        candy_manager.gateway_info.clone(),
+        candy_manager.freeze_info.clone(),
+    );
+    ix[0].accounts.extend(extra_accounts);
+
+    update_blockhash(context).await?;
+    let tx = Transaction::new_signed_with_payer(
+        ix.as_slice(),
+        Some(&candy_manager.minter.pubkey()),
+        &[&candy_manager.minter],
+        context.last_blockhash,
+    );
+    context.banks_client.process_transaction(tx).await
+}
+
+#[tokio::test]
+async fn configure_bot_tax() {
+    test_start("Configure Bot Tax");
+    let mut context = candy_machine_program_test().start_with_context().await;
+    let context = &mut context;
+    let mut candy_manager = CandyManager::init(context, None, false, None, None, None).await;
+
+    airdrop(context, &candy_manager.minter.pubkey(), sol(10.0))
+        .await
+        .unwrap();
+
+    // Not live yet, so every mint from the minter is a failure
+    let candy_data = auto_config(&candy_manager, Some(i64::MAX), true, true, None, None);
+    candy_manager
+        .create(context, candy_data.clone())
+        .await
+        .unwrap();
+    candy_manager.fill_config_lines(context).await.unwrap();
+    let candy_machine = candy_manager.candy_machine.pubkey();
+    let wallet = candy_manager.wallet;
+
+    let candy_balance = get_balance(context, &candy_machine).await;
+    mint(context, &candy_manager).await.unwrap();
+    assert_eq!(
+        get_balance(context, &candy_machine).await,
+        candy_balance + BOT_FEE
+    );
+
+    // Not live mints fail outright instead of being taxed
+    let bot_tax = BotTax {
+        lamports: sol(0.001),
+        destination: BotTaxDestination::Wallet,
+        taxed_failures: (BotTaxFailures::all() - BotTaxFailures::NOT_LIVE).bits(),
+    };
+    update_bot_tax(
+        context,
+        &candy_machine,
+        &candy_manager.authority,
+        &wallet,
+        Some(bot_tax.clone()),
+    )
+    .await
+    .unwrap();
+    let err = match mint(context, &candy_manager).await.unwrap_err() {
+        BanksClientError::TransactionError(TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(err_num),
+        )) => err_num,
+        _ => 0,
+    };
+    assert_eq!(err, 6010);
+
+    // Taxed mints pay the configured tax to the wallet
+    update_bot_tax(
+        context,
+        &candy_machine,
+        &candy_manager.authority,
+        &wallet,
+        Some(BotTax {
+            taxed_failures: BotTaxFailures::all().bits(),
+            ..bot_tax
+        }),
+    )
+    .await
+    .unwrap();
+    let wallet_balance = get_balance(context, &wallet).await;
+    mint(context, &candy_manager).await.unwrap();
+    assert_eq!(
+        get_balance(context, &wallet).await,
+        wallet_balance + sol(0.001)
+    );
+
+    // Clearing the bot tax goes back to the default one
+    update_bot_tax(
+        context,
+        &candy_machine,
+        &candy_manager.authority,
+        &wallet,
+        None,
+    )
+    .await
+    .unwrap();
+    let candy_balance = get_balance(context, &candy_machine).await;
+    mint(context, &candy_manager).await.unwrap();
+    assert_eq!(
+        get_balance(context, &candy_machine).await,
+        candy_balance + BOT_FEE
+    );
+    assert_eq!(candy_manager.get_candy(context).await.items_redeemed, 0);
+}
+
+#[tokio::test]
+async fn bot_tax_to_address() {
+    test_start("Bot Tax To Address");
+    let mut context = candy_machine_program_test().start_with_context().await;
+    let context = &mut context;
+    let mut candy_manager = CandyManager::init(context, None, false, None, None, None).await;
+
+    airdrop(context, &candy_manager.minter.pubkey(), sol(10.0))
+        .await
+        .unwrap();
+
+    let candy_data = auto_config(&candy_manager, Some(i64::MAX), true, true, None, None);
+    candy_manager
+        .create(context, candy_data.clone())
+        .await
+        .unwrap();
+    candy_manager.fill_config_lines(context).await.unwrap();
+    let candy_machine = candy_manager.candy_machine.pubkey();
+    let destination = Keypair::new().pubkey();
+    airdrop(context, &destination, sol(1.0)).await.unwrap();
+
+    update_bot_tax(
+        context,
+        &candy_machine,
+        &candy_manager.authority,
+        &candy_manager.wallet,
+        Some(BotTax {
+            destination: BotTaxDestination::Address(destination),
+            ..BotTax::default()
+        }),
+    )
+    .await
+    .unwrap();
+
+    // The destination has to be passed, or the mint fails untaxed
+    let err = match mint(context, &candy_manager).await.unwrap_err() {
+        BanksClientError::TransactionError(TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(err_num),
+        )) => err_num,
+        _ => 0,
+    };
+    assert_eq!(err, 6115);
+
+    let destination_balance = get_balance(context, &destination).await;
+    mint_with_accounts(
+        context,
+        &candy_manager,
+        vec![AccountMeta::new(destination, false)],
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        get_balance(context, &destination).await,
+        destination_balance + BOT_FEE
+    );
+    assert_eq!(candy_manager.get_candy(context).await.items_redeemed, 0);
+}
+
+#[tokio::test]
+async fn no_wallet_bot_tax_with_token() {
+    test_start("No Wallet Bot Tax With Token");
+    let mut context = candy_machine_program_test().start_with_context().await;
+    let context = &mut context;
+    let mut candy_manager = CandyManager::init(context, None, true, None, None, None).await;
+
+    let candy_data = auto_config(&candy_manager, Some(0), true, true, None, None);
+    candy_manager
+        .create(context, candy_data.clone())
+        .await
+        .unwrap();
+
+    // The wallet of a candy machine paid in tokens is a token account
+    let err = match update_bot_tax(
+        context,
+        &candy_manager.candy_machine.pubkey(),
+        &candy_manager.authority,
+        &candy_manager.wallet,
+        Some(BotTax {
+            destination: BotTaxDestination::Wallet,
+            ..BotTax::default()
+        }),
+    )
+    .await
+    .unwrap_err()
+    {
+        BanksClientError::TransactionError(TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(err_num),
+        )) => err_num,
+        _ => 0,
+    };
+    assert_eq!(err, 6116);
+}
@@ -0,0 +1,274 @@
+#![cfg(feature = "test-bpf")]
+#![allow(dead_code)]
+
+use anchor_lang::{AccountDeserialize, AccountSerialize};
+use mpl_candy_machine::{
+    constants::{CONFIG_ARRAY_START, LEGACY_CONFIG_ARRAY_START, VERSION_OFFSET},
+    get_candy_machine_features, get_candy_machine_version, get_config_indices_start, CandyFeatures,
+    CandyMachine,
+};
+use solana_program::instruction::InstructionError;
+use solana_program_test::*;
+use solana_sdk::{account::AccountSharedData, signer::Signer, transaction::TransactionError};
+
+use crate::{
+    core::{
+        helpers::{airdrop, get_account, get_balance},
+        MetadataManager,
+    },
+    utils::{
+        auto_config, candy_machine_program_test, close_config_data,
+        helpers::{sol, test_start},
+        migrate_candy_machine_version, migrate_config_indices, update_sequential, CandyManager,
+    },
+};
+
+pub mod core;
+pub mod utils;
+
+async fn setup_candy(
+    context: &mut ProgramTestContext,
+    items_available: u64,
+    is_sequential: bool,
+) -> CandyManager {
+    let mut candy_manager = CandyManager::init(context, None, false, None, None, None).await;
+    airdrop(context, &candy_manager.minter.pubkey(), sol(10.0))
+        .await
+        .unwrap();
+
+    let mut candy_data = auto_config(&candy_manager, Some(0), true, true, None, None);
+    candy_data.items_available = items_available;
+    candy_manager
+        .create(context, candy_data.clone())
+        .await
+        .unwrap();
+    if is_sequential {
+        update_sequential(
+            context,
+            &candy_manager.candy_machine.pubkey(),
+            &candy_manager.authority,
+            &candy_manager.wallet,
+            true,
+        )
+        .await
+        .unwrap();
+    }
+    candy_manager.fill_config_lines(context).await.unwrap();
+    candy_manager
+}
+
+async fn mint_names(
+    context: &mut ProgramTestContext,
+    candy_manager: &mut CandyManager,
+    count: usize,
+) -> Vec<String> {
+    let mut names = Vec::with_capacity(count);
+    for _ in 0..count {
+        let nft_info = candy_manager.mint_nft(context).await.unwrap();
+        let metadata =
+            MetadataManager::get_data_from_account(context, &nft_info.metadata_pubkey).await;
+        names.push(
+            metadata
+                .data
+                .name
+                .trim_end_matches(char::from(0))
+                .to_string(),
+        );
+    }
+    names.sort();
+    names
+}
+
+#[tokio::test]
+async fn mint_every_line_once() {
+    test_start("Mint Every Line Once");
+    let mut context = candy_machine_program_test().start_with_context().await;
+    let context = &mut context;
+    let mut candy_manager = setup_candy(context, 5, false).await;
+
+    let mut names = mint_names(context, &mut candy_manager, 5).await;
+    names.sort();
+    let expected: Vec<String> = (0..5).map(|i| format!("Item #{}", i)).collect();
+    assert_eq!(names, expected);
+}
+
+#[tokio::test]
+async fn migrate_legacy_bitmask() {
+    test_start("Migrate Legacy Bitmask");
+    let mut context = candy_machine_program_test().start_with_context().await;
+    let context = &mut context;
+    let mut candy_manager = setup_candy(context, 5, false).await;
+    let candy_machine = candy_manager.candy_machine.pubkey();
+
+    // Rewrite the account in the legacy layout with the first two lines already minted
+    let mut account = get_account(context, &candy_machine).await;
+    let indices_start = get_config_indices_start(CONFIG_ARRAY_START, 5, None).unwrap();
+    // the legacy taken-lines u32 header and bitmask overlap the index array header
+    account.data.truncate(indices_start + 3 + (5 / 8 + 1) + 1);
+    for byte in account.data[indices_start..].iter_mut() {
+        *byte = 0;
+    }
+    account.data[indices_start + 3] = 0b1100_0000;
+    context.set_account(&candy_machine, &AccountSharedData::from(account));
+
+    migrate_config_indices(context, &candy_machine, &candy_manager.authority)
+        .await
+        .unwrap();
+    let err = match migrate_config_indices(context, &candy_machine, &candy_manager.authority)
+        .await
+        .unwrap_err()
+    {
+        BanksClientError::TransactionError(TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(err_num),
+        )) => err_num,
+        _ => 0,
+    };
+    assert_eq!(err, 6075);
+
+    // Only the lines left in the legacy bitmask can be minted
+    let mut names = mint_names(context, &mut candy_manager, 3).await;
+    names.sort();
+    let expected: Vec<String> = (2..5).map(|i| format!("Item #{}", i)).collect();
+    assert_eq!(names, expected);
+}
+
+#[tokio::test]
+async fn migrate_legacy_feature_flags() {
+    test_start("Migrate Legacy Feature Flags");
+    let mut context = candy_machine_program_test().start_with_context().await;
+    let context = &mut context;
+    let mut candy_manager = setup_candy(context, 5, false).await;
+    let candy_machine = candy_manager.candy_machine.pubkey();
+
+    // Rewrite the account in the legacy layout, flagging the freeze lock in the uuid
+    let mut account = get_account(context, &candy_machine).await;
+    let mut candy = CandyMachine::try_deserialize(&mut account.data.as_ref()).unwrap();
+    candy.data.uuid = "00#000".to_string();
+    candy.version = 0;
+    candy.features = 0;
+    candy
+        .try_serialize(&mut account.data.as_mut_slice())
+        .unwrap();
+    account.data[VERSION_OFFSET] = 0;
+    account
+        .data
+        .drain(LEGACY_CONFIG_ARRAY_START..CONFIG_ARRAY_START);
+    let indices_start = get_config_indices_start(LEGACY_CONFIG_ARRAY_START, 5, None).unwrap();
+    account.data.truncate(indices_start + 3 + (5 / 8 + 1) + 1);
+    for byte in account.data[indices_start..].iter_mut() {
+        *byte = 0;
+    }
+    context.set_account(&candy_machine, &AccountSharedData::from(account));
+
+    // Legacy candy machines keep minting from their original layout
+    let mut names = mint_names(context, &mut candy_manager, 1).await;
+
+    migrate_config_indices(context, &candy_machine, &candy_manager.authority)
+        .await
+        .unwrap_err();
+    migrate_candy_machine_version(context, &candy_machine, &candy_manager.authority)
+        .await
+        .unwrap();
+
+    let account = get_account(context, &candy_machine).await;
+    let candy = CandyMachine::try_deserialize(&mut account.data.as_ref()).unwrap();
+    assert_eq!(candy.data.uuid, "000000");
+    assert_eq!(candy.version, 1);
+    assert_eq!(get_candy_machine_version(&candy, &account.data), 1);
+    assert_eq!(candy.features, CandyFeatures::FREEZE_LOCK.bits());
+    assert_eq!(
+        get_candy_machine_features(&candy, &account.data),
+        CandyFeatures::FREEZE_LOCK
+    );
+
+    let err = match migrate_candy_machine_version(context, &candy_machine, &candy_manager.authority)
+        .await
+        .unwrap_err()
+    {
+        BanksClientError::TransactionError(TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(err_num),
+        )) => err_num,
+        _ => 0,
+    };
+    assert_eq!(err, 6088);
+
+    migrate_config_indices(context, &candy_machine, &candy_manager.authority)
+        .await
+        .unwrap();
+    names.append(&mut mint_names(context, &mut candy_manager, 4).await);
+    names.sort();
+    let expected: Vec<String> = (0..5).map(|i| format!("Item #{}", i)).collect();
+    assert_eq!(names, expected);
+}
+
+#[tokio::test]
+async fn mint_in_sequence() {
+    test_start("Mint In Sequence");
+    let mut context = candy_machine_program_test().start_with_context().await;
+    let context = &mut context;
+    let mut candy_manager = setup_candy(context, 5, true).await;
+
+    let names = mint_names(context, &mut candy_manager, 5).await;
+    let expected: Vec<String> = (0..5).map(|i| format!("Item #{}", i)).collect();
+    assert_eq!(names, expected);
+
+    // The order is fixed once the first line has been minted
+    let err = match update_sequential(
+        context,
+        &candy_manager.candy_machine.pubkey(),
+        &candy_manager.authority,
+        &candy_manager.wallet,
+        false,
+    )
+    .await
+    .unwrap_err()
+    {
+        BanksClientError::TransactionError(TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(err_num),
+        )) => err_num,
+        _ => 0,
+    };
+    assert_eq!(err, 6079);
+}
+
+#[tokio::test]
+async fn close_config_data_after_sell_out() {
+    test_start("Close Config Data After Sell Out");
+    let mut context = candy_machine_program_test().start_with_context().await;
+    let context = &mut context;
+    let mut candy_manager = setup_candy(context, 3, false).await;
+    let candy_machine = candy_manager.candy_machine.pubkey();
+
+    mint_names(context, &mut candy_manager, 2).await;
+    let err = match close_config_data(context, &candy_machine, &candy_manager.authority)
+        .await
+        .unwrap_err()
+    {
+        BanksClientError::TransactionError(TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(err_num),
+        )) => err_num,
+        _ => 0,
+    };
+    assert_eq!(err, 6100);
+
+    mint_names(context, &mut candy_manager, 1).await;
+    let candy_balance = get_balance(context, &candy_machine).await;
+    close_config_data(context, &candy_machine, &candy_manager.authority)
+        .await
+        .unwrap();
+
+    // Only the header is left, with the mint stats still readable
+    let account = get_account(context, &candy_machine).await;
+    assert_eq!(account.data.len(), CONFIG_ARRAY_START);
+    assert!(account.lamports < candy_balance);
+    let candy = CandyMachine::try_deserialize(&mut account.data.as_ref()).unwrap();
+    assert_eq!(candy.items_redeemed, 3);
+
+    close_config_data(context, &candy_machine, &candy_manager.authority)
+        .await
+        .unwrap_err();
+}
@@ -0,0 +1,117 @@
+#![cfg(feature = "test-bpf")]
+#![allow(dead_code)]
+
+use mpl_candy_machine::{ConfigLine, ConfigLineSettings};
+use solana_program::instruction::InstructionError;
+use solana_program_test::*;
+use solana_sdk::{signer::Signer, transaction::TransactionError};
+
+use crate::{
+    core::{helpers::airdrop, MetadataManager},
+    utils::{
+        add_config_lines, auto_config, candy_machine_program_test,
+        helpers::{sol, test_start},
+        CandyManager,
+    },
+};
+
+pub mod core;
+pub mod utils;
+
+fn config_line_settings() -> ConfigLineSettings {
+    ConfigLineSettings {
+        prefix_name: "Item #".to_string(),
+        name_length: 2,
+        prefix_uri: "https://arweave.net/".to_string(),
+        uri_length: 8,
+    }
+}
+
+fn compressed_config_lines(total: u8) -> Vec<ConfigLine> {
+    (0..total)
+        .map(|i| ConfigLine {
+            name: i.to_string(),
+            uri: format!("uri-{}", i),
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn mint_compressed_config_lines() {
+    test_start("Mint Compressed Config Lines");
+    let mut context = candy_machine_program_test().start_with_context().await;
+    let context = &mut context;
+    let mut candy_manager = CandyManager::init(context, None, false, None, None, None).await;
+    airdrop(context, &candy_manager.minter.pubkey(), sol(10.0))
+        .await
+        .unwrap();
+
+    let mut candy_data = auto_config(&candy_manager, Some(0), true, true, None, None);
+    candy_data.items_available = 3;
+    candy_manager
+        .create_compressed(context, candy_data, config_line_settings())
+        .await
+        .unwrap();
+    let candy_machine = candy_manager.candy_machine.pubkey();
+
+    // suffixes longer than the settings allow are rejected
+    let err = match add_config_lines(
+        context,
+        &candy_machine,
+        &candy_manager.authority,
+        0,
+        vec![ConfigLine {
+            name: "100".to_string(),
+            uri: "uri-100".to_string(),
+        }],
+    )
+    .await
+    .unwrap_err()
+    {
+        BanksClientError::TransactionError(TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(err_num),
+        )) => err_num,
+        _ => 0,
+    };
+    assert_eq!(err, 6077);
+
+    add_config_lines(
+        context,
+        &candy_machine,
+        &candy_manager.authority,
+        0,
+        compressed_config_lines(3),
+    )
+    .await
+    .unwrap();
+
+    let mut minted = Vec::with_capacity(3);
+    for _ in 0..3 {
+        let nft_info = candy_manager.mint_nft(context).await.unwrap();
+        let metadata =
+            MetadataManager::get_data_from_account(context, &nft_info.metadata_pubkey).await;
+        minted.push((
+            metadata
+                .data
+                .name
+                .trim_end_matches(char::from(0))
+                .to_string(),
+            metadata
+                .data
+                .uri
+                .trim_end_matches(char::from(0))
+                .to_string(),
+        ));
+    }
+    minted.sort();
+    let expected: Vec<(String, String)> = (0..3)
+        .map(|i| {
+            (
+                format!("Item #{}", i),
+                format!("https://arweave.net/uri-{}", i),
+            )
+        })
+        .collect();
+    assert_eq!(minted, expected);
+}
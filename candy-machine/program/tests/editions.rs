@@ -0,0 +1,286 @@
+#![cfg(feature = "test-bpf")]
+#![allow(dead_code)]
+
+use anchor_client::solana_sdk::transaction::Transaction;
+use mpl_token_metadata::state::{EDITION, EDITION_MARKER_BIT_SIZE, PREFIX as METADATA_PREFIX};
+use solana_program::{
+    instruction::{AccountMeta, InstructionError},
+    pubkey::Pubkey,
+};
+use solana_program_test::*;
+use solana_sdk::{signer::Signer, transaction::TransactionError};
+
+use crate::{
+    core::{
+        helpers::{
+            airdrop, create_associated_token_account, get_token_balance, prepare_nft,
+            update_blockhash,
+        },
+        MasterEditionManager, MetadataManager,
+    },
+    utils::{
+        auto_config, candy_machine_program_test,
+        helpers::{find_candy_creator, find_edition_settings, sol, test_start},
+        mint_nft_ix, remove_edition_settings, set_edition_settings, CandyManager,
+    },
+};
+
+pub mod core;
+pub mod utils;
+
+fn find_edition_marker(master_mint: &Pubkey, edition: u64) -> Pubkey {
+    let program_id = mpl_token_metadata::id();
+    Pubkey::find_program_address(
+        &[
+            METADATA_PREFIX.as_bytes(),
+            program_id.as_ref(),
+            master_mint.as_ref(),
+            EDITION.as_bytes(),
+            (edition / EDITION_MARKER_BIT_SIZE).to_string().as_bytes(),
+        ],
+        &program_id,
+    )
+    .0
+}
+
+async fn mint_edition(
+    context: &mut ProgramTestContext,
+    candy_manager: &CandyManager,
+    master: &MasterEditionManager,
+    master_token_account: &Pubkey,
+    edition: u64,
+) -> Result<MasterEditionManager, BanksClientError> {
+    let candy_machine = candy_manager.candy_machine.pubkey();
+    let (candy_machine_creator, creator_bump) = find_candy_creator(&candy_machine);
+    let nft_info = prepare_nft(context, &candy_manager.minter).await;
+    let mut ix = mint_nft_ix(
+        &candy_machine,
+        &candy_machine_creator,
+        creator_bump,
+        &candy_manager.wallet,
+        &candy_manager.authority.pubkey(),
+        &candy_manager.minter,
+        &nft_info,
+        candy_manager.token_info.clone(),
+        candy_manager.whitelist_info.clone(),
+        candy_manager.collection_info.clone(),
+        candy_manager.gateway_info.clone(),
+        candy_manager.freeze_info.clone(),
+    );
+    ix[0].accounts.extend([
+        AccountMeta::new_readonly(find_edition_settings(&candy_machine).0, false),
+        AccountMeta::new_readonly(master.metadata_pubkey, false),
+        AccountMeta::new(master.edition_pubkey, false),
+        AccountMeta::new_readonly(*master_token_account, false),
+        AccountMeta::new(find_edition_marker(&master.mint.pubkey(), edition), false),
+    ]);
+
+    update_blockhash(context).await?;
+    let tx = Transaction::new_signed_with_payer(
+        ix.as_slice(),
+        Some(&candy_manager.minter.pubkey()),
+        &[&candy_manager.minter],
+        context.last_blockhash,
+    );
+    context.banks_client.process_transaction(tx).await?;
+    Ok(nft_info)
+}
+
+#[tokio::test]
+async fn mint_editions_from_master() {
+    test_start("Mint Editions From Master");
+    let mut context = candy_machine_program_test().start_with_context().await;
+    let context = &mut context;
+    let mut candy_manager = CandyManager::init(context, None, false, None, None, None).await;
+
+    airdrop(context, &candy_manager.minter.pubkey(), sol(10.0))
+        .await
+        .unwrap();
+
+    let mut candy_data = auto_config(&candy_manager, Some(0), true, true, None, None);
+    candy_data.items_available = 2;
+    candy_manager
+        .create(context, candy_data.clone())
+        .await
+        .unwrap();
+    candy_manager.fill_config_lines(context).await.unwrap();
+    let candy_machine = candy_manager.candy_machine.pubkey();
+    let (candy_machine_creator, _) = find_candy_creator(&candy_machine);
+
+    let metadata_info = MetadataManager::new(&candy_manager.authority);
+    metadata_info
+        .create_v3(
+            context,
+            "Master Name".to_string(),
+            "MASTER".to_string(),
+            "URI".to_string(),
+            None,
+            0,
+            true,
+            None,
+            None,
+            false,
+        )
+        .await
+        .unwrap();
+    let master = MasterEditionManager::new(&metadata_info);
+    master.create_v3(context, Some(10)).await.unwrap();
+
+    // The master edition token has to be held by the candy machine creator
+    let master_token_account =
+        create_associated_token_account(context, &candy_machine_creator, &master.mint.pubkey())
+            .await
+            .unwrap();
+    let err = match set_edition_settings(
+        context,
+        &candy_machine,
+        &candy_manager.authority,
+        &master,
+        &master_token_account,
+    )
+    .await
+    .unwrap_err()
+    {
+        BanksClientError::TransactionError(TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(err_num),
+        )) => err_num,
+        _ => 0,
+    };
+    assert_eq!(err, 6106);
+
+    update_blockhash(context).await.unwrap();
+    let tx = Transaction::new_signed_with_payer(
+        &[spl_token::instruction::transfer(
+            &spl_token::id(),
+            &master.token_account,
+            &master_token_account,
+            &candy_manager.authority.pubkey(),
+            &[],
+            1,
+        )
+        .unwrap()],
+        Some(&candy_manager.authority.pubkey()),
+        &[&candy_manager.authority],
+        context.last_blockhash,
+    );
+    context.banks_client.process_transaction(tx).await.unwrap();
+    set_edition_settings(
+        context,
+        &candy_machine,
+        &candy_manager.authority,
+        &master,
+        &master_token_account,
+    )
+    .await
+    .unwrap();
+
+    for edition in 1..=2 {
+        let nft_info = mint_edition(
+            context,
+            &candy_manager,
+            &master,
+            &master_token_account,
+            edition,
+        )
+        .await
+        .unwrap();
+        let metadata = nft_info.get_metadata(context).await;
+        assert_eq!(metadata.update_authority, candy_manager.authority.pubkey());
+        assert!(metadata.primary_sale_happened);
+    }
+    assert_eq!(master.get_data(context).await.supply, 2);
+    assert_eq!(candy_manager.get_candy(context).await.items_redeemed, 2);
+
+    // Sold out, the master edition goes back to the authority
+    mint_edition(context, &candy_manager, &master, &master_token_account, 3)
+        .await
+        .unwrap_err();
+    remove_edition_settings(
+        context,
+        &candy_machine,
+        &candy_manager.authority,
+        &master_token_account,
+        &master.token_account,
+    )
+    .await
+    .unwrap();
+    assert_eq!(get_token_balance(context, &master.token_account).await, 1);
+}
+
+#[tokio::test]
+async fn master_edition_supply_too_low() {
+    test_start("Master Edition Supply Too Low");
+    let mut context = candy_machine_program_test().start_with_context().await;
+    let context = &mut context;
+    let mut candy_manager = CandyManager::init(context, None, false, None, None, None).await;
+
+    let mut candy_data = auto_config(&candy_manager, Some(0), true, true, None, None);
+    candy_data.items_available = 2;
+    candy_manager
+        .create(context, candy_data.clone())
+        .await
+        .unwrap();
+    candy_manager.fill_config_lines(context).await.unwrap();
+    let candy_machine = candy_manager.candy_machine.pubkey();
+    let (candy_machine_creator, _) = find_candy_creator(&candy_machine);
+
+    let metadata_info = MetadataManager::new(&candy_manager.authority);
+    metadata_info
+        .create_v3(
+            context,
+            "Master Name".to_string(),
+            "MASTER".to_string(),
+            "URI".to_string(),
+            None,
+            0,
+            true,
+            None,
+            None,
+            false,
+        )
+        .await
+        .unwrap();
+    let master = MasterEditionManager::new(&metadata_info);
+    master.create_v3(context, Some(1)).await.unwrap();
+
+    let master_token_account =
+        create_associated_token_account(context, &candy_machine_creator, &master.mint.pubkey())
+            .await
+            .unwrap();
+    update_blockhash(context).await.unwrap();
+    let tx = Transaction::new_signed_with_payer(
+        &[spl_token::instruction::transfer(
+            &spl_token::id(),
+            &master.token_account,
+            &master_token_account,
+            &candy_manager.authority.pubkey(),
+            &[],
+            1,
+        )
+        .unwrap()],
+        Some(&candy_manager.authority.pubkey()),
+        &[&candy_manager.authority],
+        context.last_blockhash,
+    );
+    context.banks_client.process_transaction(tx).await.unwrap();
+
+    // Two items can't be printed from a master edition with a max supply of one
+    let err = match set_edition_settings(
+        context,
+        &candy_machine,
+        &candy_manager.authority,
+        &master,
+        &master_token_account,
+    )
+    .await
+    .unwrap_err()
+    {
+        BanksClientError::TransactionError(TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(err_num),
+        )) => err_num,
+        _ => 0,
+    };
+    assert_eq!(err, 6117);
+}
@@ -1,13 +1,13 @@
 #![cfg(feature = "test-bpf")]
 #![allow(dead_code)]
 
-use solana_program::clock::Clock;
+use solana_program::{clock::Clock, instruction::InstructionError};
 use solana_program_test::*;
-use solana_sdk::{signature::Keypair, signer::Signer};
+use solana_sdk::{signature::Keypair, signer::Signer, transaction::TransactionError};
 
 use mpl_candy_machine::{
-    constants::{FREEZE_FEATURE_INDEX, FREEZE_FEE, FREEZE_LOCK_FEATURE_INDEX, MAX_FREEZE_TIME},
-    is_feature_active, CandyMachineData, FreezePDA,
+    constants::{FREEZE_FEE, MAX_FREEZE_TIME},
+    CandyFeatures, CandyMachineData, FreezePDA,
     WhitelistMintMode::BurnEveryTime,
 };
 
@@ -19,7 +19,7 @@ use crate::{
     utils::{
         auto_config, candy_machine_program_test,
         helpers::{sol, test_start},
-        CandyManager, FreezeConfig, WhitelistConfig,
+        refund_nft, remove_freeze, thaw_nfts, CandyManager, FreezeConfig, WhitelistConfig,
     },
 };
 
@@ -69,6 +69,9 @@ async fn freeze_flow_with_spl_token() {
         frozen_count: 0,
         allow_thaw: false,
         mint_start: None,
+        refund: false,
+        refund_price: 0,
+        escrowed_count: 0,
     };
 
     candy_manager
@@ -175,6 +178,9 @@ async fn freeze_update() {
         frozen_count: 0,
         allow_thaw: false,
         mint_start: None,
+        refund: false,
+        refund_price: 0,
+        escrowed_count: 0,
     };
     candy_manager
         .assert_freeze_set(context, &expected_freeze_pda)
@@ -182,16 +188,9 @@ async fn freeze_update() {
 
     candy_manager.remove_freeze(context).await.unwrap();
 
-    let candy_machine_account = candy_manager.get_candy(context).await;
+    let features = candy_manager.get_features(context).await;
     assert_account_empty(context, &candy_manager.freeze_info.pda).await;
-    assert!(!is_feature_active(
-        &candy_machine_account.data.uuid,
-        FREEZE_FEATURE_INDEX
-    ));
-    assert!(!is_feature_active(
-        &candy_machine_account.data.uuid,
-        FREEZE_LOCK_FEATURE_INDEX
-    ));
+    assert!(!features.intersects(CandyFeatures::FREEZE | CandyFeatures::FREEZE_LOCK));
 
     candy_manager.set_freeze(context).await.unwrap();
     candy_manager
@@ -227,9 +226,9 @@ async fn freeze_update() {
     expected_freeze_pda.allow_thaw = true;
     let freeze_pda = candy_manager.get_freeze_pda(context).await;
     assert_eq!(freeze_pda, expected_freeze_pda);
-    let uuid = candy_manager.get_candy(context).await.data.uuid;
-    assert!(!is_feature_active(&uuid, FREEZE_FEATURE_INDEX));
-    assert!(is_feature_active(&uuid, FREEZE_LOCK_FEATURE_INDEX));
+    let features = candy_manager.get_features(context).await;
+    assert!(!features.contains(CandyFeatures::FREEZE));
+    assert!(features.contains(CandyFeatures::FREEZE_LOCK));
 
     candy_manager
         .thaw_nft(context, &new_nft, &random_key)
@@ -296,6 +295,9 @@ async fn thaw_after_freeze_time() {
         frozen_count: 0,
         allow_thaw: false,
         mint_start: None,
+        refund: false,
+        refund_price: 0,
+        escrowed_count: 0,
     };
     candy_manager
         .assert_freeze_set(context, &expected_freeze_pda)
@@ -381,6 +383,9 @@ async fn unlock_funds() {
         frozen_count: 0,
         allow_thaw: false,
         mint_start: None,
+        refund: false,
+        refund_price: 0,
+        escrowed_count: 0,
     };
     candy_manager
         .assert_freeze_set(context, &expected_freeze_pda)
@@ -405,6 +410,200 @@ async fn unlock_funds() {
     assert!(post_balance - pre_balance >= sol(1.0));
 }
 
+#[tokio::test]
+async fn batch_thaw() {
+    test_start("Batch Thaw");
+    let mut context = candy_machine_program_test().start_with_context().await;
+    let context = &mut context;
+    let freeze_time = 30; //30 seconds
+    let mut candy_manager = CandyManager::init(
+        context,
+        None,
+        false,
+        Some(FreezeConfig::new(true, freeze_time)),
+        None,
+        None,
+    )
+    .await;
+
+    airdrop(context, &candy_manager.minter.pubkey(), sol(20.0))
+        .await
+        .unwrap();
+
+    let candy_data = auto_config(&candy_manager, Some(0), true, true, None, None);
+    candy_manager
+        .create(context, candy_data.clone())
+        .await
+        .unwrap();
+    candy_manager.fill_config_lines(context).await.unwrap();
+    candy_manager.set_freeze(context).await.unwrap();
+
+    let mut nfts = Vec::new();
+    for _ in 0..3 {
+        nfts.push(
+            candy_manager
+                .mint_and_assert_successful(context, Some(sol(1.0)), true)
+                .await
+                .unwrap(),
+        );
+    }
+    let cranker = new_funded_keypair(context, sol(1.0)).await;
+
+    // still frozen
+    thaw_nfts(
+        context,
+        &candy_manager.candy_machine.pubkey(),
+        &cranker,
+        &candy_manager.freeze_info,
+        &[&nfts[0], &nfts[1]],
+    )
+    .await
+    .unwrap_err();
+
+    candy_manager.remove_freeze(context).await.unwrap();
+    candy_manager
+        .thaw_nft(context, &nfts[0], &nfts[0].owner)
+        .await
+        .unwrap();
+    // already thawed NFTs are skipped
+    thaw_nfts(
+        context,
+        &candy_manager.candy_machine.pubkey(),
+        &cranker,
+        &candy_manager.freeze_info,
+        &[&nfts[0], &nfts[1], &nfts[2]],
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(candy_manager.get_freeze_pda(context).await.frozen_count, 0);
+    for nft in &nfts[1..] {
+        candy_manager.assert_thawed(context, nft, false).await;
+    }
+    candy_manager.unlock_funds(context).await.unwrap();
+}
+
+#[tokio::test]
+async fn refund_without_sell_out() {
+    test_start("Refund Without Sell Out");
+    let mut context = candy_machine_program_test().start_with_context().await;
+    let context = &mut context;
+    let freeze_time = 30; //30 seconds
+    let mut candy_manager = CandyManager::init(
+        context,
+        None,
+        false,
+        Some(FreezeConfig::new(true, freeze_time)),
+        None,
+        None,
+    )
+    .await;
+
+    airdrop(context, &candy_manager.minter.pubkey(), sol(20.0))
+        .await
+        .unwrap();
+
+    let candy_data = auto_config(&candy_manager, Some(0), true, true, None, None);
+    candy_manager
+        .create(context, candy_data.clone())
+        .await
+        .unwrap();
+    candy_manager.fill_config_lines(context).await.unwrap();
+
+    candy_manager.freeze_info.refund = true;
+    candy_manager.set_freeze(context).await.unwrap();
+
+    let kept_nft = candy_manager
+        .mint_and_assert_successful(context, Some(sol(1.0)), true)
+        .await
+        .unwrap();
+    let refunded_nft = candy_manager
+        .mint_and_assert_successful(context, Some(sol(1.0)), true)
+        .await
+        .unwrap();
+    let freeze_pda = candy_manager.get_freeze_pda(context).await;
+    assert_eq!(freeze_pda.refund_price, candy_data.price);
+    assert_eq!(freeze_pda.escrowed_count, 2);
+
+    // every refund returns the same price, so it can't change during the mint
+    let mut new_data = candy_data.clone();
+    new_data.price = candy_data.price / 2;
+    candy_manager.update(context, None, new_data).await.unwrap();
+    let err = match candy_manager.mint_nft(context).await.unwrap_err().unwrap() {
+        TransactionError::InstructionError(_, InstructionError::Custom(err_num)) => err_num,
+        _ => 0,
+    };
+    assert_eq!(err, 6114);
+    candy_manager
+        .update(context, None, candy_data.clone())
+        .await
+        .unwrap();
+
+    // refunds can't be dodged by thawing everything early
+    let err = match remove_freeze(
+        context,
+        &candy_manager.candy_machine.pubkey(),
+        &candy_manager.authority,
+        &candy_manager.freeze_info,
+    )
+    .await
+    .unwrap_err()
+    {
+        BanksClientError::TransactionError(TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(err_num),
+        )) => err_num,
+        _ => 0,
+    };
+    assert_eq!(err, 6094);
+    refund_nft(
+        context,
+        &candy_manager.candy_machine.pubkey(),
+        &candy_manager.freeze_info,
+        &refunded_nft,
+    )
+    .await
+    .unwrap_err();
+
+    let current_slot = context.banks_client.get_root_slot().await.unwrap();
+    context.warp_to_slot(current_slot + 20000).unwrap();
+
+    // the drop ended without a sell-out
+    candy_manager.mint_nft(context).await.unwrap_err();
+    candy_manager
+        .thaw_nft(context, &kept_nft, &clone_keypair(&candy_manager.authority))
+        .await
+        .unwrap_err();
+    candy_manager
+        .thaw_nft(context, &kept_nft, &kept_nft.owner)
+        .await
+        .unwrap();
+    candy_manager.assert_thawed(context, &kept_nft, true).await;
+    // the kept NFT can't be refunded anymore, so its price isn't held back
+    assert_eq!(candy_manager.get_freeze_pda(context).await.escrowed_count, 1);
+
+    let pre_balance = get_balance(context, &refunded_nft.owner.pubkey()).await;
+    refund_nft(
+        context,
+        &candy_manager.candy_machine.pubkey(),
+        &candy_manager.freeze_info,
+        &refunded_nft,
+    )
+    .await
+    .unwrap();
+    let post_balance = get_balance(context, &refunded_nft.owner.pubkey()).await;
+    assert!(post_balance - pre_balance >= candy_data.price);
+    assert_account_empty(context, &refunded_nft.token_account).await;
+
+    // the kept NFT's price is all that is left for the creator
+    let pre_balance = get_balance(context, &candy_manager.wallet).await;
+    candy_manager.unlock_funds(context).await.unwrap();
+    let post_balance = get_balance(context, &candy_manager.wallet).await;
+    assert!(post_balance - pre_balance >= candy_data.price);
+    assert!(post_balance - pre_balance < candy_data.price * 2);
+    assert_account_empty(context, &candy_manager.freeze_info.pda).await;
+}
+
 #[tokio::test]
 async fn withdraw_funds() {
     test_start("Withdraw Funds");
@@ -442,6 +641,9 @@ async fn withdraw_funds() {
         frozen_count: 0,
         allow_thaw: false,
         mint_start: None,
+        refund: false,
+        refund_price: 0,
+        escrowed_count: 0,
     };
     candy_manager
         .assert_freeze_set(context, &expected_freeze_pda)
@@ -510,6 +712,9 @@ async fn mint_out_unfreeze() {
         frozen_count: 0,
         allow_thaw: false,
         mint_start: None,
+        refund: false,
+        refund_price: 0,
+        escrowed_count: 0,
     };
 
     candy_manager
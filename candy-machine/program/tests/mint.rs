@@ -2,8 +2,12 @@
 #![allow(dead_code)]
 
 use anchor_client::solana_sdk::transaction::Transaction;
+use anchor_lang::InstructionData;
 use mpl_candy_machine::WhitelistMintMode;
-use solana_program::{instruction::InstructionError, pubkey::Pubkey};
+use solana_program::{
+    instruction::{AccountMeta, InstructionError},
+    pubkey::Pubkey,
+};
 use solana_program_test::*;
 use solana_sdk::{
     account::{AccountSharedData, WritableAccount},
@@ -13,7 +17,13 @@ use solana_sdk::{
 };
 
 use crate::{
-    core::helpers::{airdrop, prepare_nft, update_blockhash},
+    core::{
+        helpers::{
+            airdrop, clone_keypair, create_mint, get_balance, mint_to_wallets, prepare_nft,
+            update_blockhash,
+        },
+        MasterEditionManager, MetadataManager,
+    },
     utils::{
         auto_config, candy_machine_program_test,
         helpers::{find_candy_creator, sol, test_start},
@@ -166,3 +176,83 @@ async fn metadata_check_before_bot_tax() {
     };
     assert_eq!(err, 6031)
 }
+
+#[tokio::test]
+async fn mint_to_recipient() {
+    test_start("Mint To Recipient");
+    let mut context = candy_machine_program_test().start_with_context().await;
+    let context = &mut context;
+    let mut candy_manager = CandyManager::init(context, None, false, None, None, None).await;
+
+    airdrop(context, &candy_manager.minter.pubkey(), sol(6.0))
+        .await
+        .unwrap();
+
+    let candy_data = auto_config(&candy_manager, Some(0), true, true, None, None);
+    candy_manager
+        .create(context, candy_data.clone())
+        .await
+        .unwrap();
+    candy_manager.fill_config_lines(context).await.unwrap();
+
+    // The payer mints the token straight to the recipient
+    let recipient = Keypair::new();
+    let metadata_info = MetadataManager::new(&candy_manager.minter);
+    create_mint(
+        context,
+        &candy_manager.minter.pubkey(),
+        Some(&candy_manager.minter.pubkey()),
+        0,
+        Some(clone_keypair(&metadata_info.mint)),
+    )
+    .await
+    .unwrap();
+    mint_to_wallets(
+        context,
+        &metadata_info.mint.pubkey(),
+        &candy_manager.minter,
+        vec![(recipient.pubkey(), 1)],
+    )
+    .await
+    .unwrap();
+    let nft_info = MasterEditionManager::new(&metadata_info);
+
+    let candy_machine = candy_manager.candy_machine.pubkey();
+    let (candy_machine_creator, creator_bump) = find_candy_creator(&candy_machine);
+    let mut ix = mint_nft_ix(
+        &candy_machine,
+        &candy_machine_creator,
+        creator_bump,
+        &candy_manager.wallet,
+        &candy_manager.authority.pubkey(),
+        &candy_manager.minter,
+        &nft_info,
+        candy_manager.token_info.clone(),
+        candy_manager.whitelist_info.clone(),
+        candy_manager.collection_info.clone(),
+        candy_manager.gateway_info.clone(),
+        candy_manager.freeze_info.clone(),
+    );
+    ix[0].data = mpl_candy_machine::instruction::MintNftTo { creator_bump }.data();
+    ix[0]
+        .accounts
+        .push(AccountMeta::new_readonly(recipient.pubkey(), false));
+
+    let wallet_balance = get_balance(context, &candy_manager.wallet).await;
+    update_blockhash(context).await.unwrap();
+    let tx = Transaction::new_signed_with_payer(
+        ix.as_slice(),
+        Some(&candy_manager.minter.pubkey()),
+        &[&candy_manager.minter],
+        context.last_blockhash,
+    );
+    context.banks_client.process_transaction(tx).await.unwrap();
+
+    let metadata = MetadataManager::get_data_from_account(context, &nft_info.metadata_pubkey).await;
+    assert!(metadata.data.name.starts_with("Item #"));
+    assert_eq!(
+        get_balance(context, &candy_manager.wallet).await,
+        wallet_balance + candy_data.price
+    );
+    assert_eq!(get_balance(context, &recipient.pubkey()).await, 0);
+}
@@ -0,0 +1,175 @@
+#![cfg(feature = "test-bpf")]
+#![allow(dead_code)]
+
+use anchor_client::solana_sdk::transaction::Transaction;
+use mpl_candy_machine::{EndSettingType, EndSettings};
+use solana_program::instruction::{AccountMeta, InstructionError};
+use solana_program_test::*;
+use solana_sdk::{signer::Signer, transaction::TransactionError};
+
+use crate::{
+    core::helpers::{airdrop, get_balance, prepare_nft, update_blockhash},
+    utils::{
+        auto_config, candy_machine_program_test, close_mint_counter,
+        helpers::{find_candy_creator, find_mint_counter, find_mint_limit_pda, sol, test_start},
+        mint_nft_ix, set_mint_limit, CandyManager,
+    },
+};
+
+pub mod core;
+pub mod utils;
+
+async fn mint_with_limit(
+    context: &mut ProgramTestContext,
+    candy_manager: &CandyManager,
+) -> Result<(), BanksClientError> {
+    let nft_info = prepare_nft(context, &candy_manager.minter).await;
+    let candy_machine = candy_manager.candy_machine.pubkey();
+    let (candy_machine_creator, creator_bump) = find_candy_creator(&candy_machine);
+    let mut ix = mint_nft_ix(
+        &candy_machine,
+        &candy_machine_creator,
+        creator_bump,
+        &candy_manager.wallet,
+        &candy_manager.authority.pubkey(),
+        &candy_manager.minter,
+        &nft_info,
+        candy_manager.token_info.clone(),
+        candy_manager.whitelist_info.clone(),
+        candy_manager.collection_info.clone(),
+        candy_manager.gateway_info.clone(),
+        candy_manager.freeze_info.clone(),
+    );
+    ix[0].accounts.push(AccountMeta::new_readonly(
+        find_mint_limit_pda(&candy_machine).0,
+        false,
+    ));
+    ix[0].accounts.push(AccountMeta::new(
+        find_mint_counter(&candy_machine, &candy_manager.minter.pubkey()).0,
+        false,
+    ));
+
+    update_blockhash(context).await?;
+    let tx = Transaction::new_signed_with_payer(
+        ix.as_slice(),
+        Some(&candy_manager.minter.pubkey()),
+        &[&candy_manager.minter],
+        context.last_blockhash,
+    );
+    context.banks_client.process_transaction(tx).await
+}
+
+#[tokio::test]
+async fn mint_limit_reached() {
+    test_start("Mint Limit Reached");
+    let mut context = candy_machine_program_test().start_with_context().await;
+    let context = &mut context;
+    let mut candy_manager = CandyManager::init(context, None, false, None, None, None).await;
+
+    airdrop(context, &candy_manager.minter.pubkey(), sol(10.0))
+        .await
+        .unwrap();
+
+    let mut candy_data = auto_config(&candy_manager, Some(0), true, true, None, None);
+    candy_data.items_available = 5;
+    candy_manager
+        .create(context, candy_data.clone())
+        .await
+        .unwrap();
+    candy_manager.fill_config_lines(context).await.unwrap();
+
+    let err = match set_mint_limit(
+        context,
+        &candy_manager.candy_machine.pubkey(),
+        &candy_manager.authority,
+        0,
+    )
+    .await
+    .unwrap_err()
+    {
+        BanksClientError::TransactionError(TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(err_num),
+        )) => err_num,
+        _ => 0,
+    };
+    assert_eq!(err, 6058);
+    set_mint_limit(
+        context,
+        &candy_manager.candy_machine.pubkey(),
+        &candy_manager.authority,
+        2,
+    )
+    .await
+    .unwrap();
+
+    // Lamports sent to the counter address before the first mint don't block its creation
+    let counter = find_mint_counter(
+        &candy_manager.candy_machine.pubkey(),
+        &candy_manager.minter.pubkey(),
+    )
+    .0;
+    airdrop(context, &counter, sol(0.01)).await.unwrap();
+
+    mint_with_limit(context, &candy_manager).await.unwrap();
+    mint_with_limit(context, &candy_manager).await.unwrap();
+
+    // The third mint fails without charging the bot tax
+    let start_balance = get_balance(context, &candy_manager.minter.pubkey()).await;
+    let err = match mint_with_limit(context, &candy_manager).await.unwrap_err() {
+        BanksClientError::TransactionError(TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(err_num),
+        )) => err_num,
+        _ => 0,
+    };
+    assert_eq!(err, 6056);
+    let end_balance = get_balance(context, &candy_manager.minter.pubkey()).await;
+    assert!(start_balance - end_balance < sol(0.01));
+    assert_eq!(candy_manager.get_candy(context).await.items_redeemed, 2);
+
+    // Counters can't be closed while the candy machine is still minting
+    let err = match close_mint_counter(
+        context,
+        &candy_manager.candy_machine.pubkey(),
+        &candy_manager.authority,
+        &candy_manager.minter.pubkey(),
+    )
+    .await
+    .unwrap_err()
+    {
+        BanksClientError::TransactionError(TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(err_num),
+        )) => err_num,
+        _ => 0,
+    };
+    assert_eq!(err, 6109);
+
+    candy_data.end_settings = Some(EndSettings {
+        end_setting_type: EndSettingType::Date,
+        number: 0,
+    });
+    candy_manager
+        .update(context, None, candy_data)
+        .await
+        .unwrap();
+
+    // Closing the counter once minting ended refunds its rent to the minter
+    let start_balance = get_balance(context, &candy_manager.minter.pubkey()).await;
+    close_mint_counter(
+        context,
+        &candy_manager.candy_machine.pubkey(),
+        &candy_manager.authority,
+        &candy_manager.minter.pubkey(),
+    )
+    .await
+    .unwrap();
+    assert!(context
+        .banks_client
+        .get_account(counter)
+        .await
+        .unwrap()
+        .is_none());
+    assert!(get_balance(context, &candy_manager.minter.pubkey()).await > start_balance);
+}
@@ -0,0 +1,185 @@
+#![cfg(feature = "test-bpf")]
+#![allow(dead_code)]
+
+use anchor_client::solana_sdk::transaction::Transaction;
+use anchor_lang::InstructionData;
+use mpl_candy_machine::WhitelistMintMode::NeverBurn;
+use solana_program::instruction::{AccountMeta, InstructionError};
+use solana_program_test::*;
+use solana_sdk::{signer::Signer, transaction::TransactionError};
+
+use crate::{
+    core::{
+        helpers::{airdrop, get_balance, prepare_nft, update_blockhash},
+        MasterEditionManager, MetadataManager,
+    },
+    utils::{
+        auto_config, candy_machine_program_test,
+        helpers::{find_candy_creator, sol, test_start},
+        mint_nft_ix, CandyManager, WhitelistConfig,
+    },
+};
+
+pub mod core;
+pub mod utils;
+
+async fn mint_many(
+    context: &mut ProgramTestContext,
+    candy_manager: &CandyManager,
+    nfts: &[MasterEditionManager],
+    quantity: u8,
+) -> Result<(), BanksClientError> {
+    let candy_machine = candy_manager.candy_machine.pubkey();
+    let (candy_machine_creator, creator_bump) = find_candy_creator(&candy_machine);
+    let mut ix = mint_nft_ix(
+        &candy_machine,
+        &candy_machine_creator,
+        creator_bump,
+        &candy_manager.wallet,
+        &candy_manager.authority.pubkey(),
+        &candy_manager.minter,
+        &nfts[0],
+        candy_manager.token_info.clone(),
+        candy_manager.whitelist_info.clone(),
+        candy_manager.collection_info.clone(),
+        candy_manager.gateway_info.clone(),
+        candy_manager.freeze_info.clone(),
+    );
+    ix[0].data = mpl_candy_machine::instruction::MintMany {
+        creator_bump,
+        quantity,
+    }
+    .data();
+    for nft in &nfts[1..] {
+        ix[0]
+            .accounts
+            .push(AccountMeta::new(nft.mint.pubkey(), false));
+        ix[0]
+            .accounts
+            .push(AccountMeta::new(nft.metadata_pubkey, false));
+        ix[0]
+            .accounts
+            .push(AccountMeta::new(nft.edition_pubkey, false));
+    }
+
+    update_blockhash(context).await?;
+    let tx = Transaction::new_signed_with_payer(
+        ix.as_slice(),
+        Some(&candy_manager.minter.pubkey()),
+        &[&candy_manager.minter],
+        context.last_blockhash,
+    );
+    context.banks_client.process_transaction(tx).await
+}
+
+#[tokio::test]
+async fn mint_many_at_once() {
+    test_start("Mint Many At Once");
+    let mut context = candy_machine_program_test().start_with_context().await;
+    let context = &mut context;
+    let mut candy_manager = CandyManager::init(context, None, false, None, None, None).await;
+
+    airdrop(context, &candy_manager.minter.pubkey(), sol(10.0))
+        .await
+        .unwrap();
+
+    let mut candy_data = auto_config(&candy_manager, Some(0), true, true, None, None);
+    candy_data.items_available = 4;
+    candy_manager
+        .create(context, candy_data.clone())
+        .await
+        .unwrap();
+    candy_manager.fill_config_lines(context).await.unwrap();
+
+    let mut nfts = Vec::new();
+    for _ in 0..3 {
+        nfts.push(prepare_nft(context, &candy_manager.minter).await);
+    }
+
+    // At least one NFT has to be minted
+    let err = match mint_many(context, &candy_manager, &nfts, 0)
+        .await
+        .unwrap_err()
+    {
+        BanksClientError::TransactionError(TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(err_num),
+        )) => err_num,
+        _ => 0,
+    };
+    assert_eq!(err, 6098);
+
+    let wallet_balance = get_balance(context, &candy_manager.wallet).await;
+    mint_many(context, &candy_manager, &nfts, 3).await.unwrap();
+
+    assert_eq!(
+        get_balance(context, &candy_manager.wallet).await,
+        wallet_balance + candy_data.price * 3
+    );
+    assert_eq!(candy_manager.get_candy(context).await.items_redeemed, 3);
+    let mut names = Vec::new();
+    for nft in &nfts {
+        let metadata = MetadataManager::get_data_from_account(context, &nft.metadata_pubkey).await;
+        names.push(metadata.data.name);
+    }
+    names.sort();
+    names.dedup();
+    assert_eq!(names.len(), 3);
+
+    // Only one item is left
+    let nfts: Vec<MasterEditionManager> = vec![
+        prepare_nft(context, &candy_manager.minter).await,
+        prepare_nft(context, &candy_manager.minter).await,
+    ];
+    mint_many(context, &candy_manager, &nfts, 2)
+        .await
+        .unwrap_err();
+}
+
+#[tokio::test]
+async fn whitelist_discount_per_token() {
+    test_start("Whitelist Discount Per Token");
+    let mut context = candy_machine_program_test().start_with_context().await;
+    let context = &mut context;
+    let mut candy_manager = CandyManager::init(
+        context,
+        None,
+        false,
+        None,
+        Some(WhitelistConfig::new(NeverBurn, false, Some(1))),
+        None,
+    )
+    .await;
+
+    airdrop(context, &candy_manager.minter.pubkey(), sol(10.0))
+        .await
+        .unwrap();
+
+    let mut candy_data = auto_config(&candy_manager, Some(0), true, true, None, None);
+    candy_data.items_available = 4;
+    candy_manager
+        .create(context, candy_data.clone())
+        .await
+        .unwrap();
+    candy_manager.fill_config_lines(context).await.unwrap();
+
+    // The minter holds a single whitelist token, which doesn't discount two NFTs
+    let nfts = vec![
+        prepare_nft(context, &candy_manager.minter).await,
+        prepare_nft(context, &candy_manager.minter).await,
+    ];
+    let wallet_balance = get_balance(context, &candy_manager.wallet).await;
+    mint_many(context, &candy_manager, &nfts, 2).await.unwrap();
+    assert_eq!(
+        get_balance(context, &candy_manager.wallet).await,
+        wallet_balance + candy_data.price * 2
+    );
+
+    let nfts = vec![prepare_nft(context, &candy_manager.minter).await];
+    let wallet_balance = get_balance(context, &candy_manager.wallet).await;
+    mint_many(context, &candy_manager, &nfts, 1).await.unwrap();
+    assert_eq!(
+        get_balance(context, &candy_manager.wallet).await,
+        wallet_balance + 1
+    );
+}
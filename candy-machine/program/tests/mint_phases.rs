@@ -0,0 +1,417 @@
+#![cfg(feature = "test-bpf")]
+#![allow(dead_code)]
+
+use anchor_client::solana_sdk::transaction::Transaction;
+use mpl_candy_machine::{
+    constants::BOT_FEE, MintPhase, PhaseGate, WhitelistMintMode::BurnEveryTime,
+};
+use solana_program::{
+    instruction::{AccountMeta, InstructionError},
+    pubkey::Pubkey,
+};
+use solana_program_test::*;
+use solana_sdk::{signer::Signer, transaction::TransactionError};
+
+use crate::{
+    core::helpers::{
+        airdrop, assert_account_empty, get_account, get_balance, get_token_balance, prepare_nft,
+        update_blockhash,
+    },
+    utils::{
+        auto_config, candy_machine_program_test,
+        helpers::{
+            find_candy_creator, find_holder_mint_record, find_mint_phases, find_phase_mint_counter,
+            sol, test_start,
+        },
+        mint_nft_ix, set_mint_phases, CandyManager, WhitelistConfig,
+    },
+};
+
+pub mod core;
+pub mod utils;
+
+fn phase(start_time: i64, price: u64) -> MintPhase {
+    MintPhase {
+        label: "Phase".to_string(),
+        start_time,
+        end_time: None,
+        price,
+        payment: None,
+        wallet_limit: None,
+        gate: None,
+    }
+}
+
+async fn mint_in_phase(
+    context: &mut ProgramTestContext,
+    candy_manager: &CandyManager,
+    phase_accounts: Vec<AccountMeta>,
+) -> Result<(), BanksClientError> {
+    let candy_machine = candy_manager.candy_machine.pubkey();
+    let (candy_machine_creator, creator_bump) = find_candy_creator(&candy_machine);
+    let nft_info = prepare_nft(context, &candy_manager.minter).await;
+    let mut ix = mint_nft_ix(
+        &candy_machine,
+        &candy_machine_creator,
+        creator_bump,
+        &candy_manager.wallet,
+        &candy_manager.authority.pubkey(),
+        &candy_manager.minter,
+        &nft_info,
+        candy_manager.token_info.clone(),
+        candy_manager.whitelist_info.clone(),
+        candy_manager.collection_info.clone(),
+        candy_manager.gateway_info.clone(),
+        candy_manager.freeze_info.clone(),
+    );
+    ix[0].accounts.push(AccountMeta::new_readonly(
+        find_mint_phases(&candy_machine).0,
+        false,
+    ));
+    ix[0].accounts.extend(phase_accounts);
+
+    update_blockhash(context).await?;
+    let tx = Transaction::new_signed_with_payer(
+        ix.as_slice(),
+        Some(&candy_manager.minter.pubkey()),
+        &[&candy_manager.minter],
+        context.last_blockhash,
+    );
+    context.banks_client.process_transaction(tx).await
+}
+
+#[tokio::test]
+async fn phase_replaces_go_live_date() {
+    test_start("Phase Replaces Go Live Date");
+    let mut context = candy_machine_program_test().start_with_context().await;
+    let context = &mut context;
+    let mut candy_manager = CandyManager::init(context, None, false, None, None, None).await;
+
+    airdrop(context, &candy_manager.minter.pubkey(), sol(10.0))
+        .await
+        .unwrap();
+
+    // Never live by its go live date
+    let candy_data = auto_config(&candy_manager, Some(i64::MAX), true, true, None, None);
+    candy_manager
+        .create(context, candy_data.clone())
+        .await
+        .unwrap();
+    candy_manager.fill_config_lines(context).await.unwrap();
+    let candy_machine = candy_manager.candy_machine.pubkey();
+
+    let phase_price = candy_data.price / 2;
+    set_mint_phases(
+        context,
+        &candy_machine,
+        &candy_manager.authority,
+        vec![phase(0, phase_price)],
+    )
+    .await
+    .unwrap();
+
+    let wallet_balance = get_balance(context, &candy_manager.wallet).await;
+    mint_in_phase(context, &candy_manager, vec![])
+        .await
+        .unwrap();
+
+    assert_eq!(candy_manager.get_candy(context).await.items_redeemed, 1);
+    assert_eq!(
+        get_balance(context, &candy_manager.wallet).await,
+        wallet_balance + phase_price
+    );
+}
+
+#[tokio::test]
+async fn mint_outside_phases_is_taxed() {
+    test_start("Mint Outside Phases Is Taxed");
+    let mut context = candy_machine_program_test().start_with_context().await;
+    let context = &mut context;
+    let mut candy_manager = CandyManager::init(context, None, false, None, None, None).await;
+
+    airdrop(context, &candy_manager.minter.pubkey(), sol(10.0))
+        .await
+        .unwrap();
+
+    // Live by its go live date, but the only phase has not started yet
+    let candy_data = auto_config(&candy_manager, Some(0), true, true, None, None);
+    candy_manager
+        .create(context, candy_data.clone())
+        .await
+        .unwrap();
+    candy_manager.fill_config_lines(context).await.unwrap();
+    let candy_machine = candy_manager.candy_machine.pubkey();
+
+    set_mint_phases(
+        context,
+        &candy_machine,
+        &candy_manager.authority,
+        vec![phase(i64::MAX - 1, candy_data.price)],
+    )
+    .await
+    .unwrap();
+
+    let candy_balance = get_balance(context, &candy_machine).await;
+    mint_in_phase(context, &candy_manager, vec![])
+        .await
+        .unwrap();
+
+    assert_eq!(candy_manager.get_candy(context).await.items_redeemed, 0);
+    assert_eq!(
+        get_balance(context, &candy_machine).await,
+        candy_balance + BOT_FEE
+    );
+}
+
+#[tokio::test]
+async fn phase_wallet_limit() {
+    test_start("Phase Wallet Limit");
+    let mut context = candy_machine_program_test().start_with_context().await;
+    let context = &mut context;
+    let mut candy_manager = CandyManager::init(context, None, false, None, None, None).await;
+
+    airdrop(context, &candy_manager.minter.pubkey(), sol(10.0))
+        .await
+        .unwrap();
+
+    let candy_data = auto_config(&candy_manager, Some(0), true, true, None, None);
+    candy_manager
+        .create(context, candy_data.clone())
+        .await
+        .unwrap();
+    candy_manager.fill_config_lines(context).await.unwrap();
+    let candy_machine = candy_manager.candy_machine.pubkey();
+
+    set_mint_phases(
+        context,
+        &candy_machine,
+        &candy_manager.authority,
+        vec![MintPhase {
+            wallet_limit: Some(1),
+            ..phase(0, candy_data.price)
+        }],
+    )
+    .await
+    .unwrap();
+
+    let mint_counter = find_phase_mint_counter(&candy_machine, &candy_manager.minter.pubkey(), 0).0;
+    mint_in_phase(
+        context,
+        &candy_manager,
+        vec![AccountMeta::new(mint_counter, false)],
+    )
+    .await
+    .unwrap();
+
+    // Hitting the limit is an honest mistake, so the mint fails without the bot tax
+    let err = match mint_in_phase(
+        context,
+        &candy_manager,
+        vec![AccountMeta::new(mint_counter, false)],
+    )
+    .await
+    .unwrap_err()
+    {
+        BanksClientError::TransactionError(TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(err_num),
+        )) => err_num,
+        _ => 0,
+    };
+    assert_eq!(err, 6056);
+    assert_eq!(candy_manager.get_candy(context).await.items_redeemed, 1);
+}
+
+#[tokio::test]
+async fn failed_phase_gate_writes_nothing() {
+    test_start("Failed Phase Gate Writes Nothing");
+    let mut context = candy_machine_program_test().start_with_context().await;
+    let context = &mut context;
+    let mut candy_manager = CandyManager::init(
+        context,
+        None,
+        false,
+        None,
+        Some(WhitelistConfig::new(BurnEveryTime, false, Some(1))),
+        None,
+    )
+    .await;
+
+    airdrop(context, &candy_manager.minter.pubkey(), sol(10.0))
+        .await
+        .unwrap();
+
+    let candy_data = auto_config(&candy_manager, Some(0), true, true, None, None);
+    candy_manager
+        .create(context, candy_data.clone())
+        .await
+        .unwrap();
+    candy_manager.fill_config_lines(context).await.unwrap();
+    let candy_machine = candy_manager.candy_machine.pubkey();
+
+    // The minter holds no NFT of the collection
+    set_mint_phases(
+        context,
+        &candy_machine,
+        &candy_manager.authority,
+        vec![MintPhase {
+            wallet_limit: Some(1),
+            gate: Some(PhaseGate::CollectionHolder {
+                collection: Pubkey::new_unique(),
+                once_per_nft: false,
+            }),
+            ..phase(0, candy_data.price)
+        }],
+    )
+    .await
+    .unwrap();
+
+    let mint_counter = find_phase_mint_counter(&candy_machine, &candy_manager.minter.pubkey(), 0).0;
+    let candy_balance = get_balance(context, &candy_machine).await;
+    mint_in_phase(
+        context,
+        &candy_manager,
+        vec![
+            AccountMeta::new(mint_counter, false),
+            AccountMeta::new_readonly(candy_manager.whitelist_info.minter_account, false),
+            AccountMeta::new_readonly(Pubkey::new_unique(), false),
+        ],
+    )
+    .await
+    .unwrap();
+
+    // The payer is taxed, but neither the whitelist token is burned nor the mint counted
+    assert_eq!(
+        get_balance(context, &candy_machine).await,
+        candy_balance + BOT_FEE
+    );
+    assert_eq!(
+        get_token_balance(context, &candy_manager.whitelist_info.minter_account).await,
+        1
+    );
+    assert_account_empty(context, &mint_counter).await;
+    assert_eq!(candy_manager.get_candy(context).await.items_redeemed, 0);
+}
+
+#[tokio::test]
+async fn collection_holder_gate() {
+    test_start("Collection Holder Gate");
+    let mut context = candy_machine_program_test().start_with_context().await;
+    let context = &mut context;
+    let mut candy_manager = CandyManager::init(context, Some(true), false, None, None, None).await;
+
+    airdrop(context, &candy_manager.minter.pubkey(), sol(10.0))
+        .await
+        .unwrap();
+
+    let candy_data = auto_config(&candy_manager, Some(0), true, true, None, None);
+    candy_manager
+        .create(context, candy_data.clone())
+        .await
+        .unwrap();
+    candy_manager.fill_config_lines(context).await.unwrap();
+    candy_manager.set_collection(context).await.unwrap();
+    let candy_machine = candy_manager.candy_machine.pubkey();
+
+    // An NFT of the collection minted before the phase opens it to its holder
+    let held_nft = candy_manager.mint_nft(context).await.unwrap();
+    set_mint_phases(
+        context,
+        &candy_machine,
+        &candy_manager.authority,
+        vec![MintPhase {
+            gate: Some(PhaseGate::CollectionHolder {
+                collection: candy_manager.collection_info.mint.pubkey(),
+                once_per_nft: true,
+            }),
+            ..phase(0, candy_data.price)
+        }],
+    )
+    .await
+    .unwrap();
+
+    let holder_mint_record = find_holder_mint_record(&candy_machine, &held_nft.mint.pubkey()).0;
+    let holder_accounts = vec![
+        AccountMeta::new_readonly(held_nft.token_account, false),
+        AccountMeta::new_readonly(held_nft.metadata_pubkey, false),
+        AccountMeta::new(holder_mint_record, false),
+    ];
+    mint_in_phase(context, &candy_manager, holder_accounts.clone())
+        .await
+        .unwrap();
+    assert_eq!(candy_manager.get_candy(context).await.items_redeemed, 2);
+    assert_eq!(
+        get_account(context, &holder_mint_record).await.owner,
+        mpl_candy_machine::id()
+    );
+
+    // Each held NFT mints once, and reusing it is not taxed
+    let err = match mint_in_phase(context, &candy_manager, holder_accounts)
+        .await
+        .unwrap_err()
+    {
+        BanksClientError::TransactionError(TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(err_num),
+        )) => err_num,
+        _ => 0,
+    };
+    assert_eq!(err, 6068);
+    assert_eq!(candy_manager.get_candy(context).await.items_redeemed, 2);
+}
+
+#[tokio::test]
+async fn collection_holder_gate_rejects_other_metadata() {
+    test_start("Collection Holder Gate Rejects Other Metadata");
+    let mut context = candy_machine_program_test().start_with_context().await;
+    let context = &mut context;
+    let mut candy_manager = CandyManager::init(context, Some(true), false, None, None, None).await;
+
+    airdrop(context, &candy_manager.minter.pubkey(), sol(10.0))
+        .await
+        .unwrap();
+
+    let candy_data = auto_config(&candy_manager, Some(0), true, true, None, None);
+    candy_manager
+        .create(context, candy_data.clone())
+        .await
+        .unwrap();
+    candy_manager.fill_config_lines(context).await.unwrap();
+    candy_manager.set_collection(context).await.unwrap();
+    let candy_machine = candy_manager.candy_machine.pubkey();
+
+    let held_nft = candy_manager.mint_nft(context).await.unwrap();
+    let other_nft = candy_manager.mint_nft(context).await.unwrap();
+    set_mint_phases(
+        context,
+        &candy_machine,
+        &candy_manager.authority,
+        vec![MintPhase {
+            gate: Some(PhaseGate::CollectionHolder {
+                collection: candy_manager.collection_info.mint.pubkey(),
+                once_per_nft: false,
+            }),
+            ..phase(0, candy_data.price)
+        }],
+    )
+    .await
+    .unwrap();
+
+    // The metadata of another NFT does not prove holding this one
+    let candy_balance = get_balance(context, &candy_machine).await;
+    mint_in_phase(
+        context,
+        &candy_manager,
+        vec![
+            AccountMeta::new_readonly(held_nft.token_account, false),
+            AccountMeta::new_readonly(other_nft.metadata_pubkey, false),
+        ],
+    )
+    .await
+    .unwrap();
+    assert_eq!(candy_manager.get_candy(context).await.items_redeemed, 2);
+    assert_eq!(
+        get_balance(context, &candy_machine).await,
+        candy_balance + BOT_FEE
+    );
+}
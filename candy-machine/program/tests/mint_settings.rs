@@ -0,0 +1,503 @@
+#![cfg(feature = "test-bpf")]
+#![allow(dead_code)]
+
+use anchor_client::solana_sdk::transaction::Transaction;
+use anchor_lang::AccountDeserialize;
+use mpl_candy_machine::{
+    constants::BOT_FEE, get_slot_hash, HiddenMintRecord, HiddenReveal, HiddenSettings, MintRequest,
+    MintSettings, ProgrammableSettings, RandomnessOracle,
+};
+use mpl_token_metadata::{pda::find_token_record_account, state::TokenStandard};
+use solana_program::{
+    instruction::{AccountMeta, InstructionError},
+    keccak, sysvar,
+};
+use solana_program_test::*;
+use solana_sdk::{signature::Keypair, signer::Signer, transaction::TransactionError};
+
+use crate::{
+    core::{
+        helpers::{
+            airdrop, clone_keypair, create_mint, get_account, get_balance, get_token_account,
+            prepare_nft, update_blockhash,
+        },
+        MasterEditionManager, MetadataManager,
+    },
+    utils::{
+        add_reveal_lines, auto_config, candy_machine_program_test, fulfill_mint,
+        helpers::{
+            find_candy_creator, find_hidden_mint_record, find_mint_request, find_mint_settings_pda,
+            make_config_lines, sol, test_start,
+        },
+        mint_nft_ix, reveal_mint_randomness, reveal_nft, set_mint_settings, CandyManager,
+    },
+};
+
+pub mod core;
+pub mod utils;
+
+async fn mint_with_settings(
+    context: &mut ProgramTestContext,
+    candy_manager: &CandyManager,
+    nft_info: &MasterEditionManager,
+    settings_accounts: Vec<AccountMeta>,
+) -> Result<(), BanksClientError> {
+    let candy_machine = candy_manager.candy_machine.pubkey();
+    let (candy_machine_creator, creator_bump) = find_candy_creator(&candy_machine);
+    let mut ix = mint_nft_ix(
+        &candy_machine,
+        &candy_machine_creator,
+        creator_bump,
+        &candy_manager.wallet,
+        &candy_manager.authority.pubkey(),
+        &candy_manager.minter,
+        nft_info,
+        candy_manager.token_info.clone(),
+        candy_manager.whitelist_info.clone(),
+        candy_manager.collection_info.clone(),
+        candy_manager.gateway_info.clone(),
+        candy_manager.freeze_info.clone(),
+    );
+    ix[0].accounts.push(AccountMeta::new_readonly(
+        find_mint_settings_pda(&candy_machine).0,
+        false,
+    ));
+    ix[0].accounts.extend(settings_accounts);
+
+    update_blockhash(context).await?;
+    let tx = Transaction::new_signed_with_payer(
+        ix.as_slice(),
+        Some(&candy_manager.minter.pubkey()),
+        &[&candy_manager.minter],
+        context.last_blockhash,
+    );
+    context.banks_client.process_transaction(tx).await
+}
+
+#[tokio::test]
+async fn mint_with_randomness_oracle() {
+    test_start("Mint With Randomness Oracle");
+    let mut context = candy_machine_program_test().start_with_context().await;
+    let context = &mut context;
+    let mut candy_manager = CandyManager::init(context, None, false, None, None, None).await;
+    // Local mock oracle, committed to its first seed
+    let oracle = Keypair::new();
+    let seed = [7; 32];
+    let next_commitment = keccak::hash(&[8; 32]).0;
+
+    airdrop(context, &candy_manager.minter.pubkey(), sol(10.0))
+        .await
+        .unwrap();
+    airdrop(context, &oracle.pubkey(), sol(1.0)).await.unwrap();
+
+    let mut candy_data = auto_config(&candy_manager, Some(0), true, true, None, None);
+    candy_data.items_available = 5;
+    candy_manager
+        .create(context, candy_data.clone())
+        .await
+        .unwrap();
+    candy_manager.fill_config_lines(context).await.unwrap();
+    let candy_machine = candy_manager.candy_machine.pubkey();
+    set_mint_settings(
+        context,
+        &candy_machine,
+        &candy_manager.authority,
+        Some(RandomnessOracle {
+            oracle: oracle.pubkey(),
+            commitment: keccak::hash(&seed).0,
+        }),
+        None,
+        None,
+        None,
+    )
+    .await
+    .unwrap();
+
+    // Requesting the mint reserves an item without creating the NFT yet
+    let nft_info = prepare_nft(context, &candy_manager.minter).await;
+    let mint_request = find_mint_request(&candy_machine, &nft_info.mint.pubkey()).0;
+    mint_with_settings(
+        context,
+        &candy_manager,
+        &nft_info,
+        vec![AccountMeta::new(mint_request, false)],
+    )
+    .await
+    .unwrap();
+    assert_eq!(candy_manager.get_candy(context).await.items_redeemed, 1);
+    assert!(context
+        .banks_client
+        .get_account(nft_info.metadata_pubkey)
+        .await
+        .unwrap()
+        .is_none());
+
+    // The NFT can't be minted before the oracle reveals the randomness
+    let err = match fulfill_mint(context, &candy_manager, &nft_info, &oracle)
+        .await
+        .unwrap_err()
+    {
+        BanksClientError::TransactionError(TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(err_num),
+        )) => err_num,
+        _ => 0,
+    };
+    assert_eq!(err, 6073);
+
+    let slot = context.banks_client.get_root_slot().await.unwrap();
+    context.warp_to_slot(slot + 2).unwrap();
+
+    // Only the committed seed is accepted
+    let err = match reveal_mint_randomness(
+        context,
+        &candy_machine,
+        &oracle,
+        &mint_request,
+        [8; 32],
+        next_commitment,
+    )
+    .await
+    .unwrap_err()
+    {
+        BanksClientError::TransactionError(TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(err_num),
+        )) => err_num,
+        _ => 0,
+    };
+    assert_eq!(err, 6072);
+
+    reveal_mint_randomness(
+        context,
+        &candy_machine,
+        &oracle,
+        &mint_request,
+        seed,
+        next_commitment,
+    )
+    .await
+    .unwrap();
+
+    // Anyone can check the randomness against the seed and the hash of the request slot
+    let request_account = get_account(context, &mint_request).await;
+    let request = MintRequest::try_deserialize(&mut request_account.data.as_ref()).unwrap();
+    let slot_hashes = get_account(context, &sysvar::slot_hashes::id()).await;
+    let request_slot_hash = get_slot_hash(&slot_hashes.data, request.request_slot).unwrap();
+    assert_eq!(
+        request.randomness,
+        Some(request.randomness(&seed, &request_slot_hash))
+    );
+    assert_eq!(request.minter, candy_manager.minter.pubkey());
+
+    // Requests from now on are made under the next commitment
+    let settings_account = get_account(context, &find_mint_settings_pda(&candy_machine).0).await;
+    let mint_settings = MintSettings::try_deserialize(&mut settings_account.data.as_ref()).unwrap();
+    assert_eq!(
+        mint_settings.randomness_oracle.unwrap().commitment,
+        next_commitment
+    );
+
+    fulfill_mint(context, &candy_manager, &nft_info, &oracle)
+        .await
+        .unwrap();
+
+    let metadata = MetadataManager::get_data_from_account(context, &nft_info.metadata_pubkey).await;
+    assert!(metadata.data.name.starts_with("Item #"));
+    assert!(context
+        .banks_client
+        .get_account(mint_request)
+        .await
+        .unwrap()
+        .is_none());
+}
+
+#[tokio::test]
+async fn reveal_hidden_settings_on_chain() {
+    test_start("Reveal Hidden Settings On Chain");
+    let mut context = candy_machine_program_test().start_with_context().await;
+    let context = &mut context;
+    let mut candy_manager = CandyManager::init(context, None, false, None, None, None).await;
+
+    airdrop(context, &candy_manager.minter.pubkey(), sol(10.0))
+        .await
+        .unwrap();
+
+    let reveal_lines = make_config_lines(0, 3);
+    let mut reveal_hash = [0; 32];
+    for line in &reveal_lines {
+        reveal_hash = HiddenReveal::chain_hash(&reveal_hash, line).unwrap();
+    }
+    let hidden_settings = HiddenSettings {
+        name: "Hidden ".to_string(),
+        uri: "Hidden URI".to_string(),
+        hash: [7; 32],
+    };
+    let mut candy_data = auto_config(
+        &candy_manager,
+        Some(0),
+        true,
+        false,
+        None,
+        Some(hidden_settings),
+    );
+    candy_data.items_available = 3;
+    candy_manager
+        .create(context, candy_data.clone())
+        .await
+        .unwrap();
+    let candy_machine = candy_manager.candy_machine.pubkey();
+    set_mint_settings(
+        context,
+        &candy_machine,
+        &candy_manager.authority,
+        None,
+        Some(reveal_hash),
+        None,
+        None,
+    )
+    .await
+    .unwrap();
+
+    // The NFT mints hidden and stays with the creator PDA until revealed
+    let nft_info = prepare_nft(context, &candy_manager.minter).await;
+    let hidden_mint_record = find_hidden_mint_record(&candy_machine, &nft_info.mint.pubkey()).0;
+    mint_with_settings(
+        context,
+        &candy_manager,
+        &nft_info,
+        vec![AccountMeta::new(hidden_mint_record, false)],
+    )
+    .await
+    .unwrap();
+    let metadata = MetadataManager::get_data_from_account(context, &nft_info.metadata_pubkey).await;
+    assert!(metadata.data.name.starts_with("Hidden #1"));
+    assert_eq!(
+        metadata.update_authority,
+        find_candy_creator(&candy_machine).0
+    );
+    let record = get_account(context, &hidden_mint_record).await;
+    let record = HiddenMintRecord::try_deserialize(&mut record.data.as_ref()).unwrap();
+    assert_eq!(record.mint, nft_info.mint.pubkey());
+    assert_eq!(record.minter, candy_manager.minter.pubkey());
+    assert_eq!(record.mint_number, 0);
+
+    // Lines that don't hash to the reveal hash are rejected
+    let err = match add_reveal_lines(
+        context,
+        &candy_machine,
+        &candy_manager.authority,
+        0,
+        make_config_lines(1, 3),
+    )
+    .await
+    .unwrap_err()
+    {
+        BanksClientError::TransactionError(TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(err_num),
+        )) => err_num,
+        _ => 0,
+    };
+    assert_eq!(err, 6082);
+
+    add_reveal_lines(
+        context,
+        &candy_machine,
+        &candy_manager.authority,
+        0,
+        reveal_lines,
+    )
+    .await
+    .unwrap();
+
+    // Anyone can reveal, and only once
+    reveal_nft(
+        context,
+        &candy_machine,
+        &nft_info.mint.pubkey(),
+        &nft_info.metadata_pubkey,
+        &candy_manager.minter.pubkey(),
+        &candy_manager.minter,
+    )
+    .await
+    .unwrap();
+    let metadata = MetadataManager::get_data_from_account(context, &nft_info.metadata_pubkey).await;
+    assert!(metadata.data.name.starts_with("Item #0"));
+    assert!(metadata.data.uri.starts_with("Item #0 URI"));
+    // The candy machine doesn't retain authority, so the minter gets it
+    assert_eq!(metadata.update_authority, candy_manager.minter.pubkey());
+    // The minter gets the rent of the hidden mint record back
+    assert!(context
+        .banks_client
+        .get_account(hidden_mint_record)
+        .await
+        .unwrap()
+        .is_none());
+
+    let err = match reveal_nft(
+        context,
+        &candy_machine,
+        &nft_info.mint.pubkey(),
+        &nft_info.metadata_pubkey,
+        &candy_manager.minter.pubkey(),
+        &candy_manager.minter,
+    )
+    .await
+    .unwrap_err()
+    {
+        BanksClientError::TransactionError(TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(err_num),
+        )) => err_num,
+        _ => 0,
+    };
+    // The hidden mint record is gone after the reveal
+    assert_eq!(err, 3012);
+}
+
+#[tokio::test]
+async fn mint_programmable_nft() {
+    test_start("Mint Programmable NFT");
+    let mut context = candy_machine_program_test().start_with_context().await;
+    let context = &mut context;
+    let mut candy_manager = CandyManager::init(context, None, false, None, None, None).await;
+
+    airdrop(context, &candy_manager.minter.pubkey(), sol(10.0))
+        .await
+        .unwrap();
+
+    let mut candy_data = auto_config(&candy_manager, Some(0), true, true, None, None);
+    candy_data.items_available = 3;
+    candy_manager
+        .create(context, candy_data.clone())
+        .await
+        .unwrap();
+    candy_manager.fill_config_lines(context).await.unwrap();
+    let candy_machine = candy_manager.candy_machine.pubkey();
+
+    // Mint requests mint the token before the metadata exists
+    let err = match set_mint_settings(
+        context,
+        &candy_machine,
+        &candy_manager.authority,
+        Some(RandomnessOracle {
+            oracle: Keypair::new().pubkey(),
+            commitment: [0; 32],
+        }),
+        None,
+        Some(ProgrammableSettings { rule_set: None }),
+        None,
+    )
+    .await
+    .unwrap_err()
+    {
+        BanksClientError::TransactionError(TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(err_num),
+        )) => err_num,
+        _ => 0,
+    };
+    assert_eq!(err, 6085);
+
+    set_mint_settings(
+        context,
+        &candy_machine,
+        &candy_manager.authority,
+        None,
+        None,
+        Some(ProgrammableSettings { rule_set: None }),
+        None,
+    )
+    .await
+    .unwrap();
+
+    // The token is minted by token metadata, so the mint starts out without supply
+    update_blockhash(context).await.unwrap();
+    let metadata_info = MetadataManager::new(&candy_manager.minter);
+    create_mint(
+        context,
+        &candy_manager.minter.pubkey(),
+        Some(&candy_manager.minter.pubkey()),
+        0,
+        Some(clone_keypair(&metadata_info.mint)),
+    )
+    .await
+    .unwrap();
+    let nft_info = MasterEditionManager::new(&metadata_info);
+    let token_record =
+        find_token_record_account(&nft_info.mint.pubkey(), &nft_info.token_account).0;
+    mint_with_settings(
+        context,
+        &candy_manager,
+        &nft_info,
+        vec![
+            AccountMeta::new(nft_info.token_account, false),
+            AccountMeta::new(token_record, false),
+            AccountMeta::new_readonly(spl_associated_token_account::id(), false),
+        ],
+    )
+    .await
+    .unwrap();
+
+    let metadata = MetadataManager::get_data_from_account(context, &nft_info.metadata_pubkey).await;
+    assert_eq!(
+        metadata.token_standard,
+        Some(TokenStandard::ProgrammableNonFungible)
+    );
+    assert_eq!(metadata.update_authority, candy_manager.authority.pubkey());
+    assert!(metadata.primary_sale_happened);
+    let token_account = get_token_account(context, &nft_info.token_account)
+        .await
+        .unwrap();
+    assert_eq!(token_account.amount, 1);
+    assert!(token_account.is_frozen());
+}
+
+#[tokio::test]
+async fn mint_only_through_mint_authority_program() {
+    test_start("Mint Only Through Mint Authority Program");
+    let mut context = candy_machine_program_test().start_with_context().await;
+    let context = &mut context;
+    let mut candy_manager = CandyManager::init(context, None, false, None, None, None).await;
+
+    airdrop(context, &candy_manager.minter.pubkey(), sol(10.0))
+        .await
+        .unwrap();
+
+    let candy_data = auto_config(&candy_manager, Some(0), true, true, None, None);
+    candy_manager
+        .create(context, candy_data.clone())
+        .await
+        .unwrap();
+    candy_manager.fill_config_lines(context).await.unwrap();
+    set_mint_settings(
+        context,
+        &candy_manager.candy_machine.pubkey(),
+        &candy_manager.authority,
+        None,
+        None,
+        None,
+        Some(Keypair::new().pubkey()),
+    )
+    .await
+    .unwrap();
+
+    // Calling the candy machine directly is taxed, even with a signer in place of the PDA
+    let start_balance = get_balance(context, &candy_manager.minter.pubkey()).await;
+    let nft_info = prepare_nft(context, &candy_manager.minter).await;
+    mint_with_settings(
+        context,
+        &candy_manager,
+        &nft_info,
+        vec![AccountMeta::new_readonly(
+            candy_manager.minter.pubkey(),
+            true,
+        )],
+    )
+    .await
+    .unwrap();
+
+    assert!(get_balance(context, &candy_manager.minter.pubkey()).await < start_balance - BOT_FEE);
+    assert_eq!(candy_manager.get_candy(context).await.items_redeemed, 0);
+}
@@ -0,0 +1,360 @@
+#![cfg(feature = "test-bpf")]
+#![allow(dead_code)]
+
+use anchor_client::solana_sdk::transaction::Transaction;
+use mpl_candy_machine::{MintPhase, PaymentOption, RevenueSplit};
+use mpl_token_metadata::state::CollectionDetails;
+use solana_program::instruction::{AccountMeta, InstructionError};
+use solana_program_test::*;
+use solana_sdk::{signer::Signer, transaction::TransactionError};
+
+use crate::{
+    core::helpers::{
+        airdrop, assert_account_empty, create_mint, get_balance, get_mint, get_token_balance,
+        mint_to_wallets, prepare_nft, update_blockhash,
+    },
+    utils::{
+        auto_config, candy_machine_program_test,
+        helpers::{find_candy_creator, find_payment_options, sol, test_start},
+        mint_nft_ix, set_mint_phases, set_payment_options, set_revenue_splits, CandyManager,
+    },
+};
+
+pub mod core;
+pub mod utils;
+
+async fn mint_with_payment(
+    context: &mut ProgramTestContext,
+    candy_manager: &CandyManager,
+    payment_accounts: Vec<AccountMeta>,
+) -> Result<(), BanksClientError> {
+    let candy_machine = candy_manager.candy_machine.pubkey();
+    let (candy_machine_creator, creator_bump) = find_candy_creator(&candy_machine);
+    let nft_info = prepare_nft(context, &candy_manager.minter).await;
+    let mut ix = mint_nft_ix(
+        &candy_machine,
+        &candy_machine_creator,
+        creator_bump,
+        &candy_manager.wallet,
+        &candy_manager.authority.pubkey(),
+        &candy_manager.minter,
+        &nft_info,
+        candy_manager.token_info.clone(),
+        candy_manager.whitelist_info.clone(),
+        candy_manager.collection_info.clone(),
+        candy_manager.gateway_info.clone(),
+        candy_manager.freeze_info.clone(),
+    );
+    ix[0].accounts.push(AccountMeta::new_readonly(
+        find_payment_options(&candy_machine).0,
+        false,
+    ));
+    ix[0].accounts.extend(payment_accounts);
+
+    update_blockhash(context).await?;
+    let tx = Transaction::new_signed_with_payer(
+        ix.as_slice(),
+        Some(&candy_manager.minter.pubkey()),
+        &[&candy_manager.minter],
+        context.last_blockhash,
+    );
+    context.banks_client.process_transaction(tx).await
+}
+
+fn error_code(err: BanksClientError) -> u32 {
+    match err {
+        BanksClientError::TransactionError(TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(err_num),
+        )) => err_num,
+        _ => 0,
+    }
+}
+
+#[tokio::test]
+async fn pay_with_token() {
+    test_start("Pay With Token");
+    let mut context = candy_machine_program_test().start_with_context().await;
+    let context = &mut context;
+    let mut candy_manager = CandyManager::init(context, None, false, None, None, None).await;
+
+    airdrop(context, &candy_manager.minter.pubkey(), sol(10.0))
+        .await
+        .unwrap();
+
+    let candy_data = auto_config(&candy_manager, Some(0), true, true, None, None);
+    candy_manager
+        .create(context, candy_data.clone())
+        .await
+        .unwrap();
+    candy_manager.fill_config_lines(context).await.unwrap();
+    let candy_machine = candy_manager.candy_machine.pubkey();
+
+    let mint = create_mint(context, &candy_manager.authority.pubkey(), None, 0, None)
+        .await
+        .unwrap()
+        .pubkey();
+    let atas = mint_to_wallets(
+        context,
+        &mint,
+        &candy_manager.authority,
+        vec![
+            (candy_manager.authority.pubkey(), 0),
+            (candy_manager.minter.pubkey(), 10),
+        ],
+    )
+    .await
+    .unwrap();
+    set_payment_options(
+        context,
+        &candy_machine,
+        &candy_manager.authority,
+        vec![PaymentOption::Token {
+            mint,
+            price: 4,
+            destination: atas[0],
+        }],
+        false,
+    )
+    .await
+    .unwrap();
+
+    // The token price replaces the candy machine price, the wallet gets no SOL
+    let wallet_balance = get_balance(context, &candy_manager.wallet).await;
+    mint_with_payment(
+        context,
+        &candy_manager,
+        vec![
+            AccountMeta::new(atas[1], false),
+            AccountMeta::new(atas[0], false),
+        ],
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(candy_manager.get_candy(context).await.items_redeemed, 1);
+    assert_eq!(get_token_balance(context, &atas[0]).await, 4);
+    assert_eq!(get_token_balance(context, &atas[1]).await, 6);
+    assert_eq!(
+        get_balance(context, &candy_manager.wallet).await,
+        wallet_balance
+    );
+}
+
+#[tokio::test]
+async fn pay_by_burning_tokens() {
+    test_start("Pay By Burning Tokens");
+    let mut context = candy_machine_program_test().start_with_context().await;
+    let context = &mut context;
+    let mut candy_manager = CandyManager::init(context, None, false, None, None, None).await;
+
+    airdrop(context, &candy_manager.minter.pubkey(), sol(10.0))
+        .await
+        .unwrap();
+
+    let candy_data = auto_config(&candy_manager, Some(0), true, true, None, None);
+    candy_manager
+        .create(context, candy_data.clone())
+        .await
+        .unwrap();
+    candy_manager.fill_config_lines(context).await.unwrap();
+    let candy_machine = candy_manager.candy_machine.pubkey();
+
+    let mint = create_mint(context, &candy_manager.authority.pubkey(), None, 0, None)
+        .await
+        .unwrap()
+        .pubkey();
+    let atas = mint_to_wallets(
+        context,
+        &mint,
+        &candy_manager.authority,
+        vec![(candy_manager.minter.pubkey(), 3)],
+    )
+    .await
+    .unwrap();
+    set_payment_options(
+        context,
+        &candy_machine,
+        &candy_manager.authority,
+        vec![PaymentOption::BurnToken { mint, amount: 2 }],
+        true,
+    )
+    .await
+    .unwrap();
+
+    mint_with_payment(
+        context,
+        &candy_manager,
+        vec![
+            AccountMeta::new(atas[0], false),
+            AccountMeta::new(mint, false),
+        ],
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(candy_manager.get_candy(context).await.items_redeemed, 1);
+    assert_eq!(get_token_balance(context, &atas[0]).await, 1);
+    assert_eq!(get_mint(context, &mint).await.supply, 1);
+
+    // Not enough tokens left to burn
+    let err = mint_with_payment(
+        context,
+        &candy_manager,
+        vec![
+            AccountMeta::new(atas[0], false),
+            AccountMeta::new(mint, false),
+        ],
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(error_code(err), 6007);
+    assert_eq!(candy_manager.get_candy(context).await.items_redeemed, 1);
+}
+
+#[tokio::test]
+async fn pay_by_burning_nft() {
+    test_start("Pay By Burning NFT");
+    let mut context = candy_machine_program_test().start_with_context().await;
+    let context = &mut context;
+    let mut candy_manager = CandyManager::init(context, Some(true), false, None, None, None).await;
+
+    airdrop(context, &candy_manager.minter.pubkey(), sol(10.0))
+        .await
+        .unwrap();
+
+    let candy_data = auto_config(&candy_manager, Some(0), true, true, None, None);
+    candy_manager
+        .create(context, candy_data.clone())
+        .await
+        .unwrap();
+    candy_manager.fill_config_lines(context).await.unwrap();
+    candy_manager.set_collection(context).await.unwrap();
+    let candy_machine = candy_manager.candy_machine.pubkey();
+
+    // The first NFT of the collection pays for the next one
+    let nft = candy_manager.mint_nft(context).await.unwrap();
+    set_payment_options(
+        context,
+        &candy_machine,
+        &candy_manager.authority,
+        vec![PaymentOption::BurnNft {
+            collection: candy_manager.collection_info.mint.pubkey(),
+        }],
+        false,
+    )
+    .await
+    .unwrap();
+
+    let wallet_balance = get_balance(context, &candy_manager.wallet).await;
+    mint_with_payment(
+        context,
+        &candy_manager,
+        vec![
+            AccountMeta::new(nft.token_account, false),
+            AccountMeta::new(nft.mint.pubkey(), false),
+            AccountMeta::new(nft.metadata_pubkey, false),
+            AccountMeta::new(nft.edition_pubkey, false),
+            AccountMeta::new(candy_manager.collection_info.metadata, false),
+        ],
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(candy_manager.get_candy(context).await.items_redeemed, 2);
+    assert_account_empty(context, &nft.metadata_pubkey).await;
+    assert_eq!(
+        get_balance(context, &candy_manager.wallet).await,
+        wallet_balance
+    );
+    let collection_metadata = candy_manager.collection_info.get_metadata(context).await;
+    assert_eq!(
+        collection_metadata.collection_details,
+        Some(CollectionDetails::V1 { size: 1 })
+    );
+}
+
+#[tokio::test]
+async fn payment_options_exclude_phases_and_splits() {
+    test_start("Payment Options Exclude Phases And Splits");
+    let mut context = candy_machine_program_test().start_with_context().await;
+    let context = &mut context;
+    let mut candy_manager = CandyManager::init(context, None, false, None, None, None).await;
+
+    let candy_data = auto_config(&candy_manager, Some(0), true, true, None, None);
+    candy_manager
+        .create(context, candy_data.clone())
+        .await
+        .unwrap();
+    let candy_machine = candy_manager.candy_machine.pubkey();
+    let payment_option = PaymentOption::BurnToken {
+        mint: candy_manager.minter.pubkey(),
+        amount: 1,
+    };
+    let phase = MintPhase {
+        label: "Phase".to_string(),
+        start_time: 0,
+        end_time: None,
+        price: candy_data.price / 2,
+        payment: None,
+        wallet_limit: None,
+        gate: None,
+    };
+    let split = RevenueSplit {
+        address: candy_manager.wallet,
+        basis_points: 10000,
+    };
+
+    // A payment option would silently replace the phase price and skip the splits
+    set_payment_options(
+        context,
+        &candy_machine,
+        &candy_manager.authority,
+        vec![payment_option.clone()],
+        false,
+    )
+    .await
+    .unwrap();
+    let err = set_mint_phases(
+        context,
+        &candy_machine,
+        &candy_manager.authority,
+        vec![phase.clone()],
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(error_code(err), 6111);
+    let err = set_revenue_splits(
+        context,
+        &candy_machine,
+        &candy_manager.authority,
+        vec![split.clone()],
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(error_code(err), 6111);
+
+    // The same holds the other way around
+    let mut other_manager = CandyManager::init(context, None, false, None, None, None).await;
+    let other_data = auto_config(&other_manager, Some(0), true, true, None, None);
+    other_manager.create(context, other_data).await.unwrap();
+    let other_machine = other_manager.candy_machine.pubkey();
+    set_mint_phases(
+        context,
+        &other_machine,
+        &other_manager.authority,
+        vec![phase],
+    )
+    .await
+    .unwrap();
+    let err = set_payment_options(
+        context,
+        &other_machine,
+        &other_manager.authority,
+        vec![payment_option],
+        false,
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(error_code(err), 6111);
+}
@@ -0,0 +1,136 @@
+#![cfg(feature = "test-bpf")]
+#![allow(dead_code)]
+
+use anchor_client::solana_sdk::transaction::Transaction;
+use mpl_candy_machine::RevenueSplit;
+use solana_program::{
+    instruction::{AccountMeta, InstructionError},
+    pubkey::Pubkey,
+};
+use solana_program_test::*;
+use solana_sdk::{signature::Keypair, signer::Signer, transaction::TransactionError};
+
+use crate::{
+    core::helpers::{airdrop, get_balance, prepare_nft, update_blockhash},
+    utils::{
+        auto_config, candy_machine_program_test,
+        helpers::{find_candy_creator, find_revenue_splits, sol, test_start},
+        mint_nft_ix, set_revenue_splits, CandyManager,
+    },
+};
+
+pub mod core;
+pub mod utils;
+
+async fn mint_with_splits(
+    context: &mut ProgramTestContext,
+    candy_manager: &CandyManager,
+    recipients: &[Pubkey],
+) -> Result<(), BanksClientError> {
+    let candy_machine = candy_manager.candy_machine.pubkey();
+    let (candy_machine_creator, creator_bump) = find_candy_creator(&candy_machine);
+    let nft_info = prepare_nft(context, &candy_manager.minter).await;
+    let mut ix = mint_nft_ix(
+        &candy_machine,
+        &candy_machine_creator,
+        creator_bump,
+        &candy_manager.wallet,
+        &candy_manager.authority.pubkey(),
+        &candy_manager.minter,
+        &nft_info,
+        candy_manager.token_info.clone(),
+        candy_manager.whitelist_info.clone(),
+        candy_manager.collection_info.clone(),
+        candy_manager.gateway_info.clone(),
+        candy_manager.freeze_info.clone(),
+    );
+    ix[0].accounts.push(AccountMeta::new_readonly(
+        find_revenue_splits(&candy_machine).0,
+        false,
+    ));
+    ix[0].accounts.extend(
+        recipients
+            .iter()
+            .map(|recipient| AccountMeta::new(*recipient, false)),
+    );
+
+    update_blockhash(context).await?;
+    let tx = Transaction::new_signed_with_payer(
+        ix.as_slice(),
+        Some(&candy_manager.minter.pubkey()),
+        &[&candy_manager.minter],
+        context.last_blockhash,
+    );
+    context.banks_client.process_transaction(tx).await
+}
+
+#[tokio::test]
+async fn mint_with_revenue_splits() {
+    test_start("Mint With Revenue Splits");
+    let mut context = candy_machine_program_test().start_with_context().await;
+    let context = &mut context;
+    let mut candy_manager = CandyManager::init(context, None, false, None, None, None).await;
+
+    airdrop(context, &candy_manager.minter.pubkey(), sol(10.0))
+        .await
+        .unwrap();
+
+    let candy_data = auto_config(&candy_manager, Some(0), true, true, None, None);
+    candy_manager
+        .create(context, candy_data.clone())
+        .await
+        .unwrap();
+    candy_manager.fill_config_lines(context).await.unwrap();
+    let candy_machine = candy_manager.candy_machine.pubkey();
+
+    let recipients = [Keypair::new().pubkey(), Keypair::new().pubkey()];
+    let split = |address, basis_points| RevenueSplit {
+        address,
+        basis_points,
+    };
+
+    // Shares have to add up to the whole price
+    let err = match set_revenue_splits(
+        context,
+        &candy_machine,
+        &candy_manager.authority,
+        vec![split(recipients[0], 6000), split(recipients[1], 3000)],
+    )
+    .await
+    .unwrap_err()
+    {
+        BanksClientError::TransactionError(TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(err_num),
+        )) => err_num,
+        _ => 0,
+    };
+    assert_eq!(err, 6089);
+
+    set_revenue_splits(
+        context,
+        &candy_machine,
+        &candy_manager.authority,
+        vec![split(recipients[0], 6000), split(recipients[1], 4000)],
+    )
+    .await
+    .unwrap();
+
+    // Recipients have to be passed in split order
+    mint_with_splits(context, &candy_manager, &[recipients[1], recipients[0]])
+        .await
+        .unwrap_err();
+
+    let wallet_balance = get_balance(context, &candy_manager.wallet).await;
+    mint_with_splits(context, &candy_manager, &recipients)
+        .await
+        .unwrap();
+
+    let price = candy_data.price;
+    assert_eq!(get_balance(context, &recipients[0]).await, price * 6 / 10);
+    assert_eq!(get_balance(context, &recipients[1]).await, price * 4 / 10);
+    assert_eq!(
+        get_balance(context, &candy_manager.wallet).await,
+        wallet_balance
+    );
+}
@@ -13,8 +13,9 @@ use spl_associated_token_account::get_associated_token_address;
 use spl_token::state::AccountState;
 
 use mpl_candy_machine::{
-    constants::{BOT_FEE, FREEZE_FEATURE_INDEX, FREEZE_LOCK_FEATURE_INDEX},
-    is_feature_active, CandyMachine, CandyMachineData, CollectionPDA, FreezePDA, WhitelistMintMode,
+    constants::BOT_FEE,
+    get_candy_machine_features, CandyFeatures, CandyMachine, CandyMachineData, CollectionPDA,
+    ConfigLineSettings, FreezePDA, WhitelistMintMode,
     WhitelistMintMode::{BurnEveryTime, NeverBurn},
 };
 
@@ -330,6 +331,7 @@ impl Clone for GatekeeperInfo {
 pub struct FreezeInfo {
     pub freeze_time: i64,
     pub set: bool,
+    pub refund: bool,
     pub ata: Pubkey,
     pub pda: Pubkey,
 }
@@ -341,6 +343,7 @@ impl FreezeInfo {
         let freeze_ata = get_associated_token_address(&pda, &mint);
         FreezeInfo {
             set,
+            refund: false,
             pda,
             freeze_time,
             ata: freeze_ata,
@@ -634,6 +637,12 @@ impl CandyManager {
         CandyMachine::try_deserialize(&mut account.data.as_ref()).unwrap()
     }
 
+    pub async fn get_features(&self, context: &mut ProgramTestContext) -> CandyFeatures {
+        let account = get_account(context, &self.candy_machine.pubkey()).await;
+        let candy_machine = CandyMachine::try_deserialize(&mut account.data.as_ref()).unwrap();
+        get_candy_machine_features(&candy_machine, &account.data)
+    }
+
     pub async fn get_collection_pda(&self, context: &mut ProgramTestContext) -> CollectionPDA {
         let account = get_account(context, &self.collection_info.pda).await;
         CollectionPDA::try_deserialize(&mut account.data.as_ref()).unwrap()
@@ -650,16 +659,9 @@ impl CandyManager {
         expected_freeze_pda: &FreezePDA,
     ) -> FreezePDA {
         let freeze_pda_account = self.get_freeze_pda(context).await;
-        let candy_machine_account = self.get_candy(context).await;
+        let features = self.get_features(context).await;
         assert_eq!(*expected_freeze_pda, freeze_pda_account);
-        assert!(is_feature_active(
-            &candy_machine_account.data.uuid,
-            FREEZE_FEATURE_INDEX
-        ));
-        assert!(is_feature_active(
-            &candy_machine_account.data.uuid,
-            FREEZE_LOCK_FEATURE_INDEX
-        ));
+        assert!(features.contains(CandyFeatures::FREEZE | CandyFeatures::FREEZE_LOCK));
         freeze_pda_account
     }
 
@@ -737,6 +739,28 @@ impl CandyManager {
             &self.wallet,
             candy_data,
             self.token_info.clone(),
+            None,
+        )
+        .await?;
+        logger.end();
+        Ok(())
+    }
+
+    pub async fn create_compressed(
+        &mut self,
+        context: &mut ProgramTestContext,
+        candy_data: CandyMachineData,
+        config_line_settings: ConfigLineSettings,
+    ) -> transport::Result<()> {
+        let logger = CandyTestLogger::new_start("Initialize Compressed Candy Machine");
+        initialize_candy_machine(
+            context,
+            &self.candy_machine,
+            &self.authority,
+            &self.wallet,
+            candy_data,
+            self.token_info.clone(),
+            Some(config_line_settings),
         )
         .await?;
         logger.end();
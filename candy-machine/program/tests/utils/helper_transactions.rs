@@ -9,8 +9,8 @@ use solana_program_test::*;
 use solana_sdk::{signature::Keypair, transaction::Transaction};
 
 use mpl_candy_machine::{
-    constants::{CONFIG_ARRAY_START, CONFIG_LINE_SIZE},
-    CandyMachine, CandyMachineData, ConfigLine,
+    get_space_for_candy, BotTax, CandyMachine, CandyMachineData, ConfigLine, ConfigLineSettings,
+    MintPhase, PaymentOption, ProgrammableSettings, RandomnessOracle, RevenueSplit,
     WhitelistMintMode::BurnEveryTime,
 };
 
@@ -18,8 +18,13 @@ use crate::{
     core::{helpers::update_blockhash, MasterEditionManager},
     utils::{
         candy_manager::{CollectionInfo, GatekeeperInfo, TokenInfo, WhitelistInfo},
-        helpers::make_config_lines,
-        FreezeInfo,
+        helpers::{
+            find_allowlist_proof, find_allowlist_root, find_candy_creator, find_edition_settings,
+            find_hidden_mint_record, find_hidden_reveal, find_mint_counter, find_mint_limit_pda,
+            find_mint_phases, find_mint_request, find_mint_settings_pda, find_payment_options,
+            find_revenue_splits, make_config_lines,
+        },
+        CandyManager, FreezeInfo,
     },
 };
 use std::result::Result;
@@ -38,17 +43,10 @@ pub async fn initialize_candy_machine(
     wallet: &Pubkey,
     candy_data: CandyMachineData,
     token_info: TokenInfo,
+    config_line_settings: Option<ConfigLineSettings>,
 ) -> Result<(), BanksClientError> {
-    let items_available = candy_data.items_available;
-    let candy_account_size = if candy_data.hidden_settings.is_some() {
-        CONFIG_ARRAY_START
-    } else {
-        CONFIG_ARRAY_START
-            + 4
-            + items_available as usize * CONFIG_LINE_SIZE
-            + 8
-            + 2 * (items_available as usize / 8 + 1)
-    };
+    let candy_account_size =
+        get_space_for_candy(&candy_data, config_line_settings.as_ref()).unwrap();
 
     let rent = context.banks_client.get_rent().await?;
     let lamports = rent.minimum_balance(candy_account_size);
@@ -74,7 +72,16 @@ pub async fn initialize_candy_machine(
         accounts.push(AccountMeta::new_readonly(token_info.mint, false));
     }
 
-    let data = mpl_candy_machine::instruction::InitializeCandyMachine { data: candy_data }.data();
+    let data = match config_line_settings {
+        Some(config_line_settings) => {
+            mpl_candy_machine::instruction::InitializeCompressedCandyMachine {
+                data: candy_data,
+                config_line_settings,
+            }
+            .data()
+        }
+        None => mpl_candy_machine::instruction::InitializeCandyMachine { data: candy_data }.data(),
+    };
 
     let init_ix = Instruction {
         program_id: mpl_candy_machine::id(),
@@ -163,6 +170,68 @@ pub async fn update_authority(
     context.banks_client.process_transaction(tx).await
 }
 
+pub async fn update_bot_tax(
+    context: &mut ProgramTestContext,
+    candy_machine: &Pubkey,
+    authority: &Keypair,
+    wallet: &Pubkey,
+    bot_tax: Option<BotTax>,
+) -> Result<(), BanksClientError> {
+    let accounts = mpl_candy_machine::accounts::UpdateCandyMachine {
+        candy_machine: *candy_machine,
+        authority: authority.pubkey(),
+        wallet: *wallet,
+    }
+    .to_account_metas(None);
+    let data = mpl_candy_machine::instruction::UpdateBotTax { bot_tax }.data();
+    let update_ix = Instruction {
+        program_id: mpl_candy_machine::id(),
+        data,
+        accounts,
+    };
+
+    update_blockhash(context).await?;
+    let tx = Transaction::new_signed_with_payer(
+        &[update_ix],
+        Some(&authority.pubkey()),
+        &[authority],
+        context.last_blockhash,
+    );
+
+    context.banks_client.process_transaction(tx).await
+}
+
+pub async fn update_sequential(
+    context: &mut ProgramTestContext,
+    candy_machine: &Pubkey,
+    authority: &Keypair,
+    wallet: &Pubkey,
+    sequential: bool,
+) -> Result<(), BanksClientError> {
+    let accounts = mpl_candy_machine::accounts::UpdateCandyMachine {
+        candy_machine: *candy_machine,
+        authority: authority.pubkey(),
+        wallet: *wallet,
+    }
+    .to_account_metas(None);
+    let data = mpl_candy_machine::instruction::UpdateSequential { sequential }.data();
+    let update_ix = Instruction {
+        program_id: mpl_candy_machine::id(),
+        data,
+        accounts,
+    };
+
+    update_blockhash(context).await?;
+    let tx = Transaction::new_signed_with_payer(
+        &[update_ix],
+        Some(&authority.pubkey()),
+        &[authority],
+        context.last_blockhash,
+    );
+
+    context.banks_client.process_transaction(tx).await
+}
+
 pub async fn add_config_lines(
     context: &mut ProgramTestContext,
     candy_machine: &Pubkey,
@@ -220,9 +289,9 @@ pub async fn add_all_config_lines(
         let config_lines = make_config_lines(index, 10);
         add_config_lines(context, candy_machine, authority, index, config_lines).await?;
     }
-    let remainder = total_items & 10;
+    let remainder = total_items % 10;
     if remainder > 0 {
-        let index = (total_items as u32 / 10).saturating_sub(1);
+        let index = (total_items - remainder) as u32;
         let config_lines = make_config_lines(index, remainder as u8);
         add_config_lines(context, candy_machine, authority, index, config_lines).await?;
     }
@@ -324,8 +393,179 @@ pub async fn set_freeze(
         accounts.push(AccountMeta::new(freeze_info.ata, false));
     }
 
-    let data = mpl_candy_machine::instruction::SetFreeze {
-        freeze_time: freeze_info.freeze_time,
+    let data = if freeze_info.refund {
+        mpl_candy_machine::instruction::SetRefundableFreeze {
+            freeze_time: freeze_info.freeze_time,
+        }
+        .data()
+    } else {
+        mpl_candy_machine::instruction::SetFreeze {
+            freeze_time: freeze_info.freeze_time,
+        }
+        .data()
+    };
+    let set_ix = Instruction {
+        program_id: mpl_candy_machine::id(),
+        data,
+        accounts,
+    };
+
+    update_blockhash(context).await?;
+    let tx = Transaction::new_signed_with_payer(
+        &[set_ix],
+        Some(&authority.pubkey()),
+        &[authority],
+        context.last_blockhash,
+    );
+
+    context.banks_client.process_transaction(tx).await
+}
+
+pub async fn migrate_candy_machine_version(
+    context: &mut ProgramTestContext,
+    candy_machine: &Pubkey,
+    authority: &Keypair,
+) -> Result<(), BanksClientError> {
+    let accounts = mpl_candy_machine::accounts::MigrateCandyMachineVersion {
+        candy_machine: *candy_machine,
+        authority: authority.pubkey(),
+        payer: authority.pubkey(),
+        system_program: system_program::id(),
+    }
+    .to_account_metas(None);
+
+    let data = mpl_candy_machine::instruction::MigrateCandyMachineVersion {}.data();
+    let migrate_ix = Instruction {
+        program_id: mpl_candy_machine::id(),
+        data,
+        accounts,
+    };
+
+    update_blockhash(context).await?;
+    let tx = Transaction::new_signed_with_payer(
+        &[migrate_ix],
+        Some(&authority.pubkey()),
+        &[authority],
+        context.last_blockhash,
+    );
+
+    context.banks_client.process_transaction(tx).await
+}
+
+pub async fn close_config_data(
+    context: &mut ProgramTestContext,
+    candy_machine: &Pubkey,
+    authority: &Keypair,
+) -> Result<(), BanksClientError> {
+    let accounts = mpl_candy_machine::accounts::CloseConfigData {
+        candy_machine: *candy_machine,
+        authority: authority.pubkey(),
+    }
+    .to_account_metas(None);
+
+    let data = mpl_candy_machine::instruction::CloseConfigData {}.data();
+    let close_ix = Instruction {
+        program_id: mpl_candy_machine::id(),
+        data,
+        accounts,
+    };
+
+    update_blockhash(context).await?;
+    let tx = Transaction::new_signed_with_payer(
+        &[close_ix],
+        Some(&authority.pubkey()),
+        &[authority],
+        context.last_blockhash,
+    );
+
+    context.banks_client.process_transaction(tx).await
+}
+
+pub async fn migrate_config_indices(
+    context: &mut ProgramTestContext,
+    candy_machine: &Pubkey,
+    authority: &Keypair,
+) -> Result<(), BanksClientError> {
+    let accounts = mpl_candy_machine::accounts::MigrateConfigIndices {
+        candy_machine: *candy_machine,
+        authority: authority.pubkey(),
+        payer: authority.pubkey(),
+        system_program: system_program::id(),
+    }
+    .to_account_metas(None);
+
+    let data = mpl_candy_machine::instruction::MigrateConfigIndices {}.data();
+    let migrate_ix = Instruction {
+        program_id: mpl_candy_machine::id(),
+        data,
+        accounts,
+    };
+
+    update_blockhash(context).await?;
+    let tx = Transaction::new_signed_with_payer(
+        &[migrate_ix],
+        Some(&authority.pubkey()),
+        &[authority],
+        context.last_blockhash,
+    );
+
+    context.banks_client.process_transaction(tx).await
+}
+
+pub async fn set_mint_limit(
+    context: &mut ProgramTestContext,
+    candy_machine: &Pubkey,
+    authority: &Keypair,
+    limit: u16,
+) -> Result<(), BanksClientError> {
+    let accounts = mpl_candy_machine::accounts::SetMintLimit {
+        candy_machine: *candy_machine,
+        authority: authority.pubkey(),
+        mint_limit: find_mint_limit_pda(candy_machine).0,
+        system_program: system_program::id(),
+    }
+    .to_account_metas(None);
+
+    let data = mpl_candy_machine::instruction::SetMintLimit { limit }.data();
+    let set_ix = Instruction {
+        program_id: mpl_candy_machine::id(),
+        data,
+        accounts,
+    };
+
+    update_blockhash(context).await?;
+    let tx = Transaction::new_signed_with_payer(
+        &[set_ix],
+        Some(&authority.pubkey()),
+        &[authority],
+        context.last_blockhash,
+    );
+
+    context.banks_client.process_transaction(tx).await
+}
+
+pub async fn set_mint_settings(
+    context: &mut ProgramTestContext,
+    candy_machine: &Pubkey,
+    authority: &Keypair,
+    randomness_oracle: Option<RandomnessOracle>,
+    reveal_hash: Option<[u8; 32]>,
+    programmable: Option<ProgrammableSettings>,
+    mint_authority_program: Option<Pubkey>,
+) -> Result<(), BanksClientError> {
+    let accounts = mpl_candy_machine::accounts::SetMintSettings {
+        candy_machine: *candy_machine,
+        authority: authority.pubkey(),
+        mint_settings: find_mint_settings_pda(candy_machine).0,
+        system_program: system_program::id(),
+    }
+    .to_account_metas(None);
+
+    let data = mpl_candy_machine::instruction::SetMintSettings {
+        randomness_oracle,
+        reveal_hash,
+        programmable,
+        mint_authority_program,
     }
     .data();
     let set_ix = Instruction {
@@ -345,25 +585,27 @@ pub async fn set_freeze(
     context.banks_client.process_transaction(tx).await
 }
 
-pub async fn remove_freeze(
+pub async fn set_mint_phases(
     context: &mut ProgramTestContext,
     candy_machine: &Pubkey,
     authority: &Keypair,
-    freeze_info: &FreezeInfo,
+    phases: Vec<MintPhase>,
 ) -> Result<(), BanksClientError> {
-    let accounts = mpl_candy_machine::accounts::RemoveFreeze {
+    let accounts = mpl_candy_machine::accounts::SetMintPhases {
         candy_machine: *candy_machine,
         authority: authority.pubkey(),
-        freeze_pda: freeze_info.pda,
+        mint_phases: find_mint_phases(candy_machine).0,
+        system_program: system_program::id(),
     }
     .to_account_metas(None);
 
-    let data = mpl_candy_machine::instruction::RemoveFreeze {}.data();
+    let data = mpl_candy_machine::instruction::SetMintPhases { phases }.data();
     let set_ix = Instruction {
         program_id: mpl_candy_machine::id(),
         data,
         accounts,
     };
+
     update_blockhash(context).await?;
     let tx = Transaction::new_signed_with_payer(
         &[set_ix],
@@ -375,38 +617,548 @@ pub async fn remove_freeze(
     context.banks_client.process_transaction(tx).await
 }
 
-pub async fn thaw_nft(
+pub async fn set_allowlist_root(
     context: &mut ProgramTestContext,
     candy_machine: &Pubkey,
-    signer: &Keypair,
-    freeze_info: &FreezeInfo,
-    nft_info: &MasterEditionManager,
+    authority: &Keypair,
+    root: [u8; 32],
 ) -> Result<(), BanksClientError> {
-    let accounts = mpl_candy_machine::accounts::ThawNFT {
-        freeze_pda: freeze_info.pda,
+    let accounts = mpl_candy_machine::accounts::SetAllowlistRoot {
         candy_machine: *candy_machine,
-        token_account: nft_info.token_account,
-        owner: nft_info.owner.pubkey(),
-        mint: nft_info.mint.pubkey(),
-        edition: nft_info.edition_pubkey,
-        payer: signer.pubkey(),
-        token_program: spl_token::ID,
-        token_metadata_program: mpl_token_metadata::ID,
+        authority: authority.pubkey(),
+        allowlist_root: find_allowlist_root(candy_machine).0,
         system_program: system_program::id(),
     }
     .to_account_metas(None);
 
-    let data = mpl_candy_machine::instruction::ThawNft {}.data();
+    let data = mpl_candy_machine::instruction::SetAllowlistRoot { root }.data();
     let set_ix = Instruction {
         program_id: mpl_candy_machine::id(),
         data,
         accounts,
     };
+
     update_blockhash(context).await?;
     let tx = Transaction::new_signed_with_payer(
         &[set_ix],
-        Some(&signer.pubkey()),
-        &[signer],
+        Some(&authority.pubkey()),
+        &[authority],
+        context.last_blockhash,
+    );
+
+    context.banks_client.process_transaction(tx).await
+}
+
+pub async fn prove_allowlist(
+    context: &mut ProgramTestContext,
+    candy_machine: &Pubkey,
+    payer: &Keypair,
+    root: &[u8; 32],
+    proof: Vec<[u8; 32]>,
+    index: u64,
+    allocation: u64,
+) -> Result<(), BanksClientError> {
+    let accounts = mpl_candy_machine::accounts::ProveAllowlist {
+        candy_machine: *candy_machine,
+        payer: payer.pubkey(),
+        allowlist_root: find_allowlist_root(candy_machine).0,
+        allowlist_proof: find_allowlist_proof(candy_machine, &payer.pubkey(), root).0,
+        system_program: system_program::id(),
+    }
+    .to_account_metas(None);
+
+    let data = mpl_candy_machine::instruction::ProveAllowlist {
+        proof,
+        index,
+        allocation,
+    }
+    .data();
+    let prove_ix = Instruction {
+        program_id: mpl_candy_machine::id(),
+        data,
+        accounts,
+    };
+
+    update_blockhash(context).await?;
+    let tx = Transaction::new_signed_with_payer(
+        &[prove_ix],
+        Some(&payer.pubkey()),
+        &[payer],
+        context.last_blockhash,
+    );
+
+    context.banks_client.process_transaction(tx).await
+}
+
+pub async fn set_revenue_splits(
+    context: &mut ProgramTestContext,
+    candy_machine: &Pubkey,
+    authority: &Keypair,
+    splits: Vec<RevenueSplit>,
+) -> Result<(), BanksClientError> {
+    let accounts = mpl_candy_machine::accounts::SetRevenueSplits {
+        candy_machine: *candy_machine,
+        authority: authority.pubkey(),
+        revenue_splits: find_revenue_splits(candy_machine).0,
+        system_program: system_program::id(),
+    }
+    .to_account_metas(None);
+
+    let data = mpl_candy_machine::instruction::SetRevenueSplits { splits }.data();
+    let set_ix = Instruction {
+        program_id: mpl_candy_machine::id(),
+        data,
+        accounts,
+    };
+
+    update_blockhash(context).await?;
+    let tx = Transaction::new_signed_with_payer(
+        &[set_ix],
+        Some(&authority.pubkey()),
+        &[authority],
+        context.last_blockhash,
+    );
+
+    context.banks_client.process_transaction(tx).await
+}
+
+pub async fn set_payment_options(
+    context: &mut ProgramTestContext,
+    candy_machine: &Pubkey,
+    authority: &Keypair,
+    options: Vec<PaymentOption>,
+    required: bool,
+) -> Result<(), BanksClientError> {
+    let mut accounts = mpl_candy_machine::accounts::SetPaymentOptions {
+        candy_machine: *candy_machine,
+        authority: authority.pubkey(),
+        payment_options: find_payment_options(candy_machine).0,
+        system_program: system_program::id(),
+    }
+    .to_account_metas(None);
+    for option in &options {
+        if let PaymentOption::Token { destination, .. } = option {
+            accounts.push(AccountMeta::new_readonly(*destination, false));
+        }
+    }
+
+    let data = mpl_candy_machine::instruction::SetPaymentOptions { options, required }.data();
+    let set_ix = Instruction {
+        program_id: mpl_candy_machine::id(),
+        data,
+        accounts,
+    };
+
+    update_blockhash(context).await?;
+    let tx = Transaction::new_signed_with_payer(
+        &[set_ix],
+        Some(&authority.pubkey()),
+        &[authority],
+        context.last_blockhash,
+    );
+
+    context.banks_client.process_transaction(tx).await
+}
+
+pub async fn set_edition_settings(
+    context: &mut ProgramTestContext,
+    candy_machine: &Pubkey,
+    authority: &Keypair,
+    master_edition: &MasterEditionManager,
+    master_token_account: &Pubkey,
+) -> Result<(), BanksClientError> {
+    let accounts = mpl_candy_machine::accounts::SetEditionSettings {
+        candy_machine: *candy_machine,
+        authority: authority.pubkey(),
+        edition_settings: find_edition_settings(candy_machine).0,
+        candy_machine_creator: find_candy_creator(candy_machine).0,
+        master_mint: master_edition.mint.pubkey(),
+        master_edition: master_edition.edition_pubkey,
+        master_token_account: *master_token_account,
+        system_program: system_program::id(),
+    }
+    .to_account_metas(None);
+
+    let data = mpl_candy_machine::instruction::SetEditionSettings {}.data();
+    let set_ix = Instruction {
+        program_id: mpl_candy_machine::id(),
+        data,
+        accounts,
+    };
+
+    update_blockhash(context).await?;
+    let tx = Transaction::new_signed_with_payer(
+        &[set_ix],
+        Some(&authority.pubkey()),
+        &[authority],
+        context.last_blockhash,
+    );
+
+    context.banks_client.process_transaction(tx).await
+}
+
+pub async fn remove_edition_settings(
+    context: &mut ProgramTestContext,
+    candy_machine: &Pubkey,
+    authority: &Keypair,
+    master_token_account: &Pubkey,
+    destination: &Pubkey,
+) -> Result<(), BanksClientError> {
+    let accounts = mpl_candy_machine::accounts::RemoveEditionSettings {
+        candy_machine: *candy_machine,
+        authority: authority.pubkey(),
+        edition_settings: find_edition_settings(candy_machine).0,
+        candy_machine_creator: find_candy_creator(candy_machine).0,
+        master_token_account: *master_token_account,
+        destination: *destination,
+        token_program: spl_token::id(),
+    }
+    .to_account_metas(None);
+
+    let data = mpl_candy_machine::instruction::RemoveEditionSettings {}.data();
+    let remove_ix = Instruction {
+        program_id: mpl_candy_machine::id(),
+        data,
+        accounts,
+    };
+
+    update_blockhash(context).await?;
+    let tx = Transaction::new_signed_with_payer(
+        &[remove_ix],
+        Some(&authority.pubkey()),
+        &[authority],
+        context.last_blockhash,
+    );
+
+    context.banks_client.process_transaction(tx).await
+}
+
+pub async fn reveal_mint_randomness(
+    context: &mut ProgramTestContext,
+    candy_machine: &Pubkey,
+    oracle: &Keypair,
+    mint_request: &Pubkey,
+    seed: [u8; 32],
+    next_commitment: [u8; 32],
+) -> Result<(), BanksClientError> {
+    let accounts = mpl_candy_machine::accounts::RevealMintRandomness {
+        candy_machine: *candy_machine,
+        mint_settings: find_mint_settings_pda(candy_machine).0,
+        oracle: oracle.pubkey(),
+        mint_request: *mint_request,
+        recent_slothashes: sysvar::slot_hashes::id(),
+    }
+    .to_account_metas(None);
+
+    let data = mpl_candy_machine::instruction::RevealMintRandomness {
+        seed,
+        next_commitment,
+    }
+    .data();
+    let reveal_ix = Instruction {
+        program_id: mpl_candy_machine::id(),
+        data,
+        accounts,
+    };
+
+    update_blockhash(context).await?;
+    let tx = Transaction::new_signed_with_payer(
+        &[reveal_ix],
+        Some(&oracle.pubkey()),
+        &[oracle],
+        context.last_blockhash,
+    );
+
+    context.banks_client.process_transaction(tx).await
+}
+
+pub async fn fulfill_mint(
+    context: &mut ProgramTestContext,
+    candy_manager: &CandyManager,
+    nft_info: &MasterEditionManager,
+    payer: &Keypair,
+) -> Result<(), BanksClientError> {
+    let candy_machine = candy_manager.candy_machine.pubkey();
+    let (candy_machine_creator, creator_bump) = find_candy_creator(&candy_machine);
+    let accounts = mpl_candy_machine::accounts::FulfillMint {
+        candy_machine,
+        candy_machine_creator,
+        mint_request: find_mint_request(&candy_machine, &nft_info.mint.pubkey()).0,
+        minter: candy_manager.minter.pubkey(),
+        payer: payer.pubkey(),
+        metadata: nft_info.metadata_pubkey,
+        mint: nft_info.mint.pubkey(),
+        master_edition: nft_info.edition_pubkey,
+        token_metadata_program: mpl_token_metadata::id(),
+        token_program: spl_token::id(),
+        system_program: system_program::id(),
+        rent: sysvar::rent::id(),
+    }
+    .to_account_metas(None);
+
+    let data = mpl_candy_machine::instruction::FulfillMint { creator_bump }.data();
+    let fulfill_ix = Instruction {
+        program_id: mpl_candy_machine::id(),
+        data,
+        accounts,
+    };
+
+    update_blockhash(context).await?;
+    let tx = Transaction::new_signed_with_payer(
+        &[fulfill_ix],
+        Some(&payer.pubkey()),
+        &[payer],
+        context.last_blockhash,
+    );
+
+    context.banks_client.process_transaction(tx).await
+}
+
+pub async fn add_reveal_lines(
+    context: &mut ProgramTestContext,
+    candy_machine: &Pubkey,
+    authority: &Keypair,
+    index: u32,
+    config_lines: Vec<ConfigLine>,
+) -> Result<(), BanksClientError> {
+    let accounts = mpl_candy_machine::accounts::AddRevealLines {
+        candy_machine: *candy_machine,
+        authority: authority.pubkey(),
+        mint_settings: find_mint_settings_pda(candy_machine).0,
+        hidden_reveal: find_hidden_reveal(candy_machine).0,
+        system_program: system_program::id(),
+    }
+    .to_account_metas(None);
+
+    let data = mpl_candy_machine::instruction::AddRevealLines {
+        index,
+        config_lines,
+    }
+    .data();
+    let add_ix = Instruction {
+        program_id: mpl_candy_machine::id(),
+        data,
+        accounts,
+    };
+
+    update_blockhash(context).await?;
+    let tx = Transaction::new_signed_with_payer(
+        &[add_ix],
+        Some(&authority.pubkey()),
+        &[authority],
+        context.last_blockhash,
+    );
+
+    context.banks_client.process_transaction(tx).await
+}
+
+pub async fn reveal_nft(
+    context: &mut ProgramTestContext,
+    candy_machine: &Pubkey,
+    mint: &Pubkey,
+    metadata: &Pubkey,
+    minter: &Pubkey,
+    payer: &Keypair,
+) -> Result<(), BanksClientError> {
+    let (candy_machine_creator, creator_bump) = find_candy_creator(candy_machine);
+    let accounts = mpl_candy_machine::accounts::RevealNft {
+        candy_machine: *candy_machine,
+        hidden_reveal: find_hidden_reveal(candy_machine).0,
+        hidden_mint_record: find_hidden_mint_record(candy_machine, mint).0,
+        minter: *minter,
+        candy_machine_creator,
+        metadata: *metadata,
+        token_metadata_program: mpl_token_metadata::id(),
+    }
+    .to_account_metas(None);
+
+    let data = mpl_candy_machine::instruction::RevealNft { creator_bump }.data();
+    let reveal_ix = Instruction {
+        program_id: mpl_candy_machine::id(),
+        data,
+        accounts,
+    };
+
+    update_blockhash(context).await?;
+    let tx = Transaction::new_signed_with_payer(
+        &[reveal_ix],
+        Some(&payer.pubkey()),
+        &[payer],
+        context.last_blockhash,
+    );
+
+    context.banks_client.process_transaction(tx).await
+}
+
+pub async fn close_mint_counter(
+    context: &mut ProgramTestContext,
+    candy_machine: &Pubkey,
+    authority: &Keypair,
+    wallet: &Pubkey,
+) -> Result<(), BanksClientError> {
+    let accounts = mpl_candy_machine::accounts::CloseMintCounter {
+        candy_machine: *candy_machine,
+        authority: authority.pubkey(),
+        mint_counter: find_mint_counter(candy_machine, wallet).0,
+        wallet: *wallet,
+    }
+    .to_account_metas(None);
+
+    let data = mpl_candy_machine::instruction::CloseMintCounter {}.data();
+    let close_ix = Instruction {
+        program_id: mpl_candy_machine::id(),
+        data,
+        accounts,
+    };
+
+    update_blockhash(context).await?;
+    let tx = Transaction::new_signed_with_payer(
+        &[close_ix],
+        Some(&authority.pubkey()),
+        &[authority],
+        context.last_blockhash,
+    );
+
+    context.banks_client.process_transaction(tx).await
+}
+
+pub async fn remove_freeze(
+    context: &mut ProgramTestContext,
+    candy_machine: &Pubkey,
+    authority: &Keypair,
+    freeze_info: &FreezeInfo,
+) -> Result<(), BanksClientError> {
+    let accounts = mpl_candy_machine::accounts::RemoveFreeze {
+        candy_machine: *candy_machine,
+        authority: authority.pubkey(),
+        freeze_pda: freeze_info.pda,
+    }
+    .to_account_metas(None);
+
+    let data = mpl_candy_machine::instruction::RemoveFreeze {}.data();
+    let set_ix = Instruction {
+        program_id: mpl_candy_machine::id(),
+        data,
+        accounts,
+    };
+    update_blockhash(context).await?;
+    let tx = Transaction::new_signed_with_payer(
+        &[set_ix],
+        Some(&authority.pubkey()),
+        &[authority],
+        context.last_blockhash,
+    );
+
+    context.banks_client.process_transaction(tx).await
+}
+
+pub async fn thaw_nft(
+    context: &mut ProgramTestContext,
+    candy_machine: &Pubkey,
+    signer: &Keypair,
+    freeze_info: &FreezeInfo,
+    nft_info: &MasterEditionManager,
+) -> Result<(), BanksClientError> {
+    let accounts = mpl_candy_machine::accounts::ThawNFT {
+        freeze_pda: freeze_info.pda,
+        candy_machine: *candy_machine,
+        token_account: nft_info.token_account,
+        owner: nft_info.owner.pubkey(),
+        mint: nft_info.mint.pubkey(),
+        edition: nft_info.edition_pubkey,
+        payer: signer.pubkey(),
+        token_program: spl_token::ID,
+        token_metadata_program: mpl_token_metadata::ID,
+        system_program: system_program::id(),
+    }
+    .to_account_metas(None);
+
+    let data = mpl_candy_machine::instruction::ThawNft {}.data();
+    let set_ix = Instruction {
+        program_id: mpl_candy_machine::id(),
+        data,
+        accounts,
+    };
+    update_blockhash(context).await?;
+    let tx = Transaction::new_signed_with_payer(
+        &[set_ix],
+        Some(&signer.pubkey()),
+        &[signer],
+        context.last_blockhash,
+    );
+
+    context.banks_client.process_transaction(tx).await
+}
+
+pub async fn thaw_nfts(
+    context: &mut ProgramTestContext,
+    candy_machine: &Pubkey,
+    signer: &Keypair,
+    freeze_info: &FreezeInfo,
+    nft_infos: &[&MasterEditionManager],
+) -> Result<(), BanksClientError> {
+    let mut accounts = mpl_candy_machine::accounts::ThawNFTs {
+        freeze_pda: freeze_info.pda,
+        candy_machine: *candy_machine,
+        payer: signer.pubkey(),
+        token_program: spl_token::ID,
+        token_metadata_program: mpl_token_metadata::ID,
+        system_program: system_program::id(),
+    }
+    .to_account_metas(None);
+    for nft_info in nft_infos {
+        accounts.push(AccountMeta::new(nft_info.token_account, false));
+        accounts.push(AccountMeta::new_readonly(nft_info.mint.pubkey(), false));
+        accounts.push(AccountMeta::new_readonly(nft_info.edition_pubkey, false));
+    }
+
+    let data = mpl_candy_machine::instruction::ThawNfts {}.data();
+    let thaw_ix = Instruction {
+        program_id: mpl_candy_machine::id(),
+        data,
+        accounts,
+    };
+    update_blockhash(context).await?;
+    let tx = Transaction::new_signed_with_payer(
+        &[thaw_ix],
+        Some(&signer.pubkey()),
+        &[signer],
+        context.last_blockhash,
+    );
+
+    context.banks_client.process_transaction(tx).await
+}
+
+pub async fn refund_nft(
+    context: &mut ProgramTestContext,
+    candy_machine: &Pubkey,
+    freeze_info: &FreezeInfo,
+    nft_info: &MasterEditionManager,
+) -> Result<(), BanksClientError> {
+    let accounts = mpl_candy_machine::accounts::RefundNFT {
+        freeze_pda: freeze_info.pda,
+        candy_machine: *candy_machine,
+        token_account: nft_info.token_account,
+        owner: nft_info.owner.pubkey(),
+        mint: nft_info.mint.pubkey(),
+        metadata: nft_info.metadata_pubkey,
+        edition: nft_info.edition_pubkey,
+        token_program: spl_token::ID,
+        token_metadata_program: mpl_token_metadata::ID,
+    }
+    .to_account_metas(None);
+
+    let data = mpl_candy_machine::instruction::RefundNft {}.data();
+    let refund_ix = Instruction {
+        program_id: mpl_candy_machine::id(),
+        data,
+        accounts,
+    };
+    update_blockhash(context).await?;
+    let tx = Transaction::new_signed_with_payer(
+        &[refund_ix],
+        Some(&nft_info.owner.pubkey()),
+        &[&nft_info.owner],
         context.last_blockhash,
     );
 
@@ -3,7 +3,11 @@ use solana_program::{native_token::LAMPORTS_PER_SOL, pubkey::Pubkey};
 use spl_associated_token_account::get_associated_token_address;
 
 use crate::utils::{FreezeInfo, TokenInfo};
-use mpl_candy_machine::{constants::PREFIX as CANDY_PREFIX, CollectionPDA, ConfigLine};
+use mpl_candy_machine::{
+    constants::PREFIX as CANDY_PREFIX, AllowlistProof, AllowlistRoot, CollectionPDA, ConfigLine,
+    EditionSettings, HiddenMintRecord, HiddenReveal, HolderMintRecord, MintCounter, MintLimitPDA,
+    MintPhases, MintRequest, MintSettings, PaymentOptions, RevenueSplits,
+};
 
 pub fn make_config_lines(start_index: u32, total: u8) -> Vec<ConfigLine> {
     let mut config_lines = Vec::with_capacity(total as usize);
@@ -21,6 +25,23 @@ pub fn find_candy_creator(candy_machine_key: &Pubkey) -> (Pubkey, u8) {
     Pubkey::find_program_address(seeds, &mpl_candy_machine::id())
 }
 
+pub fn find_revenue_splits(candy_machine_key: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[RevenueSplits::PREFIX.as_bytes(), candy_machine_key.as_ref()],
+        &mpl_candy_machine::id(),
+    )
+}
+
+pub fn find_edition_settings(candy_machine_key: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            EditionSettings::PREFIX.as_bytes(),
+            candy_machine_key.as_ref(),
+        ],
+        &mpl_candy_machine::id(),
+    )
+}
+
 pub fn find_collection_pda(candy_machine_key: &Pubkey) -> (Pubkey, u8) {
     Pubkey::find_program_address(
         &[CollectionPDA::PREFIX.as_bytes(), candy_machine_key.as_ref()],
@@ -28,6 +49,127 @@ pub fn find_collection_pda(candy_machine_key: &Pubkey) -> (Pubkey, u8) {
     )
 }
 
+pub fn find_mint_limit_pda(candy_machine_key: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[MintLimitPDA::PREFIX.as_bytes(), candy_machine_key.as_ref()],
+        &mpl_candy_machine::id(),
+    )
+}
+
+pub fn find_mint_settings_pda(candy_machine_key: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[MintSettings::PREFIX.as_bytes(), candy_machine_key.as_ref()],
+        &mpl_candy_machine::id(),
+    )
+}
+
+pub fn find_mint_counter(candy_machine_key: &Pubkey, wallet: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            MintCounter::PREFIX.as_bytes(),
+            candy_machine_key.as_ref(),
+            wallet.as_ref(),
+        ],
+        &mpl_candy_machine::id(),
+    )
+}
+
+pub fn find_mint_phases(candy_machine_key: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[MintPhases::PREFIX.as_bytes(), candy_machine_key.as_ref()],
+        &mpl_candy_machine::id(),
+    )
+}
+
+pub fn find_holder_mint_record(candy_machine_key: &Pubkey, held_mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            HolderMintRecord::PREFIX.as_bytes(),
+            candy_machine_key.as_ref(),
+            held_mint.as_ref(),
+        ],
+        &mpl_candy_machine::id(),
+    )
+}
+
+pub fn find_payment_options(candy_machine_key: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            PaymentOptions::PREFIX.as_bytes(),
+            candy_machine_key.as_ref(),
+        ],
+        &mpl_candy_machine::id(),
+    )
+}
+
+pub fn find_phase_mint_counter(
+    candy_machine_key: &Pubkey,
+    wallet: &Pubkey,
+    phase_index: u8,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            MintCounter::PREFIX.as_bytes(),
+            candy_machine_key.as_ref(),
+            wallet.as_ref(),
+            &[phase_index],
+        ],
+        &mpl_candy_machine::id(),
+    )
+}
+
+pub fn find_allowlist_root(candy_machine_key: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[AllowlistRoot::PREFIX.as_bytes(), candy_machine_key.as_ref()],
+        &mpl_candy_machine::id(),
+    )
+}
+
+pub fn find_allowlist_proof(
+    candy_machine_key: &Pubkey,
+    wallet: &Pubkey,
+    root: &[u8; 32],
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            AllowlistProof::PREFIX.as_bytes(),
+            candy_machine_key.as_ref(),
+            wallet.as_ref(),
+            root.as_ref(),
+        ],
+        &mpl_candy_machine::id(),
+    )
+}
+
+pub fn find_hidden_reveal(candy_machine_key: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[HiddenReveal::PREFIX.as_bytes(), candy_machine_key.as_ref()],
+        &mpl_candy_machine::id(),
+    )
+}
+
+pub fn find_hidden_mint_record(candy_machine_key: &Pubkey, mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            HiddenMintRecord::PREFIX.as_bytes(),
+            candy_machine_key.as_ref(),
+            mint.as_ref(),
+        ],
+        &mpl_candy_machine::id(),
+    )
+}
+
+pub fn find_mint_request(candy_machine_key: &Pubkey, mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            MintRequest::PREFIX.as_bytes(),
+            candy_machine_key.as_ref(),
+            mint.as_ref(),
+        ],
+        &mpl_candy_machine::id(),
+    )
+}
+
 pub fn find_freeze_ata(freeze_info: &FreezeInfo, token_info: &TokenInfo) -> Pubkey {
     get_associated_token_address(&freeze_info.pda, &token_info.mint)
 }